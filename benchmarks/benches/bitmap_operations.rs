@@ -0,0 +1,151 @@
+//! Measures the bitmap operations used to compute the candidates of a search, on their own
+//! and through the searches of a generated index.
+//!
+//! Compare the results with and without the `simd` feature, which requires a nightly compiler:
+//! `cargo bench --bench bitmap_operations -- --save-baseline scalar` then
+//! `cargo +nightly bench --bench bitmap_operations --features simd -- --baseline scalar`.
+
+use std::fs::{create_dir_all, remove_dir_all};
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use milli::heed::EnvOpenOptions;
+use milli::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig};
+use milli::{Index, Object, TermsMatchingStrategy};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use roaring::{MultiOps, RoaringBitmap};
+use serde_json::json;
+
+#[global_allocator]
+static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+/// The number of documents of the generated bitmaps.
+const UNIVERSE: u32 = 10_000_000;
+
+/// Generates a bitmap containing each document with the given probability.
+fn random_bitmap(rng: &mut ChaCha8Rng, density: f64) -> RoaringBitmap {
+    let len = (UNIVERSE as f64 * density) as usize;
+    let mut bitmap = RoaringBitmap::new();
+    while (bitmap.len() as usize) < len {
+        bitmap.insert(rng.gen_range(0..UNIVERSE));
+    }
+    bitmap
+}
+
+/// The densities of the bitmaps of rare words, stored in array containers,
+/// and of frequent words, stored in bitmap containers.
+const DENSITIES: &[(&str, f64)] = &[("sparse", 0.001), ("medium", 0.05), ("dense", 0.5)];
+
+fn bench_bitmap_operations(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let bitmaps: Vec<_> = DENSITIES
+        .iter()
+        .map(|(name, density)| {
+            (*name, random_bitmap(&mut rng, *density), random_bitmap(&mut rng, *density))
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("intersect_with");
+    for (name, left, right) in &bitmaps {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &(left, right), |b, (l, r)| {
+            b.iter_batched(|| (*l).clone(), |mut left| left &= *r, BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("union_with");
+    for (name, left, right) in &bitmaps {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &(left, right), |b, (l, r)| {
+            b.iter_batched(|| (*l).clone(), |mut left| left |= *r, BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+
+    // The candidates of a query term are the union of the documents of all its derivations.
+    let derivations: Vec<_> = (0..50).map(|_| random_bitmap(&mut rng, 0.01)).collect();
+    c.bench_function("union of derivations", |b| b.iter(|| derivations.iter().union()));
+}
+
+/// The number of documents of the generated index.
+const DOCUMENTS: usize = 200_000;
+/// The number of distinct words of the generated index, the frequency of a word
+/// decreases with its number, `w0` is in almost every document.
+const VOCABULARY: u32 = 10_000;
+
+/// Generates an index of documents made of random words of the vocabulary.
+fn generated_index(rng: &mut ChaCha8Rng) -> Index {
+    let database_name = "bitmap_operations.mmdb";
+    match remove_dir_all(database_name) {
+        Ok(_) => (),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+        Err(e) => panic!("{}", e),
+    }
+    create_dir_all(database_name).unwrap();
+
+    let mut options = EnvOpenOptions::new();
+    options.map_size(10 * 1024 * 1024 * 1024); // 10 GB
+    let index = Index::new(options, database_name).unwrap();
+
+    let mut documents = DocumentsBatchBuilder::new(Vec::new());
+    for id in 0..DOCUMENTS {
+        let words: Vec<_> = (0..20)
+            .map(|_| {
+                let rank = rng.gen_range(0.0f64..1.0).powi(4) * VOCABULARY as f64;
+                format!("w{}", rank as u32)
+            })
+            .collect();
+        let mut document = Object::new();
+        document.insert("id".to_string(), json!(id));
+        document.insert("text".to_string(), json!(words.join(" ")));
+        documents.append_json_object(&document).unwrap();
+    }
+    let documents = documents.into_inner().unwrap();
+
+    let config = IndexerConfig::default();
+    let mut wtxn = index.write_txn().unwrap();
+    let builder = IndexDocuments::new(
+        &mut wtxn,
+        &index,
+        &config,
+        IndexDocumentsConfig::default(),
+        |_| (),
+        || false,
+    )
+    .unwrap();
+    let documents = DocumentsBatchReader::from_reader(Cursor::new(documents)).unwrap();
+    let (builder, user_error) = builder.add_documents(documents).unwrap();
+    user_error.unwrap();
+    builder.execute().unwrap();
+    wtxn.commit().unwrap();
+
+    index
+}
+
+/// Queries mixing frequent and rare words, the frequent words have large bitmap containers
+/// and the prefix of the last word is the union of many bitmaps.
+const QUERIES: &[&str] = &["w0 w1", "w0 w1 w2 w3", "w5 w500", "w10 w2000 w9", "w1 w2 w"];
+
+fn bench_search(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let index = generated_index(&mut rng);
+
+    let mut group = c.benchmark_group("search");
+    for &query in QUERIES {
+        group.bench_with_input(BenchmarkId::from_parameter(query), &query, |b, &query| {
+            b.iter(|| {
+                let rtxn = index.read_txn().unwrap();
+                let mut search = index.search(&rtxn);
+                search.query(query).terms_matching_strategy(TermsMatchingStrategy::default());
+                let _ids = search.execute().unwrap();
+            });
+        });
+    }
+    group.finish();
+
+    index.prepare_for_closing().wait();
+}
+
+criterion_group!(benches, bench_bitmap_operations, bench_search);
+criterion_main!(benches);
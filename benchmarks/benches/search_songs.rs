@@ -149,6 +149,11 @@ fn bench_songs(c: &mut criterion::Criterion) {
             filter: Some("released-timestamp 946728000 TO 1262347200"), // year 2000 to 2010
             ..BASE_CONF
         },
+        utils::Conf {
+            group_name: "prefix filter: STARTS WITH",
+            filter: Some("artist STARTS WITH the"), // a high-cardinality field
+            ..BASE_CONF
+        },
         utils::Conf {
             group_name: "big filter",
             filter: Some("released-timestamp != 1262347200 AND (NOT (released-timestamp = 946728000)) AND (duration-float = 1 OR (duration-float 1.1 TO 1.5 AND released-timestamp > 315576000))"),
@@ -0,0 +1,58 @@
+//! Scores the search of an index against judgments and compares the scores with a baseline,
+//! e.g. `cargo run --release --bin relevancy -- ./songs.mmdb judgments.jsonl --save base.json`
+//! before a ranking change, then `--baseline base.json` after it.
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use benchmarks::relevancy::{evaluate, read_judgments, Report};
+use clap::Parser;
+use milli::heed::EnvOpenOptions;
+use milli::Index;
+
+#[derive(Debug, Parser)]
+#[command(about)]
+struct Opt {
+    /// The directory of the index to search in.
+    index: PathBuf,
+    /// The JSON lines file of the judgments.
+    judgments: PathBuf,
+    /// The number of results scored for every query.
+    #[arg(short, default_value_t = 10)]
+    k: usize,
+    /// A report saved by a previous run, the scores are compared with its scores.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Saves the report of this run to be the baseline of the next runs.
+    #[arg(long)]
+    save: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt = Opt::parse();
+
+    let mut options = EnvOpenOptions::new();
+    options.map_size(100 * 1024 * 1024 * 1024); // 100 GB
+    let index = Index::new(options, &opt.index)
+        .with_context(|| format!("opening the index {}", opt.index.display()))?;
+    let judgments = read_judgments(&opt.judgments)?;
+
+    let baseline: Option<Report> = match &opt.baseline {
+        Some(path) => {
+            let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+            Some(serde_json::from_reader(file)?)
+        }
+        None => None,
+    };
+
+    let report = evaluate(&index, &judgments, opt.k)?;
+    report.print(baseline.as_ref());
+
+    if let Some(path) = &opt.save {
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        serde_json::to_writer_pretty(file, &report)?;
+    }
+
+    Ok(())
+}
@@ -3,3 +3,5 @@
 //!
 //! It does not include interesting functions for milli library
 //! users only for milli contributors.
+
+pub mod relevancy;
@@ -0,0 +1,216 @@
+//! Measures the relevancy of the search against judgments, i.e. queries along with the
+//! documents that are expected at the top of their results, to check that a change of the
+//! ranking doesn't degrade the results that are known to be good.
+//!
+//! The judgments are stored as JSON lines, one query per line, the expected documents are
+//! given by their primary key from the most to the least relevant:
+//!
+//! ```json
+//! {"query": "david bowie", "expected": ["1304", "9732", "5121"]}
+//! {"query": "jazz", "filter": "genre = jazz", "expected": ["3356"]}
+//! ```
+//!
+//! Each query is scored with its NDCG, where the gain of an expected document decreases
+//! with its position in the judgment, and its reciprocal rank, the inverse of the position of
+//! the first expected document in the results. The report of a run can be saved to be the
+//! baseline of the next runs.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::Context;
+use milli::{Filter, Index};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Judgment {
+    pub query: String,
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// The primary keys of the expected documents, from the most to the least relevant.
+    pub expected: Vec<String>,
+}
+
+/// Reads the judgments of a JSON lines file, the empty lines are ignored.
+pub fn read_judgments(path: &Path) -> anyhow::Result<Vec<Judgment>> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let mut judgments = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let judgment = serde_json::from_str(&line)
+            .with_context(|| format!("parsing the judgment at line {}", i + 1))?;
+        judgments.push(judgment);
+    }
+    Ok(judgments)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryScores {
+    pub query: String,
+    pub ndcg: f64,
+    pub reciprocal_rank: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// The number of results scored for every query.
+    pub k: usize,
+    /// The mean of the NDCG of the queries.
+    pub ndcg: f64,
+    /// The mean of the reciprocal ranks of the queries.
+    pub mrr: f64,
+    pub queries: Vec<QueryScores>,
+}
+
+impl Report {
+    /// Prints the scores of this report, along with their deltas to the baseline, if any.
+    ///
+    /// Only the queries whose scores changed are listed when there is a baseline.
+    pub fn print(&self, baseline: Option<&Report>) {
+        let baseline_queries: HashMap<_, _> = baseline
+            .map(|baseline| baseline.queries.iter().map(|q| (q.query.as_str(), q)).collect())
+            .unwrap_or_default();
+
+        for scores in &self.queries {
+            match baseline_queries.get(scores.query.as_str()) {
+                Some(before)
+                    if before.ndcg == scores.ndcg
+                        && before.reciprocal_rank == scores.reciprocal_rank => {}
+                Some(before) => println!(
+                    "{:<40} ndcg@{} {:.4} ({:+.4})  rr {:.4} ({:+.4})",
+                    scores.query,
+                    self.k,
+                    scores.ndcg,
+                    scores.ndcg - before.ndcg,
+                    scores.reciprocal_rank,
+                    scores.reciprocal_rank - before.reciprocal_rank,
+                ),
+                None => println!(
+                    "{:<40} ndcg@{} {:.4}  rr {:.4}",
+                    scores.query, self.k, scores.ndcg, scores.reciprocal_rank,
+                ),
+            }
+        }
+
+        match baseline {
+            Some(baseline) => println!(
+                "mean ndcg@{} {:.4} ({:+.4}), mrr {:.4} ({:+.4}) over {} queries",
+                self.k,
+                self.ndcg,
+                self.ndcg - baseline.ndcg,
+                self.mrr,
+                self.mrr - baseline.mrr,
+                self.queries.len(),
+            ),
+            None => println!(
+                "mean ndcg@{} {:.4}, mrr {:.4} over {} queries",
+                self.k,
+                self.ndcg,
+                self.mrr,
+                self.queries.len()
+            ),
+        }
+    }
+}
+
+/// Runs the queries of the judgments against the index and scores their first `k` results.
+pub fn evaluate(index: &Index, judgments: &[Judgment], k: usize) -> anyhow::Result<Report> {
+    let rtxn = index.read_txn()?;
+    let mut queries = Vec::with_capacity(judgments.len());
+    for judgment in judgments {
+        let mut search = index.search(&rtxn);
+        search.query(&judgment.query).limit(k);
+        if let Some(filter) =
+            judgment.filter.as_deref().map(Filter::from_str).transpose()?.flatten()
+        {
+            search.filter(filter);
+        }
+
+        let result =
+            search.execute().with_context(|| format!("searching for {:?}", judgment.query))?;
+        let mut results = Vec::with_capacity(result.documents_ids.len());
+        for docid in result.documents_ids {
+            results.push(index.external_id_of(&rtxn, docid)?.unwrap_or_default());
+        }
+
+        queries.push(QueryScores {
+            query: judgment.query.clone(),
+            ndcg: ndcg(&results, &judgment.expected, k),
+            reciprocal_rank: reciprocal_rank(&results, &judgment.expected, k),
+        });
+    }
+
+    let count = queries.len().max(1) as f64;
+    Ok(Report {
+        k,
+        ndcg: queries.iter().map(|q| q.ndcg).sum::<f64>() / count,
+        mrr: queries.iter().map(|q| q.reciprocal_rank).sum::<f64>() / count,
+        queries,
+    })
+}
+
+/// The gain of the expected documents decreases with their position in the judgment,
+/// the other documents have no gain.
+fn gain(expected: &[String], document: &str) -> f64 {
+    match expected.iter().position(|e| e == document) {
+        Some(position) => (expected.len() - position) as f64,
+        None => 0.0,
+    }
+}
+
+fn dcg<'a>(documents: impl Iterator<Item = &'a String>, expected: &[String], k: usize) -> f64 {
+    documents
+        .take(k)
+        .enumerate()
+        .map(|(rank, document)| gain(expected, document) / (rank as f64 + 2.0).log2())
+        .sum()
+}
+
+/// Returns the DCG of the results divided by the DCG of the expected documents.
+pub fn ndcg(results: &[String], expected: &[String], k: usize) -> f64 {
+    let ideal = dcg(expected.iter(), expected, k);
+    if ideal == 0.0 {
+        return 0.0;
+    }
+    dcg(results.iter(), expected, k) / ideal
+}
+
+/// Returns the inverse of the rank of the first expected document among the first `k`
+/// results, 0 if there is none.
+pub fn reciprocal_rank(results: &[String], expected: &[String], k: usize) -> f64 {
+    results
+        .iter()
+        .take(k)
+        .position(|document| expected.contains(document))
+        .map_or(0.0, |position| 1.0 / (position as f64 + 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn ranking_metrics() {
+        let expected = ids(&["a", "b", "c"]);
+
+        assert_eq!(ndcg(&ids(&["a", "b", "c", "d"]), &expected, 10), 1.0);
+        assert_eq!(ndcg(&ids(&["d", "e"]), &expected, 10), 0.0);
+        let swapped = ndcg(&ids(&["b", "a", "c"]), &expected, 10);
+        assert!(0.9 < swapped && swapped < 1.0);
+        // the documents after the first k results are ignored
+        assert_eq!(ndcg(&ids(&["d", "a"]), &expected, 1), 0.0);
+
+        assert_eq!(reciprocal_rank(&ids(&["a", "b"]), &expected, 10), 1.0);
+        assert_eq!(reciprocal_rank(&ids(&["d", "e", "c"]), &expected, 10), 1.0 / 3.0);
+        assert_eq!(reciprocal_rank(&ids(&["d", "e", "c"]), &expected, 2), 0.0);
+    }
+}
@@ -116,6 +116,8 @@ pub enum KindDump {
     IndexUpdate {
         primary_key: Option<String>,
     },
+    IndexCompaction,
+    IndexOptimization,
     IndexSwap {
         swaps: Vec<IndexSwap>,
     },
@@ -186,6 +188,8 @@ impl From<KindWithContent> for KindDump {
             KindWithContent::IndexUpdate { primary_key, .. } => {
                 KindDump::IndexUpdate { primary_key }
             }
+            KindWithContent::IndexCompaction { .. } => KindDump::IndexCompaction,
+            KindWithContent::IndexOptimization { .. } => KindDump::IndexOptimization,
             KindWithContent::IndexSwap { swaps } => KindDump::IndexSwap { swaps },
             KindWithContent::TaskCancelation { query, tasks } => {
                 KindDump::TaskCancelation { query, tasks }
@@ -214,7 +218,9 @@ pub(crate) mod test {
     use meilisearch_types::keys::{Action, Key};
     use meilisearch_types::milli;
     use meilisearch_types::milli::update::Setting;
-    use meilisearch_types::settings::{Checked, FacetingSettings, Settings};
+    use meilisearch_types::settings::{
+        Checked, FacetingSettings, LocalizedAttributesRuleView, Settings,
+    };
     use meilisearch_types::tasks::{Details, Status};
     use serde_json::{json, Map, Value};
     use time::macros::datetime;
@@ -271,6 +277,10 @@ pub(crate) mod test {
                 ),
             }),
             pagination: Setting::NotSet,
+            localized_attributes: Setting::Set(vec![LocalizedAttributesRuleView {
+                attribute_patterns: vec![S("name")],
+                locales: vec![S("fra")],
+            }]),
             _kind: std::marker::PhantomData,
         };
         settings.check()
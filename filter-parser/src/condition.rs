@@ -26,6 +26,7 @@ pub enum Condition<'a> {
     LowerThan(Token<'a>),
     LowerThanOrEqual(Token<'a>),
     Between { from: Token<'a>, to: Token<'a> },
+    StartsWith(Token<'a>),
 }
 
 /// condition      = value ("==" | ">" ...) value
@@ -92,6 +93,20 @@ pub fn parse_not_exists(input: Span) -> IResult<FilterCondition> {
     Ok((input, FilterCondition::Not(Box::new(FilterCondition::Condition { fid: key, op: Exists }))))
 }
 
+/// starts_with    = value "STARTS" WS+ "WITH" WS+ value
+pub fn parse_starts_with(input: Span) -> IResult<FilterCondition> {
+    let (input, (fid, _, _, _, _, value)) = tuple((
+        parse_value,
+        tag("STARTS"),
+        multispace1,
+        tag("WITH"),
+        multispace1,
+        cut(parse_value),
+    ))(input)?;
+
+    Ok((input, FilterCondition::Condition { fid, op: StartsWith(value) }))
+}
+
 /// to             = value value "TO" WS+ value
 pub fn parse_to(input: Span) -> IResult<FilterCondition> {
     let (input, (key, from, _, _, to)) =
@@ -33,6 +33,8 @@ enum AutobatchKind {
     IndexCreation,
     IndexDeletion,
     IndexUpdate,
+    IndexCompaction,
+    IndexOptimization,
     IndexSwap,
 }
 
@@ -76,6 +78,8 @@ impl From<KindWithContent> for AutobatchKind {
             KindWithContent::IndexDeletion { .. } => AutobatchKind::IndexDeletion,
             KindWithContent::IndexCreation { .. } => AutobatchKind::IndexCreation,
             KindWithContent::IndexUpdate { .. } => AutobatchKind::IndexUpdate,
+            KindWithContent::IndexCompaction { .. } => AutobatchKind::IndexCompaction,
+            KindWithContent::IndexOptimization { .. } => AutobatchKind::IndexOptimization,
             KindWithContent::IndexSwap { .. } => AutobatchKind::IndexSwap,
             KindWithContent::TaskCancelation { .. }
             | KindWithContent::TaskDeletion { .. }
@@ -129,6 +133,12 @@ pub enum BatchKind {
     IndexUpdate {
         id: TaskId,
     },
+    IndexCompaction {
+        id: TaskId,
+    },
+    IndexOptimization {
+        id: TaskId,
+    },
     IndexSwap {
         id: TaskId,
     },
@@ -174,6 +184,8 @@ impl BatchKind {
             K::IndexCreation => (Break(BatchKind::IndexCreation { id: task_id }), true),
             K::IndexDeletion => (Break(BatchKind::IndexDeletion { ids: vec![task_id] }), false),
             K::IndexUpdate => (Break(BatchKind::IndexUpdate { id: task_id }), false),
+            K::IndexCompaction => (Break(BatchKind::IndexCompaction { id: task_id }), false),
+            K::IndexOptimization => (Break(BatchKind::IndexOptimization { id: task_id }), false),
             K::IndexSwap => (Break(BatchKind::IndexSwap { id: task_id }), false),
             K::DocumentClear => (Continue(BatchKind::DocumentClear { ids: vec![task_id] }), false),
             K::DocumentImport { method, allow_index_creation, primary_key: pk }
@@ -222,7 +234,7 @@ impl BatchKind {
 
         match (self, kind) {
             // We don't batch any of these operations
-            (this, K::IndexCreation | K::IndexUpdate | K::IndexSwap | K::DocumentDeletionByFilter) => Break(this),
+            (this, K::IndexCreation | K::IndexUpdate | K::IndexCompaction | K::IndexOptimization | K::IndexSwap | K::DocumentDeletionByFilter) => Break(this),
             // We must not batch tasks that don't have the same index creation rights if the index doesn't already exists.
             (this, kind) if !index_already_exists && this.allow_index_creation() == Some(false) && kind.allow_index_creation() == Some(true) => {
                 Break(this)
@@ -518,6 +530,8 @@ impl BatchKind {
                 BatchKind::IndexCreation { .. }
                 | BatchKind::IndexDeletion { .. }
                 | BatchKind::IndexUpdate { .. }
+                | BatchKind::IndexCompaction { .. }
+                | BatchKind::IndexOptimization { .. }
                 | BatchKind::IndexSwap { .. }
                 | BatchKind::DocumentDeletionByFilter { .. },
                 _,
@@ -630,6 +644,14 @@ mod tests {
         KindWithContent::IndexUpdate { index_uid: String::from("doggo"), primary_key: None }
     }
 
+    fn idx_compact() -> KindWithContent {
+        KindWithContent::IndexCompaction { index_uid: String::from("doggo") }
+    }
+
+    fn idx_optimize() -> KindWithContent {
+        KindWithContent::IndexOptimization { index_uid: String::from("doggo") }
+    }
+
     fn idx_del() -> KindWithContent {
         KindWithContent::IndexDeletion { index_uid: String::from("doggo") }
     }
@@ -731,6 +753,13 @@ mod tests {
         debug_snapshot!(autobatch_from(true, None, [doc_imp(UpdateDocuments, true, None), idx_update()]), @"Some((DocumentOperation { method: UpdateDocuments, allow_index_creation: true, primary_key: None, operation_ids: [0] }, true))");
         debug_snapshot!(autobatch_from(true, None, [doc_del(), idx_update()]), @"Some((DocumentDeletion { deletion_ids: [0] }, false))");
 
+        debug_snapshot!(autobatch_from(true, None, [doc_imp(ReplaceDocuments, true, None), idx_compact()]), @"Some((DocumentOperation { method: ReplaceDocuments, allow_index_creation: true, primary_key: None, operation_ids: [0] }, true))");
+        debug_snapshot!(autobatch_from(true, None, [doc_del(), idx_compact()]), @"Some((DocumentDeletion { deletion_ids: [0] }, false))");
+        debug_snapshot!(autobatch_from(true, None, [idx_compact(), doc_del()]), @"Some((IndexCompaction { id: 0 }, false))");
+
+        debug_snapshot!(autobatch_from(true, None, [doc_del(), idx_optimize()]), @"Some((DocumentDeletion { deletion_ids: [0] }, false))");
+        debug_snapshot!(autobatch_from(true, None, [idx_optimize(), idx_compact()]), @"Some((IndexOptimization { id: 0 }, false))");
+
         debug_snapshot!(autobatch_from(true, None, [doc_imp(ReplaceDocuments, true, None), idx_swap()]), @"Some((DocumentOperation { method: ReplaceDocuments, allow_index_creation: true, primary_key: None, operation_ids: [0] }, true))");
         debug_snapshot!(autobatch_from(true, None, [doc_imp(UpdateDocuments, true, None), idx_swap()]), @"Some((DocumentOperation { method: UpdateDocuments, allow_index_creation: true, primary_key: None, operation_ids: [0] }, true))");
         debug_snapshot!(autobatch_from(true, None, [doc_del(), idx_swap()]), @"Some((DocumentDeletion { deletion_ids: [0] }, false))");
@@ -29,8 +29,8 @@ use meilisearch_types::heed::{RoTxn, RwTxn};
 use meilisearch_types::milli::documents::{obkv_to_object, DocumentsBatchReader};
 use meilisearch_types::milli::heed::CompactionOption;
 use meilisearch_types::milli::update::{
-    DeleteDocuments, DocumentDeletionResult, IndexDocumentsConfig, IndexDocumentsMethod,
-    Settings as MilliSettings,
+    CompactVectors, DeleteDocuments, DocumentDeletionResult, IndexDocumentsConfig,
+    IndexDocumentsMethod, OptimizeIndex, Settings as MilliSettings,
 };
 use meilisearch_types::milli::{self, Filter, BEU32};
 use meilisearch_types::settings::{apply_settings_to_builder, Settings, Unchecked};
@@ -81,6 +81,14 @@ pub(crate) enum Batch {
         primary_key: Option<String>,
         task: Task,
     },
+    IndexCompaction {
+        index_uid: String,
+        task: Task,
+    },
+    IndexOptimization {
+        index_uid: String,
+        task: Task,
+    },
     IndexDeletion {
         index_uid: String,
         tasks: Vec<Task>,
@@ -156,7 +164,9 @@ impl Batch {
             | Batch::Dump(task)
             | Batch::IndexCreation { task, .. }
             | Batch::IndexDocumentDeletionByFilter { task, .. }
-            | Batch::IndexUpdate { task, .. } => vec![task.uid],
+            | Batch::IndexUpdate { task, .. }
+            | Batch::IndexCompaction { task, .. }
+            | Batch::IndexOptimization { task, .. } => vec![task.uid],
             Batch::SnapshotCreation(tasks) | Batch::IndexDeletion { tasks, .. } => {
                 tasks.iter().map(|task| task.uid).collect()
             }
@@ -194,6 +204,8 @@ impl Batch {
             IndexOperation { op, .. } => Some(op.index_uid()),
             IndexCreation { index_uid, .. }
             | IndexUpdate { index_uid, .. }
+            | IndexCompaction { index_uid, .. }
+            | IndexOptimization { index_uid, .. }
             | IndexDeletion { index_uid, .. }
             | IndexDocumentDeletionByFilter { index_uid, .. } => Some(index_uid),
         }
@@ -449,6 +461,14 @@ impl IndexScheduler {
                 };
                 Ok(Some(Batch::IndexUpdate { index_uid, primary_key, task }))
             }
+            BatchKind::IndexCompaction { id } => {
+                let task = self.get_task(rtxn, id)?.ok_or(Error::CorruptedTaskQueue)?;
+                Ok(Some(Batch::IndexCompaction { index_uid, task }))
+            }
+            BatchKind::IndexOptimization { id } => {
+                let task = self.get_task(rtxn, id)?.ok_or(Error::CorruptedTaskQueue)?;
+                Ok(Some(Batch::IndexOptimization { index_uid, task }))
+            }
             BatchKind::IndexDeletion { ids } => Ok(Some(Batch::IndexDeletion {
                 index_uid,
                 index_has_been_created: must_create_index,
@@ -991,6 +1011,46 @@ impl IndexScheduler {
 
                 Ok(vec![task])
             }
+            Batch::IndexCompaction { index_uid, mut task } => {
+                let index = {
+                    let rtxn = self.env.read_txn()?;
+                    self.index_mapper.index(&rtxn, &index_uid)?
+                };
+
+                // an explicit compaction rebuilds the HNSW as soon as it has a dead point
+                let mut index_wtxn = index.write_txn()?;
+                let mut builder = CompactVectors::new(&mut index_wtxn, &index);
+                builder.dead_points_ratio_threshold(0.0);
+                let removed_vector_points = match builder.execute()? {
+                    Some(result) => result.points_before - result.points_after,
+                    None => 0,
+                };
+                index_wtxn.commit()?;
+
+                task.status = Status::Succeeded;
+                task.details = Some(Details::IndexCompaction {
+                    removed_vector_points: Some(removed_vector_points),
+                });
+
+                Ok(vec![task])
+            }
+            Batch::IndexOptimization { index_uid, mut task } => {
+                let index = {
+                    let rtxn = self.env.read_txn()?;
+                    self.index_mapper.index(&rtxn, &index_uid)?
+                };
+
+                // an explicit optimization performs all the steps without time budget
+                let mut index_wtxn = index.write_txn()?;
+                let result = OptimizeIndex::new(&mut index_wtxn, &index).execute()?;
+                index_wtxn.commit()?;
+
+                task.status = Status::Succeeded;
+                task.details =
+                    Some(Details::IndexOptimization { performed_steps: Some(result.performed) });
+
+                Ok(vec![task])
+            }
             Batch::IndexDeletion { index_uid, index_has_been_created, mut tasks } => {
                 let wtxn = self.env.write_txn()?;
 
@@ -1497,9 +1557,10 @@ fn delete_document_by_filter(filter: &serde_json::Value, index: Index) -> Result
         let mut wtxn = index.write_txn()?;
 
         let candidates = filter.evaluate(&wtxn, &index).map_err(|err| match err {
-            milli::Error::UserError(milli::UserError::InvalidFilter(_)) => {
-                Error::from(err).with_custom_error_code(Code::InvalidDocumentFilter)
-            }
+            milli::Error::UserError(
+                milli::UserError::InvalidFilter(_)
+                | milli::UserError::InvalidFilterableAttribute { .. },
+            ) => Error::from(err).with_custom_error_code(Code::InvalidDocumentFilter),
             e => e.into(),
         })?;
         let mut delete_operation = DeleteDocuments::new(&mut wtxn, &index)?;
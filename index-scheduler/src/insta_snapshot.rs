@@ -181,6 +181,12 @@ fn snapshot_details(d: &Details) -> String {
         Details::IndexInfo { primary_key } => {
             format!("{{ primary_key: {primary_key:?} }}")
         }
+        Details::IndexCompaction { removed_vector_points } => {
+            format!("{{ removed_vector_points: {removed_vector_points:?} }}")
+        }
+        Details::IndexOptimization { performed_steps } => {
+            format!("{{ performed_steps: {performed_steps:?} }}")
+        }
         Details::DocumentDeletion {
             provided_ids: received_document_ids,
             deleted_documents,
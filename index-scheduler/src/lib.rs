@@ -1373,6 +1373,12 @@ impl<'a> Dump<'a> {
                     index_uid: task.index_uid.ok_or(Error::CorruptedDump)?,
                     primary_key,
                 },
+                KindDump::IndexCompaction => KindWithContent::IndexCompaction {
+                    index_uid: task.index_uid.ok_or(Error::CorruptedDump)?,
+                },
+                KindDump::IndexOptimization => KindWithContent::IndexOptimization {
+                    index_uid: task.index_uid.ok_or(Error::CorruptedDump)?,
+                },
                 KindDump::IndexSwap { swaps } => KindWithContent::IndexSwap { swaps },
                 KindDump::TaskCancelation { query, tasks } => {
                     KindWithContent::TaskCancelation { query, tasks }
@@ -1498,11 +1504,11 @@ mod tests {
     use meilisearch_types::document_formats::DocumentFormatError;
     use meilisearch_types::error::ErrorCode;
     use meilisearch_types::index_uid_pattern::IndexUidPattern;
-    use meilisearch_types::milli::obkv_to_json;
     use meilisearch_types::milli::update::IndexDocumentsMethod::{
         ReplaceDocuments, UpdateDocuments,
     };
-    use meilisearch_types::tasks::IndexSwap;
+    use meilisearch_types::milli::{obkv_to_json, OptimizationStep};
+    use meilisearch_types::tasks::{Details, IndexSwap};
     use meilisearch_types::VERSION_FILE_NAME;
     use tempfile::{NamedTempFile, TempDir};
     use time::Duration;
@@ -4097,4 +4103,81 @@ mod tests {
         snapshot!(json_string!(tasks, { "[].enqueuedAt" => "[date]", "[].startedAt" => "[date]", "[].finishedAt" => "[date]", ".**.original_filter" => "[filter]", ".**.query" => "[query]" }), name: "everything_has_been_processed");
         drop(rtxn);
     }
+
+    #[test]
+    fn index_compaction() {
+        let (index_scheduler, mut handle) = IndexScheduler::test(true, vec![]);
+
+        index_scheduler
+            .register(KindWithContent::IndexCreation { index_uid: S("doggo"), primary_key: None })
+            .unwrap();
+        handle.advance_one_successful_batch();
+
+        index_scheduler
+            .register(KindWithContent::IndexCompaction { index_uid: S("doggo") })
+            .unwrap();
+        handle.advance_one_successful_batch();
+
+        // the index must exist to be compacted
+        index_scheduler
+            .register(KindWithContent::IndexCompaction { index_uid: S("catto") })
+            .unwrap();
+        handle.advance_one_failed_batch();
+
+        let rtxn = index_scheduler.env.read_txn().unwrap();
+        let compacted = index_scheduler.get_task(&rtxn, 1).unwrap().unwrap();
+        assert_eq!(compacted.status, Status::Succeeded);
+        assert_eq!(
+            compacted.details,
+            Some(Details::IndexCompaction { removed_vector_points: Some(0) })
+        );
+        let failed = index_scheduler.get_task(&rtxn, 2).unwrap().unwrap();
+        assert_eq!(failed.status, Status::Failed);
+        assert_eq!(
+            failed.details,
+            Some(Details::IndexCompaction { removed_vector_points: Some(0) })
+        );
+    }
+
+    #[test]
+    fn index_optimization() {
+        let (index_scheduler, mut handle) = IndexScheduler::test(true, vec![]);
+
+        index_scheduler
+            .register(KindWithContent::IndexCreation { index_uid: S("doggo"), primary_key: None })
+            .unwrap();
+        handle.advance_one_successful_batch();
+
+        index_scheduler
+            .register(KindWithContent::IndexOptimization { index_uid: S("doggo") })
+            .unwrap();
+        handle.advance_one_successful_batch();
+
+        // the index must exist to be optimized
+        index_scheduler
+            .register(KindWithContent::IndexOptimization { index_uid: S("catto") })
+            .unwrap();
+        handle.advance_one_failed_batch();
+
+        let rtxn = index_scheduler.env.read_txn().unwrap();
+        let optimized = index_scheduler.get_task(&rtxn, 1).unwrap().unwrap();
+        assert_eq!(optimized.status, Status::Succeeded);
+        // nothing was pending, a full pass is performed
+        assert_eq!(
+            optimized.details,
+            Some(Details::IndexOptimization {
+                performed_steps: Some(OptimizationStep::ALL.to_vec())
+            })
+        );
+        let failed = index_scheduler.get_task(&rtxn, 2).unwrap().unwrap();
+        assert_eq!(failed.status, Status::Failed);
+        assert_eq!(
+            failed.details,
+            Some(Details::IndexOptimization { performed_steps: Some(vec![]) })
+        );
+
+        let index = index_scheduler.index("doggo").unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.pending_optimizations(&rtxn).unwrap().is_empty());
+    }
 }
@@ -245,6 +245,8 @@ pub fn swap_index_uid_in_task(task: &mut Task, swap: (&str, &str)) {
         K::IndexDeletion { index_uid } => index_uids.push(index_uid),
         K::IndexCreation { index_uid, .. } => index_uids.push(index_uid),
         K::IndexUpdate { index_uid, .. } => index_uids.push(index_uid),
+        K::IndexCompaction { index_uid } => index_uids.push(index_uid),
+        K::IndexOptimization { index_uid } => index_uids.push(index_uid),
         K::IndexSwap { swaps } => {
             for IndexSwap { indexes: (lhs, rhs) } in swaps.iter_mut() {
                 if lhs == swap.0 || lhs == swap.1 {
@@ -532,6 +534,18 @@ impl IndexScheduler {
                     Details::Dump { dump_uid: _ } => {
                         assert_eq!(kind.as_kind(), Kind::DumpCreation);
                     }
+                    Details::IndexCompaction { removed_vector_points } => {
+                        assert_eq!(kind.as_kind(), Kind::IndexCompaction);
+                        if removed_vector_points.is_some() {
+                            assert!(matches!(status, Status::Succeeded | Status::Failed));
+                        }
+                    }
+                    Details::IndexOptimization { performed_steps } => {
+                        assert_eq!(kind.as_kind(), Kind::IndexOptimization);
+                        if performed_steps.is_some() {
+                            assert!(matches!(status, Status::Succeeded | Status::Failed));
+                        }
+                    }
                 }
             }
 
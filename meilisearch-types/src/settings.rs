@@ -9,7 +9,7 @@ use std::str::FromStr;
 use deserr::{DeserializeError, Deserr, ErrorKind, MergeWithError, ValuePointerRef};
 use fst::IntoStreamer;
 use milli::update::Setting;
-use milli::{Criterion, CriterionError, Index, DEFAULT_VALUES_PER_FACET};
+use milli::{Criterion, CriterionError, Index, LocalizedAttributesRule, DEFAULT_VALUES_PER_FACET};
 use serde::{Deserialize, Serialize, Serializer};
 
 use crate::deserr::DeserrJsonError;
@@ -117,6 +117,29 @@ pub struct PaginationSettings {
     pub max_total_hits: Setting<usize>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Deserr)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[deserr(rename_all = camelCase, deny_unknown_fields)]
+pub struct LocalizedAttributesRuleView {
+    pub attribute_patterns: Vec<String>,
+    /// The ISO 639-3 codes of the languages, e.g. `jpn` or `cmn`.
+    pub locales: Vec<String>,
+}
+
+impl From<LocalizedAttributesRule> for LocalizedAttributesRuleView {
+    fn from(value: LocalizedAttributesRule) -> Self {
+        let LocalizedAttributesRule { attribute_patterns, locales } = value;
+        LocalizedAttributesRuleView { attribute_patterns, locales }
+    }
+}
+
+impl From<LocalizedAttributesRuleView> for LocalizedAttributesRule {
+    fn from(value: LocalizedAttributesRuleView) -> Self {
+        let LocalizedAttributesRuleView { attribute_patterns, locales } = value;
+        LocalizedAttributesRule { attribute_patterns, locales }
+    }
+}
+
 impl MergeWithError<milli::CriterionError> for DeserrJsonError<InvalidSettingsRankingRules> {
     fn merge(
         _self_: Option<Self>,
@@ -185,6 +208,9 @@ pub struct Settings<T> {
     #[serde(default, skip_serializing_if = "Setting::is_not_set")]
     #[deserr(default, error = DeserrJsonError<InvalidSettingsPagination>)]
     pub pagination: Setting<PaginationSettings>,
+    #[serde(default, skip_serializing_if = "Setting::is_not_set")]
+    #[deserr(default, error = DeserrJsonError<InvalidSettingsLocalizedAttributes>)]
+    pub localized_attributes: Setting<Vec<LocalizedAttributesRuleView>>,
 
     #[serde(skip)]
     #[deserr(skip)]
@@ -205,6 +231,7 @@ impl Settings<Checked> {
             typo_tolerance: Setting::Reset,
             faceting: Setting::Reset,
             pagination: Setting::Reset,
+            localized_attributes: Setting::Reset,
             _kind: PhantomData,
         }
     }
@@ -222,6 +249,7 @@ impl Settings<Checked> {
             typo_tolerance,
             faceting,
             pagination,
+            localized_attributes,
             ..
         } = self;
 
@@ -237,6 +265,7 @@ impl Settings<Checked> {
             typo_tolerance,
             faceting,
             pagination,
+            localized_attributes,
             _kind: PhantomData,
         }
     }
@@ -278,6 +307,7 @@ impl Settings<Unchecked> {
             typo_tolerance: self.typo_tolerance,
             faceting: self.faceting,
             pagination: self.pagination,
+            localized_attributes: self.localized_attributes,
             _kind: PhantomData,
         }
     }
@@ -433,6 +463,14 @@ pub fn apply_settings_to_builder(
         Setting::Reset => builder.reset_pagination_max_total_hits(),
         Setting::NotSet => (),
     }
+
+    match settings.localized_attributes {
+        Setting::Set(ref rules) => builder.set_localized_attributes_rules(
+            rules.iter().cloned().map(LocalizedAttributesRule::from).collect(),
+        ),
+        Setting::Reset => builder.reset_localized_attributes_rules(),
+        Setting::NotSet => (),
+    }
 }
 
 pub fn settings(
@@ -507,6 +545,12 @@ pub fn settings(
         ),
     };
 
+    let localized_attributes = index
+        .localized_attributes_rules(rtxn)?
+        .into_iter()
+        .map(LocalizedAttributesRuleView::from)
+        .collect();
+
     Ok(Settings {
         displayed_attributes: match displayed_attributes {
             Some(attrs) => Setting::Set(attrs),
@@ -528,6 +572,7 @@ pub fn settings(
         typo_tolerance: Setting::Set(typo_tolerance),
         faceting: Setting::Set(faceting),
         pagination: Setting::Set(pagination),
+        localized_attributes: Setting::Set(localized_attributes),
         _kind: PhantomData,
     })
 }
@@ -550,6 +595,9 @@ pub enum RankingRuleView {
     Sort,
     /// Sorted by the similarity of the matched words with the query words.
     Exactness,
+    /// Documents with query words appearing in the same order as in the query
+    /// are considered better than documents where they are reversed.
+    Order,
     /// Sorted by the increasing value of the field specified.
     Asc(String),
     /// Sorted by the decreasing value of the field specified.
@@ -608,6 +656,7 @@ impl From<Criterion> for RankingRuleView {
             Criterion::Attribute => RankingRuleView::Attribute,
             Criterion::Sort => RankingRuleView::Sort,
             Criterion::Exactness => RankingRuleView::Exactness,
+            Criterion::Order => RankingRuleView::Order,
             Criterion::Asc(x) => RankingRuleView::Asc(x),
             Criterion::Desc(x) => RankingRuleView::Desc(x),
         }
@@ -622,6 +671,7 @@ impl From<RankingRuleView> for Criterion {
             RankingRuleView::Attribute => Criterion::Attribute,
             RankingRuleView::Sort => Criterion::Sort,
             RankingRuleView::Exactness => Criterion::Exactness,
+            RankingRuleView::Order => Criterion::Order,
             RankingRuleView::Asc(x) => Criterion::Asc(x),
             RankingRuleView::Desc(x) => Criterion::Desc(x),
         }
@@ -647,6 +697,7 @@ pub(crate) mod test {
             typo_tolerance: Setting::NotSet,
             faceting: Setting::NotSet,
             pagination: Setting::NotSet,
+            localized_attributes: Setting::NotSet,
             _kind: PhantomData::<Unchecked>,
         };
 
@@ -668,6 +719,7 @@ pub(crate) mod test {
             typo_tolerance: Setting::NotSet,
             faceting: Setting::NotSet,
             pagination: Setting::NotSet,
+            localized_attributes: Setting::NotSet,
             _kind: PhantomData::<Unchecked>,
         };
 
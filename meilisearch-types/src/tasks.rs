@@ -5,6 +5,7 @@ use std::str::FromStr;
 
 use enum_iterator::Sequence;
 use milli::update::IndexDocumentsMethod;
+use milli::OptimizationStep;
 use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize, Serializer};
 use time::{Duration, OffsetDateTime};
@@ -54,6 +55,8 @@ impl Task {
             | SettingsUpdate { index_uid, .. }
             | IndexCreation { index_uid, .. }
             | IndexUpdate { index_uid, .. }
+            | IndexCompaction { index_uid }
+            | IndexOptimization { index_uid }
             | IndexDeletion { index_uid } => Some(index_uid),
         }
     }
@@ -74,6 +77,8 @@ impl Task {
             | KindWithContent::IndexDeletion { .. }
             | KindWithContent::IndexCreation { .. }
             | KindWithContent::IndexUpdate { .. }
+            | KindWithContent::IndexCompaction { .. }
+            | KindWithContent::IndexOptimization { .. }
             | KindWithContent::IndexSwap { .. }
             | KindWithContent::TaskCancelation { .. }
             | KindWithContent::TaskDeletion { .. }
@@ -122,6 +127,12 @@ pub enum KindWithContent {
         index_uid: String,
         primary_key: Option<String>,
     },
+    IndexCompaction {
+        index_uid: String,
+    },
+    IndexOptimization {
+        index_uid: String,
+    },
     IndexSwap {
         swaps: Vec<IndexSwap>,
     },
@@ -157,6 +168,8 @@ impl KindWithContent {
             KindWithContent::IndexCreation { .. } => Kind::IndexCreation,
             KindWithContent::IndexDeletion { .. } => Kind::IndexDeletion,
             KindWithContent::IndexUpdate { .. } => Kind::IndexUpdate,
+            KindWithContent::IndexCompaction { .. } => Kind::IndexCompaction,
+            KindWithContent::IndexOptimization { .. } => Kind::IndexOptimization,
             KindWithContent::IndexSwap { .. } => Kind::IndexSwap,
             KindWithContent::TaskCancelation { .. } => Kind::TaskCancelation,
             KindWithContent::TaskDeletion { .. } => Kind::TaskDeletion,
@@ -180,6 +193,8 @@ impl KindWithContent {
             | SettingsUpdate { index_uid, .. }
             | IndexCreation { index_uid, .. }
             | IndexUpdate { index_uid, .. }
+            | IndexCompaction { index_uid }
+            | IndexOptimization { index_uid }
             | IndexDeletion { index_uid } => vec![index_uid],
             IndexSwap { swaps } => {
                 let mut indexes = HashSet::<&str>::default();
@@ -224,6 +239,12 @@ impl KindWithContent {
             | KindWithContent::IndexUpdate { primary_key, .. } => {
                 Some(Details::IndexInfo { primary_key: primary_key.clone() })
             }
+            KindWithContent::IndexCompaction { .. } => {
+                Some(Details::IndexCompaction { removed_vector_points: None })
+            }
+            KindWithContent::IndexOptimization { .. } => {
+                Some(Details::IndexOptimization { performed_steps: None })
+            }
             KindWithContent::IndexSwap { swaps } => {
                 Some(Details::IndexSwap { swaps: swaps.clone() })
             }
@@ -273,6 +294,12 @@ impl KindWithContent {
             | KindWithContent::IndexUpdate { primary_key, .. } => {
                 Some(Details::IndexInfo { primary_key: primary_key.clone() })
             }
+            KindWithContent::IndexCompaction { .. } => {
+                Some(Details::IndexCompaction { removed_vector_points: Some(0) })
+            }
+            KindWithContent::IndexOptimization { .. } => {
+                Some(Details::IndexOptimization { performed_steps: Some(Vec::new()) })
+            }
             KindWithContent::IndexSwap { .. } => {
                 todo!()
             }
@@ -314,6 +341,12 @@ impl From<&KindWithContent> for Option<Details> {
             KindWithContent::IndexUpdate { primary_key, .. } => {
                 Some(Details::IndexInfo { primary_key: primary_key.clone() })
             }
+            KindWithContent::IndexCompaction { .. } => {
+                Some(Details::IndexCompaction { removed_vector_points: None })
+            }
+            KindWithContent::IndexOptimization { .. } => {
+                Some(Details::IndexOptimization { performed_steps: None })
+            }
             KindWithContent::IndexSwap { .. } => None,
             KindWithContent::TaskCancelation { query, tasks } => Some(Details::TaskCancelation {
                 matched_tasks: tasks.len(),
@@ -404,6 +437,9 @@ pub enum Kind {
     TaskDeletion,
     DumpCreation,
     SnapshotCreation,
+    // Must stay the last variants, the kinds are stored by index in the task queue.
+    IndexCompaction,
+    IndexOptimization,
 }
 
 impl Kind {
@@ -414,7 +450,9 @@ impl Kind {
             | Kind::SettingsUpdate
             | Kind::IndexCreation
             | Kind::IndexDeletion
-            | Kind::IndexUpdate => true,
+            | Kind::IndexUpdate
+            | Kind::IndexCompaction
+            | Kind::IndexOptimization => true,
             Kind::IndexSwap
             | Kind::TaskCancelation
             | Kind::TaskDeletion
@@ -432,6 +470,8 @@ impl Display for Kind {
             Kind::IndexCreation => write!(f, "indexCreation"),
             Kind::IndexDeletion => write!(f, "indexDeletion"),
             Kind::IndexUpdate => write!(f, "indexUpdate"),
+            Kind::IndexCompaction => write!(f, "indexCompaction"),
+            Kind::IndexOptimization => write!(f, "indexOptimization"),
             Kind::IndexSwap => write!(f, "indexSwap"),
             Kind::TaskCancelation => write!(f, "taskCancelation"),
             Kind::TaskDeletion => write!(f, "taskDeletion"),
@@ -448,6 +488,10 @@ impl FromStr for Kind {
             Ok(Kind::IndexCreation)
         } else if kind.eq_ignore_ascii_case("indexUpdate") {
             Ok(Kind::IndexUpdate)
+        } else if kind.eq_ignore_ascii_case("indexCompaction") {
+            Ok(Kind::IndexCompaction)
+        } else if kind.eq_ignore_ascii_case("indexOptimization") {
+            Ok(Kind::IndexOptimization)
         } else if kind.eq_ignore_ascii_case("indexSwap") {
             Ok(Kind::IndexSwap)
         } else if kind.eq_ignore_ascii_case("indexDeletion") {
@@ -498,6 +542,8 @@ pub enum Details {
     DocumentAdditionOrUpdate { received_documents: u64, indexed_documents: Option<u64> },
     SettingsUpdate { settings: Box<Settings<Unchecked>> },
     IndexInfo { primary_key: Option<String> },
+    IndexCompaction { removed_vector_points: Option<u64> },
+    IndexOptimization { performed_steps: Option<Vec<OptimizationStep>> },
     DocumentDeletion { provided_ids: usize, deleted_documents: Option<u64> },
     DocumentDeletionByFilter { original_filter: String, deleted_documents: Option<u64> },
     ClearAll { deleted_documents: Option<u64> },
@@ -519,6 +565,8 @@ impl Details {
                 *deleted_documents = Some(0)
             }
             Self::ClearAll { deleted_documents } => *deleted_documents = Some(0),
+            Self::IndexCompaction { removed_vector_points } => *removed_vector_points = Some(0),
+            Self::IndexOptimization { performed_steps } => *performed_steps = Some(Vec::new()),
             Self::TaskCancelation { canceled_tasks, .. } => *canceled_tasks = Some(0),
             Self::TaskDeletion { deleted_tasks, .. } => *deleted_tasks = Some(0),
             Self::SettingsUpdate { .. }
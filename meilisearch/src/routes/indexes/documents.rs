@@ -569,9 +569,10 @@ fn retrieve_documents<S: AsRef<str>>(
 
     let candidates = if let Some(filter) = filter {
         filter.evaluate(&rtxn, index).map_err(|err| match err {
-            milli::Error::UserError(milli::UserError::InvalidFilter(_)) => {
-                ResponseError::from_msg(err.to_string(), Code::InvalidDocumentFilter)
-            }
+            milli::Error::UserError(
+                milli::UserError::InvalidFilter(_)
+                | milli::UserError::InvalidFilterableAttribute { .. },
+            ) => ResponseError::from_msg(err.to_string(), Code::InvalidDocumentFilter),
             e => e.into(),
         })?
     } else {
@@ -210,6 +210,8 @@ pub enum MatchingStrategy {
     Last,
     /// All query words are mandatory
     All,
+    /// Remove the query words contained in the fewest documents first
+    Frequency,
 }
 
 impl Default for MatchingStrategy {
@@ -223,6 +225,7 @@ impl From<MatchingStrategy> for TermsMatchingStrategy {
         match other {
             MatchingStrategy::Last => Self::Last,
             MatchingStrategy::All => Self::All,
+            MatchingStrategy::Frequency => Self::Frequency,
         }
     }
 }
@@ -486,6 +489,12 @@ pub fn perform_search(
     let mut tokenizer_builder = TokenizerBuilder::default();
     tokenizer_builder.create_char_map(true);
 
+    let settings = index.cached_settings(&rtxn)?;
+    let dictionary = settings.words_dict();
+    if let Some(ref dictionary) = dictionary {
+        tokenizer_builder.words_dict(dictionary);
+    }
+
     let script_lang_map = index.script_language(&rtxn)?;
     if !script_lang_map.is_empty() {
         tokenizer_builder.allow_list(&script_lang_map);
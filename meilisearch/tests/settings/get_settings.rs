@@ -32,6 +32,7 @@ static DEFAULT_SETTINGS_VALUES: Lazy<HashMap<&'static str, Value>> = Lazy::new(|
             "maxTotalHits": json!(1000),
         }),
     );
+    map.insert("localized_attributes", json!([]));
     map
 });
 
@@ -51,7 +52,7 @@ async fn get_settings() {
     let (response, code) = index.settings().await;
     assert_eq!(code, 200);
     let settings = response.as_object().unwrap();
-    assert_eq!(settings.keys().len(), 11);
+    assert_eq!(settings.keys().len(), 12);
     assert_eq!(settings["displayedAttributes"], json!(["*"]));
     assert_eq!(settings["searchableAttributes"], json!(["*"]));
     assert_eq!(settings["filterableAttributes"], json!([]));
@@ -77,6 +78,7 @@ async fn get_settings() {
             "maxTotalHits": 1000,
         })
     );
+    assert_eq!(settings["localizedAttributes"], json!([]));
 }
 
 #[actix_rt::test]
@@ -275,7 +277,8 @@ test_setting_routes!(
     ranking_rules put,
     synonyms put,
     pagination patch,
-    faceting patch
+    faceting patch,
+    localized_attributes put
 );
 
 #[actix_rt::test]
@@ -288,7 +291,7 @@ async fn error_set_invalid_ranking_rules() {
     meili_snap::snapshot!(code, @"400 Bad Request");
     meili_snap::snapshot!(meili_snap::json_string!(response), @r###"
     {
-      "message": "Invalid value at `.rankingRules[0]`: `manyTheFish` ranking rule is invalid. Valid ranking rules are words, typo, sort, proximity, attribute, exactness and custom ranking rules.",
+      "message": "Invalid value at `.rankingRules[0]`: `manyTheFish` ranking rule is invalid. Valid ranking rules are words, typo, sort, proximity, attribute, exactness, order and custom ranking rules.",
       "code": "invalid_settings_ranking_rules",
       "type": "invalid_request",
       "link": "https://docs.meilisearch.com/errors#invalid_settings_ranking_rules"
@@ -316,3 +319,23 @@ async fn set_and_reset_distinct_attribute_with_dedicated_route() {
 
     assert_eq!(response, json!(null));
 }
+
+#[actix_rt::test]
+async fn set_and_reset_localized_attributes() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    let rules = json!([{ "attributePatterns": ["title_ja"], "locales": ["jpn"] }]);
+    let (_response, code) = index.update_settings(json!({ "localizedAttributes": rules })).await;
+    assert_eq!(code, 202);
+    index.wait_task(0).await;
+
+    let (response, _) = index.settings().await;
+    assert_eq!(response["localizedAttributes"], rules);
+
+    index.update_settings(json!({ "localizedAttributes": null })).await;
+    index.wait_task(1).await;
+
+    let (response, _) = index.settings().await;
+    assert_eq!(response["localizedAttributes"], json!([]));
+}
@@ -0,0 +1,50 @@
+/*
+ * C bindings to embed the Meilisearch engine.
+ *
+ * The documents, the settings, the search queries and the search results are
+ * NUL-terminated JSON strings. The strings returned by the library must be
+ * released with `milli_string_free`. A function that fails returns NULL, or
+ * false, and its error message can be read with `milli_last_error`.
+ */
+
+#ifndef MILLI_H
+#define MILLI_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct MilliIndex MilliIndex;
+
+/* Opens or creates the index stored in the directory, map_size is in bytes. */
+MilliIndex *milli_index_open(const char *path, size_t map_size);
+
+/* Closes the index, does nothing if it is NULL. */
+void milli_index_close(MilliIndex *index);
+
+/* Adds or replaces a JSON array of documents, returns
+ * {"indexedDocuments": ..., "numberOfDocuments": ...}. */
+char *milli_index_add_documents(MilliIndex *index, const char *documents);
+
+/* Updates the settings, e.g. {"filterableAttributes": ["genre"]},
+ * a null value resets a setting. */
+bool milli_index_update_settings(MilliIndex *index, const char *settings);
+
+/* Executes a search query, e.g. {"query": "dog", "filter": "genre = tale", "limit": 10},
+ * returns {"hits": [...], "estimatedTotalHits": ..., "degraded": ...}. */
+char *milli_index_search(MilliIndex *index, const char *query);
+
+/* Returns the message of the last error of the thread, NULL if the last call succeeded. */
+const char *milli_last_error(void);
+
+/* Releases a string returned by the library, does nothing if it is NULL. */
+void milli_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MILLI_H */
@@ -0,0 +1,464 @@
+//! C bindings to embed milli in applications that are not written in Rust.
+//!
+//! The documents, the settings, the search queries and the search results are exchanged as
+//! NUL-terminated JSON strings. The strings returned by these functions are owned by the
+//! caller and must be released with [`milli_string_free`]. A function that fails returns a
+//! null pointer, or `false`, and its error message can be read with [`milli_last_error`].
+//!
+//! An index can be moved between threads but its functions must not be called concurrently.
+//! The declarations of the functions are in the `include/milli.h` header.
+//!
+//! The `python` feature adds a Python module built on the same functions, see `src/python.rs`.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::io::Cursor;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use milli::heed::EnvOpenOptions;
+use milli::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Setting, Settings};
+use milli::{obkv_to_json, Criterion, Index, Object, SearchQuery, SearchResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "python")]
+mod python;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// An index opened with [`milli_index_open`].
+pub struct MilliIndex {
+    index: Index,
+    indexer_config: IndexerConfig,
+}
+
+impl MilliIndex {
+    fn open(path: &str, map_size: usize) -> Result<MilliIndex, Error> {
+        std::fs::create_dir_all(path).map_err(milli::Error::from)?;
+        let mut options = EnvOpenOptions::new();
+        options.map_size(map_size);
+        let index = Index::new(options, path)?;
+        Ok(MilliIndex { index, indexer_config: IndexerConfig::default() })
+    }
+
+    fn add_documents(&self, documents: &str) -> Result<DocumentAdditionResponse, Error> {
+        let MilliIndex { index, indexer_config } = self;
+
+        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        builder.append_json_array(documents.as_bytes()).map_err(|error| match error {
+            milli::documents::Error::Json(error) => Error::InvalidJson("documents", error),
+            error => Error::Milli(error.into()),
+        })?;
+        let documents = builder.into_inner().map_err(milli::Error::from)?;
+        let documents = DocumentsBatchReader::from_reader(Cursor::new(documents))
+            .map_err(milli::Error::from)?;
+
+        let mut wtxn = index.write_txn()?;
+        let builder = IndexDocuments::new(
+            &mut wtxn,
+            index,
+            indexer_config,
+            IndexDocumentsConfig::default(),
+            |_| (),
+            || false,
+        )?;
+        let (builder, user_result) = builder.add_documents(documents)?;
+        user_result.map_err(milli::Error::from)?;
+        let result = builder.execute()?;
+        wtxn.commit()?;
+
+        Ok(DocumentAdditionResponse {
+            indexed_documents: result.indexed_documents,
+            number_of_documents: result.number_of_documents,
+        })
+    }
+
+    fn update_settings(&self, settings: SettingsUpdate) -> Result<(), Error> {
+        let MilliIndex { index, indexer_config } = self;
+        let mut wtxn = index.write_txn()?;
+        let mut builder = Settings::new(&mut wtxn, index, indexer_config);
+        settings.apply(&mut builder)?;
+        builder.execute(|_| (), || false)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    fn search(&self, query: SearchQuery) -> Result<SearchResponse, Error> {
+        let index = &self.index;
+        let rtxn = index.read_txn()?;
+        let SearchResult { documents_ids, candidates, degraded, .. } =
+            query.execute(index, &rtxn)?;
+
+        let fields_ids_map = index.fields_ids_map(&rtxn)?;
+        let displayed_fields = match index.displayed_fields_ids(&rtxn)? {
+            Some(fields) => fields,
+            None => fields_ids_map.ids().collect(),
+        };
+        let hits = index
+            .documents(&rtxn, documents_ids)?
+            .into_iter()
+            .map(|(_, obkv)| obkv_to_json(&displayed_fields, &fields_ids_map, obkv))
+            .collect::<milli::Result<_>>()?;
+
+        Ok(SearchResponse { hits, estimated_total_hits: candidates.len(), degraded })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("The `{0}` argument must not be null.")]
+    NullPointer(&'static str),
+    #[error("The `{0}` argument is not a valid UTF-8 string: {1}.")]
+    InvalidUtf8(&'static str, std::str::Utf8Error),
+    #[error("The `{0}` argument is not valid JSON: {1}.")]
+    InvalidJson(&'static str, serde_json::Error),
+    #[error(transparent)]
+    Milli(#[from] milli::Error),
+}
+
+impl From<milli::heed::Error> for Error {
+    fn from(error: milli::heed::Error) -> Error {
+        Error::Milli(error.into())
+    }
+}
+
+/// The settings accepted by [`milli_index_update_settings`], a `null` value resets a setting.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+struct SettingsUpdate {
+    primary_key: Setting<String>,
+    searchable_attributes: Setting<Vec<String>>,
+    displayed_attributes: Setting<Vec<String>>,
+    filterable_attributes: Setting<HashSet<String>>,
+    sortable_attributes: Setting<HashSet<String>>,
+    ranking_rules: Setting<Vec<String>>,
+    stop_words: Setting<BTreeSet<String>>,
+    synonyms: Setting<HashMap<String, Vec<String>>>,
+    distinct_attribute: Setting<String>,
+}
+
+impl SettingsUpdate {
+    fn apply(self, builder: &mut Settings) -> Result<(), Error> {
+        match self.primary_key {
+            Setting::Set(primary_key) => builder.set_primary_key(primary_key),
+            Setting::Reset => builder.reset_primary_key(),
+            Setting::NotSet => (),
+        }
+        match self.searchable_attributes {
+            Setting::Set(fields) => builder.set_searchable_fields(fields),
+            Setting::Reset => builder.reset_searchable_fields(),
+            Setting::NotSet => (),
+        }
+        match self.displayed_attributes {
+            Setting::Set(fields) => builder.set_displayed_fields(fields),
+            Setting::Reset => builder.reset_displayed_fields(),
+            Setting::NotSet => (),
+        }
+        match self.filterable_attributes {
+            Setting::Set(fields) => builder.set_filterable_fields(fields),
+            Setting::Reset => builder.reset_filterable_fields(),
+            Setting::NotSet => (),
+        }
+        match self.sortable_attributes {
+            Setting::Set(fields) => builder.set_sortable_fields(fields),
+            Setting::Reset => builder.reset_sortable_fields(),
+            Setting::NotSet => (),
+        }
+        match self.ranking_rules {
+            Setting::Set(rules) => {
+                let criteria = rules
+                    .iter()
+                    .map(|rule| rule.parse())
+                    .collect::<Result<Vec<Criterion>, _>>()
+                    .map_err(milli::Error::from)?;
+                builder.set_criteria(criteria)
+            }
+            Setting::Reset => builder.reset_criteria(),
+            Setting::NotSet => (),
+        }
+        match self.stop_words {
+            Setting::Set(stop_words) => builder.set_stop_words(stop_words),
+            Setting::Reset => builder.reset_stop_words(),
+            Setting::NotSet => (),
+        }
+        match self.synonyms {
+            Setting::Set(synonyms) => builder.set_synonyms(synonyms),
+            Setting::Reset => builder.reset_synonyms(),
+            Setting::NotSet => (),
+        }
+        match self.distinct_attribute {
+            Setting::Set(field) => builder.set_distinct_field(field),
+            Setting::Reset => builder.reset_distinct_field(),
+            Setting::NotSet => (),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentAdditionResponse {
+    indexed_documents: u64,
+    number_of_documents: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    hits: Vec<Object>,
+    estimated_total_hits: u64,
+    degraded: bool,
+}
+
+/// Opens the index stored in the given directory, it is created if it doesn't exist.
+///
+/// The `map_size` is the maximum size of the index on disk, in bytes, it must be a multiple
+/// of the OS page size. Returns null on error.
+///
+/// # Safety
+///
+/// `path` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn milli_index_open(path: *const c_char, map_size: usize) -> *mut MilliIndex {
+    with_last_error(ptr::null_mut(), || {
+        let index = MilliIndex::open(str_arg("path", path)?, map_size)?;
+        Ok(Box::into_raw(Box::new(index)))
+    })
+}
+
+/// Closes an index opened with [`milli_index_open`], does nothing if the index is null.
+///
+/// # Safety
+///
+/// `index` must be null or an index returned by [`milli_index_open`] that is not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn milli_index_close(index: *mut MilliIndex) {
+    if !index.is_null() {
+        drop(Box::from_raw(index));
+    }
+}
+
+/// Adds or replaces the documents of a JSON array of objects.
+///
+/// Returns the `indexedDocuments` and the `numberOfDocuments` in the index as a JSON
+/// object, or null on error.
+///
+/// # Safety
+///
+/// `index` must be null or an open index and `documents` must be null or a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn milli_index_add_documents(
+    index: *mut MilliIndex,
+    documents: *const c_char,
+) -> *mut c_char {
+    with_last_error(ptr::null_mut(), || {
+        let index = index_arg(index)?;
+        let documents = str_arg("documents", documents)?;
+        Ok(json_output(&index.add_documents(documents)?))
+    })
+}
+
+/// Updates the settings given as a JSON object, the documents are reindexed if needed.
+///
+/// The supported settings are `primaryKey`, `searchableAttributes`, `displayedAttributes`,
+/// `filterableAttributes`, `sortableAttributes`, `rankingRules`, `stopWords`, `synonyms`
+/// and `distinctAttribute`. Returns false on error.
+///
+/// # Safety
+///
+/// `index` must be null or an open index and `settings` must be null or a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn milli_index_update_settings(
+    index: *mut MilliIndex,
+    settings: *const c_char,
+) -> bool {
+    with_last_error(false, || {
+        let index = index_arg(index)?;
+        index.update_settings(json_arg("settings", settings)?)?;
+        Ok(true)
+    })
+}
+
+/// Executes a search query, given as a JSON serialized [`SearchQuery`].
+///
+/// Returns the displayed fields of the `hits` and the `estimatedTotalHits` as a JSON
+/// object, or null on error.
+///
+/// # Safety
+///
+/// `index` must be null or an open index and `query` must be null or a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn milli_index_search(
+    index: *mut MilliIndex,
+    query: *const c_char,
+) -> *mut c_char {
+    with_last_error(ptr::null_mut(), || {
+        let index = index_arg(index)?;
+        let query: SearchQuery = json_arg("query", query)?;
+        Ok(json_output(&index.search(query)?))
+    })
+}
+
+/// Returns the message of the last error of the calling thread, or null if the last call
+/// succeeded. The message is valid until the next call to a function of this library.
+#[no_mangle]
+pub extern "C" fn milli_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |error| error.as_ptr()))
+}
+
+/// Releases a string returned by this library, does nothing if the string is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this library that is not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn milli_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Executes the function, stores its error, if any, as the last error of the thread and
+/// returns the default value instead. The panics must not unwind into the foreign code.
+fn with_last_error<T>(default: T, f: impl FnOnce() -> Result<T, Error>) -> T {
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            LAST_ERROR.with(|error| error.borrow_mut().take());
+            return value;
+        }
+        Ok(Err(error)) => error.to_string(),
+        Err(_) => String::from("The engine panicked."),
+    };
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+    default
+}
+
+unsafe fn index_arg<'a>(index: *mut MilliIndex) -> Result<&'a MilliIndex, Error> {
+    index.as_ref().ok_or(Error::NullPointer("index"))
+}
+
+unsafe fn str_arg<'a>(name: &'static str, string: *const c_char) -> Result<&'a str, Error> {
+    if string.is_null() {
+        return Err(Error::NullPointer(name));
+    }
+    CStr::from_ptr(string).to_str().map_err(|error| Error::InvalidUtf8(name, error))
+}
+
+unsafe fn json_arg<T: DeserializeOwned>(
+    name: &'static str,
+    string: *const c_char,
+) -> Result<T, Error> {
+    serde_json::from_str(str_arg(name, string)?).map_err(|error| Error::InvalidJson(name, error))
+}
+
+fn json_output(value: &impl Serialize) -> *mut c_char {
+    let json = serde_json::to_string(value).expect("the responses are serializable");
+    // The NUL characters of the strings are escaped in JSON.
+    CString::new(json).unwrap().into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn c_string(value: Value) -> CString {
+        CString::new(value.to_string()).unwrap()
+    }
+
+    unsafe fn take_json(string: *mut c_char) -> Value {
+        assert!(!string.is_null(), "{}", last_error());
+        let value = serde_json::from_slice(CStr::from_ptr(string).to_bytes()).unwrap();
+        milli_string_free(string);
+        value
+    }
+
+    unsafe fn last_error() -> String {
+        let error = milli_last_error();
+        assert!(!error.is_null());
+        CStr::from_ptr(error).to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn index_and_search_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = CString::new(dir.path().join("index").to_str().unwrap()).unwrap();
+
+        unsafe {
+            let index = milli_index_open(path.as_ptr(), 10 * 1024 * 1024);
+            assert!(!index.is_null());
+
+            let settings = c_string(json!({
+                "primaryKey": "id",
+                "filterableAttributes": ["genre"],
+                "displayedAttributes": ["id", "title"],
+            }));
+            assert!(milli_index_update_settings(index, settings.as_ptr()));
+
+            let documents = c_string(json!([
+                { "id": 0, "title": "the quick brown fox", "genre": "tale" },
+                { "id": 1, "title": "the lazy dog", "genre": "tale" },
+                { "id": 2, "title": "a dog", "genre": "puzzle" },
+            ]));
+            let result = take_json(milli_index_add_documents(index, documents.as_ptr()));
+            assert_eq!(result, json!({ "indexedDocuments": 3, "numberOfDocuments": 3 }));
+            assert!(milli_last_error().is_null());
+
+            let query = c_string(json!({ "query": "dog", "filter": "genre = tale" }));
+            let result = take_json(milli_index_search(index, query.as_ptr()));
+            assert_eq!(
+                result,
+                json!({
+                    "hits": [{ "id": 1, "title": "the lazy dog" }],
+                    "estimatedTotalHits": 1,
+                    "degraded": false,
+                })
+            );
+
+            milli_index_close(index);
+        }
+    }
+
+    #[test]
+    fn report_the_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = CString::new(dir.path().to_str().unwrap()).unwrap();
+
+        unsafe {
+            assert!(milli_index_open(ptr::null(), 10 * 1024 * 1024).is_null());
+            assert_eq!(last_error(), "The `path` argument must not be null.");
+
+            let index = milli_index_open(path.as_ptr(), 10 * 1024 * 1024);
+            assert!(!index.is_null());
+
+            let documents = CString::new("[{ \"id\": 0,").unwrap();
+            assert!(milli_index_add_documents(index, documents.as_ptr()).is_null());
+            assert!(last_error().starts_with("The `documents` argument is not valid JSON"));
+
+            let settings = c_string(json!({ "unknownSetting": true }));
+            assert!(!milli_index_update_settings(index, settings.as_ptr()));
+            assert!(last_error().starts_with("The `settings` argument is not valid JSON"));
+
+            let query = c_string(json!({ "filter": "title = dog" }));
+            assert!(milli_index_search(index, query.as_ptr()).is_null());
+            assert!(last_error().contains("Attribute `title` is not filterable."));
+
+            let query = c_string(json!({}));
+            assert!(milli_index_search(ptr::null_mut(), query.as_ptr()).is_null());
+            assert_eq!(last_error(), "The `index` argument must not be null.");
+
+            milli_index_close(index);
+        }
+    }
+}
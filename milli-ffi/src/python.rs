@@ -0,0 +1,143 @@
+//! Python bindings, enabled by the `python` feature, to build and query indexes from notebooks.
+//!
+//! The extension module is named `milli`, it is built with `maturin build` in the directory of
+//! this crate, or by renaming the library built by `cargo build --release --features python`.
+//!
+//! ```python
+//! import milli
+//!
+//! index = milli.Index("./movies")
+//! index.update_settings({"filterableAttributes": ["genre"]})
+//! index.add_documents(dataframe.to_dict("records"))
+//! results = index.search("dog", filter="genre = tale", limit=10)
+//! ```
+//!
+//! The documents and the settings are converted with the `json` module of Python, the values
+//! it can't serialize, like the timestamps of pandas, are converted to strings. The missing
+//! values of a dataframe must be replaced by `None`, `NaN` is not a valid JSON value.
+
+use milli::{AscDesc, SearchQuery, TermsMatchingStrategy};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyString};
+use serde::Serialize;
+
+use crate::{Error, MilliIndex, SettingsUpdate};
+
+/// The default maximum size of an index on disk, in bytes.
+const DEFAULT_MAP_SIZE: usize = 2 * 1024 * 1024 * 1024;
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> PyErr {
+        match error {
+            Error::Milli(milli::Error::InternalError(_) | milli::Error::IoError(_)) => {
+                PyRuntimeError::new_err(error.to_string())
+            }
+            error => PyValueError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// An index stored in a directory, opened with `milli.Index(path, map_size=2 GiB)`.
+#[pyclass(name = "Index", module = "milli")]
+struct PyIndex(MilliIndex);
+
+#[pymethods]
+impl PyIndex {
+    #[new]
+    #[pyo3(signature = (path, map_size = DEFAULT_MAP_SIZE))]
+    fn new(py: Python, path: &str, map_size: usize) -> PyResult<PyIndex> {
+        let index = py.allow_threads(|| MilliIndex::open(path, map_size))?;
+        Ok(PyIndex(index))
+    }
+
+    /// Adds or replaces documents given as a list of dicts, e.g. `dataframe.to_dict("records")`.
+    ///
+    /// Returns the `indexedDocuments` and the `numberOfDocuments` in the index as a dict.
+    fn add_documents(&self, py: Python, documents: &PyAny) -> PyResult<PyObject> {
+        let documents = dumps(py, documents)?;
+        let response = py.allow_threads(|| self.0.add_documents(&documents))?;
+        loads(py, &response)
+    }
+
+    /// Updates the settings given as a dict, with the same keys as the C bindings.
+    fn update_settings(&self, py: Python, settings: &PyDict) -> PyResult<()> {
+        let settings: SettingsUpdate = serde_json::from_str(&dumps(py, settings)?)
+            .map_err(|error| Error::InvalidJson("settings", error))?;
+        py.allow_threads(|| self.0.update_settings(settings))?;
+        Ok(())
+    }
+
+    /// Returns the `hits`, the `estimatedTotalHits` and whether the search is `degraded`.
+    ///
+    /// The `sort` criteria are written like `"price:asc"` and the `matching_strategy` is
+    /// one of `"last"`, `"all"` or `"frequency"`.
+    #[pyo3(signature = (
+        query = None,
+        *,
+        filter = None,
+        sort = None,
+        offset = 0,
+        limit = 20,
+        matching_strategy = "last",
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        py: Python,
+        query: Option<String>,
+        filter: Option<String>,
+        sort: Option<Vec<String>>,
+        offset: usize,
+        limit: usize,
+        matching_strategy: &str,
+    ) -> PyResult<PyObject> {
+        let strategy = match matching_strategy {
+            "last" => TermsMatchingStrategy::Last,
+            "all" => TermsMatchingStrategy::All,
+            "frequency" => TermsMatchingStrategy::Frequency,
+            strategy => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown matching strategy `{strategy}`, expected one of `last`, `all`, `frequency`."
+                )))
+            }
+        };
+
+        let mut search =
+            SearchQuery::new().offset(offset).limit(limit).terms_matching_strategy(strategy);
+        if let Some(query) = query {
+            search = search.query(query);
+        }
+        if let Some(filter) = filter {
+            search = search.filter(filter);
+        }
+        if let Some(sort) = sort {
+            let criteria = sort
+                .iter()
+                .map(|criterion| criterion.parse::<AscDesc>())
+                .collect::<Result<_, _>>()
+                .map_err(|error| Error::Milli(milli::CriterionError::from(error).into()))?;
+            search = search.sort_criteria(criteria);
+        }
+
+        let response = py.allow_threads(|| self.0.search(search))?;
+        loads(py, &response)
+    }
+}
+
+/// Serializes a Python object in JSON, the values that are not serializable become strings.
+fn dumps(py: Python, value: &PyAny) -> PyResult<String> {
+    let kwargs = [("default", py.get_type::<PyString>())].into_py_dict(py);
+    py.import("json")?.call_method("dumps", (value,), Some(kwargs))?.extract()
+}
+
+fn loads(py: Python, value: &impl Serialize) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).expect("the responses are serializable");
+    Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+}
+
+#[pymodule]
+#[pyo3(name = "milli")]
+fn python_module(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyIndex>()
+}
@@ -0,0 +1,203 @@
+//! Imports the documents of a Tantivy index into a milli index, to ease the migrations to this
+//! engine. The documents are indexed in batches by the update pipeline, like any other documents.
+//!
+//! Only the stored fields are imported, the other fields can't be rebuilt from the inverted
+//! index of Tantivy. A field with a single value is imported as this value, a field with many
+//! values as an array. The deleted documents of the Tantivy index are skipped.
+//!
+//! The `sql` feature also imports the rows of a SQL query, see the [`sql`] module.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use milli::update::{DocumentAdditionResult, IndexDocuments, IndexDocumentsConfig, IndexerConfig};
+use milli::{Index, Object};
+use serde_json::Value;
+use tantivy::schema::{NamedFieldDocument, Schema};
+
+#[cfg(feature = "sql")]
+pub mod sql;
+
+/// The number of blocks of documents cached by the readers of the stored fields.
+const STORE_CACHE_BLOCKS: usize = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid Tantivy index: {0}.")]
+    Tantivy(#[from] tantivy::TantivyError),
+    #[error("Invalid stored value: {0}.")]
+    InvalidValue(#[from] serde_json::Error),
+    #[cfg(feature = "sql")]
+    #[error("SQL error: {0}.")]
+    Sql(#[from] sqlx::Error),
+    #[cfg(feature = "sql")]
+    #[error("The `{column}` column has the unsupported `{type_name}` type.")]
+    UnsupportedColumnType { column: String, type_name: String },
+    #[error(transparent)]
+    Milli(#[from] milli::Error),
+}
+
+impl From<milli::heed::Error> for Error {
+    fn from(error: milli::heed::Error) -> Error {
+        Error::Milli(error.into())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportStats {
+    /// The number of documents read from the Tantivy index.
+    pub imported_documents: u64,
+    /// The number of documents in the milli index at the end of the import.
+    pub number_of_documents: u64,
+}
+
+impl ImportStats {
+    fn add(&mut self, result: DocumentAdditionResult) {
+        self.imported_documents += result.indexed_documents;
+        self.number_of_documents = result.number_of_documents;
+    }
+}
+
+/// Imports the documents of the Tantivy index stored in the given directory, they are indexed
+/// in batches of `batch_size` documents.
+pub fn import_tantivy_index(
+    tantivy_path: &Path,
+    index: &Index,
+    indexer_config: &IndexerConfig,
+    batch_size: usize,
+) -> Result<ImportStats, Error> {
+    let tantivy_index = tantivy::Index::open_in_dir(tantivy_path)?;
+    let schema = tantivy_index.schema();
+    let searcher = tantivy_index.reader()?.searcher();
+
+    let mut stats = ImportStats::default();
+    let mut batch = DocumentsBatchBuilder::new(Vec::new());
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader
+            .get_store_reader(STORE_CACHE_BLOCKS)
+            .map_err(tantivy::TantivyError::from)?;
+        for document in store_reader.iter(segment_reader.alive_bitset()) {
+            let object = tantivy_document_to_object(&schema, &document?)?;
+            batch.append_json_object(&object).map_err(milli::Error::from)?;
+            if batch.documents_count() as usize >= batch_size {
+                let batch = std::mem::replace(&mut batch, DocumentsBatchBuilder::new(Vec::new()));
+                stats.add(index_batch(index, indexer_config, batch)?);
+            }
+        }
+    }
+    if batch.documents_count() > 0 {
+        stats.add(index_batch(index, indexer_config, batch)?);
+    }
+
+    Ok(stats)
+}
+
+fn tantivy_document_to_object(
+    schema: &Schema,
+    document: &tantivy::Document,
+) -> Result<Object, Error> {
+    let NamedFieldDocument(fields) = schema.to_named_doc(document);
+    let mut object = Object::new();
+    for (name, values) in fields {
+        let value = if let [value] = values.as_slice() {
+            serde_json::to_value(value)?
+        } else {
+            Value::Array(values.iter().map(serde_json::to_value).collect::<Result<_, _>>()?)
+        };
+        object.insert(name, value);
+    }
+    Ok(object)
+}
+
+fn index_batch(
+    index: &Index,
+    indexer_config: &IndexerConfig,
+    batch: DocumentsBatchBuilder<Vec<u8>>,
+) -> Result<DocumentAdditionResult, Error> {
+    let documents = batch.into_inner().map_err(milli::Error::from)?;
+    let documents =
+        DocumentsBatchReader::from_reader(Cursor::new(documents)).map_err(milli::Error::from)?;
+
+    let mut wtxn = index.write_txn()?;
+    let builder = IndexDocuments::new(
+        &mut wtxn,
+        index,
+        indexer_config,
+        IndexDocumentsConfig::default(),
+        |_| (),
+        || false,
+    )?;
+    let (builder, user_result) = builder.add_documents(documents)?;
+    user_result.map_err(milli::Error::from)?;
+    let result = builder.execute()?;
+    wtxn.commit()?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use milli::heed::EnvOpenOptions;
+    use milli::obkv_to_json;
+    use serde_json::json;
+    use tantivy::doc;
+    use tantivy::schema::{INDEXED, STORED, TEXT};
+
+    use super::*;
+
+    #[test]
+    fn import_the_stored_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let tantivy_path = dir.path().join("tantivy");
+        std::fs::create_dir(&tantivy_path).unwrap();
+
+        let mut schema = Schema::builder();
+        let id = schema.add_u64_field("id", INDEXED | STORED);
+        let title = schema.add_text_field("title", TEXT | STORED);
+        let tag = schema.add_text_field("tag", TEXT | STORED);
+        let body = schema.add_text_field("body", TEXT);
+        let tantivy_index = tantivy::Index::create_in_dir(&tantivy_path, schema.build()).unwrap();
+        let mut writer = tantivy_index.writer(50_000_000).unwrap();
+        writer
+            .add_document(doc!(id => 0u64, title => "the quick brown fox", tag => "fox"))
+            .unwrap();
+        writer
+            .add_document(doc!(id => 1u64, title => "the lazy dog", body => "not stored"))
+            .unwrap();
+        writer
+            .add_document(doc!(id => 2u64, title => "a dog", tag => "dog", tag => "pet"))
+            .unwrap();
+        writer.add_document(doc!(id => 3u64, title => "a deleted dog")).unwrap();
+        writer.commit().unwrap();
+        writer.delete_term(tantivy::Term::from_field_u64(id, 3));
+        writer.commit().unwrap();
+
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024);
+        let index = Index::new(options, dir.path()).unwrap();
+        let config = IndexerConfig::default();
+        let stats = import_tantivy_index(&tantivy_path, &index, &config, 2).unwrap();
+        assert_eq!(stats, ImportStats { imported_documents: 3, number_of_documents: 3 });
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let fields: Vec<_> = fields_ids_map.ids().collect();
+        let mut documents: Vec<_> = index
+            .all_documents(&rtxn)
+            .unwrap()
+            .map(|result| {
+                let (_, obkv) = result.unwrap();
+                Value::Object(obkv_to_json(&fields, &fields_ids_map, obkv).unwrap())
+            })
+            .collect();
+        documents.sort_by_key(|document| document["id"].as_u64());
+        assert_eq!(
+            documents,
+            vec![
+                json!({ "id": 0, "title": "the quick brown fox", "tag": "fox" }),
+                json!({ "id": 1, "title": "the lazy dog" }),
+                json!({ "id": 2, "title": "a dog", "tag": ["dog", "pet"] }),
+            ]
+        );
+    }
+}
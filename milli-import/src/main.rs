@@ -0,0 +1,109 @@
+//! Imports the documents of an index of another search engine into a milli index, e.g.
+//! `milli-import tantivy ./tantivy-index ./index --primary-key id`, or with the `sql` feature
+//! `milli-import sql sqlite://shop.db "SELECT * FROM products" ./index`.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use milli::heed::EnvOpenOptions;
+use milli::update::{IndexerConfig, Settings};
+use milli::Index;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Opt {
+    #[command(subcommand)]
+    source: Source,
+
+    /// The maximum size of the index on disk, in bytes.
+    #[arg(long, global = true, default_value_t = 100 * 1024 * 1024 * 1024)]
+    map_size: usize,
+
+    /// The number of documents indexed at once.
+    #[arg(long, global = true, default_value_t = 100_000)]
+    batch_size: usize,
+
+    /// The primary key of the documents, it is inferred from their fields if not set.
+    #[arg(long, global = true)]
+    primary_key: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Source {
+    /// Imports the stored fields of the documents of a Tantivy index.
+    Tantivy {
+        /// The directory of the Tantivy index.
+        tantivy_index: PathBuf,
+        /// The directory of the milli index, it is created if it doesn't exist.
+        index: PathBuf,
+    },
+    /// Imports the rows returned by a query to a SQLite or Postgres database.
+    #[cfg(feature = "sql")]
+    Sql {
+        /// The URL of the database, e.g. `postgres://localhost/shop`.
+        database_url: String,
+        /// The query returning the documents, its columns are the fields of the documents.
+        query: String,
+        /// The directory of the milli index, it is created if it doesn't exist.
+        index: PathBuf,
+    },
+}
+
+impl Source {
+    fn index_path(&self) -> &PathBuf {
+        match self {
+            Source::Tantivy { index, .. } => index,
+            #[cfg(feature = "sql")]
+            Source::Sql { index, .. } => index,
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt = Opt::parse();
+    let index_path = opt.source.index_path();
+
+    std::fs::create_dir_all(index_path)
+        .with_context(|| format!("creating the index directory {}", index_path.display()))?;
+    let mut options = EnvOpenOptions::new();
+    options.map_size(opt.map_size);
+    let index = Index::new(options, index_path)?;
+    let indexer_config = IndexerConfig::default();
+
+    if let Some(primary_key) = &opt.primary_key {
+        let mut wtxn = index.write_txn()?;
+        let mut builder = Settings::new(&mut wtxn, &index, &indexer_config);
+        builder.set_primary_key(primary_key.clone());
+        builder.execute(|_| (), || false)?;
+        wtxn.commit()?;
+    }
+
+    let stats = match &opt.source {
+        Source::Tantivy { tantivy_index, .. } => milli_import::import_tantivy_index(
+            tantivy_index,
+            &index,
+            &indexer_config,
+            opt.batch_size,
+        )
+        .with_context(|| format!("importing the Tantivy index {}", tantivy_index.display()))?,
+        #[cfg(feature = "sql")]
+        Source::Sql { database_url, query, .. } => {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            let import = milli_import::sql::import_sql_query(
+                database_url,
+                query,
+                &index,
+                &indexer_config,
+                opt.batch_size,
+            );
+            runtime.block_on(import).context("importing the rows of the query")?
+        }
+    };
+    println!(
+        "Imported {} documents, the index contains {} documents.",
+        stats.imported_documents, stats.number_of_documents
+    );
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Imports the rows returned by a SQL query, enabled by the `sql` feature.
+//!
+//! The rows are streamed from a SQLite or Postgres database and each row becomes a document
+//! whose fields are the columns of the query. The integer and float columns become numbers,
+//! and can be filtered and sorted as numbers, the boolean columns become booleans and the
+//! text columns become strings. A query must alias its columns to rename the fields.
+
+use futures_util::TryStreamExt;
+use milli::documents::DocumentsBatchBuilder;
+use milli::update::IndexerConfig;
+use milli::{Index, Object};
+use serde_json::Value;
+use sqlx::any::AnyRow;
+use sqlx::{AnyConnection, Column, Connection, Row, TypeInfo};
+
+use crate::{index_batch, Error, ImportStats};
+
+/// Imports the rows returned by the query, e.g. `SELECT id, title, price FROM products`, from
+/// the database at the given URL, e.g. `sqlite://products.db` or `postgres://localhost/shop`.
+///
+/// The rows are indexed in batches of `batch_size` documents, the indexing of a batch blocks
+/// the current thread.
+pub async fn import_sql_query(
+    database_url: &str,
+    query: &str,
+    index: &Index,
+    indexer_config: &IndexerConfig,
+    batch_size: usize,
+) -> Result<ImportStats, Error> {
+    sqlx::any::install_default_drivers();
+    let mut connection = AnyConnection::connect(database_url).await?;
+    let mut rows = sqlx::query(query).fetch(&mut connection);
+
+    let mut stats = ImportStats::default();
+    let mut batch = DocumentsBatchBuilder::new(Vec::new());
+    while let Some(row) = rows.try_next().await? {
+        batch.append_json_object(&row_to_object(&row)?).map_err(milli::Error::from)?;
+        if batch.documents_count() as usize >= batch_size {
+            let batch = std::mem::replace(&mut batch, DocumentsBatchBuilder::new(Vec::new()));
+            stats.add(index_batch(index, indexer_config, batch)?);
+        }
+    }
+    if batch.documents_count() > 0 {
+        stats.add(index_batch(index, indexer_config, batch)?);
+    }
+
+    Ok(stats)
+}
+
+fn row_to_object(row: &AnyRow) -> Result<Object, Error> {
+    let mut object = Object::new();
+    for column in row.columns() {
+        let i = column.ordinal();
+        let value = if let Ok(value) = row.try_get::<Option<i64>, _>(i) {
+            value.map(Value::from)
+        } else if let Ok(value) = row.try_get::<Option<f64>, _>(i) {
+            value.map(Value::from)
+        } else if let Ok(value) = row.try_get::<Option<bool>, _>(i) {
+            value.map(Value::from)
+        } else if let Ok(value) = row.try_get::<Option<String>, _>(i) {
+            value.map(Value::from)
+        } else {
+            return Err(Error::UnsupportedColumnType {
+                column: column.name().to_owned(),
+                type_name: column.type_info().name().to_owned(),
+            });
+        };
+        object.insert(column.name().to_owned(), value.unwrap_or(Value::Null));
+    }
+    Ok(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use milli::heed::EnvOpenOptions;
+    use milli::obkv_to_json;
+    use serde_json::json;
+    use sqlx::Executor;
+
+    use super::*;
+
+    #[test]
+    fn import_the_rows_of_a_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("shop.db");
+        let database_url = format!("sqlite://{}?mode=rwc", database.display());
+
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024);
+        let index = Index::new(options, dir.path()).unwrap();
+        let config = IndexerConfig::default();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let stats = runtime.block_on(async {
+            sqlx::any::install_default_drivers();
+            let mut connection = AnyConnection::connect(&database_url).await.unwrap();
+            connection
+                .execute(
+                    "CREATE TABLE products (id INTEGER, name TEXT, price REAL, color TEXT);
+                     INSERT INTO products VALUES (1, 'table', 120.5, 'brown');
+                     INSERT INTO products VALUES (2, 'chair', 45.0, NULL);
+                     INSERT INTO products VALUES (3, 'lamp', 20.0, 'white');",
+                )
+                .await
+                .unwrap();
+
+            let query = "SELECT id, name AS title, price, color FROM products WHERE price > 30";
+            import_sql_query(&database_url, query, &index, &config, 1).await.unwrap()
+        });
+        assert_eq!(stats, ImportStats { imported_documents: 2, number_of_documents: 2 });
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let fields: Vec<_> = fields_ids_map.ids().collect();
+        let mut documents: Vec<_> = index
+            .all_documents(&rtxn)
+            .unwrap()
+            .map(|result| {
+                let (_, obkv) = result.unwrap();
+                Value::Object(obkv_to_json(&fields, &fields_ids_map, obkv).unwrap())
+            })
+            .collect();
+        documents.sort_by_key(|document| document["id"].as_u64());
+        assert_eq!(
+            documents,
+            vec![
+                json!({ "id": 1, "title": "table", "price": 120.5, "color": "brown" }),
+                json!({ "id": 2, "title": "chair", "price": 45.0, "color": null }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Exports the snapshots of the milli indexes, enabled by the `export` feature.
+
+use fst::Streamer;
+use milli::heed::RoTxn;
+use milli::{obkv_to_json, Index};
+
+use crate::{Error, SnapshotBuilder};
+
+/// Exports the words and the displayed fields of the documents of an index in the format
+/// read by [`StaticIndex::from_bytes`](crate::StaticIndex::from_bytes).
+pub fn snapshot(index: &Index, rtxn: &RoTxn) -> Result<Vec<u8>, Error> {
+    let soft_deleted_docids = index.soft_deleted_documents_ids(rtxn)?;
+    let mut builder = SnapshotBuilder::new();
+
+    let words_fst = index.words_fst(rtxn)?;
+    let mut stream = words_fst.stream();
+    while let Some(word) = stream.next() {
+        let word = std::str::from_utf8(word).map_err(milli::Error::from)?;
+        let mut docids =
+            index.word_docids.get(rtxn, word).map_err(milli::Error::from)?.unwrap_or_default();
+        if let Some(exact_docids) =
+            index.exact_word_docids.get(rtxn, word).map_err(milli::Error::from)?
+        {
+            docids |= exact_docids;
+        }
+        docids -= &soft_deleted_docids;
+        if !docids.is_empty() {
+            builder.insert_word(word, &docids)?;
+        }
+    }
+
+    let fields_ids_map = index.fields_ids_map(rtxn)?;
+    let displayed_fields = match index.displayed_fields_ids(rtxn)? {
+        Some(fields) => fields,
+        None => fields_ids_map.ids().collect(),
+    };
+    for result in index.all_documents(rtxn)? {
+        let (docid, obkv) = result?;
+        let document = obkv_to_json(&displayed_fields, &fields_ids_map, obkv)?;
+        builder.insert_document(docid, &document)?;
+    }
+
+    builder.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use milli::documents::documents_batch_reader_from_objects;
+    use milli::heed::EnvOpenOptions;
+    use milli::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
+    use serde_json::json;
+
+    use super::*;
+    use crate::StaticIndex;
+
+    #[test]
+    fn export_a_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024);
+        let index = Index::new(options, dir.path()).unwrap();
+        let config = IndexerConfig::default();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut settings = Settings::new(&mut wtxn, &index, &config);
+        settings.set_primary_key("id".to_string());
+        settings.set_displayed_fields(vec!["id".to_string(), "title".to_string()]);
+        settings.execute(|_| (), || false).unwrap();
+
+        let documents = json!([
+            { "id": 0, "title": "The Quick Brown Fox", "price": 10 },
+            { "id": 1, "title": "the lazy dog", "price": 20 },
+            { "id": 2, "title": "a quick café", "price": 30 },
+        ]);
+        let objects = documents.as_array().unwrap().iter().map(|d| d.as_object().unwrap().clone());
+        let builder = IndexDocuments::new(
+            &mut wtxn,
+            &index,
+            &config,
+            IndexDocumentsConfig::default(),
+            |_| (),
+            || false,
+        )
+        .unwrap();
+        let (builder, user_error) =
+            builder.add_documents(documents_batch_reader_from_objects(objects)).unwrap();
+        user_error.unwrap();
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let snapshot = StaticIndex::from_bytes(&snapshot(&index, &rtxn).unwrap()).unwrap();
+        assert_eq!(snapshot.number_of_documents(), 3);
+        assert_eq!(snapshot.search("dog", 10), vec![r#"{"id":1,"title":"the lazy dog"}"#]);
+        // The query is normalized like the documents were.
+        assert_eq!(snapshot.search("CAFE", 10), vec![r#"{"id":2,"title":"a quick café"}"#]);
+        assert_eq!(snapshot.search("quick", 10).len(), 2);
+    }
+}
@@ -0,0 +1,248 @@
+//! A read-only search over static snapshots of milli indexes.
+//!
+//! A snapshot contains the words of an index along with the documents containing them, and the
+//! displayed fields of the documents, it is exported by the `export` feature with
+//! [`export::snapshot`]. Without it, this crate doesn't depend on LMDB and compiles to
+//! `wasm32-unknown-unknown`, to search small indexes in a browser, the `wasm` feature exports
+//! the functions of the [`wasm`] module.
+//!
+//! The words of a query are normalized by charabia like the words of the documents were, and
+//! must all be contained in a document, the last one as a prefix. When no document contains
+//! all of them, the last words are ignored one by one.
+
+use std::collections::BTreeMap;
+
+use charabia::Tokenize;
+use fst::automaton::{Automaton, Str};
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(test, feature = "export"))]
+pub mod export;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid snapshot: {0}.")]
+    InvalidSnapshot(#[from] bincode::Error),
+    #[error("Invalid snapshot words: {0}.")]
+    InvalidWords(#[from] fst::Error),
+    #[error("Invalid snapshot bitmap: {0}.")]
+    InvalidBitmap(#[from] std::io::Error),
+    #[error("Invalid snapshot words: the word `{word}` refers to the missing bitmap {index}.")]
+    MissingBitmap { word: String, index: u64 },
+    #[error("Invalid document: {0}.")]
+    InvalidDocument(#[from] serde_json::Error),
+    #[cfg(any(test, feature = "export"))]
+    #[error(transparent)]
+    Index(#[from] milli::Error),
+}
+
+/// The serialized format of a snapshot.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Snapshot {
+    /// The words of the index, associated with their index in `word_docids`.
+    words_fst: Vec<u8>,
+    /// The serialized bitmaps of the documents containing each word.
+    word_docids: Vec<Vec<u8>>,
+    /// The displayed fields of the documents, serialized in JSON.
+    documents: BTreeMap<u32, String>,
+}
+
+/// Builds a snapshot, the words must be inserted in lexicographic order.
+pub struct SnapshotBuilder {
+    words: MapBuilder<Vec<u8>>,
+    word_docids: Vec<Vec<u8>>,
+    documents: BTreeMap<u32, String>,
+}
+
+impl SnapshotBuilder {
+    pub fn new() -> SnapshotBuilder {
+        SnapshotBuilder {
+            words: MapBuilder::memory(),
+            word_docids: Vec::new(),
+            documents: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert_word(&mut self, word: &str, docids: &RoaringBitmap) -> Result<(), Error> {
+        self.words.insert(word, self.word_docids.len() as u64)?;
+        let mut bytes = Vec::with_capacity(docids.serialized_size());
+        docids.serialize_into(&mut bytes)?;
+        self.word_docids.push(bytes);
+        Ok(())
+    }
+
+    pub fn insert_document(
+        &mut self,
+        docid: u32,
+        document: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), Error> {
+        self.documents.insert(docid, serde_json::to_string(document)?);
+        Ok(())
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>, Error> {
+        let SnapshotBuilder { words, word_docids, documents } = self;
+        let snapshot = Snapshot { words_fst: words.into_inner()?, word_docids, documents };
+        Ok(bincode::serialize(&snapshot)?)
+    }
+}
+
+impl Default for SnapshotBuilder {
+    fn default() -> Self {
+        SnapshotBuilder::new()
+    }
+}
+
+/// A snapshot loaded in memory.
+pub struct StaticIndex {
+    words: Map<Vec<u8>>,
+    word_docids: Vec<RoaringBitmap>,
+    documents: BTreeMap<u32, String>,
+}
+
+impl StaticIndex {
+    pub fn from_bytes(bytes: &[u8]) -> Result<StaticIndex, Error> {
+        let Snapshot { words_fst, word_docids, documents } = bincode::deserialize(bytes)?;
+        let words = Map::new(words_fst)?;
+        let word_docids: Vec<_> = word_docids
+            .iter()
+            .map(|bytes| RoaringBitmap::deserialize_from(bytes.as_slice()))
+            .collect::<Result<_, _>>()?;
+
+        // The words are searched without bound checks, every one of them must have a bitmap.
+        let mut stream = words.stream();
+        while let Some((word, index)) = stream.next() {
+            if index >= word_docids.len() as u64 {
+                let word = String::from_utf8_lossy(word).into_owned();
+                return Err(Error::MissingBitmap { word, index });
+            }
+        }
+
+        Ok(StaticIndex { words, word_docids, documents })
+    }
+
+    pub fn number_of_documents(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns the documents matching the query, serialized in JSON, in the order of their ids.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&str> {
+        let words: Vec<_> = query
+            .tokenize()
+            .filter(|token| token.is_word())
+            .map(|token| token.lemma().to_string())
+            .collect();
+
+        let mut docids = RoaringBitmap::new();
+        if words.is_empty() {
+            docids.extend(self.documents.keys().copied());
+        }
+        for len in (1..=words.len()).rev() {
+            docids = self.words_docids(&words[..len]);
+            if !docids.is_empty() {
+                break;
+            }
+        }
+
+        docids
+            .iter()
+            .filter_map(|docid| self.documents.get(&docid))
+            .map(String::as_str)
+            .take(limit)
+            .collect()
+    }
+
+    /// Returns the documents containing all the words, the last one as a prefix.
+    fn words_docids(&self, words: &[String]) -> RoaringBitmap {
+        let Some((prefix, words)) = words.split_last() else { return RoaringBitmap::new() };
+        let mut docids = RoaringBitmap::new();
+        let mut stream = self.words.search(Str::new(prefix).starts_with()).into_stream();
+        while let Some((_, index)) = stream.next() {
+            docids |= &self.word_docids[index as usize];
+        }
+        for word in words {
+            match self.words.get(word) {
+                Some(index) => docids &= &self.word_docids[index as usize],
+                None => return RoaringBitmap::new(),
+            }
+        }
+        docids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn create_index() -> StaticIndex {
+        let documents = [
+            json!({ "id": 0, "title": "the quick brown fox" }),
+            json!({ "id": 1, "title": "the lazy dog" }),
+            json!({ "id": 2, "title": "a quick dog" }),
+        ];
+
+        let mut words = BTreeMap::<String, RoaringBitmap>::new();
+        let mut builder = SnapshotBuilder::new();
+        for (docid, document) in documents.iter().enumerate() {
+            let title = document["title"].as_str().unwrap();
+            for word in title.split_whitespace() {
+                words.entry(word.to_owned()).or_default().insert(docid as u32);
+            }
+            builder.insert_document(docid as u32, document.as_object().unwrap()).unwrap();
+        }
+        for (word, docids) in &words {
+            builder.insert_word(word, docids).unwrap();
+        }
+        StaticIndex::from_bytes(&builder.into_bytes().unwrap()).unwrap()
+    }
+
+    fn ids(hits: Vec<&str>) -> Vec<u64> {
+        hits.into_iter()
+            .map(|hit| {
+                serde_json::from_str::<serde_json::Value>(hit).unwrap()["id"].as_u64().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn search_in_a_snapshot() {
+        let index = create_index();
+        assert_eq!(index.number_of_documents(), 3);
+
+        assert_eq!(ids(index.search("Quick", 10)), vec![0, 2]);
+        assert_eq!(ids(index.search("Qüick", 10)), vec![0, 2]);
+        assert_eq!(ids(index.search("quick do", 10)), vec![2]);
+        assert_eq!(ids(index.search("the", 1)), vec![0]);
+        assert_eq!(ids(index.search("", 10)), vec![0, 1, 2]);
+        // The last words are ignored when no document contains all of them.
+        assert_eq!(ids(index.search("lazy unicorn", 10)), vec![1]);
+        assert!(index.search("unicorn", 10).is_empty());
+    }
+
+    #[test]
+    fn invalid_snapshot() {
+        assert!(StaticIndex::from_bytes(b"not a snapshot").is_err());
+
+        let mut builder = SnapshotBuilder::new();
+        builder.insert_word("dog", &RoaringBitmap::new()).unwrap();
+        assert!(builder.insert_word("cat", &RoaringBitmap::new()).is_err());
+
+        // A word referring to a bitmap that is not in the snapshot.
+        let mut words = MapBuilder::memory();
+        words.insert("dog", 1).unwrap();
+        let snapshot = Snapshot {
+            words_fst: words.into_inner().unwrap(),
+            word_docids: vec![Vec::new()],
+            documents: BTreeMap::new(),
+        };
+        let bytes = bincode::serialize(&snapshot).unwrap();
+        let error = StaticIndex::from_bytes(&bytes).err().unwrap();
+        assert!(matches!(error, Error::MissingBitmap { index: 1, .. }));
+    }
+}
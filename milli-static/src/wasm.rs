@@ -0,0 +1,74 @@
+//! The functions exported to JavaScript when compiled to WebAssembly.
+//!
+//! The snapshots and the queries are copied in buffers allocated with [`static_alloc`]
+//! and all the buffers must be released with [`static_free`].
+
+use std::ptr;
+
+use crate::StaticIndex;
+
+/// Allocates a buffer of the given length in the memory of the module.
+#[no_mangle]
+pub extern "C" fn static_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0; len].into_boxed_slice()) as *mut u8
+}
+
+/// Releases a buffer returned by this module.
+///
+/// # Safety
+///
+/// The buffer must have been returned by this module with the same length.
+#[no_mangle]
+pub unsafe extern "C" fn static_free(buffer: *mut u8, len: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)));
+}
+
+/// Loads a snapshot, returns null if it is invalid.
+///
+/// # Safety
+///
+/// The snapshot must be a buffer of the given length.
+#[no_mangle]
+pub unsafe extern "C" fn static_index_load(snapshot: *const u8, len: usize) -> *mut StaticIndex {
+    match StaticIndex::from_bytes(std::slice::from_raw_parts(snapshot, len)) {
+        Ok(index) => Box::into_raw(Box::new(index)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Releases an index loaded with [`static_index_load`].
+///
+/// # Safety
+///
+/// The index must be null or an index returned by [`static_index_load`] not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn static_index_free(index: *mut StaticIndex) {
+    if !index.is_null() {
+        drop(Box::from_raw(index));
+    }
+}
+
+/// Searches the index, see [`StaticIndex::search`].
+///
+/// Returns a buffer starting with the length of the results, as a little-endian `u32`,
+/// followed by the results as a JSON array, it must be released with a length of `4 + length`.
+///
+/// # Safety
+///
+/// The index must be a loaded index and the query a buffer of the given length.
+#[no_mangle]
+pub unsafe extern "C" fn static_index_search(
+    index: *const StaticIndex,
+    query: *const u8,
+    query_len: usize,
+    limit: usize,
+) -> *mut u8 {
+    let query = String::from_utf8_lossy(std::slice::from_raw_parts(query, query_len));
+    let hits = (*index).search(&query, limit);
+    let json = format!("[{}]", hits.join(","));
+
+    let mut buffer = Vec::with_capacity(4 + json.len());
+    buffer.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(json.as_bytes());
+    Box::into_raw(buffer.into_boxed_slice()) as *mut u8
+}
@@ -8,7 +8,7 @@ use crate::{AscDesc, Member};
 
 #[derive(Error, Debug)]
 pub enum CriterionError {
-    #[error("`{name}` ranking rule is invalid. Valid ranking rules are words, typo, sort, proximity, attribute, exactness and custom ranking rules.")]
+    #[error("`{name}` ranking rule is invalid. Valid ranking rules are words, typo, sort, proximity, attribute, exactness, order and custom ranking rules.")]
     InvalidName { name: String },
     #[error("`{name}` is a reserved keyword and thus can't be used as a ranking rule")]
     ReservedName { name: String },
@@ -41,6 +41,9 @@ pub enum Criterion {
     Sort,
     /// Sorted by the similarity of the matched words with the query words.
     Exactness,
+    /// Documents with query words appearing in the same order as in the query
+    /// are considered better than documents where they are reversed.
+    Order,
     /// Sorted by the increasing value of the field specified.
     Asc(String),
     /// Sorted by the decreasing value of the field specified.
@@ -68,6 +71,7 @@ impl FromStr for Criterion {
             "attribute" => Ok(Criterion::Attribute),
             "sort" => Ok(Criterion::Sort),
             "exactness" => Ok(Criterion::Exactness),
+            "order" => Ok(Criterion::Order),
             text => match AscDesc::from_str(text)? {
                 AscDesc::Asc(Member::Field(field)) => Ok(Criterion::Asc(field)),
                 AscDesc::Desc(Member::Field(field)) => Ok(Criterion::Desc(field)),
@@ -101,6 +105,7 @@ impl fmt::Display for Criterion {
             Attribute => f.write_str("attribute"),
             Sort => f.write_str("sort"),
             Exactness => f.write_str("exactness"),
+            Order => f.write_str("order"),
             Asc(attr) => write!(f, "{}:asc", attr),
             Desc(attr) => write!(f, "{}:desc", attr),
         }
@@ -123,6 +128,7 @@ mod tests {
             ("attribute", Criterion::Attribute),
             ("sort", Criterion::Sort),
             ("exactness", Criterion::Exactness),
+            ("order", Criterion::Order),
             ("price:asc", Criterion::Asc(S("price"))),
             ("price:desc", Criterion::Desc(S("price"))),
             ("price:asc:desc", Criterion::Desc(S("price:asc"))),
@@ -185,4 +191,27 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        let criteria = [
+            Criterion::Words,
+            Criterion::Typo,
+            Criterion::Proximity,
+            Criterion::Attribute,
+            Criterion::Sort,
+            Criterion::Exactness,
+            Criterion::Order,
+            Criterion::Asc(S("price")),
+            Criterion::Desc(S("release_date")),
+            Criterion::Asc(S("price:desc")),
+            Criterion::Desc(S("nested.field:with:colons")),
+            Criterion::Asc(S("it's spacy over there")),
+        ];
+
+        for criterion in criteria {
+            let text = criterion.to_string();
+            assert_eq!(text.parse::<Criterion>().unwrap(), criterion, "for `{text}`");
+        }
+    }
 }
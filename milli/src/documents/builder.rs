@@ -87,6 +87,15 @@ impl<W: Write> DocumentsBatchBuilder<W> {
         de.deserialize_any(&mut visitor)?
     }
 
+    /// Appends a new stream of newline-delimited JSON objects into the batch
+    /// and updates the `DocumentsBatchIndex` accordingly.
+    pub fn append_ndjson<R: io::Read>(&mut self, reader: R) -> Result<(), Error> {
+        for object in serde_json::Deserializer::from_reader(reader).into_iter::<Object>() {
+            self.append_json_object(&object?)?;
+        }
+        Ok(())
+    }
+
     /// Appends a new CSV file into the batch and updates the `DocumentsBatchIndex` accordingly.
     pub fn append_csv<R: io::Read>(&mut self, mut reader: csv::Reader<R>) -> Result<(), Error> {
         // Make sure that we insert the fields ids in order as the obkv writer has this requirement.
@@ -278,6 +287,39 @@ mod test {
         assert!(cursor.next_document().unwrap().is_none());
     }
 
+    #[test]
+    fn add_documents_ndjson() {
+        let ndjson_content = r#"{"id":1,"field":"hello!"}
+
+{"id":2,"other":[1,2]}
+"#;
+
+        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        builder.append_ndjson(Cursor::new(ndjson_content)).unwrap();
+        assert_eq!(builder.documents_count(), 2);
+        let vector = builder.into_inner().unwrap();
+
+        let (mut cursor, index) = DocumentsBatchReader::from_reader(Cursor::new(vector))
+            .unwrap()
+            .into_cursor_and_fields_index();
+        assert_eq!(index.len(), 3);
+
+        let doc = cursor.next_document().unwrap().unwrap();
+        let val = obkv_to_object(&doc, &index).map(Value::from).unwrap();
+        assert_eq!(val, json!({ "id": 1, "field": "hello!" }));
+
+        let doc = cursor.next_document().unwrap().unwrap();
+        let val = obkv_to_object(&doc, &index).map(Value::from).unwrap();
+        assert_eq!(val, json!({ "id": 2, "other": [1, 2] }));
+
+        assert!(cursor.next_document().unwrap().is_none());
+
+        // A line that isn't an object is rejected.
+        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        let error = builder.append_ndjson(Cursor::new("{\"id\":1}\n[1, 2]")).unwrap_err();
+        assert!(matches!(error, Error::Json(_)));
+    }
+
     #[test]
     fn simple_csv_document() {
         let csv_content = r#"city,country,pop
@@ -0,0 +1,379 @@
+//! Dumps of an index, a copy of its documents and settings that doesn't depend on the
+//! on-disk layout of the index. They are used to migrate an index from a version of the
+//! engine to another, when the layout of the databases changes.
+//!
+//! A dump is a directory containing:
+//!  - `metadata.json`, the version of the dump format and the number of documents,
+//!  - `settings.json`, the user-defined settings of the index,
+//!  - `documents.jsonl`, all the documents, one JSON object per line.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use fst::Streamer;
+use heed::{RoTxn, RwTxn};
+use serde::{Deserialize, Serialize};
+
+use crate::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use crate::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
+use crate::{
+    all_obkv_to_json, Criterion, Index, InternalError, LocalizedAttributesRule, OrderBy, Result,
+    View,
+};
+
+/// The version of the format of the dumps written by this version of the engine.
+pub const DUMP_VERSION: u32 = 1;
+
+const METADATA_FILE: &str = "metadata.json";
+const SETTINGS_FILE: &str = "settings.json";
+const DOCUMENTS_FILE: &str = "documents.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpMetadata {
+    pub dump_version: u32,
+    /// The version of the engine that wrote the dump.
+    pub engine_version: String,
+    pub number_of_documents: u64,
+}
+
+/// The settings of an index stored in a dump, a `None` is a setting that was not defined
+/// by the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpSettings {
+    pub primary_key: Option<String>,
+    pub searchable_attributes: Option<Vec<String>>,
+    pub displayed_attributes: Option<Vec<String>>,
+    pub filterable_attributes: HashSet<String>,
+    pub sortable_attributes: HashSet<String>,
+    pub ranking_rules: Vec<Criterion>,
+    pub stop_words: BTreeSet<String>,
+    pub dictionary: BTreeSet<String>,
+    /// The synonyms are stored normalized, the words of a synonym are separated by spaces.
+    pub synonyms: HashMap<String, Vec<String>>,
+    pub distinct_attribute: Option<String>,
+    pub recency_attribute: Option<String>,
+    pub boost_attribute: Option<String>,
+    pub authorize_typos: bool,
+    pub searchable_numbers_and_booleans: bool,
+    pub min_word_len_one_typo: u8,
+    pub min_word_len_two_typos: u8,
+    pub exact_words: BTreeSet<String>,
+    pub exact_attributes: HashSet<String>,
+    pub max_values_per_facet: Option<usize>,
+    pub sort_facet_values_by: HashMap<String, OrderBy>,
+    pub pagination_max_total_hits: Option<usize>,
+    pub typo_derivations_limit: Option<usize>,
+    pub views: BTreeMap<String, View>,
+    pub localized_attributes_rules: Vec<LocalizedAttributesRule>,
+}
+
+impl DumpSettings {
+    /// Reads the current settings of the index.
+    pub fn from_index(index: &Index, rtxn: &RoTxn) -> Result<DumpSettings> {
+        let fst_strings = |set: Option<fst::Set<&[u8]>>| -> Result<BTreeSet<String>> {
+            let mut strings = BTreeSet::new();
+            if let Some(set) = set {
+                let mut stream = set.stream();
+                while let Some(string) = stream.next() {
+                    strings.insert(std::str::from_utf8(string)?.to_string());
+                }
+            }
+            Ok(strings)
+        };
+
+        let exact_words = match index.exact_words(rtxn)? {
+            Some(words) => words.stream().into_strs()?.into_iter().collect(),
+            None => BTreeSet::new(),
+        };
+        let synonyms = index
+            .synonyms(rtxn)?
+            .into_iter()
+            .map(|(word, synonyms)| {
+                (word.join(" "), synonyms.into_iter().map(|synonym| synonym.join(" ")).collect())
+            })
+            .collect();
+        let to_strings = |fields: Vec<&str>| fields.into_iter().map(String::from).collect();
+
+        Ok(DumpSettings {
+            primary_key: index.primary_key(rtxn)?.map(String::from),
+            searchable_attributes: index.user_defined_searchable_fields(rtxn)?.map(to_strings),
+            displayed_attributes: index.displayed_fields(rtxn)?.map(to_strings),
+            filterable_attributes: index.filterable_fields(rtxn)?,
+            sortable_attributes: index.sortable_fields(rtxn)?,
+            ranking_rules: index.criteria(rtxn)?,
+            stop_words: fst_strings(index.stop_words(rtxn)?)?,
+            dictionary: fst_strings(index.dictionary(rtxn)?)?,
+            synonyms,
+            distinct_attribute: index.distinct_field(rtxn)?.map(String::from),
+            recency_attribute: index.recency_field(rtxn)?.map(String::from),
+            boost_attribute: index.boost_field(rtxn)?.map(String::from),
+            authorize_typos: index.authorize_typos(rtxn)?,
+            searchable_numbers_and_booleans: index.searchable_numbers_and_booleans(rtxn)?,
+            min_word_len_one_typo: index.min_word_len_one_typo(rtxn)?,
+            min_word_len_two_typos: index.min_word_len_two_typos(rtxn)?,
+            exact_words,
+            exact_attributes: index.exact_attributes(rtxn)?.into_iter().map(String::from).collect(),
+            max_values_per_facet: index.max_values_per_facet(rtxn)?,
+            sort_facet_values_by: index.sort_facet_values_by(rtxn)?,
+            pagination_max_total_hits: index.pagination_max_total_hits(rtxn)?,
+            typo_derivations_limit: index.typo_derivations_limit(rtxn)?,
+            views: index.views(rtxn)?,
+            localized_attributes_rules: index.localized_attributes_rules(rtxn)?,
+        })
+    }
+
+    /// Registers all the settings in the settings builder.
+    pub fn apply(self, builder: &mut Settings) {
+        let DumpSettings {
+            primary_key,
+            searchable_attributes,
+            displayed_attributes,
+            filterable_attributes,
+            sortable_attributes,
+            ranking_rules,
+            stop_words,
+            dictionary,
+            synonyms,
+            distinct_attribute,
+            recency_attribute,
+            boost_attribute,
+            authorize_typos,
+            searchable_numbers_and_booleans,
+            min_word_len_one_typo,
+            min_word_len_two_typos,
+            exact_words,
+            exact_attributes,
+            max_values_per_facet,
+            sort_facet_values_by,
+            pagination_max_total_hits,
+            typo_derivations_limit,
+            views,
+            localized_attributes_rules,
+        } = self;
+
+        if let Some(primary_key) = primary_key {
+            builder.set_primary_key(primary_key);
+        }
+        match searchable_attributes {
+            Some(fields) => builder.set_searchable_fields(fields),
+            None => builder.reset_searchable_fields(),
+        }
+        match displayed_attributes {
+            Some(fields) => builder.set_displayed_fields(fields),
+            None => builder.reset_displayed_fields(),
+        }
+        builder.set_filterable_fields(filterable_attributes);
+        builder.set_sortable_fields(sortable_attributes);
+        builder.set_criteria(ranking_rules);
+        builder.set_stop_words(stop_words);
+        builder.set_dictionary(dictionary);
+        builder.set_synonyms(synonyms);
+        match distinct_attribute {
+            Some(field) => builder.set_distinct_field(field),
+            None => builder.reset_distinct_field(),
+        }
+        match recency_attribute {
+            Some(field) => builder.set_recency_field(field),
+            None => builder.reset_recency_field(),
+        }
+        match boost_attribute {
+            Some(field) => builder.set_boost_field(field),
+            None => builder.reset_boost_field(),
+        }
+        builder.set_autorize_typos(authorize_typos);
+        builder.set_searchable_numbers_and_booleans(searchable_numbers_and_booleans);
+        builder.set_min_word_len_one_typo(min_word_len_one_typo);
+        builder.set_min_word_len_two_typos(min_word_len_two_typos);
+        builder.set_exact_words(exact_words);
+        builder.set_exact_attributes(exact_attributes);
+        match max_values_per_facet {
+            Some(value) => builder.set_max_values_per_facet(value),
+            None => builder.reset_max_values_per_facet(),
+        }
+        builder.set_sort_facet_values_by(sort_facet_values_by);
+        match pagination_max_total_hits {
+            Some(value) => builder.set_pagination_max_total_hits(value),
+            None => builder.reset_pagination_max_total_hits(),
+        }
+        match typo_derivations_limit {
+            Some(value) => builder.set_typo_derivations_limit(value),
+            None => builder.reset_typo_derivations_limit(),
+        }
+        builder.set_views(views);
+        builder.set_localized_attributes_rules(localized_attributes_rules);
+    }
+}
+
+/// Writes a dump of the index in the given directory, which is created if needed.
+///
+/// The documents are streamed from the index, they are never all loaded in memory.
+pub fn export_index<P: AsRef<Path>>(index: &Index, rtxn: &RoTxn, dir: P) -> Result<DumpMetadata> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+
+    let settings = DumpSettings::from_index(index, rtxn)?;
+    write_json(&dir.join(SETTINGS_FILE), &settings)?;
+
+    let fields_ids_map = index.fields_ids_map(rtxn)?;
+    let mut writer = BufWriter::new(File::create(dir.join(DOCUMENTS_FILE))?);
+    let mut number_of_documents = 0;
+    for result in index.all_documents(rtxn)? {
+        let (_docid, obkv) = result?;
+        let document = all_obkv_to_json(obkv, &fields_ids_map)?;
+        serde_json::to_writer(&mut writer, &document).map_err(InternalError::SerdeJson)?;
+        writer.write_all(b"\n")?;
+        number_of_documents += 1;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+    let metadata = DumpMetadata {
+        dump_version: DUMP_VERSION,
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        number_of_documents,
+    };
+    // The metadata are written last, a dump without them is incomplete.
+    write_json(&dir.join(METADATA_FILE), &metadata)?;
+
+    Ok(metadata)
+}
+
+/// Reads the metadata of the dump stored in the given directory.
+pub fn dump_metadata<P: AsRef<Path>>(dir: P) -> Result<DumpMetadata> {
+    let metadata: DumpMetadata = read_json(&dir.as_ref().join(METADATA_FILE))?;
+    if metadata.dump_version > DUMP_VERSION {
+        return Err(InternalError::UnsupportedDumpVersion { version: metadata.dump_version }.into());
+    }
+    Ok(metadata)
+}
+
+/// Rebuilds an index from the dump stored in the given directory, the index should be empty.
+///
+/// The settings are applied before the documents are indexed, so that the documents
+/// are indexed only once.
+pub fn import_index<P: AsRef<Path>>(
+    wtxn: &mut RwTxn,
+    index: &Index,
+    indexer_config: &IndexerConfig,
+    dir: P,
+) -> Result<DumpMetadata> {
+    let dir = dir.as_ref();
+    let metadata = dump_metadata(dir)?;
+
+    let settings: DumpSettings = read_json(&dir.join(SETTINGS_FILE))?;
+    let mut builder = Settings::new(wtxn, index, indexer_config);
+    settings.apply(&mut builder);
+    builder.execute(|_| (), || false)?;
+
+    let documents = BufReader::new(File::open(dir.join(DOCUMENTS_FILE))?);
+    let mut batch = DocumentsBatchBuilder::new(tempfile::tempfile()?);
+    batch.append_ndjson(documents)?;
+    let mut file = batch.into_inner()?;
+    file.seek(SeekFrom::Start(0))?;
+    let reader = DocumentsBatchReader::from_reader(file)?;
+
+    if !reader.is_empty() {
+        let config = IndexDocumentsConfig::default();
+        let builder = IndexDocuments::new(wtxn, index, indexer_config, config, |_| (), || false)?;
+        let (builder, user_result) = builder.add_documents(reader)?;
+        user_result?;
+        builder.execute()?;
+    }
+
+    Ok(metadata)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, value).map_err(InternalError::SerdeJson)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let reader = BufReader::new(File::open(path)?);
+    let value = serde_json::from_reader(reader).map_err(InternalError::SerdeJson)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::{btreeset, hashmap, hashset};
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn export_and_import_an_index() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_searchable_fields(vec![S("title")]);
+                settings.set_filterable_fields(hashset! { S("price") });
+                settings.set_stop_words(btreeset! { S("the") });
+                settings.set_synonyms(hashmap! { S("puppy") => vec![S("small dog")] });
+                settings.set_autorize_typos(false);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick fox", "price": 10 },
+                { "id": 1, "title": "the lazy dog", "price": 20, "tags": ["pet"] },
+                { "id": 2, "title": "a small dog", "price": 30 },
+            ]))
+            .unwrap();
+        index.delete_document("0");
+
+        let dir = tempfile::tempdir().unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let metadata = export_index(&index, &rtxn, dir.path()).unwrap();
+        assert_eq!(metadata.dump_version, DUMP_VERSION);
+        assert_eq!(metadata.number_of_documents, 2);
+
+        let imported = TempIndex::new();
+        let mut wtxn = imported.write_txn().unwrap();
+        import_index(&mut wtxn, &imported, &imported.indexer_config, dir.path()).unwrap();
+        wtxn.commit().unwrap();
+
+        let imported_rtxn = imported.read_txn().unwrap();
+        assert_eq!(
+            DumpSettings::from_index(&imported, &imported_rtxn).unwrap(),
+            DumpSettings::from_index(&index, &rtxn).unwrap(),
+        );
+        let documents = |index: &Index, rtxn: &RoTxn| {
+            let fields_ids_map = index.fields_ids_map(rtxn).unwrap();
+            index
+                .all_documents(rtxn)
+                .unwrap()
+                .map(|result| all_obkv_to_json(result.unwrap().1, &fields_ids_map).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(documents(&imported, &imported_rtxn), documents(&index, &rtxn));
+
+        let result = imported.search(&imported_rtxn).query("puppy").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 1);
+    }
+
+    #[test]
+    fn refuse_newer_dumps() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = DumpMetadata {
+            dump_version: DUMP_VERSION + 1,
+            engine_version: S("99.0.0"),
+            number_of_documents: 0,
+        };
+        write_json(&dir.path().join(METADATA_FILE), &metadata).unwrap();
+
+        let error = dump_metadata(dir.path()).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::InternalError(InternalError::UnsupportedDumpVersion { version })
+                if version == DUMP_VERSION + 1
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::BTreeSet;
 use std::convert::Infallible;
 use std::fmt::Write;
+use std::ops::Range;
 use std::{io, str};
 
 use heed::{Error as HeedError, MdbError};
@@ -8,6 +9,7 @@ use rayon::ThreadPoolBuildError;
 use serde_json::Value;
 use thiserror::Error;
 
+#[cfg(feature = "update")]
 use crate::documents::{self, DocumentsBatchCursorError};
 use crate::{CriterionError, DocumentId, FieldId, Object, SortError};
 
@@ -37,6 +39,7 @@ pub enum InternalError {
     FieldIdMappingMissingEntry { key: FieldId },
     #[error(transparent)]
     Fst(#[from] fst::Error),
+    #[cfg(feature = "update")]
     #[error(transparent)]
     DocumentsError(#[from] documents::Error),
     #[error("Invalid compression type have been specified to grenad.")]
@@ -53,6 +56,14 @@ pub enum InternalError {
     SerdeJson(#[from] serde_json::Error),
     #[error(transparent)]
     Serialization(#[from] SerializationError),
+    #[error("Invalid sealed segment: {0}.")]
+    InvalidSealedSegment(&'static str),
+    #[cfg(feature = "update")]
+    #[error(
+        "Unsupported dump version {version}, the maximum supported version is {}.",
+        crate::dump::DUMP_VERSION
+    )]
+    UnsupportedDumpVersion { version: u32 },
     #[error(transparent)]
     Store(#[from] MdbError),
     #[error(transparent)]
@@ -108,6 +119,8 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
         invalid_facets_name: BTreeSet<String>,
         valid_facets_name: BTreeSet<String>,
     },
+    #[error("The date histogram of the `{field}` attribute has more than {max_buckets} buckets. Use a wider interval.")]
+    TooManyDateHistogramBuckets { field: String, max_buckets: usize },
     #[error(transparent)]
     InvalidGeoField(#[from] GeoError),
     #[error("Invalid vector dimensions: expected: `{}`, found: `{}`.", .expected, .found)]
@@ -118,6 +131,25 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
     InvalidFilter(String),
     #[error("Invalid type for filter subexpression: expected: {}, found: {1}.", .0.join(", "))]
     InvalidFilterExpression(&'static [&'static str], Value),
+    #[error("Attribute `{}` is not filterable. {}\n{}:{} {}",
+        .field,
+        match .valid_fields.is_empty() {
+            true => "This index does not have configured filterable attributes.".to_string(),
+            false => format!("Available filterable attributes are: `{}`.",
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(" ")
+                ),
+        },
+        .position.start,
+        .position.end,
+        .filter,
+    )]
+    InvalidFilterableAttribute {
+        field: String,
+        valid_fields: BTreeSet<String>,
+        /// The columns of the field in the filter, like the spans of the parsing errors.
+        position: Range<usize>,
+        filter: String,
+    },
     #[error("Attribute `{}` is not sortable. {}",
         .field,
         match .valid_fields.is_empty() {
@@ -166,6 +198,14 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
     MultiplePrimaryKeyCandidatesFound { candidates: Vec<String> },
     #[error("There is no more space left on the device. Consider increasing the size of the disk/partition.")]
     NoSpaceLeftOnDevice,
+    #[error("A bulk build can only be done on an empty index.")]
+    BulkBuildIntoNonEmptyIndex,
+    #[error("The document `{document_id}` is part of more than one segment of the bulk build.")]
+    BulkBuildOverlappingSegments { document_id: String },
+    #[error("The sealed segments can't be filtered, the `filter` parameter can only be used to search the index alone.")]
+    SealedSegmentsCannotBeFiltered,
+    #[error("The sealed segments can't be sorted, the `sort` parameter can only be used to search the index alone.")]
+    SealedSegmentsCannotBeSorted,
     #[error("Index already has a primary key: `{0}`.")]
     PrimaryKeyCannotBeChanged(String),
     #[error(transparent)]
@@ -174,6 +214,10 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
     SortError(#[from] SortError),
     #[error("An unknown internal document id have been used: `{document_id}`.")]
     UnknownInternalDocumentId { document_id: DocumentId },
+    #[error(
+        "`{locale}` is not a supported locale, expected an ISO 639-3 code such as `eng` or `jpn`."
+    )]
+    InvalidLocale { locale: String },
     #[error("`minWordSizeForTypos` setting is invalid. `oneTypo` and `twoTypos` fields should be between `0` and `255`, and `twoTypos` should be greater or equals to `oneTypo` but found `oneTypo: {0}` and twoTypos: {1}`.")]
     InvalidMinTypoWordLenSetting(u8, u8),
 }
@@ -269,7 +313,6 @@ macro_rules! error_from_sub_error {
 error_from_sub_error! {
     FieldIdMapMissingEntry => InternalError,
     fst::Error => InternalError,
-    documents::Error => InternalError,
     str::Utf8Error => InternalError,
     ThreadPoolBuildError => InternalError,
     SerializationError => InternalError,
@@ -277,6 +320,12 @@ error_from_sub_error! {
     CriterionError => UserError,
 }
 
+#[cfg(feature = "update")]
+error_from_sub_error! {
+    documents::Error => InternalError,
+}
+
+#[cfg(feature = "update")]
 impl<E> From<grenad::Error<E>> for Error
 where
     Error: From<E>,
@@ -295,6 +344,7 @@ where
     }
 }
 
+#[cfg(feature = "update")]
 impl From<DocumentsBatchCursorError> for Error {
     fn from(error: DocumentsBatchCursorError) -> Error {
         match error {
@@ -310,6 +360,49 @@ impl From<Infallible> for Error {
     }
 }
 
+/// What an error is caused by, e.g. to choose the status code answered to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The request or the documents sent by the user.
+    User,
+    /// The machine, like a full disk or a corrupted store.
+    System,
+    /// A bug or an inconsistency of the engine.
+    Internal,
+}
+
+impl UserError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            UserError::SerdeJson(_)
+            | UserError::InvalidLmdbOpenOptions
+            | UserError::DocumentLimitReached
+            | UserError::AccessingSoftDeletedDocument { .. }
+            | UserError::UnknownInternalDocumentId { .. } => ErrorCategory::Internal,
+            UserError::InvalidStoreFile
+            | UserError::MaxDatabaseSizeReached
+            | UserError::NoSpaceLeftOnDevice => ErrorCategory::System,
+            _ => ErrorCategory::User,
+        }
+    }
+}
+
+impl Error {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::InternalError(_) => ErrorCategory::Internal,
+            Error::IoError(_) => ErrorCategory::System,
+            Error::UserError(error) => error.category(),
+        }
+    }
+
+    /// Returns whether the error is caused by the request or the documents sent by the user,
+    /// e.g. to answer with a 4xx status code.
+    pub fn is_user_error(&self) -> bool {
+        self.category() == ErrorCategory::User
+    }
+}
+
 impl From<HeedError> for Error {
     fn from(error: HeedError) -> Error {
         use self::Error::*;
@@ -346,3 +439,15 @@ fn conditionally_lookup_for_error_message() {
         assert_eq!(err.to_string(), format!("{} {}", prefix, suffix));
     }
 }
+
+#[test]
+fn user_errors_are_told_apart_from_internal_errors() {
+    assert!(Error::from(UserError::SortRankingRuleMissing).is_user_error());
+    assert!(Error::from(UserError::InvalidFilter("dog = ".to_string())).is_user_error());
+    assert!(!Error::from(UserError::NoSpaceLeftOnDevice).is_user_error());
+    assert!(!Error::from(HeedError::Mdb(MdbError::MapFull)).is_user_error());
+    assert!(!Error::from(InternalError::DatabaseClosing).is_user_error());
+    assert!(!Error::from(io::Error::from(io::ErrorKind::NotFound)).is_user_error());
+    assert!(!Error::from(UserError::DocumentLimitReached).is_user_error());
+    assert!(Error::from(UserError::BulkBuildIntoNonEmptyIndex).is_user_error());
+}
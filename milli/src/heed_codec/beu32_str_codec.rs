@@ -1,15 +1,16 @@
 use std::borrow::Cow;
-use std::convert::TryInto;
 use std::str;
 
+use crate::try_split_array_at;
+
 pub struct BEU32StrCodec;
 
 impl<'a> heed::BytesDecode<'a> for BEU32StrCodec {
     type DItem = (u32, &'a str);
 
     fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
-        let (n_bytes, str_bytes) = bytes.split_at(4);
-        let n = n_bytes.try_into().map(u32::from_be_bytes).ok()?;
+        let (n_bytes, str_bytes) = try_split_array_at(bytes)?;
+        let n = u32::from_be_bytes(n_bytes);
         let s = str::from_utf8(str_bytes).ok()?;
         Some((n, s))
     }
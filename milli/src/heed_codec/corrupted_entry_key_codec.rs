@@ -0,0 +1,28 @@
+use std::borrow::Cow;
+use std::str;
+
+/// Encodes the name of a database and one of its keys, separated by a nul byte.
+pub struct CorruptedEntryKeyCodec;
+
+impl<'a> heed::BytesDecode<'a> for CorruptedEntryKeyCodec {
+    type DItem = (&'a str, &'a [u8]);
+
+    fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
+        let nul_index = bytes.iter().position(|b| *b == 0)?;
+        let (name_bytes, key) = bytes.split_at(nul_index);
+        let name = str::from_utf8(name_bytes).ok()?;
+        Some((name, &key[1..]))
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for CorruptedEntryKeyCodec {
+    type EItem = (&'a str, &'a [u8]);
+
+    fn bytes_encode((name, key): &Self::EItem) -> Option<Cow<[u8]>> {
+        let mut bytes = Vec::with_capacity(name.len() + 1 + key.len());
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(key);
+        Some(Cow::Owned(bytes))
+    }
+}
@@ -2,7 +2,6 @@ mod field_doc_id_facet_codec;
 mod ordered_f64_codec;
 
 use std::borrow::Cow;
-use std::convert::TryFrom;
 use std::marker::PhantomData;
 
 use heed::types::{DecodeIgnore, OwnedType};
@@ -12,7 +11,7 @@ use roaring::RoaringBitmap;
 pub use self::field_doc_id_facet_codec::FieldDocIdFacetCodec;
 pub use self::ordered_f64_codec::OrderedF64Codec;
 use super::StrRefCodec;
-use crate::{CboRoaringBitmapCodec, BEU16};
+use crate::{try_split_array_at, CboRoaringBitmapCodec, BEU16};
 
 pub type FieldDocIdFacetF64Codec = FieldDocIdFacetCodec<OrderedF64Codec>;
 pub type FieldDocIdFacetStringCodec = FieldDocIdFacetCodec<StrRefCodec>;
@@ -20,6 +19,9 @@ pub type FieldDocIdFacetIgnoreCodec = FieldDocIdFacetCodec<DecodeIgnore>;
 
 pub type FieldIdCodec = OwnedType<BEU16>;
 
+/// The number of children of the nodes of the facet trees, see the `update::facet` module.
+pub const FACET_GROUP_SIZE: u8 = 4;
+
 /// Tries to split a slice in half at the given middle point,
 /// `None` if the slice is too short.
 pub fn try_split_at(slice: &[u8], mid: usize) -> Option<(&[u8], &[u8])> {
@@ -76,9 +78,10 @@ where
     type DItem = FacetGroupKey<T::DItem>;
 
     fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
-        let fid = u16::from_be_bytes(<[u8; 2]>::try_from(&bytes[0..=1]).ok()?);
-        let level = bytes[2];
-        let bound = T::bytes_decode(&bytes[3..])?;
+        let (fid_bytes, bytes) = try_split_array_at(bytes)?;
+        let fid = u16::from_be_bytes(fid_bytes);
+        let ([level], bytes) = try_split_array_at(bytes)?;
+        let bound = T::bytes_decode(bytes)?;
         Some(FacetGroupKey { field_id: fid, level, left_bound: bound })
     }
 }
@@ -96,8 +99,8 @@ impl<'a> heed::BytesEncode<'a> for FacetGroupValueCodec {
 impl<'a> heed::BytesDecode<'a> for FacetGroupValueCodec {
     type DItem = FacetGroupValue;
     fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
-        let size = bytes[0];
-        let bitmap = CboRoaringBitmapCodec::deserialize_from(&bytes[1..]).ok()?;
+        let (&size, bytes) = bytes.split_first()?;
+        let bitmap = CboRoaringBitmapCodec::deserialize_from(bytes).ok()?;
         Some(FacetGroupValue { size, bitmap })
     }
 }
@@ -1,5 +1,8 @@
+use std::borrow::Cow;
+
 mod beu32_str_codec;
 mod byte_slice_ref;
+mod corrupted_entry_key_codec;
 pub mod facet;
 mod field_id_word_count_codec;
 mod fst_set_codec;
@@ -15,10 +18,13 @@ pub use byte_slice_ref::ByteSliceRefCodec;
 pub use str_ref::StrRefCodec;
 
 pub use self::beu32_str_codec::BEU32StrCodec;
+pub use self::corrupted_entry_key_codec::CorruptedEntryKeyCodec;
 pub use self::field_id_word_count_codec::FieldIdWordCountCodec;
 pub use self::fst_set_codec::FstSetCodec;
 pub use self::obkv_codec::ObkvCodec;
-pub use self::roaring_bitmap::{BoRoaringBitmapCodec, CboRoaringBitmapCodec, RoaringBitmapCodec};
+pub use self::roaring_bitmap::{
+    BoRoaringBitmapCodec, CboRoaringBitmapCodec, DeltaRoaringBitmapCodec, RoaringBitmapCodec,
+};
 pub use self::roaring_bitmap_length::{
     BoRoaringBitmapLenCodec, CboRoaringBitmapLenCodec, RoaringBitmapLenCodec,
 };
@@ -31,3 +37,188 @@ pub trait BytesDecodeOwned {
 
     fn bytes_decode_owned(bytes: &[u8]) -> Option<Self::DItem>;
 }
+
+/// The counterpart of [`BytesDecodeOwned`], implemented by all the codecs whose items
+/// don't borrow anything, the encoded bytes never borrow from the item.
+pub trait BytesEncodeOwned {
+    type EItem;
+
+    fn bytes_encode_owned(item: &Self::EItem) -> Option<Vec<u8>>;
+}
+
+impl<C, T> BytesEncodeOwned for C
+where
+    C: for<'a> heed::BytesEncode<'a, EItem = T>,
+{
+    type EItem = T;
+
+    fn bytes_encode_owned(item: &T) -> Option<Vec<u8>> {
+        C::bytes_encode(item).map(Cow::into_owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use charabia::{Language, Script};
+    use heed::{BytesDecode, BytesEncode};
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use roaring::RoaringBitmap;
+
+    use super::facet::{
+        FacetGroupKey, FacetGroupKeyCodec, FacetGroupValue, FacetGroupValueCodec,
+        FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec, OrderedF64Codec,
+    };
+    use super::*;
+
+    const ITERATIONS: usize = 200;
+
+    /// Encodes the item, checks that it is decoded back and that decoding any truncation
+    /// of the bytes doesn't panic, then returns the bytes.
+    macro_rules! assert_round_trip {
+        ($codec:ty, $item:expr) => {{
+            let item = $item;
+            let bytes = <$codec as BytesEncode>::bytes_encode(&item).unwrap().into_owned();
+            assert_eq!(<$codec as BytesDecode>::bytes_decode(&bytes), Some(item));
+            for len in 0..bytes.len() {
+                let _ = <$codec as BytesDecode>::bytes_decode(&bytes[..len]);
+            }
+            bytes
+        }};
+    }
+
+    /// Checks that the keys are sorted by LMDB in the order of their items.
+    fn assert_same_order<T>(entries: &[(T, Vec<u8>)], cmp: impl Fn(&T, &T) -> Ordering) {
+        for (left, left_bytes) in entries {
+            for (right, right_bytes) in entries {
+                assert_eq!(cmp(left, right), left_bytes.cmp(right_bytes));
+            }
+        }
+    }
+
+    fn random_string(rng: &mut SmallRng) -> String {
+        // multi-bytes characters and characters ordered before the digits
+        const CHARS: &[char] = &['a', 'b', 'z', '0', ' ', '-', 'é', '日', '🦀'];
+        let len = rng.gen_range(0..6);
+        (0..len).map(|_| CHARS[rng.gen_range(0..CHARS.len())]).collect()
+    }
+
+    fn random_f64(rng: &mut SmallRng) -> f64 {
+        match rng.gen_range(0..4) {
+            0 => rng.gen_range(-10..10) as f64,
+            1 => rng.gen_range(-1e-3..1e-3),
+            _ => rng.gen_range(-1e12..1e12),
+        }
+    }
+
+    fn random_bitmap(rng: &mut SmallRng) -> RoaringBitmap {
+        // the small bitmaps are encoded as raw integers by the CBO codecs
+        let len = if rng.gen_bool(0.5) { rng.gen_range(0..8) } else { rng.gen_range(0..2000) };
+        (0..len).map(|_| rng.gen_range(0..100_000)).collect()
+    }
+
+    #[test]
+    fn string_keys_round_trip_and_keep_the_order() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let mut beu32_str = Vec::new();
+        let mut str_beu16 = Vec::new();
+        let mut u8_str_str = Vec::new();
+        for _ in 0..ITERATIONS {
+            let (n, s1, s2) =
+                (rng.gen::<u32>() % 4, random_string(&mut rng), random_string(&mut rng));
+
+            let bytes = assert_round_trip!(BEU32StrCodec, (n, s1.as_str()));
+            beu32_str.push(((n, s1.clone()), bytes));
+            let bytes = assert_round_trip!(StrBEU16Codec, (s1.as_str(), n as u16));
+            str_beu16.push(((s1.clone(), n as u16), bytes));
+            let bytes = assert_round_trip!(U8StrStrCodec, (n as u8, s1.as_str(), s2.as_str()));
+            u8_str_str.push(((n as u8, s1.clone(), s2.clone()), bytes));
+
+            assert_round_trip!(StrBEU32Codec, (s1.as_str(), n));
+            assert_round_trip!(UncheckedU8StrStrCodec, (n as u8, s1.as_bytes(), s2.as_bytes()));
+            assert_round_trip!(CorruptedEntryKeyCodec, (s1.as_str(), s2.as_bytes()));
+            assert_round_trip!(StrRefCodec, s1.as_str());
+            assert_round_trip!(ByteSliceRefCodec, s1.as_bytes());
+        }
+
+        assert_same_order(&beu32_str, Ord::cmp);
+        assert_same_order(&str_beu16, Ord::cmp);
+        assert_same_order(&u8_str_str, Ord::cmp);
+    }
+
+    #[test]
+    fn fixed_size_keys_round_trip_and_keep_the_order() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let mut field_id_word_count = Vec::new();
+        let mut f64s = Vec::new();
+        for _ in 0..ITERATIONS {
+            let item = (rng.gen_range(0..4), rng.gen());
+            let bytes = assert_round_trip!(FieldIdWordCountCodec, item);
+            field_id_word_count.push((item, bytes));
+
+            let f = random_f64(&mut rng);
+            let bytes = assert_round_trip!(OrderedF64Codec, f);
+            f64s.push((f, bytes));
+        }
+        assert_round_trip!(ScriptLanguageCodec, (Script::Cj, Language::Jpn));
+
+        assert_same_order(&field_id_word_count, Ord::cmp);
+        assert_same_order(&f64s, |left, right| left.partial_cmp(right).unwrap());
+    }
+
+    #[test]
+    fn facet_keys_round_trip_and_keep_the_order() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let mut f64_keys = Vec::new();
+        let mut string_keys = Vec::new();
+        for _ in 0..ITERATIONS {
+            let (field_id, level) = (rng.gen_range(0..3), rng.gen_range(0..3));
+            let (f, s) = (random_f64(&mut rng), random_string(&mut rng));
+
+            let key = FacetGroupKey { field_id, level, left_bound: f };
+            let bytes = assert_round_trip!(FacetGroupKeyCodec<OrderedF64Codec>, key);
+            f64_keys.push((key, bytes));
+            let key = FacetGroupKey { field_id, level, left_bound: s.as_str() };
+            let bytes = assert_round_trip!(FacetGroupKeyCodec<StrRefCodec>, key);
+            string_keys.push((FacetGroupKey { field_id, level, left_bound: s.clone() }, bytes));
+
+            let docid = rng.gen();
+            assert_round_trip!(FieldDocIdFacetF64Codec, (field_id, docid, f));
+            assert_round_trip!(FieldDocIdFacetStringCodec, (field_id, docid, s.as_str()));
+        }
+
+        assert_same_order(&f64_keys, |left, right| left.partial_cmp(right).unwrap());
+        assert_same_order(&string_keys, Ord::cmp);
+    }
+
+    #[test]
+    fn bitmaps_round_trip() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        for _ in 0..ITERATIONS {
+            let bitmap = random_bitmap(&mut rng);
+            let len = bitmap.len();
+
+            let bytes = RoaringBitmapCodec::bytes_encode_owned(&bitmap).unwrap();
+            assert_eq!(RoaringBitmapCodec::bytes_decode(&bytes).as_ref(), Some(&bitmap));
+            assert_eq!(RoaringBitmapCodec::bytes_decode_owned(&bytes).as_ref(), Some(&bitmap));
+            assert_eq!(RoaringBitmapLenCodec::bytes_decode(&bytes), Some(len));
+
+            let bytes = CboRoaringBitmapCodec::bytes_encode_owned(&bitmap).unwrap();
+            assert_eq!(CboRoaringBitmapCodec::bytes_decode(&bytes).as_ref(), Some(&bitmap));
+            assert_eq!(CboRoaringBitmapCodec::bytes_decode_owned(&bytes).as_ref(), Some(&bitmap));
+            assert_eq!(CboRoaringBitmapLenCodec::bytes_decode(&bytes), Some(len));
+
+            let bytes = BoRoaringBitmapCodec::bytes_encode_owned(&bitmap).unwrap();
+            assert_eq!(BoRoaringBitmapCodec::bytes_decode(&bytes).as_ref(), Some(&bitmap));
+            assert_eq!(BoRoaringBitmapLenCodec::bytes_decode(&bytes), Some(len));
+
+            let value = FacetGroupValue { size: rng.gen(), bitmap: bitmap.clone() };
+            let bytes = FacetGroupValueCodec::bytes_encode_owned(&value).unwrap();
+            let decoded = FacetGroupValueCodec::bytes_decode(&bytes).unwrap();
+            assert_eq!((decoded.size, decoded.bitmap), (value.size, bitmap));
+            assert!(FacetGroupValueCodec::bytes_decode(&[]).is_none());
+        }
+    }
+}
@@ -5,6 +5,7 @@ use std::mem::size_of;
 use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
 use roaring::RoaringBitmap;
 
+use super::DeltaRoaringBitmapCodec;
 use crate::heed_codec::BytesDecodeOwned;
 
 /// This is the limit where using a byteorder became less size efficient
@@ -48,6 +49,8 @@ impl CboRoaringBitmapCodec {
                 bitmap.insert(integer);
             }
             Ok(bitmap)
+        } else if DeltaRoaringBitmapCodec::is_delta_encoded(bytes) {
+            DeltaRoaringBitmapCodec::deserialize_from(bytes)
         } else {
             // Otherwise, it means we used the classic RoaringBitmapCodec and
             // that the header takes threshold integers.
@@ -70,6 +73,8 @@ impl CboRoaringBitmapCodec {
                 while let Ok(integer) = reader.read_u32::<NativeEndian>() {
                     vec.push(integer);
                 }
+            } else if DeltaRoaringBitmapCodec::is_delta_encoded(bytes) {
+                roaring |= DeltaRoaringBitmapCodec::deserialize_from(bytes)?;
             } else {
                 roaring |= RoaringBitmap::deserialize_unchecked_from(bytes.as_ref())?;
             }
@@ -0,0 +1,193 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io;
+use std::mem::size_of;
+
+use roaring::RoaringBitmap;
+
+use super::cbo_roaring_bitmap_codec::THRESHOLD;
+use super::CboRoaringBitmapCodec;
+use crate::heed_codec::BytesDecodeOwned;
+
+/// The first byte of the delta-encoded bitmaps. A serialized `RoaringBitmap` always starts
+/// with a cookie whose first byte is `0x3A` or `0x3B`, so both encodings can be stored in
+/// the same database and are told apart by the roaring codecs.
+pub const DELTA_ENCODING_TAG: u8 = 0xDE;
+
+/// A compressed encoding of the bitmaps: a tag byte, the number of integers and the gaps
+/// between the sorted integers, all encoded as LEB128 varints.
+///
+/// It is much smaller than the roaring encoding for the sparse bitmaps, which make most of
+/// the posting lists, but bigger for the dense ones, [`Self::compress_into`] only uses it
+/// when it is smaller. Reading the length only requires reading the header.
+pub struct DeltaRoaringBitmapCodec;
+
+impl DeltaRoaringBitmapCodec {
+    pub fn is_delta_encoded(bytes: &[u8]) -> bool {
+        bytes.first() == Some(&DELTA_ENCODING_TAG)
+    }
+
+    pub fn serialize_into(bitmap: &RoaringBitmap, out: &mut Vec<u8>) {
+        out.push(DELTA_ENCODING_TAG);
+        write_varint(out, bitmap.len());
+        let mut previous = 0;
+        for integer in bitmap {
+            write_varint(out, u64::from(integer - previous));
+            previous = integer;
+        }
+    }
+
+    pub fn deserialize_from(mut bytes: &[u8]) -> io::Result<RoaringBitmap> {
+        let len = Self::read_header(&mut bytes)?;
+        let mut bitmap = RoaringBitmap::new();
+        let mut integer = 0u32;
+        for i in 0..len {
+            let gap = u32::try_from(read_varint(&mut bytes)?).map_err(|_| invalid_data())?;
+            integer = integer.checked_add(gap).ok_or_else(invalid_data)?;
+            // the integers are strictly increasing, only the first one can be zero
+            if (i != 0 && gap == 0) || !bitmap.push(integer) {
+                return Err(invalid_data());
+            }
+        }
+        if bytes.is_empty() {
+            Ok(bitmap)
+        } else {
+            Err(invalid_data())
+        }
+    }
+
+    /// Returns the number of integers of a delta-encoded bitmap without decoding them.
+    pub fn deserialize_len_from(mut bytes: &[u8]) -> io::Result<u64> {
+        Self::read_header(&mut bytes)
+    }
+
+    fn read_header(bytes: &mut &[u8]) -> io::Result<u64> {
+        match bytes.split_first() {
+            Some((&DELTA_ENCODING_TAG, tail)) => {
+                *bytes = tail;
+                read_varint(bytes)
+            }
+            _ => Err(invalid_data()),
+        }
+    }
+
+    /// Re-encodes a bitmap serialized by the roaring or the CBO roaring codec with the delta
+    /// encoding when it is smaller, returns whether the output has been written.
+    ///
+    /// The small values are kept as they are, they are encoded as raw integers in the
+    /// CBO databases and a delta-encoded value of their size could be mistaken for them.
+    pub fn compress_into(value: &[u8], out: &mut Vec<u8>) -> io::Result<bool> {
+        if value.len() <= THRESHOLD * size_of::<u32>() || Self::is_delta_encoded(value) {
+            return Ok(false);
+        }
+        let bitmap = CboRoaringBitmapCodec::deserialize_from(value)?;
+        out.clear();
+        Self::serialize_into(&bitmap, out);
+        Ok(out.len() < value.len() && out.len() > THRESHOLD * size_of::<u32>())
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> io::Result<u64> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, tail) = bytes.split_first().ok_or_else(invalid_data)?;
+        *bytes = tail;
+        n |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(invalid_data())
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid delta-encoded bitmap")
+}
+
+impl heed::BytesDecode<'_> for DeltaRoaringBitmapCodec {
+    type DItem = RoaringBitmap;
+
+    fn bytes_decode(bytes: &[u8]) -> Option<Self::DItem> {
+        Self::deserialize_from(bytes).ok()
+    }
+}
+
+impl BytesDecodeOwned for DeltaRoaringBitmapCodec {
+    type DItem = RoaringBitmap;
+
+    fn bytes_decode_owned(bytes: &[u8]) -> Option<Self::DItem> {
+        Self::deserialize_from(bytes).ok()
+    }
+}
+
+impl heed::BytesEncode<'_> for DeltaRoaringBitmapCodec {
+    type EItem = RoaringBitmap;
+
+    fn bytes_encode(item: &Self::EItem) -> Option<Cow<[u8]>> {
+        let mut out = Vec::new();
+        Self::serialize_into(item, &mut out);
+        Some(Cow::Owned(out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use heed::{BytesDecode, BytesEncode};
+
+    use super::*;
+    use crate::heed_codec::{RoaringBitmapCodec, RoaringBitmapLenCodec};
+
+    #[test]
+    fn sparse_bitmaps_are_compressed() {
+        let bitmap = RoaringBitmap::from_iter((0..1000).map(|i| i * 100));
+        let roaring = RoaringBitmapCodec::bytes_encode(&bitmap).unwrap();
+        let delta = DeltaRoaringBitmapCodec::bytes_encode(&bitmap).unwrap();
+        assert!(delta.len() < roaring.len());
+        assert_eq!(DeltaRoaringBitmapCodec::bytes_decode(&delta), Some(bitmap.clone()));
+
+        // the roaring codecs read both encodings
+        assert_eq!(RoaringBitmapCodec::bytes_decode(&delta), Some(bitmap.clone()));
+        assert_eq!(CboRoaringBitmapCodec::deserialize_from(&delta).unwrap(), bitmap);
+        assert_eq!(RoaringBitmapLenCodec::bytes_decode(&delta), Some(1000));
+
+        let mut out = Vec::new();
+        assert!(DeltaRoaringBitmapCodec::compress_into(&roaring, &mut out).unwrap());
+        assert_eq!(out, delta.as_ref());
+    }
+
+    #[test]
+    fn dense_and_small_bitmaps_are_not_compressed() {
+        let mut out = Vec::new();
+        let dense = RoaringBitmap::from_iter(0..100_000);
+        let roaring = RoaringBitmapCodec::bytes_encode(&dense).unwrap();
+        assert!(!DeltaRoaringBitmapCodec::compress_into(&roaring, &mut out).unwrap());
+
+        let small = RoaringBitmap::from_iter([1, 1_000_000]);
+        let cbo = CboRoaringBitmapCodec::bytes_encode(&small).unwrap();
+        assert!(!DeltaRoaringBitmapCodec::compress_into(&cbo, &mut out).unwrap());
+    }
+
+    #[test]
+    fn reject_invalid_bytes() {
+        let bitmap = RoaringBitmap::from_iter([0, 7, u32::MAX]);
+        let bytes = DeltaRoaringBitmapCodec::bytes_encode(&bitmap).unwrap();
+        for len in 0..bytes.len() {
+            assert_eq!(DeltaRoaringBitmapCodec::bytes_decode(&bytes[..len]), None);
+        }
+        // a gap of zero would insert the same integer twice
+        assert_eq!(DeltaRoaringBitmapCodec::bytes_decode(&[DELTA_ENCODING_TAG, 2, 5, 0]), None);
+        // the integers can't overflow
+        let overflow = [DELTA_ENCODING_TAG, 2, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 1];
+        assert_eq!(DeltaRoaringBitmapCodec::bytes_decode(&overflow), None);
+    }
+}
@@ -1,16 +1,34 @@
 use std::borrow::Cow;
+use std::io;
 
 use roaring::RoaringBitmap;
 
+use super::DeltaRoaringBitmapCodec;
 use crate::heed_codec::BytesDecodeOwned;
 
+/// Encodes the bitmaps with the roaring encoding, the delta-encoded bitmaps
+/// are decoded too, see [`DeltaRoaringBitmapCodec`].
 pub struct RoaringBitmapCodec;
 
+impl RoaringBitmapCodec {
+    pub fn deserialize_from(bytes: &[u8]) -> io::Result<RoaringBitmap> {
+        if DeltaRoaringBitmapCodec::is_delta_encoded(bytes) {
+            DeltaRoaringBitmapCodec::deserialize_from(bytes)
+        } else {
+            RoaringBitmap::deserialize_from(bytes)
+        }
+    }
+}
+
 impl heed::BytesDecode<'_> for RoaringBitmapCodec {
     type DItem = RoaringBitmap;
 
     fn bytes_decode(bytes: &[u8]) -> Option<Self::DItem> {
-        RoaringBitmap::deserialize_unchecked_from(bytes).ok()
+        if DeltaRoaringBitmapCodec::is_delta_encoded(bytes) {
+            DeltaRoaringBitmapCodec::deserialize_from(bytes).ok()
+        } else {
+            RoaringBitmap::deserialize_unchecked_from(bytes).ok()
+        }
     }
 }
 
@@ -18,7 +36,7 @@ impl BytesDecodeOwned for RoaringBitmapCodec {
     type DItem = RoaringBitmap;
 
     fn bytes_decode_owned(bytes: &[u8]) -> Option<Self::DItem> {
-        RoaringBitmap::deserialize_from(bytes).ok()
+        Self::deserialize_from(bytes).ok()
     }
 }
 
@@ -3,7 +3,7 @@ use std::mem;
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
-use crate::heed_codec::BytesDecodeOwned;
+use crate::heed_codec::{BytesDecodeOwned, DeltaRoaringBitmapCodec};
 
 const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
 const SERIAL_COOKIE: u16 = 12347;
@@ -57,7 +57,11 @@ impl heed::BytesDecode<'_> for RoaringBitmapLenCodec {
     type DItem = u64;
 
     fn bytes_decode(bytes: &[u8]) -> Option<Self::DItem> {
-        RoaringBitmapLenCodec::deserialize_from_slice(bytes).ok()
+        if DeltaRoaringBitmapCodec::is_delta_encoded(bytes) {
+            DeltaRoaringBitmapCodec::deserialize_len_from(bytes).ok()
+        } else {
+            RoaringBitmapLenCodec::deserialize_from_slice(bytes).ok()
+        }
     }
 }
 
@@ -65,7 +69,7 @@ impl BytesDecodeOwned for RoaringBitmapLenCodec {
     type DItem = u64;
 
     fn bytes_decode_owned(bytes: &[u8]) -> Option<Self::DItem> {
-        RoaringBitmapLenCodec::deserialize_from_slice(bytes).ok()
+        Self::bytes_decode(bytes)
     }
 }
 
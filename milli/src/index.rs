@@ -1,33 +1,49 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
+use std::hash::Hasher;
 use std::mem::size_of;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 use charabia::{Language, Script};
+use fst::Streamer;
+use fxhash::FxHasher64;
 use heed::flags::Flags;
 use heed::types::*;
 use heed::{CompactionOption, Database, PolyDatabase, RoTxn, RwTxn};
+use log::warn;
 use rand_pcg::Pcg32;
 use roaring::RoaringBitmap;
 use rstar::RTree;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
 use crate::distance::DotProduct;
 use crate::error::{InternalError, UserError};
+use crate::external_documents_ids::validate_document_id_value;
 use crate::facet::FacetType;
 use crate::fields_ids_map::FieldsIdsMap;
 use crate::heed_codec::facet::{
     FacetGroupKeyCodec, FacetGroupValueCodec, FieldDocIdFacetF64Codec, FieldDocIdFacetStringCodec,
     FieldIdCodec, OrderedF64Codec,
 };
-use crate::heed_codec::{FstSetCodec, ScriptLanguageCodec, StrBEU16Codec, StrRefCodec};
+use crate::heed_codec::{
+    ByteSliceRefCodec, CorruptedEntryKeyCodec, FstSetCodec, ScriptLanguageCodec, StrBEU16Codec,
+    StrRefCodec,
+};
+use crate::localized_attributes_rules::localized_fields_ids;
+use crate::query_suggestions::{normalize_query, QuerySuggestions};
 use crate::readable_slices::ReadableSlices;
+use crate::sealed_segment::SealedSegmentBuilder;
+use crate::search::facet::get_highest_level;
+use crate::settings_cache::{CachedSettings, SettingsCache};
+use crate::stop_words_suggestions::StopWordsSuggestions;
 use crate::{
-    default_criteria, CboRoaringBitmapCodec, Criterion, DocumentId, ExternalDocumentsIds,
-    FacetDistribution, FieldDistribution, FieldId, FieldIdWordCountCodec, GeoPoint, ObkvCodec,
-    OrderBy, Result, RoaringBitmapCodec, RoaringBitmapLenCodec, Search, U8StrStrCodec, BEU16,
-    BEU32,
+    all_obkv_to_json, default_criteria, CboRoaringBitmapCodec, Criterion, DocumentId,
+    ExternalDocumentsIds, FacetDistribution, FieldDistribution, FieldId, FieldIdWordCountCodec,
+    GeoPoint, LocalizedAttributesRule, LocalizedFieldsIds, Object, ObkvCodec, OrderBy, Result,
+    RoaringBitmapCodec, RoaringBitmapLenCodec, Search, U8StrStrCodec, View, BEU16, BEU32, BEU64,
 };
 
 /// The HNSW data-structure that we serialize, fill and search in.
@@ -37,6 +53,7 @@ pub const DEFAULT_MIN_WORD_LEN_ONE_TYPO: u8 = 5;
 pub const DEFAULT_MIN_WORD_LEN_TWO_TYPOS: u8 = 9;
 
 pub mod main_key {
+    pub const BOOST_FIELD_KEY: &str = "boost-field";
     pub const CRITERIA_KEY: &str = "criteria";
     pub const DISPLAYED_FIELDS_KEY: &str = "displayed-fields";
     pub const DISTINCT_FIELD_KEY: &str = "distinct-field-key";
@@ -56,10 +73,13 @@ pub mod main_key {
     pub const HARD_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "hard-external-documents-ids";
     pub const NUMBER_FACETED_DOCUMENTS_IDS_PREFIX: &str = "number-faceted-documents-ids";
     pub const PRIMARY_KEY_KEY: &str = "primary-key";
+    pub const RECENCY_FIELD_KEY: &str = "recency-field";
     pub const SEARCHABLE_FIELDS_KEY: &str = "searchable-fields";
+    pub const SETTINGS_TOKEN_KEY: &str = "settings-token";
     pub const USER_DEFINED_SEARCHABLE_FIELDS_KEY: &str = "user-defined-searchable-fields";
     pub const SOFT_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "soft-external-documents-ids";
     pub const STOP_WORDS_KEY: &str = "stop-words";
+    pub const DICTIONARY_KEY: &str = "dictionary";
     pub const STRING_FACETED_DOCUMENTS_IDS_PREFIX: &str = "string-faceted-documents-ids";
     pub const SYNONYMS_KEY: &str = "synonyms";
     pub const WORDS_FST_KEY: &str = "words-fst";
@@ -67,6 +87,7 @@ pub mod main_key {
     pub const CREATED_AT_KEY: &str = "created-at";
     pub const UPDATED_AT_KEY: &str = "updated-at";
     pub const AUTHORIZE_TYPOS: &str = "authorize-typos";
+    pub const SEARCHABLE_NUMBERS_AND_BOOLEANS: &str = "searchable-numbers-and-booleans";
     pub const ONE_TYPO_WORD_LEN: &str = "one-typo-word-len";
     pub const TWO_TYPOS_WORD_LEN: &str = "two-typos-word-len";
     pub const EXACT_WORDS: &str = "exact-words";
@@ -74,12 +95,19 @@ pub mod main_key {
     pub const MAX_VALUES_PER_FACET: &str = "max-values-per-facet";
     pub const SORT_FACET_VALUES_BY: &str = "sort-facet-values-by";
     pub const PAGINATION_MAX_TOTAL_HITS: &str = "pagination-max-total-hits";
+    pub const TYPO_DERIVATIONS_LIMIT: &str = "typo-derivations-limit";
+    pub const VIEWS_KEY: &str = "views";
+    pub const PENDING_OPTIMIZATIONS_KEY: &str = "pending-optimizations";
+    pub const LOCALIZED_ATTRIBUTES_RULES_KEY: &str = "localized-attributes-rules";
+    pub const BITMAP_COMPRESSION_KEY: &str = "bitmap-compression";
+    pub const FACET_NORMALIZATION_KEY: &str = "facet-normalization";
 }
 
 pub mod db_name {
     pub const MAIN: &str = "main";
     pub const WORD_DOCIDS: &str = "word-docids";
     pub const EXACT_WORD_DOCIDS: &str = "exact-word-docids";
+    pub const WORD_DOCIDS_COUNT: &str = "word-docids-count";
     pub const WORD_PREFIX_DOCIDS: &str = "word-prefix-docids";
     pub const EXACT_WORD_PREFIX_DOCIDS: &str = "exact-word-prefix-docids";
     pub const DOCID_WORD_POSITIONS: &str = "docid-word-positions";
@@ -102,6 +130,8 @@ pub mod db_name {
     pub const VECTOR_ID_DOCID: &str = "vector-id-docids";
     pub const DOCUMENTS: &str = "documents";
     pub const SCRIPT_LANGUAGE_DOCIDS: &str = "script_language_docids";
+    pub const QUERY_SUGGESTIONS: &str = "query-suggestions";
+    pub const CORRUPTED_ENTRIES: &str = "corrupted-entries";
 }
 
 #[derive(Clone)]
@@ -118,6 +148,9 @@ pub struct Index {
     /// A word and all the documents ids containing the word, from attributes for which typos are not allowed.
     pub exact_word_docids: Database<Str, RoaringBitmapCodec>,
 
+    /// A word and the number of documents ids of the word docids database.
+    pub word_docids_count: Database<Str, OwnedType<BEU64>>,
+
     /// A prefix of word and all the documents ids containing this prefix.
     pub word_prefix_docids: Database<Str, RoaringBitmapCodec>,
 
@@ -170,6 +203,19 @@ pub struct Index {
 
     /// Maps the document id to the document as an obkv store.
     pub(crate) documents: Database<OwnedType<BEU32>, ObkvCodec>,
+
+    /// Maps the normalized queries recorded by the users to the number of times they were recorded.
+    pub query_suggestions: Database<Str, OwnedType<BEU64>>,
+
+    /// Maps the name of a database and a key that couldn't be decoded to its raw value,
+    /// see [`QuarantineCorruptedEntries`](crate::update::QuarantineCorruptedEntries).
+    pub corrupted_entries: Database<CorruptedEntryKeyCodec, ByteSlice>,
+    /// The names of the databases and the keys the searches skipped because they couldn't be
+    /// decoded, until they are quarantined. The clones of an index share the same record.
+    pub(crate) skipped_corrupted_entries: Arc<Mutex<BTreeSet<(String, Vec<u8>)>>>,
+
+    /// The settings read by the searches, parsed once until they are updated.
+    pub(crate) settings_cache: SettingsCache,
 }
 
 impl Index {
@@ -181,7 +227,7 @@ impl Index {
     ) -> Result<Index> {
         use db_name::*;
 
-        options.max_dbs(24);
+        options.max_dbs(27);
         unsafe { options.flag(Flags::MdbAlwaysFreePages) };
 
         let env = options.open(path)?;
@@ -189,6 +235,7 @@ impl Index {
         let main = env.create_poly_database(&mut wtxn, Some(MAIN))?;
         let word_docids = env.create_database(&mut wtxn, Some(WORD_DOCIDS))?;
         let exact_word_docids = env.create_database(&mut wtxn, Some(EXACT_WORD_DOCIDS))?;
+        let word_docids_count = env.create_database(&mut wtxn, Some(WORD_DOCIDS_COUNT))?;
         let word_prefix_docids = env.create_database(&mut wtxn, Some(WORD_PREFIX_DOCIDS))?;
         let exact_word_prefix_docids =
             env.create_database(&mut wtxn, Some(EXACT_WORD_PREFIX_DOCIDS))?;
@@ -224,6 +271,8 @@ impl Index {
             env.create_database(&mut wtxn, Some(FIELD_ID_DOCID_FACET_STRINGS))?;
         let vector_id_docid = env.create_database(&mut wtxn, Some(VECTOR_ID_DOCID))?;
         let documents = env.create_database(&mut wtxn, Some(DOCUMENTS))?;
+        let query_suggestions = env.create_database(&mut wtxn, Some(QUERY_SUGGESTIONS))?;
+        let corrupted_entries = env.create_database(&mut wtxn, Some(CORRUPTED_ENTRIES))?;
         wtxn.commit()?;
 
         Index::set_creation_dates(&env, main, created_at, updated_at)?;
@@ -233,6 +282,7 @@ impl Index {
             main,
             word_docids,
             exact_word_docids,
+            word_docids_count,
             word_prefix_docids,
             exact_word_prefix_docids,
             word_pair_proximity_docids,
@@ -254,6 +304,10 @@ impl Index {
             field_id_docid_facet_strings,
             vector_id_docid,
             documents,
+            query_suggestions,
+            corrupted_entries,
+            skipped_corrupted_entries: Arc::default(),
+            settings_cache: SettingsCache::default(),
         })
     }
 
@@ -262,6 +316,28 @@ impl Index {
         Self::new_with_creation_dates(options, path, now, now)
     }
 
+    /// Creates or opens an index like [`Index::new`], the bitmaps of the word databases of
+    /// a new index are then written with the given compression.
+    ///
+    /// The compression of an index that already has one or that contains documents isn't changed.
+    pub fn new_with_bitmap_compression<P: AsRef<Path>>(
+        options: heed::EnvOpenOptions,
+        path: P,
+        compression: BitmapCompression,
+    ) -> Result<Index> {
+        let index = Self::new(options, path)?;
+        let mut wtxn = index.write_txn()?;
+        let stored = index
+            .main
+            .get::<_, Str, DecodeIgnore>(&wtxn, main_key::BITMAP_COMPRESSION_KEY)?
+            .is_some();
+        if !stored && index.number_of_documents(&wtxn)? == 0 {
+            index.put_bitmap_compression(&mut wtxn, compression)?;
+            wtxn.commit()?;
+        }
+        Ok(index)
+    }
+
     fn set_creation_dates(
         env: &heed::Env,
         main: PolyDatabase,
@@ -282,6 +358,11 @@ impl Index {
                 main_key::CREATED_AT_KEY,
                 &created_at,
             )?;
+            main.put::<_, Str, SerdeJson<FacetNormalization>>(
+                &mut txn,
+                main_key::FACET_NORMALIZATION_KEY,
+                &FacetNormalization::Words,
+            )?;
             txn.commit()?;
         }
         Ok(())
@@ -326,6 +407,34 @@ impl Index {
         self.env.copy_to_path(path, option).map_err(Into::into)
     }
 
+    /// Writes a compacted copy of the index into the `data.mdb` file of the given directory,
+    /// which must not exist yet, and returns the size of the copy in bytes.
+    ///
+    /// The copy is made from a read transaction: it contains the last committed changes and
+    /// the writes into the index can continue while it is made. It can be opened with
+    /// [`Index::open_snapshot`].
+    pub fn snapshot_to<P: AsRef<Path>>(&self, dir: P) -> Result<u64> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let file = self.copy_to_path(dir.join("data.mdb"), CompactionOption::Enabled)?;
+        file.sync_all()?;
+        Ok(file.metadata()?.len())
+    }
+
+    /// Opens the index written into the given directory by [`Index::snapshot_to`].
+    ///
+    /// Contrary to [`Index::new`], it doesn't create an empty index when the directory
+    /// doesn't contain a snapshot.
+    pub fn open_snapshot<P: AsRef<Path>>(options: heed::EnvOpenOptions, dir: P) -> Result<Index> {
+        let dir = dir.as_ref();
+        let data_path = dir.join("data.mdb");
+        if !data_path.is_file() {
+            let message = format!("no index snapshot at {}", data_path.display());
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, message).into());
+        }
+        Index::new(options, dir)
+    }
+
     /// Returns an `EnvClosingEvent` that can be used to wait for the closing event,
     /// multiple threads can wait on this event.
     ///
@@ -338,6 +447,7 @@ impl Index {
     /* documents ids */
 
     /// Writes the documents ids that corresponds to the user-ids-documents-ids FST.
+    #[cfg(feature = "update")]
     pub(crate) fn put_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -364,6 +474,7 @@ impl Index {
     /* deleted documents ids */
 
     /// Writes the soft deleted documents ids.
+    #[cfg(feature = "update")]
     pub(crate) fn put_soft_deleted_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -387,12 +498,14 @@ impl Index {
     /* primary key */
 
     /// Writes the documents primary key, this is the field name that is used to store the id.
+    #[cfg(feature = "update")]
     pub(crate) fn put_primary_key(&self, wtxn: &mut RwTxn, primary_key: &str) -> heed::Result<()> {
         self.set_updated_at(wtxn, &OffsetDateTime::now_utc())?;
         self.main.put::<_, Str, Str>(wtxn, main_key::PRIMARY_KEY_KEY, primary_key)
     }
 
     /// Deletes the primary key of the documents, this can be done to reset indexes settings.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_primary_key(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::PRIMARY_KEY_KEY)
     }
@@ -405,6 +518,7 @@ impl Index {
     /* external documents ids */
 
     /// Writes the external documents ids and internal ids (i.e. `u32`).
+    #[cfg(feature = "update")]
     pub(crate) fn put_external_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -445,15 +559,79 @@ impl Index {
         Ok(ExternalDocumentsIds::new(hard, soft, soft_deleted_docids))
     }
 
+    /// Returns the internal id of the document with the given external id,
+    /// `None` if there is no such document.
+    pub fn internal_id_of(&self, rtxn: &RoTxn, external_id: &str) -> Result<Option<DocumentId>> {
+        Ok(self.external_documents_ids(rtxn)?.get(external_id))
+    }
+
+    /// Returns the external id of a document, i.e. the value of its primary key,
+    /// `None` if the document doesn't exist or doesn't have a valid external id.
+    pub fn external_id_of(&self, rtxn: &RoTxn, docid: DocumentId) -> Result<Option<String>> {
+        let primary_key = match self.primary_key(rtxn)? {
+            Some(primary_key) => primary_key,
+            None => return Ok(None),
+        };
+        if self.soft_deleted_documents_ids(rtxn)?.contains(docid) {
+            return Ok(None);
+        }
+        let obkv = match self.documents.get(rtxn, &BEU32::new(docid))? {
+            Some(obkv) => obkv,
+            None => return Ok(None),
+        };
+
+        // The primary key can be nested in the document.
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let document = flatten_serde_json::flatten(&all_obkv_to_json(obkv, &fields_ids_map)?);
+        match document.get(primary_key).cloned() {
+            Some(value) => Ok(validate_document_id_value(value)?.ok()),
+            None => Ok(None),
+        }
+    }
+
+    /* settings cache */
+
+    /// Invalidates the [`cached_settings`](Self::cached_settings), the token is random so that
+    /// the token of an aborted transaction can't be written again by another one.
+    fn put_settings_token(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        let token = uuid::Uuid::new_v4();
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::SETTINGS_TOKEN_KEY, token.as_bytes())
+    }
+
+    /// Returns the criteria, the searchable fields ids, the filterable fields, the synonyms and
+    /// the dictionary, they are only read from the database the first time they are requested
+    /// after an update.
+    pub fn cached_settings(&self, rtxn: &RoTxn) -> Result<Arc<CachedSettings>> {
+        let token = self.main.get::<_, Str, ByteSlice>(rtxn, main_key::SETTINGS_TOKEN_KEY)?;
+        if let Some(settings) = self.settings_cache.get(token) {
+            return Ok(settings);
+        }
+
+        let settings = Arc::new(CachedSettings {
+            criteria: self.criteria(rtxn)?,
+            searchable_fields_ids: self.searchable_fields_ids(rtxn)?,
+            filterable_fields: self.filterable_fields(rtxn)?,
+            synonyms: self.synonyms(rtxn)?,
+            dictionary: match self.dictionary(rtxn)? {
+                Some(dictionary) => Some(dictionary.stream().into_strs()?),
+                None => None,
+            },
+        });
+        self.settings_cache.store(token, settings.clone());
+        Ok(settings)
+    }
+
     /* fields ids map */
 
     /// Writes the fields ids map which associate the documents keys with an internal field id
     /// (i.e. `u8`), this field id is used to identify fields in the obkv documents.
+    #[cfg(feature = "update")]
     pub(crate) fn put_fields_ids_map(
         &self,
         wtxn: &mut RwTxn,
         map: &FieldsIdsMap,
     ) -> heed::Result<()> {
+        self.put_settings_token(wtxn)?;
         self.main.put::<_, Str, SerdeJson<FieldsIdsMap>>(wtxn, main_key::FIELDS_IDS_MAP_KEY, map)
     }
 
@@ -469,6 +647,7 @@ impl Index {
     /* geo rtree */
 
     /// Writes the provided `rtree` which associates coordinates to documents ids.
+    #[cfg(feature = "update")]
     pub(crate) fn put_geo_rtree(
         &self,
         wtxn: &mut RwTxn,
@@ -478,6 +657,7 @@ impl Index {
     }
 
     /// Delete the `rtree` which associates coordinates to documents ids.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_geo_rtree(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::GEO_RTREE_KEY)
     }
@@ -496,6 +676,7 @@ impl Index {
     /* geo faceted */
 
     /// Writes the documents ids that are faceted with a _geo field.
+    #[cfg(feature = "update")]
     pub(crate) fn put_geo_faceted_documents_ids(
         &self,
         wtxn: &mut RwTxn,
@@ -509,6 +690,7 @@ impl Index {
     }
 
     /// Delete the documents ids that are faceted with a _geo field.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_geo_faceted_documents_ids(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::GEO_FACETED_DOCUMENTS_IDS_KEY)
     }
@@ -527,6 +709,7 @@ impl Index {
     /* vector HNSW */
 
     /// Writes the provided `hnsw`.
+    #[cfg(feature = "update")]
     pub(crate) fn put_vector_hnsw(&self, wtxn: &mut RwTxn, hnsw: &Hnsw) -> heed::Result<()> {
         // We must delete all the chunks before we write the new HNSW chunks.
         self.delete_vector_hnsw(wtxn)?;
@@ -543,6 +726,7 @@ impl Index {
     }
 
     /// Delete the `hnsw`.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_vector_hnsw(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         let mut iter = self.main.prefix_iter_mut::<_, ByteSlice, DecodeIgnore>(
             wtxn,
@@ -578,6 +762,7 @@ impl Index {
 
     /// Writes the field distribution which associates every field name with
     /// the number of times it occurs in the documents.
+    #[cfg(feature = "update")]
     pub(crate) fn put_field_distribution(
         &self,
         wtxn: &mut RwTxn,
@@ -603,6 +788,7 @@ impl Index {
 
     /// Writes the fields that must be displayed in the defined order.
     /// There must be not be any duplicate field id.
+    #[cfg(feature = "update")]
     pub(crate) fn put_displayed_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -617,6 +803,7 @@ impl Index {
 
     /// Deletes the displayed fields ids, this will make the engine to display
     /// all the documents attributes in the order of the `FieldsIdsMap`.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_displayed_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::DISPLAYED_FIELDS_KEY)
     }
@@ -647,6 +834,7 @@ impl Index {
     /* searchable fields */
 
     /// Write the user defined searchable fields and generate the real searchable fields from the specified fields ids map.
+    #[cfg(feature = "update")]
     pub(crate) fn put_all_searchable_fields_from_fields_ids_map(
         &self,
         wtxn: &mut RwTxn,
@@ -676,6 +864,7 @@ impl Index {
         self.put_searchable_fields(wtxn, &real_fields)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_all_searchable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         let did_delete_searchable = self.delete_searchable_fields(wtxn)?;
         let did_delete_user_defined = self.delete_user_defined_searchable_fields(wtxn)?;
@@ -684,6 +873,7 @@ impl Index {
 
     /// Writes the searchable fields, when this list is specified, only these are indexed.
     fn put_searchable_fields(&self, wtxn: &mut RwTxn, fields: &[&str]) -> heed::Result<()> {
+        self.put_settings_token(wtxn)?;
         self.main.put::<_, Str, SerdeBincode<&[&str]>>(
             wtxn,
             main_key::SEARCHABLE_FIELDS_KEY,
@@ -693,6 +883,7 @@ impl Index {
 
     /// Deletes the searchable fields, when no fields are specified, all fields are indexed.
     fn delete_searchable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.put_settings_token(wtxn)?;
         self.main.delete::<_, Str>(wtxn, main_key::SEARCHABLE_FIELDS_KEY)
     }
 
@@ -720,6 +911,7 @@ impl Index {
     }
 
     /// Writes the searchable fields, when this list is specified, only these are indexed.
+    #[cfg(feature = "update")]
     pub(crate) fn put_user_defined_searchable_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -733,6 +925,7 @@ impl Index {
     }
 
     /// Deletes the searchable fields, when no fields are specified, all fields are indexed.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_user_defined_searchable_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -752,16 +945,20 @@ impl Index {
     /* filterable fields */
 
     /// Writes the filterable fields names in the database.
+    #[cfg(feature = "update")]
     pub(crate) fn put_filterable_fields(
         &self,
         wtxn: &mut RwTxn,
         fields: &HashSet<String>,
     ) -> heed::Result<()> {
+        self.put_settings_token(wtxn)?;
         self.main.put::<_, Str, SerdeJson<_>>(wtxn, main_key::FILTERABLE_FIELDS_KEY, fields)
     }
 
     /// Deletes the filterable fields ids in the database.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_filterable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.put_settings_token(wtxn)?;
         self.main.delete::<_, Str>(wtxn, main_key::FILTERABLE_FIELDS_KEY)
     }
 
@@ -791,6 +988,7 @@ impl Index {
     /* sortable fields */
 
     /// Writes the sortable fields names in the database.
+    #[cfg(feature = "update")]
     pub(crate) fn put_sortable_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -800,6 +998,7 @@ impl Index {
     }
 
     /// Deletes the sortable fields ids in the database.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_sortable_fields(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::SORTABLE_FIELDS_KEY)
     }
@@ -822,6 +1021,7 @@ impl Index {
     /* faceted fields */
 
     /// Writes the faceted fields in the database.
+    #[cfg(feature = "update")]
     pub(crate) fn put_faceted_fields(
         &self,
         wtxn: &mut RwTxn,
@@ -857,7 +1057,8 @@ impl Index {
 
     /// Returns the user defined faceted fields names.
     ///
-    /// The user faceted fields are the union of all the filterable, sortable, distinct, and Asc/Desc fields.
+    /// The user faceted fields are the union of all the filterable, sortable, distinct, recency,
+    /// boost and Asc/Desc fields.
     pub fn user_defined_faceted_fields(&self, rtxn: &RoTxn) -> Result<HashSet<String>> {
         let filterable_fields = self.filterable_fields(rtxn)?;
         let sortable_fields = self.sortable_fields(rtxn)?;
@@ -874,6 +1075,12 @@ impl Index {
         if let Some(field) = distinct_field {
             faceted_fields.insert(field.to_owned());
         }
+        if let Some(field) = self.recency_field(rtxn)? {
+            faceted_fields.insert(field.to_owned());
+        }
+        if let Some(field) = self.boost_field(rtxn)? {
+            faceted_fields.insert(field.to_owned());
+        }
 
         Ok(faceted_fields)
     }
@@ -971,6 +1178,7 @@ impl Index {
 
     /* distinct field */
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_distinct_field(
         &self,
         wtxn: &mut RwTxn,
@@ -983,21 +1191,66 @@ impl Index {
         self.main.get::<_, Str, Str>(rtxn, main_key::DISTINCT_FIELD_KEY)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_distinct_field(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::DISTINCT_FIELD_KEY)
     }
 
+    /* recency field */
+
+    #[cfg(feature = "update")]
+    pub(crate) fn put_recency_field(
+        &self,
+        wtxn: &mut RwTxn,
+        recency_field: &str,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, Str>(wtxn, main_key::RECENCY_FIELD_KEY, recency_field)
+    }
+
+    /// The field whose most recent values are ranked first among the documents
+    /// that all the ranking rules consider equal.
+    pub fn recency_field<'a>(&self, rtxn: &'a RoTxn) -> heed::Result<Option<&'a str>> {
+        self.main.get::<_, Str, Str>(rtxn, main_key::RECENCY_FIELD_KEY)
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_recency_field(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::RECENCY_FIELD_KEY)
+    }
+
+    /* boost field */
+
+    #[cfg(feature = "update")]
+    pub(crate) fn put_boost_field(&self, wtxn: &mut RwTxn, boost_field: &str) -> heed::Result<()> {
+        self.main.put::<_, Str, Str>(wtxn, main_key::BOOST_FIELD_KEY, boost_field)
+    }
+
+    /// The numeric field multiplying the global score of the documents, the best ranked
+    /// documents of a search are reordered by their boosted score.
+    pub fn boost_field<'a>(&self, rtxn: &'a RoTxn) -> heed::Result<Option<&'a str>> {
+        self.main.get::<_, Str, Str>(rtxn, main_key::BOOST_FIELD_KEY)
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_boost_field(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::BOOST_FIELD_KEY)
+    }
+
     /* criteria */
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_criteria(
         &self,
         wtxn: &mut RwTxn,
         criteria: &[Criterion],
     ) -> heed::Result<()> {
+        self.put_settings_token(wtxn)?;
         self.main.put::<_, Str, SerdeJson<&[Criterion]>>(wtxn, main_key::CRITERIA_KEY, &criteria)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_criteria(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.put_settings_token(wtxn)?;
         self.main.delete::<_, Str>(wtxn, main_key::CRITERIA_KEY)
     }
 
@@ -1011,6 +1264,7 @@ impl Index {
     /* words fst */
 
     /// Writes the FST which is the words dictionary of the engine.
+    #[cfg(feature = "update")]
     pub(crate) fn put_words_fst<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
@@ -1020,15 +1274,19 @@ impl Index {
     }
 
     /// Returns the FST which is the words dictionary of the engine.
+    ///
+    /// The FST is never copied, it is read from the LMDB pages mapped in memory
+    /// and lives as long as the transaction.
     pub fn words_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
-        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::WORDS_FST_KEY)? {
-            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
+        match self.main.get::<_, Str, FstSetCodec>(rtxn, main_key::WORDS_FST_KEY)? {
+            Some(fst) => Ok(fst.map_data(Cow::Borrowed)?),
             None => Ok(fst::Set::default().map_data(Cow::Owned)?),
         }
     }
 
     /* stop words */
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_stop_words<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
@@ -1037,6 +1295,7 @@ impl Index {
         self.main.put::<_, Str, ByteSlice>(wtxn, main_key::STOP_WORDS_KEY, fst.as_fst().as_bytes())
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_stop_words(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(wtxn, main_key::STOP_WORDS_KEY)
     }
@@ -1048,17 +1307,49 @@ impl Index {
         }
     }
 
+    /* dictionary */
+
+    #[cfg(feature = "update")]
+    pub(crate) fn put_dictionary<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        self.put_settings_token(wtxn)?;
+        self.main.put::<_, Str, ByteSlice>(wtxn, main_key::DICTIONARY_KEY, fst.as_fst().as_bytes())
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_dictionary(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.put_settings_token(wtxn)?;
+        self.main.delete::<_, Str>(wtxn, main_key::DICTIONARY_KEY)
+    }
+
+    /// Returns the words that the tokenizer must never split, e.g. the product names.
+    ///
+    /// The tokenizers use the words cached by [`Self::cached_settings`] instead.
+    pub fn dictionary<'t>(&self, rtxn: &'t RoTxn) -> Result<Option<fst::Set<&'t [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::DICTIONARY_KEY)? {
+            Some(bytes) => Ok(Some(fst::Set::new(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     /* synonyms */
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_synonyms(
         &self,
         wtxn: &mut RwTxn,
         synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
     ) -> heed::Result<()> {
+        self.put_settings_token(wtxn)?;
         self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::SYNONYMS_KEY, synonyms)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_synonyms(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.put_settings_token(wtxn)?;
         self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)
     }
 
@@ -1081,6 +1372,7 @@ impl Index {
     /* words prefixes fst */
 
     /// Writes the FST which is the words prefixes dictionnary of the engine.
+    #[cfg(feature = "update")]
     pub(crate) fn put_words_prefixes_fst<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
@@ -1095,8 +1387,8 @@ impl Index {
 
     /// Returns the FST which is the words prefixes dictionnary of the engine.
     pub fn words_prefixes_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Set<Cow<'t, [u8]>>> {
-        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::WORDS_PREFIXES_FST_KEY)? {
-            Some(bytes) => Ok(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?),
+        match self.main.get::<_, Str, FstSetCodec>(rtxn, main_key::WORDS_PREFIXES_FST_KEY)? {
+            Some(fst) => Ok(fst.map_data(Cow::Borrowed)?),
             None => Ok(fst::Set::default().map_data(Cow::Owned)?),
         }
     }
@@ -1104,9 +1396,24 @@ impl Index {
     /* word documents count */
 
     /// Returns the number of documents ids associated with the given word,
-    /// it is much faster than deserializing the bitmap and getting the length of it.
+    /// it is read from the word docids count database without reading the bitmap.
+    ///
+    /// The words indexed before this database existed are counted from the bitmap header.
     pub fn word_documents_count(&self, rtxn: &RoTxn, word: &str) -> heed::Result<Option<u64>> {
-        self.word_docids.remap_data_type::<RoaringBitmapLenCodec>().get(rtxn, word)
+        match self.word_docids_count.get(rtxn, word)? {
+            Some(count) => Ok(Some(count.get())),
+            None => self.word_docids.remap_data_type::<RoaringBitmapLenCodec>().get(rtxn, word),
+        }
+    }
+
+    /// Returns the number of documents ids associated with the given prefix
+    /// of the word prefix databases.
+    pub fn word_prefix_documents_count(
+        &self,
+        rtxn: &RoTxn,
+        prefix: &str,
+    ) -> heed::Result<Option<u64>> {
+        self.word_prefix_docids.remap_data_type::<RoaringBitmapLenCodec>().get(rtxn, prefix)
     }
 
     /* documents */
@@ -1140,6 +1447,70 @@ impl Index {
         self.iter_documents(rtxn, ids)?.collect()
     }
 
+    /// Returns a [`Vec`] of the requested documents where only the given fields are decoded.
+    ///
+    /// It is much faster than decoding the whole documents when only a few fields
+    /// of large documents are needed. The fields are returned in the requested order
+    /// and the fields unknown to the index are ignored. Returns an error if a document is missing.
+    pub fn documents_with_fields(
+        &self,
+        rtxn: &RoTxn,
+        ids: impl IntoIterator<Item = DocumentId>,
+        fields: &[&str],
+    ) -> Result<Vec<(DocumentId, Object)>> {
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let fields: Vec<_> = fields
+            .iter()
+            .filter_map(|&name| fields_ids_map.id(name).map(|field_id| (field_id, name)))
+            .collect();
+
+        self.iter_documents(rtxn, ids)?
+            .map(|result| {
+                let (id, obkv) = result?;
+                let mut document = Object::new();
+                for &(field_id, name) in &fields {
+                    if let Some(value) = obkv.get(field_id) {
+                        let value =
+                            serde_json::from_slice(value).map_err(InternalError::SerdeJson)?;
+                        document.insert(name.to_string(), value);
+                    }
+                }
+                Ok((id, document))
+            })
+            .collect()
+    }
+
+    /// Returns a [`Vec`] of the requested documents as seen through the given view.
+    ///
+    /// The documents are projected on the fields displayed by the view and the documents
+    /// that don't match the filter of the view are not returned.
+    pub fn view_documents(
+        &self,
+        rtxn: &RoTxn,
+        view: &View,
+        ids: impl IntoIterator<Item = DocumentId>,
+    ) -> Result<Vec<(DocumentId, Object)>> {
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let displayed_fields = match view.displayed_fields_ids(rtxn, self)? {
+            Some(fields) => fields,
+            None => fields_ids_map.ids().collect(),
+        };
+        let ids: Vec<_> = match view.filter()? {
+            Some(filter) => {
+                let candidates = filter.evaluate(rtxn, self)?;
+                ids.into_iter().filter(|id| candidates.contains(*id)).collect()
+            }
+            None => ids.into_iter().collect(),
+        };
+
+        self.iter_documents(rtxn, ids)?
+            .map(|result| {
+                let (id, obkv) = result?;
+                Ok((id, crate::obkv_to_json(&displayed_fields, &fields_ids_map, obkv)?))
+            })
+            .collect()
+    }
+
     /// Returns an iterator over all the documents in the index.
     pub fn all_documents<'a, 't: 'a>(
         &'a self,
@@ -1196,6 +1567,7 @@ impl Index {
         }
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_authorize_typos(&self, txn: &mut RwTxn, flag: bool) -> heed::Result<()> {
         // It is not possible to put a bool in heed with OwnedType, so we put a u8 instead. We
         // identify 0 as being false, and anything else as true. The absence of a value is true,
@@ -1205,6 +1577,40 @@ impl Index {
         Ok(())
     }
 
+    /// Returns `true` if the numbers and booleans of the searchable fields
+    /// are indexed as words, and are therefore full-text searchable.
+    pub fn searchable_numbers_and_booleans(&self, txn: &RoTxn) -> heed::Result<bool> {
+        // The absence of a value is true, because by default, we index the numbers and booleans.
+        match self
+            .main
+            .get::<_, Str, OwnedType<u8>>(txn, main_key::SEARCHABLE_NUMBERS_AND_BOOLEANS)?
+        {
+            Some(0) => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn put_searchable_numbers_and_booleans(
+        &self,
+        txn: &mut RwTxn,
+        flag: bool,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<u8>>(
+            txn,
+            main_key::SEARCHABLE_NUMBERS_AND_BOOLEANS,
+            &(flag as u8),
+        )
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_searchable_numbers_and_booleans(
+        &self,
+        txn: &mut RwTxn,
+    ) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(txn, main_key::SEARCHABLE_NUMBERS_AND_BOOLEANS)
+    }
+
     pub fn min_word_len_one_typo(&self, txn: &RoTxn) -> heed::Result<u8> {
         // It is not possible to put a bool in heed with OwnedType, so we put a u8 instead. We
         // identify 0 as being false, and anything else as true. The absence of a value is true,
@@ -1215,6 +1621,7 @@ impl Index {
             .unwrap_or(DEFAULT_MIN_WORD_LEN_ONE_TYPO))
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_min_word_len_one_typo(&self, txn: &mut RwTxn, val: u8) -> heed::Result<()> {
         // It is not possible to put a bool in heed with OwnedType, so we put a u8 instead. We
         // identify 0 as being false, and anything else as true. The absence of a value is true,
@@ -1233,6 +1640,7 @@ impl Index {
             .unwrap_or(DEFAULT_MIN_WORD_LEN_TWO_TYPOS))
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_min_word_len_two_typos(&self, txn: &mut RwTxn, val: u8) -> heed::Result<()> {
         // It is not possible to put a bool in heed with OwnedType, so we put a u8 instead. We
         // identify 0 as being false, and anything else as true. The absence of a value is true,
@@ -1249,6 +1657,7 @@ impl Index {
         }
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_exact_words<A: AsRef<[u8]>>(
         &self,
         txn: &mut RwTxn,
@@ -1278,12 +1687,14 @@ impl Index {
     }
 
     /// Writes the exact attributes to the database.
+    #[cfg(feature = "update")]
     pub(crate) fn put_exact_attributes(&self, txn: &mut RwTxn, attrs: &[&str]) -> Result<()> {
         self.main.put::<_, Str, SerdeBincode<&[&str]>>(txn, main_key::EXACT_ATTRIBUTES, &attrs)?;
         Ok(())
     }
 
     /// Clears the exact attributes from the store.
+    #[cfg(feature = "update")]
     pub(crate) fn delete_exact_attributes(&self, txn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(txn, main_key::EXACT_ATTRIBUTES)
     }
@@ -1292,10 +1703,12 @@ impl Index {
         self.main.get::<_, Str, OwnedType<usize>>(txn, main_key::MAX_VALUES_PER_FACET)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_max_values_per_facet(&self, txn: &mut RwTxn, val: usize) -> heed::Result<()> {
         self.main.put::<_, Str, OwnedType<usize>>(txn, main_key::MAX_VALUES_PER_FACET, &val)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_max_values_per_facet(&self, txn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(txn, main_key::MAX_VALUES_PER_FACET)
     }
@@ -1313,6 +1726,7 @@ impl Index {
         Ok(orders)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_sort_facet_values_by(
         &self,
         txn: &mut RwTxn,
@@ -1321,6 +1735,7 @@ impl Index {
         self.main.put::<_, Str, SerdeJson<_>>(txn, main_key::SORT_FACET_VALUES_BY, &val)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_sort_facet_values_by(&self, txn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(txn, main_key::SORT_FACET_VALUES_BY)
     }
@@ -1329,6 +1744,7 @@ impl Index {
         self.main.get::<_, Str, OwnedType<usize>>(txn, main_key::PAGINATION_MAX_TOTAL_HITS)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn put_pagination_max_total_hits(
         &self,
         txn: &mut RwTxn,
@@ -1337,140 +1753,1076 @@ impl Index {
         self.main.put::<_, Str, OwnedType<usize>>(txn, main_key::PAGINATION_MAX_TOTAL_HITS, &val)
     }
 
+    #[cfg(feature = "update")]
     pub(crate) fn delete_pagination_max_total_hits(&self, txn: &mut RwTxn) -> heed::Result<bool> {
         self.main.delete::<_, Str>(txn, main_key::PAGINATION_MAX_TOTAL_HITS)
     }
 
-    /* script  language docids */
-    /// Retrieve all the documents ids that correspond with (Script, Language) key, `None` if it is any.
-    pub fn script_language_documents_ids(
+    /// The maximum number of typo derivations of a query term, the ones that appear in
+    /// the most documents are kept.
+    pub fn typo_derivations_limit(&self, txn: &RoTxn) -> heed::Result<Option<usize>> {
+        self.main.get::<_, Str, OwnedType<usize>>(txn, main_key::TYPO_DERIVATIONS_LIMIT)
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn put_typo_derivations_limit(
         &self,
-        rtxn: &RoTxn,
-        key: &(Script, Language),
-    ) -> heed::Result<Option<RoaringBitmap>> {
-        let soft_deleted_documents = self.soft_deleted_documents_ids(rtxn)?;
-        let doc_ids = self.script_language_docids.get(rtxn, key)?;
-        Ok(doc_ids.map(|ids| ids - soft_deleted_documents))
+        txn: &mut RwTxn,
+        val: usize,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, OwnedType<usize>>(txn, main_key::TYPO_DERIVATIONS_LIMIT, &val)
     }
 
-    pub fn script_language(&self, rtxn: &RoTxn) -> heed::Result<HashMap<Script, Vec<Language>>> {
-        let soft_deleted_documents = self.soft_deleted_documents_ids(rtxn)?;
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_typo_derivations_limit(&self, txn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(txn, main_key::TYPO_DERIVATIONS_LIMIT)
+    }
 
-        let mut script_language: HashMap<Script, Vec<Language>> = HashMap::new();
-        let mut script_language_doc_count: Vec<(Script, Language, u64)> = Vec::new();
-        let mut total = 0;
-        for sl in self.script_language_docids.iter(rtxn)? {
-            let ((script, language), docids) = sl?;
+    /* views */
 
-            // keep only Languages that contains at least 1 document.
-            let remaining_documents_count = (docids - &soft_deleted_documents).len();
-            total += remaining_documents_count;
-            if remaining_documents_count > 0 {
-                script_language_doc_count.push((script, language, remaining_documents_count));
-            }
-        }
+    /// Writes the views of this index, every view is identified by its name.
+    #[cfg(feature = "update")]
+    pub(crate) fn put_views(
+        &self,
+        txn: &mut RwTxn,
+        views: &BTreeMap<String, View>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(txn, main_key::VIEWS_KEY, views)
+    }
 
-        let threshold = total / 20; // 5% (arbitrary)
-        for (script, language, count) in script_language_doc_count {
-            if count > threshold {
-                if let Some(languages) = script_language.get_mut(&script) {
-                    (*languages).push(language);
-                } else {
-                    script_language.insert(script, vec![language]);
-                }
-            }
-        }
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_views(&self, txn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(txn, main_key::VIEWS_KEY)
+    }
 
-        Ok(script_language)
+    /// Returns all the views of this index associated with their names.
+    pub fn views(&self, txn: &RoTxn) -> heed::Result<BTreeMap<String, View>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<BTreeMap<String, View>>>(txn, main_key::VIEWS_KEY)?
+            .unwrap_or_default())
     }
-}
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use std::collections::HashSet;
-    use std::ops::Deref;
+    /// Returns the view with the given name, `None` if there is no such view.
+    pub fn view(&self, txn: &RoTxn, name: &str) -> heed::Result<Option<View>> {
+        Ok(self.views(txn)?.remove(name))
+    }
 
-    use big_s::S;
-    use heed::{EnvOpenOptions, RwTxn};
-    use maplit::hashset;
-    use tempfile::TempDir;
+    /* localized attributes rules */
 
-    use crate::documents::DocumentsBatchReader;
-    use crate::error::{Error, InternalError};
-    use crate::index::{DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS};
-    use crate::update::{
-        self, DeleteDocuments, DeletionStrategy, IndexDocuments, IndexDocumentsConfig,
-        IndexDocumentsMethod, IndexerConfig, Settings,
-    };
-    use crate::{db_snap, obkv_to_json, Filter, Index, Search, SearchResult};
+    #[cfg(feature = "update")]
+    pub(crate) fn put_localized_attributes_rules(
+        &self,
+        txn: &mut RwTxn,
+        rules: &[LocalizedAttributesRule],
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(txn, main_key::LOCALIZED_ATTRIBUTES_RULES_KEY, &rules)
+    }
 
-    pub(crate) struct TempIndex {
-        pub inner: Index,
-        pub indexer_config: IndexerConfig,
-        pub index_documents_config: IndexDocumentsConfig,
-        _tempdir: TempDir,
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_localized_attributes_rules(&self, txn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(txn, main_key::LOCALIZED_ATTRIBUTES_RULES_KEY)
     }
 
-    impl Deref for TempIndex {
-        type Target = Index;
+    /// Returns the rules associating the fields with the languages they are written in.
+    pub fn localized_attributes_rules(
+        &self,
+        txn: &RoTxn,
+    ) -> heed::Result<Vec<LocalizedAttributesRule>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<Vec<LocalizedAttributesRule>>>(
+                txn,
+                main_key::LOCALIZED_ATTRIBUTES_RULES_KEY,
+            )?
+            .unwrap_or_default())
+    }
 
-        fn deref(&self) -> &Self::Target {
-            &self.inner
+    /// Returns the allow lists of the tokenizer of the fields matching a localized attributes rule.
+    pub fn localized_fields_ids(&self, txn: &RoTxn) -> Result<LocalizedFieldsIds> {
+        let rules = self.localized_attributes_rules(txn)?;
+        if rules.is_empty() {
+            return Ok(LocalizedFieldsIds::new());
         }
+        let fields_ids_map = self.fields_ids_map(txn)?;
+        Ok(localized_fields_ids(&rules, &fields_ids_map)?)
     }
 
-    impl TempIndex {
-        /// Creates a temporary index
-        pub fn new_with_map_size(size: usize) -> Self {
-            let mut options = EnvOpenOptions::new();
-            options.map_size(size);
-            let _tempdir = TempDir::new_in(".").unwrap();
-            let inner = Index::new(options, _tempdir.path()).unwrap();
-            let indexer_config = IndexerConfig::default();
-            let index_documents_config = IndexDocumentsConfig::default();
-            Self { inner, indexer_config, index_documents_config, _tempdir }
-        }
-        /// Creates a temporary index, with a default `4096 * 2000` size. This should be enough for
-        /// most tests.
-        pub fn new() -> Self {
-            Self::new_with_map_size(4096 * 2000)
-        }
-        pub fn add_documents_using_wtxn<'t, R>(
-            &'t self,
-            wtxn: &mut RwTxn<'t, '_>,
-            documents: DocumentsBatchReader<R>,
-        ) -> Result<(), crate::error::Error>
-        where
-            R: std::io::Read + std::io::Seek,
-        {
-            let builder = IndexDocuments::new(
-                wtxn,
-                self,
-                &self.indexer_config,
-                self.index_documents_config.clone(),
-                |_| (),
-                || false,
-            )
-            .unwrap();
-            let (builder, user_error) = builder.add_documents(documents).unwrap();
-            user_error?;
-            builder.execute()?;
-            Ok(())
-        }
-        pub fn add_documents<R>(
-            &self,
-            documents: DocumentsBatchReader<R>,
-        ) -> Result<(), crate::error::Error>
-        where
-            R: std::io::Read + std::io::Seek,
-        {
-            let mut wtxn = self.write_txn().unwrap();
-            self.add_documents_using_wtxn(&mut wtxn, documents)?;
-            wtxn.commit().unwrap();
-            Ok(())
-        }
+    /* pending optimizations */
 
-        pub fn update_settings(
+    #[cfg(feature = "update")]
+    pub(crate) fn put_pending_optimizations(
+        &self,
+        txn: &mut RwTxn,
+        steps: &[OptimizationStep],
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<_>>(txn, main_key::PENDING_OPTIMIZATIONS_KEY, &steps)
+    }
+
+    #[cfg(feature = "update")]
+    pub(crate) fn delete_pending_optimizations(&self, txn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(txn, main_key::PENDING_OPTIMIZATIONS_KEY)
+    }
+
+    /// Returns the optimization steps that the last [`OptimizeIndex`](crate::update::OptimizeIndex)
+    /// didn't have the time to perform.
+    pub fn pending_optimizations(&self, txn: &RoTxn) -> heed::Result<Vec<OptimizationStep>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<Vec<OptimizationStep>>>(
+                txn,
+                main_key::PENDING_OPTIMIZATIONS_KEY,
+            )?
+            .unwrap_or_default())
+    }
+
+    /* bitmap compression */
+
+    pub(crate) fn put_bitmap_compression(
+        &self,
+        wtxn: &mut RwTxn,
+        compression: BitmapCompression,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<BitmapCompression>>(
+            wtxn,
+            main_key::BITMAP_COMPRESSION_KEY,
+            &compression,
+        )
+    }
+
+    /// Returns the encoding of the bitmaps written into the word databases.
+    pub fn bitmap_compression(&self, rtxn: &RoTxn) -> heed::Result<BitmapCompression> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<BitmapCompression>>(rtxn, main_key::BITMAP_COMPRESSION_KEY)?
+            .unwrap_or_default())
+    }
+
+    /* facet normalization */
+
+    pub(crate) fn put_facet_normalization(
+        &self,
+        wtxn: &mut RwTxn,
+        normalization: FacetNormalization,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeJson<FacetNormalization>>(
+            wtxn,
+            main_key::FACET_NORMALIZATION_KEY,
+            &normalization,
+        )
+    }
+
+    /// Returns how the facet strings stored in the index have been normalized.
+    pub fn facet_normalization(&self, rtxn: &RoTxn) -> heed::Result<FacetNormalization> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeJson<FacetNormalization>>(rtxn, main_key::FACET_NORMALIZATION_KEY)?
+            .unwrap_or_default())
+    }
+
+    /* warmup */
+
+    /// Reads the data structures described by the spec to pull them into the page cache.
+    ///
+    /// LMDB memory-maps the database file, the first queries made after the startup
+    /// of the engine, or after a snapshot restore, are slow because they fault the pages
+    /// in. Calling this method once beforehand moves this cost out of the queries.
+    pub fn warmup(&self, rtxn: &RoTxn, spec: &WarmupSpec) -> Result<WarmupReport> {
+        let mut report = WarmupReport::default();
+
+        if spec.words_fst {
+            for key in [main_key::WORDS_FST_KEY, main_key::WORDS_PREFIXES_FST_KEY] {
+                if let Some(bytes) = self.main.get::<_, Str, ByteSlice>(rtxn, key)? {
+                    report.bytes_read += touch_pages(bytes);
+                }
+            }
+        }
+
+        if spec.prefixes != 0 {
+            // We read the docids of the prefixes that match the most documents first,
+            // they are the ones that are the most likely to be used by the queries.
+            let prefixes_fst = self.words_prefixes_fst(rtxn)?;
+            let prefixes_len = self.word_prefix_docids.remap_data_type::<RoaringBitmapLenCodec>();
+            let mut prefixes = Vec::new();
+            let mut stream = prefixes_fst.stream();
+            while let Some(prefix) = stream.next() {
+                let prefix = std::str::from_utf8(prefix)?;
+                if let Some(len) = prefixes_len.get(rtxn, prefix)? {
+                    prefixes.push((len, prefix.to_string()));
+                }
+            }
+            prefixes.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+
+            let prefixes_bytes = self.word_prefix_docids.remap_data_type::<ByteSlice>();
+            for (_, prefix) in prefixes.into_iter().take(spec.prefixes) {
+                if let Some(bytes) = prefixes_bytes.get(rtxn, &prefix)? {
+                    report.bytes_read += touch_pages(bytes);
+                    report.prefixes += 1;
+                }
+            }
+        }
+
+        if spec.facet_levels != 0 {
+            let fields_ids_map = self.fields_ids_map(rtxn)?;
+            let facet_dbs = [
+                self.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>(),
+                self.facet_id_string_docids
+                    .remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>(),
+            ];
+            for field_id in spec.facet_fields.iter().filter_map(|name| fields_ids_map.id(name)) {
+                for db in facet_dbs {
+                    let highest_level = get_highest_level(rtxn, db, field_id)?;
+                    let lowest_level = highest_level.saturating_sub(spec.facet_levels - 1);
+                    for level in (lowest_level..=highest_level).rev() {
+                        let mut prefix = field_id.to_be_bytes().to_vec();
+                        prefix.push(level);
+                        let entries = db
+                            .as_polymorph()
+                            .prefix_iter::<_, ByteSlice, ByteSlice>(rtxn, &prefix)?;
+                        for result in entries {
+                            let (key, value) = result?;
+                            report.bytes_read += touch_pages(key) + touch_pages(value);
+                            report.facet_entries += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /* diff */
+
+    /// Compares the documents, the settings and the databases of two indexes,
+    /// e.g. to validate a migration or a replica.
+    ///
+    /// The documents are matched by external id and compared by content. The databases are
+    /// compared with a checksum of their entries, the internal document ids are part of the
+    /// entries, two indexes in which the same documents have different internal ids are
+    /// reported with all their databases differing.
+    ///
+    /// The entries of the main database that describe the state of the index rather than
+    /// its content are not compared: the creation and update dates, the settings token
+    /// and the pending optimizations. Neither are the entries derived from the documents,
+    /// like the words FSTs, the geo rtree or the vector HNSW, they differ only when
+    /// the documents or the databases they are built from differ.
+    pub fn diff(&self, rtxn: &RoTxn, other: &Index, other_rtxn: &RoTxn) -> Result<IndexDiff> {
+        let mut diff = IndexDiff::default();
+
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let other_fields_ids_map = other.fields_ids_map(other_rtxn)?;
+        let external_documents_ids = self.external_documents_ids(rtxn)?;
+        let other_external_documents_ids = other.external_documents_ids(other_rtxn)?;
+
+        let mut common_documents = Vec::new();
+        let external_ids_fst = external_documents_ids.to_fst()?;
+        let mut stream = external_ids_fst.stream();
+        while let Some((external_id, docid)) = stream.next() {
+            let external_id = std::str::from_utf8(external_id)?.to_string();
+            match other_external_documents_ids.get(&external_id) {
+                Some(other_docid) => {
+                    common_documents.push((external_id, docid as DocumentId, other_docid))
+                }
+                None => diff.only_in_left.push(external_id),
+            }
+        }
+
+        let documents = self.iter_documents(rtxn, common_documents.iter().map(|(_, id, _)| *id))?;
+        let other_documents =
+            other.iter_documents(other_rtxn, common_documents.iter().map(|(_, _, id)| *id))?;
+        for ((external_id, _, _), (document, other_document)) in
+            common_documents.iter().zip(documents.zip(other_documents))
+        {
+            let (_, document) = document?;
+            let (_, other_document) = other_document?;
+            if crate::all_obkv_to_json(document, &fields_ids_map)?
+                != crate::all_obkv_to_json(other_document, &other_fields_ids_map)?
+            {
+                diff.different_documents.push(external_id.clone());
+            }
+        }
+
+        let other_external_ids_fst = other_external_documents_ids.to_fst()?;
+        let mut stream = other_external_ids_fst.stream();
+        while let Some((external_id, _)) = stream.next() {
+            if external_documents_ids.get(external_id).is_none() {
+                diff.only_in_right.push(std::str::from_utf8(external_id)?.to_string());
+            }
+        }
+
+        macro_rules! compare_settings {
+            ($($key:ident => $getter:ident),* $(,)?) => {
+                $(
+                    if self.$getter(rtxn)? != other.$getter(other_rtxn)? {
+                        diff.different_settings.push(main_key::$key);
+                    }
+                )*
+            };
+        }
+        compare_settings! {
+            PRIMARY_KEY_KEY => primary_key,
+            CRITERIA_KEY => criteria,
+            DISPLAYED_FIELDS_KEY => displayed_fields,
+            USER_DEFINED_SEARCHABLE_FIELDS_KEY => user_defined_searchable_fields,
+            FILTERABLE_FIELDS_KEY => filterable_fields,
+            SORTABLE_FIELDS_KEY => sortable_fields,
+            DISTINCT_FIELD_KEY => distinct_field,
+            RECENCY_FIELD_KEY => recency_field,
+            BOOST_FIELD_KEY => boost_field,
+            SYNONYMS_KEY => synonyms,
+            AUTHORIZE_TYPOS => authorize_typos,
+            ONE_TYPO_WORD_LEN => min_word_len_one_typo,
+            TWO_TYPOS_WORD_LEN => min_word_len_two_typos,
+            EXACT_ATTRIBUTES => exact_attributes,
+            SEARCHABLE_NUMBERS_AND_BOOLEANS => searchable_numbers_and_booleans,
+            MAX_VALUES_PER_FACET => max_values_per_facet,
+            SORT_FACET_VALUES_BY => sort_facet_values_by,
+            PAGINATION_MAX_TOTAL_HITS => pagination_max_total_hits,
+            TYPO_DERIVATIONS_LIMIT => typo_derivations_limit,
+            VIEWS_KEY => views,
+            LOCALIZED_ATTRIBUTES_RULES_KEY => localized_attributes_rules,
+            BITMAP_COMPRESSION_KEY => bitmap_compression,
+            FACET_NORMALIZATION_KEY => facet_normalization,
+        }
+        let stop_words = self.stop_words(rtxn)?.map(|set| set.as_fst().as_bytes().to_vec());
+        let other_stop_words =
+            other.stop_words(other_rtxn)?.map(|set| set.as_fst().as_bytes().to_vec());
+        if stop_words != other_stop_words {
+            diff.different_settings.push(main_key::STOP_WORDS_KEY);
+        }
+        let exact_words = self.exact_words(rtxn)?.map(|set| set.as_fst().as_bytes().to_vec());
+        let other_exact_words =
+            other.exact_words(other_rtxn)?.map(|set| set.as_fst().as_bytes().to_vec());
+        if exact_words != other_exact_words {
+            diff.different_settings.push(main_key::EXACT_WORDS);
+        }
+        let dictionary = self.dictionary(rtxn)?.map(|set| set.as_fst().as_bytes().to_vec());
+        let other_dictionary =
+            other.dictionary(other_rtxn)?.map(|set| set.as_fst().as_bytes().to_vec());
+        if dictionary != other_dictionary {
+            diff.different_settings.push(main_key::DICTIONARY_KEY);
+        }
+
+        let other_databases = vec![
+            (
+                db_name::QUERY_SUGGESTIONS,
+                self.query_suggestions.remap_types::<ByteSlice, ByteSlice>(),
+                other.query_suggestions.remap_types::<ByteSlice, ByteSlice>(),
+            ),
+            (
+                db_name::CORRUPTED_ENTRIES,
+                self.corrupted_entries.remap_types::<ByteSlice, ByteSlice>(),
+                other.corrupted_entries.remap_types::<ByteSlice, ByteSlice>(),
+            ),
+        ];
+        for (name, database, other_database) in
+            self.derived_databases(other).into_iter().chain(other_databases)
+        {
+            if database_checksum(rtxn, database)? != database_checksum(other_rtxn, other_database)?
+            {
+                diff.different_databases.push(name);
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// The databases that are derived from the documents, along with
+    /// the same database of the other index, untyped.
+    fn derived_databases(
+        &self,
+        other: &Index,
+    ) -> Vec<(&'static str, Database<ByteSlice, ByteSlice>, Database<ByteSlice, ByteSlice>)> {
+        macro_rules! databases {
+            ($($name:ident => $field:ident),* $(,)?) => {
+                vec![$((
+                    db_name::$name,
+                    self.$field.remap_types::<ByteSlice, ByteSlice>(),
+                    other.$field.remap_types::<ByteSlice, ByteSlice>(),
+                )),*]
+            };
+        }
+        databases! {
+            WORD_DOCIDS => word_docids,
+            EXACT_WORD_DOCIDS => exact_word_docids,
+            WORD_DOCIDS_COUNT => word_docids_count,
+            WORD_PREFIX_DOCIDS => word_prefix_docids,
+            EXACT_WORD_PREFIX_DOCIDS => exact_word_prefix_docids,
+            WORD_PAIR_PROXIMITY_DOCIDS => word_pair_proximity_docids,
+            WORD_PREFIX_PAIR_PROXIMITY_DOCIDS => word_prefix_pair_proximity_docids,
+            PREFIX_WORD_PAIR_PROXIMITY_DOCIDS => prefix_word_pair_proximity_docids,
+            WORD_POSITION_DOCIDS => word_position_docids,
+            WORD_FIELD_ID_DOCIDS => word_fid_docids,
+            WORD_PREFIX_POSITION_DOCIDS => word_prefix_position_docids,
+            WORD_PREFIX_FIELD_ID_DOCIDS => word_prefix_fid_docids,
+            FIELD_ID_WORD_COUNT_DOCIDS => field_id_word_count_docids,
+            SCRIPT_LANGUAGE_DOCIDS => script_language_docids,
+            FACET_ID_EXISTS_DOCIDS => facet_id_exists_docids,
+            FACET_ID_IS_NULL_DOCIDS => facet_id_is_null_docids,
+            FACET_ID_IS_EMPTY_DOCIDS => facet_id_is_empty_docids,
+            FACET_ID_F64_DOCIDS => facet_id_f64_docids,
+            FACET_ID_STRING_DOCIDS => facet_id_string_docids,
+            FACET_ID_STRING_FST => facet_id_string_fst,
+            FIELD_ID_DOCID_FACET_F64S => field_id_docid_facet_f64s,
+            FIELD_ID_DOCID_FACET_STRINGS => field_id_docid_facet_strings,
+            VECTOR_ID_DOCID => vector_id_docid,
+        }
+    }
+
+    /* stats */
+
+    /// Returns the statistics of the index.
+    ///
+    /// The sizes of the databases are computed by reading all their entries, it is as slow
+    /// as a full scan of the index and must not be called on the hot path.
+    pub fn stats(&self, rtxn: &RoTxn) -> Result<IndexStats> {
+        let mut databases = BTreeMap::new();
+        let mut main = DatabaseStats::default();
+        for result in self.main.iter::<_, ByteSlice, ByteSlice>(rtxn)? {
+            let (key, value) = result?;
+            main.add_entry(key, value);
+        }
+        databases.insert(db_name::MAIN, main);
+        let other_databases = vec![
+            (db_name::DOCUMENTS, self.documents.remap_types::<ByteSlice, ByteSlice>()),
+            (
+                db_name::QUERY_SUGGESTIONS,
+                self.query_suggestions.remap_types::<ByteSlice, ByteSlice>(),
+            ),
+        ];
+        let derived_databases =
+            self.derived_databases(self).into_iter().map(|(name, database, _)| (name, database));
+        for (name, database) in derived_databases.chain(other_databases) {
+            let mut stats = DatabaseStats::default();
+            for result in database.iter(rtxn)? {
+                let (key, value) = result?;
+                stats.add_entry(key, value);
+            }
+            databases.insert(name, stats);
+        }
+
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let mut facet_values = BTreeMap::new();
+        for field_id in self.faceted_fields_ids(rtxn)? {
+            let Some(name) = fields_ids_map.name(field_id) else { continue };
+            let mut level_zero = field_id.to_be_bytes().to_vec();
+            level_zero.push(0);
+            let mut count = 0;
+            for database in [
+                self.facet_id_f64_docids.remap_types::<ByteSlice, DecodeIgnore>(),
+                self.facet_id_string_docids.remap_types::<ByteSlice, DecodeIgnore>(),
+            ] {
+                for result in database.prefix_iter(rtxn, &level_zero)? {
+                    result?;
+                    count += 1;
+                }
+            }
+            facet_values.insert(name.to_string(), count);
+        }
+
+        let pending_optimizations = self.pending_optimizations(rtxn)?;
+        let prefix_databases_up_to_date = !pending_optimizations
+            .contains(&OptimizationStep::RefreshPrefixDatabases)
+            && self.prefix_databases_match_prefixes_fst(rtxn)?;
+        let facet_levels_up_to_date = self.facet_levels_match_level_zero(rtxn)?;
+
+        Ok(IndexStats {
+            number_of_documents: self.number_of_documents(rtxn)?,
+            databases,
+            field_distribution: self.field_distribution(rtxn)?,
+            facet_values,
+            prefix_databases_up_to_date,
+            facet_levels_up_to_date,
+        })
+    }
+
+    /// Returns whether the prefix docids databases contain exactly the prefixes of the
+    /// words prefixes FST.
+    fn prefix_databases_match_prefixes_fst(&self, rtxn: &RoTxn) -> Result<bool> {
+        let prefixes_fst = self.words_prefixes_fst(rtxn)?;
+        let databases = [
+            self.word_prefix_docids.remap_data_type::<DecodeIgnore>(),
+            self.exact_word_prefix_docids.remap_data_type::<DecodeIgnore>(),
+        ];
+        for database in databases {
+            for result in database.iter(rtxn)? {
+                let (prefix, ()) = result?;
+                if !prefixes_fst.contains(prefix) {
+                    return Ok(false);
+                }
+            }
+        }
+
+        let mut stream = prefixes_fst.stream();
+        while let Some(prefix) = stream.next() {
+            let prefix = std::str::from_utf8(prefix)?;
+            if databases[0].get(rtxn, prefix)?.is_none()
+                && databases[1].get(rtxn, prefix)?.is_none()
+            {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns whether every entry of the upper levels of the facet databases is
+    /// the group of the entries of the level below it that it claims to be.
+    fn facet_levels_match_level_zero(&self, rtxn: &RoTxn) -> Result<bool> {
+        let databases = [
+            self.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>(),
+            self.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>(),
+        ];
+        for field_id in self.faceted_fields_ids(rtxn)? {
+            for database in databases {
+                let highest_level = get_highest_level(rtxn, database, field_id)?;
+                let database = database.remap_key_type::<ByteSlice>();
+                let level_prefix = |level: u8| {
+                    let mut prefix = field_id.to_be_bytes().to_vec();
+                    prefix.push(level);
+                    prefix
+                };
+
+                for level in 1..=highest_level {
+                    let mut lower_entries = database.prefix_iter(rtxn, &level_prefix(level - 1))?;
+                    for result in database.prefix_iter(rtxn, &level_prefix(level))? {
+                        let (key, group) = result?;
+                        let mut bitmap = RoaringBitmap::new();
+                        for i in 0..group.size {
+                            let Some((lower_key, lower_group)) =
+                                lower_entries.next().transpose()?
+                            else {
+                                return Ok(false);
+                            };
+                            // the keys are the field id, the level and the left bound
+                            if i == 0 && lower_key[3..] != key[3..] {
+                                return Ok(false);
+                            }
+                            bitmap |= lower_group.bitmap;
+                        }
+                        if bitmap != group.bitmap {
+                            return Ok(false);
+                        }
+                    }
+                    if lower_entries.next().is_some() {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /* sealed segments */
+
+    /// Exports the given documents in a [`SealedSegment`](crate::SealedSegment), a compact and
+    /// read-only format to serve old documents from a cheaper storage along the index with a
+    /// [`TieredSearch`](crate::TieredSearch). The documents that are not in the index are ignored.
+    pub fn sealed_segment(&self, rtxn: &RoTxn, documents_ids: &RoaringBitmap) -> Result<Vec<u8>> {
+        let documents_ids = self.documents_ids(rtxn)? & documents_ids;
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        let stop_words = self.stop_words(rtxn)?;
+        let mut builder = SealedSegmentBuilder::new(&fields_ids_map, stop_words.as_ref())?;
+
+        let words_fst = self.words_fst(rtxn)?;
+        let mut stream = words_fst.stream();
+        while let Some(word) = stream.next() {
+            let word = std::str::from_utf8(word)?;
+            let mut docids = self.word_docids.get(rtxn, word)?.unwrap_or_default();
+            if let Some(exact_docids) = self.exact_word_docids.get(rtxn, word)? {
+                docids |= exact_docids;
+            }
+            docids &= &documents_ids;
+            if !docids.is_empty() {
+                builder.insert_word(word, &docids)?;
+            }
+        }
+
+        let external_ids: HashMap<_, _> = self
+            .external_documents_ids(rtxn)?
+            .to_hash_map()
+            .into_iter()
+            .map(|(external_id, docid)| (docid, external_id))
+            .collect();
+        for result in self.iter_documents(rtxn, documents_ids.iter())? {
+            let (docid, obkv) = result?;
+            let external_id =
+                external_ids.get(&docid).ok_or(InternalError::DatabaseMissingEntry {
+                    db_name: db_name::MAIN,
+                    key: Some(main_key::HARD_EXTERNAL_DOCUMENTS_IDS_KEY),
+                })?;
+            builder.insert_document(docid, external_id, obkv)?;
+        }
+
+        builder.into_bytes()
+    }
+
+    /* query suggestions */
+
+    /// Increments the number of times the given query has been made.
+    ///
+    /// Returns `false` if the query has been ignored because it is empty or too long.
+    pub fn record_query(&self, wtxn: &mut RwTxn, query: &str) -> heed::Result<bool> {
+        let Some(query) = normalize_query(query) else { return Ok(false) };
+        let count = self.query_suggestions.get(wtxn, &query)?.map_or(0, |count| count.get());
+        self.query_suggestions.put(wtxn, &query, &BEU64::new(count.saturating_add(1)))?;
+        Ok(true)
+    }
+
+    /// Forgets all the recorded queries.
+    pub fn clear_query_suggestions(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        self.query_suggestions.clear(wtxn)
+    }
+
+    pub fn query_suggestions<'a>(&'a self, rtxn: &'a RoTxn) -> QuerySuggestions<'a> {
+        QuerySuggestions::new(rtxn, self)
+    }
+
+    /* corrupted entries */
+
+    /// Returns the entries that were moved out of their database because they
+    /// couldn't be decoded.
+    pub fn corrupted_entries(&self, rtxn: &RoTxn) -> Result<Vec<CorruptedEntry>> {
+        let mut entries = Vec::new();
+        for result in self.corrupted_entries.iter(rtxn)? {
+            let ((database, key), value) = result?;
+            entries.push(CorruptedEntry {
+                database: database.to_string(),
+                key: key.to_vec(),
+                value: value.to_vec(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Forgets the corrupted entries, e.g. once the documents have been reindexed.
+    pub fn clear_corrupted_entries(&self, wtxn: &mut RwTxn) -> heed::Result<()> {
+        self.corrupted_entries.clear(wtxn)
+    }
+
+    /// Returns the names of the databases and the keys that the searches skipped because their
+    /// value couldn't be decoded, and that weren't quarantined yet.
+    pub fn skipped_corrupted_entries(&self) -> Vec<(String, Vec<u8>)> {
+        self.skipped_corrupted_entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Records an entry that a search skipped because its value couldn't be decoded.
+    pub(crate) fn record_skipped_corrupted_entry(&self, database: &str, key: &[u8]) {
+        let mut skipped = self.skipped_corrupted_entries.lock().unwrap();
+        if skipped.insert((database.to_string(), key.to_vec())) {
+            warn!("Skipping a corrupted entry of the {database} database: {key:?}");
+        }
+    }
+
+    /* integrity */
+
+    /// Reports the inconsistencies between the words FSTs and the docids databases, e.g.
+    /// after an update that was only partially applied.
+    ///
+    /// The searches skip the words that have no docids, the documents containing them
+    /// can't be found by these words until they are reindexed.
+    pub fn verify_integrity(&self, rtxn: &RoTxn) -> Result<Vec<IntegrityIssue>> {
+        let mut issues = Vec::new();
+
+        let words_fst = self.words_fst(rtxn)?;
+        let (missing, unknown) = fst_databases_mismatches(
+            rtxn,
+            &words_fst,
+            [
+                self.word_docids.remap_data_type::<DecodeIgnore>(),
+                self.exact_word_docids.remap_data_type::<DecodeIgnore>(),
+            ],
+        )?;
+        issues.extend(missing.into_iter().map(|word| IntegrityIssue::MissingWordDocids { word }));
+        issues.extend(unknown.into_iter().map(|word| IntegrityIssue::UnknownWord { word }));
+
+        let prefixes_fst = self.words_prefixes_fst(rtxn)?;
+        let (missing, unknown) = fst_databases_mismatches(
+            rtxn,
+            &prefixes_fst,
+            [
+                self.word_prefix_docids.remap_data_type::<DecodeIgnore>(),
+                self.exact_word_prefix_docids.remap_data_type::<DecodeIgnore>(),
+            ],
+        )?;
+        issues.extend(
+            missing.into_iter().map(|prefix| IntegrityIssue::MissingWordPrefixDocids { prefix }),
+        );
+        issues.extend(unknown.into_iter().map(|prefix| IntegrityIssue::UnknownPrefix { prefix }));
+
+        Ok(issues)
+    }
+
+    /* stop words suggestions */
+
+    pub fn stop_words_suggestions<'a>(&'a self, rtxn: &'a RoTxn) -> StopWordsSuggestions<'a> {
+        StopWordsSuggestions::new(rtxn, self)
+    }
+
+    /* script  language docids */
+    /// Retrieve all the documents ids that correspond with (Script, Language) key, `None` if it is any.
+    pub fn script_language_documents_ids(
+        &self,
+        rtxn: &RoTxn,
+        key: &(Script, Language),
+    ) -> heed::Result<Option<RoaringBitmap>> {
+        let soft_deleted_documents = self.soft_deleted_documents_ids(rtxn)?;
+        let doc_ids = self.script_language_docids.get(rtxn, key)?;
+        Ok(doc_ids.map(|ids| ids - soft_deleted_documents))
+    }
+
+    pub fn script_language(&self, rtxn: &RoTxn) -> heed::Result<HashMap<Script, Vec<Language>>> {
+        let soft_deleted_documents = self.soft_deleted_documents_ids(rtxn)?;
+
+        let mut script_language: HashMap<Script, Vec<Language>> = HashMap::new();
+        let mut script_language_doc_count: Vec<(Script, Language, u64)> = Vec::new();
+        let mut total = 0;
+        for sl in self.script_language_docids.iter(rtxn)? {
+            let ((script, language), docids) = sl?;
+
+            // keep only Languages that contains at least 1 document.
+            let remaining_documents_count = (docids - &soft_deleted_documents).len();
+            total += remaining_documents_count;
+            if remaining_documents_count > 0 {
+                script_language_doc_count.push((script, language, remaining_documents_count));
+            }
+        }
+
+        let threshold = total / 20; // 5% (arbitrary)
+        for (script, language, count) in script_language_doc_count {
+            if count > threshold {
+                if let Some(languages) = script_language.get_mut(&script) {
+                    (*languages).push(language);
+                } else {
+                    script_language.insert(script, vec![language]);
+                }
+            }
+        }
+
+        Ok(script_language)
+    }
+}
+
+/// The encoding of the bitmaps of the word docids, word position docids, word field id docids
+/// and word pair proximity docids databases, see [`Index::new_with_bitmap_compression`].
+///
+/// The values of the other databases and the small bitmaps are always written with the
+/// roaring codecs, which read both encodings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BitmapCompression {
+    #[default]
+    Roaring,
+    /// Uses the [`DeltaRoaringBitmapCodec`](crate::DeltaRoaringBitmapCodec) for the bitmaps it makes smaller.
+    Delta,
+}
+
+/// How the facet strings are normalized before being stored and compared,
+/// see [`normalize_facet`](crate::normalize_facet).
+///
+/// The indexes created before the diacritics were removed from the facet strings keep the
+/// [`Lowercase`](FacetNormalization::Lowercase) normalization until their documents are cleared
+/// or reindexed, the facets they store would stop matching the filters otherwise.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetNormalization {
+    /// Folds the compatibility characters and lowercases the strings.
+    #[default]
+    Lowercase,
+    /// Normalizes the strings like the words, see [`normalize_str`](crate::normalize_str).
+    Words,
+}
+
+/// A piece of maintenance work that the updates of the index defer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OptimizationStep {
+    /// Definitely removes the soft-deleted documents from the databases.
+    PurgeSoftDeleted,
+    /// Rebuilds the words FST from the keys of the word docids databases.
+    RemergeWordsFst,
+    /// Removes the prefixes that no longer match enough words from the prefix databases.
+    RefreshPrefixDatabases,
+    /// Recomputes the upper levels of the facet databases from their level 0.
+    RebuildFacetLevels,
+    /// Rebuilds the HNSW once the deletions left too many dead points in it.
+    CompactVectors,
+}
+
+impl OptimizationStep {
+    /// All the steps, in the order they are performed.
+    pub const ALL: [OptimizationStep; 5] = [
+        OptimizationStep::PurgeSoftDeleted,
+        OptimizationStep::RemergeWordsFst,
+        OptimizationStep::RefreshPrefixDatabases,
+        OptimizationStep::RebuildFacetLevels,
+        OptimizationStep::CompactVectors,
+    ];
+}
+
+/// An entry that couldn't be decoded, see [`Index::corrupted_entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptedEntry {
+    /// The name of the database the entry was moved out of.
+    pub database: String,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// An inconsistency between the words FSTs and the docids databases, see
+/// [`Index::verify_integrity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A word of the words FST is in none of the word docids databases.
+    MissingWordDocids { word: String },
+    /// A word of the word docids databases is not in the words FST.
+    UnknownWord { word: String },
+    /// A prefix of the words prefixes FST is in none of the word prefix docids databases.
+    MissingWordPrefixDocids { prefix: String },
+    /// A prefix of the word prefix docids databases is not in the words prefixes FST.
+    UnknownPrefix { prefix: String },
+}
+
+/// Returns the keys of the FST that are in none of the databases and the keys of the
+/// databases that are not in the FST.
+fn fst_databases_mismatches<A: AsRef<[u8]>>(
+    rtxn: &RoTxn,
+    fst: &fst::Set<A>,
+    databases: [Database<Str, DecodeIgnore>; 2],
+) -> Result<(Vec<String>, BTreeSet<String>)> {
+    let mut missing = Vec::new();
+    let mut stream = fst.stream();
+    while let Some(key) = stream.next() {
+        let key = std::str::from_utf8(key)?;
+        if databases[0].get(rtxn, key)?.is_none() && databases[1].get(rtxn, key)?.is_none() {
+            missing.push(key.to_string());
+        }
+    }
+
+    let mut unknown = BTreeSet::new();
+    for database in databases {
+        for result in database.iter(rtxn)? {
+            let (key, ()) = result?;
+            if !fst.contains(key) {
+                unknown.insert(key.to_string());
+            }
+        }
+    }
+
+    Ok((missing, unknown))
+}
+
+/// Describes the parts of an index that [`Index::warmup`] must read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupSpec {
+    /// Reads the words FST and the words prefixes FST.
+    pub words_fst: bool,
+    /// The number of prefixes, the ones matching the most documents, whose docids are read.
+    pub prefixes: usize,
+    /// The number of facet levels read for every field of `facet_fields`, starting
+    /// from the highest level, the one that is always used by the facet searches.
+    pub facet_levels: u8,
+    /// The fields whose facet levels are read.
+    pub facet_fields: Vec<String>,
+}
+
+impl Default for WarmupSpec {
+    fn default() -> Self {
+        WarmupSpec { words_fst: true, prefixes: 100, facet_levels: 1, facet_fields: Vec::new() }
+    }
+}
+
+/// What [`Index::warmup`] read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupReport {
+    /// The number of bytes of the entries that have been read.
+    pub bytes_read: u64,
+    /// The number of prefixes whose docids have been read.
+    pub prefixes: usize,
+    /// The number of facet entries that have been read.
+    pub facet_entries: usize,
+}
+
+/// The differences between two indexes, see [`Index::diff`].
+///
+/// The left index is the one on which the diff is called.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexDiff {
+    /// The external ids of the documents only present in the left index.
+    pub only_in_left: Vec<String>,
+    /// The external ids of the documents only present in the right index.
+    pub only_in_right: Vec<String>,
+    /// The external ids of the documents present in both indexes with different contents.
+    pub different_documents: Vec<String>,
+    /// The main keys of the settings that differ.
+    pub different_settings: Vec<&'static str>,
+    /// The names of the derived databases whose entries differ.
+    pub different_databases: Vec<&'static str>,
+}
+
+impl IndexDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_left.is_empty()
+            && self.only_in_right.is_empty()
+            && self.different_documents.is_empty()
+            && self.different_settings.is_empty()
+            && self.different_databases.is_empty()
+    }
+}
+
+/// The statistics of an index, see [`Index::stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    pub number_of_documents: u64,
+    /// The statistics of every database, by name.
+    pub databases: BTreeMap<&'static str, DatabaseStats>,
+    /// The number of documents containing each field.
+    pub field_distribution: FieldDistribution,
+    /// The number of distinct values of every faceted field.
+    pub facet_values: BTreeMap<String, u64>,
+    /// Whether the prefix databases reflect the words prefixes FST and no refresh
+    /// of the prefixes is pending.
+    pub prefix_databases_up_to_date: bool,
+    /// Whether the upper levels of the facet databases are the groups of the levels
+    /// below them, the facet searches on stale levels miss values.
+    pub facet_levels_up_to_date: bool,
+}
+
+/// The statistics of a database of an index, see [`IndexStats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseStats {
+    pub number_of_entries: u64,
+    /// The number of bytes of the keys and values, without the overhead of LMDB.
+    pub size: u64,
+}
+
+impl DatabaseStats {
+    fn add_entry(&mut self, key: &[u8], value: &[u8]) {
+        self.number_of_entries += 1;
+        self.size += (key.len() + value.len()) as u64;
+    }
+}
+
+/// Hashes all the entries of a database, in order.
+fn database_checksum(rtxn: &RoTxn, database: Database<ByteSlice, ByteSlice>) -> Result<u64> {
+    let mut hasher = FxHasher64::default();
+    for result in database.iter(rtxn)? {
+        let (key, value) = result?;
+        hasher.write_usize(key.len());
+        hasher.write(key);
+        hasher.write_usize(value.len());
+        hasher.write(value);
+    }
+    Ok(hasher.finish())
+}
+
+/// The size of the pages used by LMDB on most of the platforms.
+const PAGE_SIZE: usize = 4096;
+
+/// Reads one byte of every page of the slice to make the OS fault the page in.
+fn touch_pages(bytes: &[u8]) -> u64 {
+    let sum = bytes.iter().step_by(PAGE_SIZE).fold(0u8, |acc, b| acc.wrapping_add(*b));
+    std::hint::black_box(sum);
+    bytes.len() as u64
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::borrow::Cow;
+    use std::collections::HashSet;
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    use big_s::S;
+    use heed::types::{ByteSlice, Str};
+    use heed::{EnvOpenOptions, RwTxn};
+    use maplit::{btreemap, btreeset, hashset};
+    use roaring::RoaringBitmap;
+    use tempfile::TempDir;
+
+    use crate::documents::DocumentsBatchReader;
+    use crate::error::{Error, InternalError, UserError};
+    use crate::index::{
+        db_name, main_key, BitmapCompression, IntegrityIssue, OptimizationStep, WarmupReport,
+        WarmupSpec, DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS,
+    };
+    use crate::update::{
+        self, DeleteDocuments, DeletionStrategy, IndexDocuments, IndexDocumentsConfig,
+        IndexDocumentsMethod, IndexerConfig, Settings,
+    };
+    use crate::{
+        db_snap, obkv_to_json, DeltaRoaringBitmapCodec, Filter, Index, Search, SearchResult, View,
+    };
+
+    pub(crate) struct TempIndex {
+        pub inner: Index,
+        pub indexer_config: IndexerConfig,
+        pub index_documents_config: IndexDocumentsConfig,
+        _tempdir: TempDir,
+    }
+
+    impl Deref for TempIndex {
+        type Target = Index;
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl TempIndex {
+        /// Creates a temporary index
+        pub fn new_with_map_size(size: usize) -> Self {
+            let mut options = EnvOpenOptions::new();
+            options.map_size(size);
+            let _tempdir = TempDir::new_in(".").unwrap();
+            let inner = Index::new(options, _tempdir.path()).unwrap();
+            let indexer_config = IndexerConfig::default();
+            let index_documents_config = IndexDocumentsConfig::default();
+            Self { inner, indexer_config, index_documents_config, _tempdir }
+        }
+        /// Creates a temporary index, with a default `4096 * 2000` size. This should be enough for
+        /// most tests.
+        pub fn new() -> Self {
+            Self::new_with_map_size(4096 * 2000)
+        }
+        pub fn add_documents_using_wtxn<'t, R>(
+            &'t self,
+            wtxn: &mut RwTxn<'t, '_>,
+            documents: DocumentsBatchReader<R>,
+        ) -> Result<(), crate::error::Error>
+        where
+            R: std::io::Read + std::io::Seek,
+        {
+            let builder = IndexDocuments::new(
+                wtxn,
+                self,
+                &self.indexer_config,
+                self.index_documents_config.clone(),
+                |_| (),
+                || false,
+            )
+            .unwrap();
+            let (builder, user_error) = builder.add_documents(documents).unwrap();
+            user_error?;
+            builder.execute()?;
+            Ok(())
+        }
+        pub fn add_documents<R>(
+            &self,
+            documents: DocumentsBatchReader<R>,
+        ) -> Result<(), crate::error::Error>
+        where
+            R: std::io::Read + std::io::Seek,
+        {
+            let mut wtxn = self.write_txn().unwrap();
+            self.add_documents_using_wtxn(&mut wtxn, documents)?;
+            wtxn.commit().unwrap();
+            Ok(())
+        }
+
+        pub fn update_settings(
             &self,
             update: impl Fn(&mut Settings),
         ) -> Result<(), crate::error::Error> {
@@ -2590,6 +3942,7 @@ pub(crate) mod tests {
             candidates: _,
             document_scores: _,
             mut documents_ids,
+            degraded: _,
         } = search.execute().unwrap();
         let primary_key_id = index.fields_ids_map(&rtxn).unwrap().id("primary_key").unwrap();
         documents_ids.sort_unstable();
@@ -2665,4 +4018,497 @@ pub(crate) mod tests {
 
         db_snap!(index, geo_faceted_documents_ids); // ensure that no documents were inserted
     }
+
+    #[test]
+    fn warmup() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price"), S("color") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "red shirt", "price": 10, "color": "red" },
+                { "id": 1, "title": "blue shirt", "price": 20, "color": "blue" },
+                { "id": 2, "title": "red hat", "price": 20, "color": "red" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let spec =
+            WarmupSpec { words_fst: false, prefixes: 0, facet_levels: 0, facet_fields: vec![] };
+        let report = index.warmup(&rtxn, &spec).unwrap();
+        assert_eq!(report, WarmupReport::default());
+
+        let spec = WarmupSpec {
+            facet_levels: 2,
+            facet_fields: vec![S("price"), S("color"), S("unknown")],
+            ..WarmupSpec::default()
+        };
+        let report = index.warmup(&rtxn, &spec).unwrap();
+        // there are two distinct prices and two distinct colors, all in the level 0
+        assert_eq!(report.facet_entries, 4);
+        assert!(report.bytes_read > 0);
+    }
+
+    #[test]
+    fn documents_with_fields() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hello", "description": "a long description", "price": 10 },
+                { "id": 1, "title": "world", "price": 20 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let documents = index
+            .documents_with_fields(&rtxn, [1, 0], &["price", "description", "unknown"])
+            .unwrap();
+        let documents: Vec<_> = documents
+            .into_iter()
+            .map(|(id, document)| (id, serde_json::Value::Object(document)))
+            .collect();
+        assert_eq!(
+            documents,
+            vec![
+                (1, serde_json::json!({ "price": 20 })),
+                (0, serde_json::json!({ "price": 10, "description": "a long description" })),
+            ]
+        );
+
+        let error = index.documents_with_fields(&rtxn, [42], &["title"]).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::UserError(UserError::UnknownInternalDocumentId { document_id: 42 })
+        ));
+    }
+
+    #[test]
+    fn external_and_internal_ids() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.deletion_strategy = DeletionStrategy::AlwaysSoft;
+
+        index
+            .add_documents(documents!([
+                { "id": "kevin", "title": "hello" },
+                { "id": 42, "title": "world" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for external_id in ["kevin", "42"] {
+            let docid = index.internal_id_of(&rtxn, external_id).unwrap().unwrap();
+            assert_eq!(index.external_id_of(&rtxn, docid).unwrap().as_deref(), Some(external_id));
+        }
+        assert_eq!(index.internal_id_of(&rtxn, "unknown").unwrap(), None);
+        assert_eq!(index.external_id_of(&rtxn, 1000).unwrap(), None);
+
+        let results = index.search(&rtxn).query("world").execute().unwrap();
+        let external_ids: Vec<_> = results
+            .documents_ids
+            .iter()
+            .map(|docid| index.external_id_of(&rtxn, *docid).unwrap().unwrap())
+            .collect();
+        assert_eq!(external_ids, vec!["42"]);
+        let docid = index.internal_id_of(&rtxn, "kevin").unwrap().unwrap();
+        drop(rtxn);
+
+        // The soft-deleted documents don't have ids anymore.
+        index.delete_document("kevin");
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.internal_id_of(&rtxn, "kevin").unwrap(), None);
+        assert_eq!(index.external_id_of(&rtxn, docid).unwrap(), None);
+    }
+
+    #[test]
+    fn nested_primary_key_external_ids() {
+        let index = TempIndex::new();
+        index.update_settings(|settings| settings.set_primary_key("doc.id".to_owned())).unwrap();
+        index.add_documents(documents!([{ "doc": { "id": "nested" }, "title": "hello" }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let docid = index.internal_id_of(&rtxn, "nested").unwrap().unwrap();
+        assert_eq!(index.external_id_of(&rtxn, docid).unwrap().as_deref(), Some("nested"));
+    }
+
+    #[test]
+    fn diff() {
+        let create_index = || {
+            let index = TempIndex::new();
+            index
+                .add_documents(documents!([
+                    { "id": 0, "title": "hello", "price": 10 },
+                    { "id": 1, "title": "world", "price": 20 },
+                ]))
+                .unwrap();
+            index
+        };
+
+        let left = create_index();
+        let right = create_index();
+        let diff = left.diff(&left.read_txn().unwrap(), &right, &right.read_txn().unwrap());
+        assert!(diff.unwrap().is_empty());
+
+        right.update_settings(|s| s.set_filterable_fields(hashset! { S("price") })).unwrap();
+        right
+            .add_documents(documents!([
+                { "id": 1, "title": "world", "price": 25 },
+                { "id": 2, "title": "again", "price": 30 },
+            ]))
+            .unwrap();
+        left.add_documents(documents!([{ "id": 3, "title": "lonely" }])).unwrap();
+
+        let diff = left.diff(&left.read_txn().unwrap(), &right, &right.read_txn().unwrap());
+        let diff = diff.unwrap();
+        assert_eq!(diff.only_in_left, vec![S("3")]);
+        assert_eq!(diff.only_in_right, vec![S("2")]);
+        assert_eq!(diff.different_documents, vec![S("1")]);
+        assert_eq!(diff.different_settings, vec![main_key::FILTERABLE_FIELDS_KEY]);
+        assert!(diff.different_databases.contains(&db_name::WORD_DOCIDS));
+        assert!(diff.different_databases.contains(&db_name::FACET_ID_F64_DOCIDS));
+    }
+
+    #[test]
+    fn diff_settings_and_exclusions() {
+        let create_index = || {
+            let index = TempIndex::new();
+            index.add_documents(documents!([{ "id": 0, "title": "hello" }])).unwrap();
+            index
+        };
+
+        let left = create_index();
+        let right = create_index();
+
+        // the state of the index is not compared
+        let mut wtxn = right.write_txn().unwrap();
+        right.put_pending_optimizations(&mut wtxn, &OptimizationStep::ALL).unwrap();
+        right.set_updated_at(&mut wtxn, &time::OffsetDateTime::now_utc()).unwrap();
+        wtxn.commit().unwrap();
+        let diff = left.diff(&left.read_txn().unwrap(), &right, &right.read_txn().unwrap());
+        assert!(diff.unwrap().is_empty());
+
+        right
+            .update_settings(|s| {
+                s.set_views(btreemap! { S("hello") => View::default() });
+                s.set_dictionary(btreeset! { S("J.R.R.") });
+            })
+            .unwrap();
+        let diff = left.diff(&left.read_txn().unwrap(), &right, &right.read_txn().unwrap());
+        let diff = diff.unwrap();
+        assert!(diff.different_settings.contains(&main_key::VIEWS_KEY));
+        assert!(diff.different_settings.contains(&main_key::DICTIONARY_KEY));
+    }
+
+    #[test]
+    fn cached_settings() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hello", "price": 10 },
+                { "id": 1, "title": "world", "price": 20 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let before = index.cached_settings(&rtxn).unwrap();
+        assert!(before.filterable_fields.is_empty());
+        // the settings are only parsed again once they are updated
+        assert!(Arc::ptr_eq(&before, &index.cached_settings(&rtxn).unwrap()));
+        drop(rtxn);
+
+        index.update_settings(|s| s.set_filterable_fields(hashset! { S("price") })).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let after = index.cached_settings(&rtxn).unwrap();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert_eq!(after.filterable_fields, hashset! { S("price") });
+        let documents_ids =
+            Filter::from_str("price = 20").unwrap().unwrap().evaluate(&rtxn, &index).unwrap();
+        insta::assert_debug_snapshot!(documents_ids, @"RoaringBitmap<[1]>");
+        assert_eq!(after.words_dict(), None);
+        drop(rtxn);
+
+        index.update_settings(|s| s.set_dictionary(btreeset! { S("J.R.R.") })).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.cached_settings(&rtxn).unwrap().words_dict(), Some(vec!["J.R.R."]));
+    }
+
+    #[test]
+    fn snapshot_and_open_an_index() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick fox" },
+                { "id": 1, "title": "the lazy dog" },
+            ]))
+            .unwrap();
+
+        let dir = TempDir::new_in(".").unwrap();
+        let snapshot_dir = dir.path().join("snapshot");
+        assert!(index.snapshot_to(&snapshot_dir).unwrap() > 0);
+        // a snapshot is never overwritten
+        assert!(index.snapshot_to(&snapshot_dir).is_err());
+
+        // the changes made after the snapshot are not part of it
+        index.add_documents(documents!([{ "id": 2, "title": "the quick dog" }])).unwrap();
+
+        let mut options = EnvOpenOptions::new();
+        options.map_size(4096 * 2000);
+        let snapshot = Index::open_snapshot(options, &snapshot_dir).unwrap();
+        let rtxn = snapshot.read_txn().unwrap();
+        assert_eq!(snapshot.number_of_documents(&rtxn).unwrap(), 2);
+        assert_eq!(snapshot.primary_key(&rtxn).unwrap(), Some("id"));
+        let results = snapshot.search(&rtxn).query("quick").execute().unwrap();
+        assert_eq!(results.documents_ids, vec![0]);
+        drop(rtxn);
+
+        let missing = Index::open_snapshot(EnvOpenOptions::new(), dir.path().join("missing"));
+        assert!(matches!(missing, Err(Error::IoError(_))));
+        assert!(!dir.path().join("missing").exists());
+    }
+
+    #[test]
+    fn index_stats() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|s| s.set_filterable_fields(hashset! { S("price"), S("color") }))
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick fox", "price": 10, "color": "red" },
+                { "id": 1, "title": "the lazy dog", "price": 20 },
+                { "id": 2, "title": "the quick dog", "price": 10, "color": "blue" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let stats = index.stats(&rtxn).unwrap();
+        assert_eq!(stats.number_of_documents, 3);
+        assert_eq!(stats.databases[db_name::DOCUMENTS].number_of_entries, 3);
+        assert!(stats.databases[db_name::WORD_DOCIDS].size > 0);
+        assert_eq!(stats.field_distribution["color"], 2);
+        assert_eq!(stats.field_distribution["title"], 3);
+        assert_eq!(stats.facet_values, btreemap! { S("color") => 2, S("price") => 2 });
+        assert!(stats.prefix_databases_up_to_date);
+        assert!(stats.facet_levels_up_to_date);
+        drop(rtxn);
+
+        // an interrupted optimization leaves the prefixes to refresh
+        let mut wtxn = index.write_txn().unwrap();
+        let mut optimize = crate::update::OptimizeIndex::new(&mut wtxn, &index);
+        optimize.time_budget(std::time::Duration::ZERO);
+        optimize.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let stats = index.stats(&rtxn).unwrap();
+        assert!(!stats.prefix_databases_up_to_date);
+    }
+
+    #[test]
+    fn index_stats_stale_facet_levels() {
+        use crate::heed_codec::facet::{FacetGroupKey, FacetGroupValue};
+
+        let index = TempIndex::new();
+        index.update_settings(|s| s.set_filterable_fields(hashset! { S("price") })).unwrap();
+        // enough distinct values for the facet database to have upper levels
+        let documents: Vec<_> =
+            (0..100).map(|i| serde_json::json!({ "id": i, "price": i })).collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.stats(&rtxn).unwrap().facet_levels_up_to_date);
+        let field_id = index.fields_ids_map(&rtxn).unwrap().id("price").unwrap();
+        drop(rtxn);
+
+        // a value written in the level 0 only is missing from the upper levels
+        let mut wtxn = index.write_txn().unwrap();
+        let key = FacetGroupKey { field_id, level: 0, left_bound: 1000.0 };
+        let value = FacetGroupValue { size: 1, bitmap: RoaringBitmap::from_iter([0]) };
+        index.facet_id_f64_docids.put(&mut wtxn, &key, &value).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.stats(&rtxn).unwrap().facet_levels_up_to_date);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        crate::update::OptimizeIndex::new(&mut wtxn, &index).execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.stats(&rtxn).unwrap().facet_levels_up_to_date);
+    }
+
+    #[test]
+    fn words_fsts_are_borrowed_from_the_pages() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.words_prefix_threshold = Some(2);
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the world of words" },
+                { "id": 1, "title": "a worldwide word" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (key, fst) in [
+            (main_key::WORDS_FST_KEY, index.words_fst(&rtxn).unwrap()),
+            (main_key::WORDS_PREFIXES_FST_KEY, index.words_prefixes_fst(&rtxn).unwrap()),
+        ] {
+            assert!(fst.contains("word"), "{key} must contain word");
+            assert!(matches!(fst.as_fst().as_inner(), Cow::Borrowed(_)));
+            let bytes = index.main.get::<_, Str, ByteSlice>(&rtxn, key).unwrap().unwrap();
+            assert_eq!(fst.as_fst().as_bytes().as_ptr(), bytes.as_ptr());
+        }
+    }
+
+    #[test]
+    fn delta_encoded_bitmaps() {
+        fn documents(ids: std::ops::Range<u32>) -> DocumentsBatchReader<std::io::Cursor<Vec<u8>>> {
+            let documents = ids
+                .map(|i| {
+                    let text = if i % 100 == 0 { "rare word" } else { "common word" };
+                    serde_json::json!({ "id": i, "text": text })
+                })
+                .filter_map(|json| match json {
+                    serde_json::Value::Object(object) => Some(object),
+                    _ => None,
+                });
+            crate::documents::documents_batch_reader_from_objects(documents)
+        }
+
+        let roaring_index = TempIndex::new_with_map_size(4096 * 10_000);
+        let tempdir = TempDir::new_in(".").unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(4096 * 10_000);
+        let inner =
+            Index::new_with_bitmap_compression(options, tempdir.path(), BitmapCompression::Delta)
+                .unwrap();
+        let delta_index = TempIndex {
+            inner,
+            indexer_config: IndexerConfig::default(),
+            index_documents_config: IndexDocumentsConfig::default(),
+            _tempdir: tempdir,
+        };
+
+        // the second batch is merged with the delta-encoded bitmaps of the first one
+        for index in [&roaring_index, &delta_index] {
+            index.add_documents(documents(0..10_000)).unwrap();
+            index.add_documents(documents(10_000..10_500)).unwrap();
+        }
+
+        let rtxn = delta_index.read_txn().unwrap();
+        assert_eq!(delta_index.bitmap_compression(&rtxn).unwrap(), BitmapCompression::Delta);
+        let word_docids = delta_index.word_docids.remap_data_type::<ByteSlice>();
+        let rare = word_docids.get(&rtxn, "rare").unwrap().unwrap();
+        assert!(DeltaRoaringBitmapCodec::is_delta_encoded(rare));
+        // the dense bitmaps are smaller with the roaring encoding
+        let common = word_docids.get(&rtxn, "common").unwrap().unwrap();
+        assert!(!DeltaRoaringBitmapCodec::is_delta_encoded(common));
+        assert_eq!(delta_index.word_docids.get(&rtxn, "rare").unwrap().unwrap().len(), 105);
+
+        let roaring_rtxn = roaring_index.read_txn().unwrap();
+        assert_eq!(
+            roaring_index.bitmap_compression(&roaring_rtxn).unwrap(),
+            BitmapCompression::Roaring
+        );
+        for query in ["rare", "rare word", "common", "word"] {
+            let delta_result =
+                Search::new(&rtxn, &delta_index).query(query).limit(20_000).execute().unwrap();
+            let roaring_result = Search::new(&roaring_rtxn, &roaring_index)
+                .query(query)
+                .limit(20_000)
+                .execute()
+                .unwrap();
+            assert_eq!(delta_result.documents_ids, roaring_result.documents_ids);
+            assert_eq!(delta_result.candidates, roaring_result.candidates);
+        }
+    }
+
+    #[test]
+    fn word_docids_count() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.deletion_strategy = DeletionStrategy::AlwaysHard;
+        index.update_settings(|s| s.set_searchable_fields(vec![S("text")])).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "text": "hello world" },
+                { "id": 1, "text": "hello" },
+            ]))
+            .unwrap();
+        // the counts of the second batch are merged with the ones of the first batch
+        index.add_documents(documents!([{ "id": 2, "text": "hello kitty" }])).unwrap();
+
+        let counts = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            let counts: Vec<_> = index
+                .word_docids_count
+                .iter(&rtxn)
+                .unwrap()
+                .map(|result| result.map(|(word, count)| (word.to_owned(), count.get())).unwrap())
+                .collect();
+            counts
+        };
+        assert_eq!(counts(&index), vec![(S("hello"), 3), (S("kitty"), 1), (S("world"), 1)]);
+
+        index.delete_document("0");
+        assert_eq!(counts(&index), vec![(S("hello"), 2), (S("kitty"), 1)]);
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.word_documents_count(&rtxn, "hello").unwrap(), Some(2));
+        assert_eq!(index.word_documents_count(&rtxn, "world").unwrap(), None);
+        drop(rtxn);
+
+        index.delete_document("1");
+        index.delete_document("2");
+        assert!(counts(&index).is_empty());
+    }
+
+    #[test]
+    fn verify_integrity() {
+        let index = TempIndex::new();
+        index.update_settings(|s| s.set_searchable_fields(vec![S("text")])).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "text": "hello world" },
+                { "id": 1, "text": "hello kitty" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.verify_integrity(&rtxn).unwrap(), vec![]);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        index.word_docids.delete(&mut wtxn, "world").unwrap();
+        index
+            .word_docids
+            .put(&mut wtxn, "ghost", &RoaringBitmap::from_sorted_iter([1]).unwrap())
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            index.verify_integrity(&rtxn).unwrap(),
+            vec![
+                IntegrityIssue::MissingWordDocids { word: S("world") },
+                IntegrityIssue::UnknownWord { word: S("ghost") },
+            ]
+        );
+
+        // the words without docids are skipped by the search
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("world").execute().unwrap();
+        assert!(documents_ids.is_empty());
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("kitty").execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+    }
 }
@@ -5,23 +5,33 @@
 #[global_allocator]
 pub static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+#[cfg(feature = "update")]
 #[macro_use]
 pub mod documents;
 
 mod asc_desc;
 mod criterion;
 pub mod distance;
+#[cfg(feature = "update")]
+pub mod dump;
 mod error;
 mod external_documents_ids;
 pub mod facet;
 mod fields_ids_map;
 pub mod heed_codec;
 pub mod index;
+mod localized_attributes_rules;
 pub mod proximity;
+pub mod query_suggestions;
 mod readable_slices;
 pub mod score_details;
+mod sealed_segment;
 mod search;
+mod settings_cache;
+pub mod stop_words_suggestions;
+#[cfg(feature = "update")]
 pub mod update;
+mod view;
 
 #[cfg(test)]
 #[macro_use]
@@ -31,10 +41,14 @@ use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::hash::BuildHasherDefault;
 
-use charabia::normalizer::{CharNormalizer, CompatibilityDecompositionNormalizer};
+use charabia::normalizer::{
+    CharNormalizer, CompatibilityDecompositionNormalizer, NormalizerOption,
+};
+use charabia::Normalize;
 pub use distance::dot_product_similarity;
 pub use filter_parser::{Condition, FilterCondition, Span, Token};
 use fxhash::{FxHasher32, FxHasher64};
+#[cfg(feature = "update")]
 pub use grenad::CompressionType;
 pub use search::new::{
     execute_search, DefaultSearchLogger, GeoSortStrategy, SearchContext, SearchLogger,
@@ -46,21 +60,35 @@ pub use {charabia as tokenizer, heed};
 pub use self::asc_desc::{AscDesc, AscDescError, Member, SortError};
 pub use self::criterion::{default_criteria, Criterion, CriterionError};
 pub use self::error::{
-    Error, FieldIdMapMissingEntry, InternalError, SerializationError, UserError,
+    Error, ErrorCategory, FieldIdMapMissingEntry, InternalError, SerializationError, UserError,
 };
 pub use self::external_documents_ids::ExternalDocumentsIds;
 pub use self::fields_ids_map::FieldsIdsMap;
 pub use self::heed_codec::{
     BEU32StrCodec, BoRoaringBitmapCodec, BoRoaringBitmapLenCodec, CboRoaringBitmapCodec,
-    CboRoaringBitmapLenCodec, FieldIdWordCountCodec, ObkvCodec, RoaringBitmapCodec,
-    RoaringBitmapLenCodec, StrBEU32Codec, U8StrStrCodec, UncheckedU8StrStrCodec,
+    CboRoaringBitmapLenCodec, DeltaRoaringBitmapCodec, FieldIdWordCountCodec, ObkvCodec,
+    RoaringBitmapCodec, RoaringBitmapLenCodec, StrBEU32Codec, U8StrStrCodec,
+    UncheckedU8StrStrCodec,
+};
+pub use self::index::{
+    BitmapCompression, CorruptedEntry, DatabaseStats, FacetNormalization, Index, IndexDiff,
+    IndexStats, IntegrityIssue, OptimizationStep, WarmupReport, WarmupSpec,
 };
-pub use self::index::Index;
+pub use self::localized_attributes_rules::{
+    allow_list, parse_locale, LocalizedAttributesRule, LocalizedFieldsIds,
+};
+pub use self::query_suggestions::{QuerySuggestion, QuerySuggestions};
+pub use self::sealed_segment::{SealedSearchResult, SealedSearchSettings, SealedSegment};
 pub use self::search::{
-    FacetDistribution, FacetValueHit, Filter, FormatOptions, MatchBounds, MatcherBuilder,
-    MatchingWords, OrderBy, Search, SearchForFacetValues, SearchResult, TermsMatchingStrategy,
-    DEFAULT_VALUES_PER_FACET,
+    CalendarInterval, DateHistogram, DateHistogramBucket, FacetDistribution, FacetValueHit, Filter,
+    FormatOptions, Formatter, FoundWord, MatchBounds, MatchPosition, MatcherBuilder,
+    MatchingWordKind, MatchingWords, OrderBy, Search, SearchForFacetValues, SearchQuery,
+    SearchResult, ShardedHit, ShardedSearch, ShardedSearchResult, TermsMatchingStrategy,
+    TieBreaker, Tier, TieredHit, TieredSearch, TieredSearchResult, DEFAULT_VALUES_PER_FACET,
 };
+pub use self::search::{ExpandAbbreviations, QueryRewriter};
+pub use self::settings_cache::CachedSettings;
+pub use self::view::View;
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
@@ -159,6 +187,17 @@ pub fn all_obkv_to_json(obkv: obkv::KvReaderU16, fields_ids_map: &FieldsIdsMap)
     obkv_to_json(all_keys.as_slice(), fields_ids_map, obkv)
 }
 
+/// Transform a raw obkv store into a JSON Object only keeping the displayed fields,
+/// which can be dot-separated paths to nested fields, e.g. `address.city`.
+pub fn displayed_obkv_to_json(
+    displayed_fields: &[&str],
+    fields_ids_map: &FieldsIdsMap,
+    obkv: obkv::KvReaderU16,
+) -> Result<Object> {
+    let document = all_obkv_to_json(obkv, fields_ids_map)?;
+    Ok(permissive_json_pointer::select_values(&document, displayed_fields.iter().copied()))
+}
+
 /// Transform a JSON value into a string that can be indexed.
 pub fn json_to_string(value: &Value) -> Option<String> {
     fn inner(value: &Value, output: &mut String) -> bool {
@@ -284,8 +323,44 @@ pub fn is_faceted_by(field: &str, facet: &str) -> bool {
         && field[facet.len()..].chars().next().map(|c| c == '.').unwrap_or(true)
 }
 
-pub fn normalize_facet(original: &str) -> String {
-    CompatibilityDecompositionNormalizer.normalize_str(original.trim()).to_lowercase()
+/// Normalizes a string like the words of the documents and of the queries are normalized:
+/// the compatibility characters are folded, the string is lowercased and its diacritics are
+/// removed, e.g. `Énergie`, `ÉNERGIE` and `ｅｎｅｒｇｉｅ` all become `energie`.
+///
+/// The normalized strings are only used for the comparisons, the original strings are kept
+/// to be displayed and highlighted.
+pub fn normalize_str(original: &str) -> String {
+    original.normalize(&NormalizerOption::default()).into_owned()
+}
+
+/// Normalizes a trimmed facet string, the index tells which normalization its facets
+/// have been stored with, see [`Index::facet_normalization`].
+pub fn normalize_facet(original: &str, normalization: FacetNormalization) -> String {
+    match normalization {
+        FacetNormalization::Lowercase => {
+            CompatibilityDecompositionNormalizer.normalize_str(original.trim()).to_lowercase()
+        }
+        FacetNormalization::Words => normalize_str(original.trim()),
+    }
+}
+
+/// Returns the number represented by a facet string, e.g. `"42"`, `" 42.0 "` or `"-0.5"`.
+///
+/// These strings are indexed as numbers so that the documents sending `"42"` or `42` are
+/// filtered, sorted and aggregated the same way. Only the decimal notation without leading
+/// zeros is a number, the codes like `"042"` or the strings like `"1e3"` are kept as strings.
+pub fn facet_string_as_number(original: &str) -> Option<f64> {
+    let trimmed = original.trim();
+    let unsigned = trimmed.strip_prefix('-').unwrap_or(trimmed);
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, "0"));
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(integer)
+        || !is_digits(fraction)
+        || (integer.len() > 1 && integer.starts_with('0'))
+    {
+        return None;
+    }
+    trimmed.parse::<f64>().ok().filter(|number| number.is_finite())
 }
 
 /// Represents either a vector or an array of multiple vectors.
@@ -351,6 +426,58 @@ mod tests {
         assert_eq!(string, "name: John Doe. . 43. hello. I. am. fine. . ");
     }
 
+    #[test]
+    fn normalize_diacritics_case_and_compatibility_characters() {
+        assert_eq!(normalize_str("énergie"), "energie");
+        assert_eq!(normalize_str("ÉNERGIE"), "energie");
+        assert_eq!(normalize_str("ｅｎｅｒｇｉｅ"), "energie");
+        assert_eq!(normalize_str("\u{FB01}nance"), "finance");
+        assert_eq!(normalize_facet("  Crème Brûlée ", FacetNormalization::Words), "creme brulee");
+        assert_eq!(normalize_facet("ｅｎｅｒｇｉｅ", FacetNormalization::Lowercase), "energie");
+        assert_ne!(normalize_facet("Énergie", FacetNormalization::Lowercase), "energie");
+    }
+
+    #[test]
+    fn facet_strings_as_numbers() {
+        assert_eq!(facet_string_as_number("42"), Some(42.0));
+        assert_eq!(facet_string_as_number("-0.5"), Some(-0.5));
+        assert_eq!(facet_string_as_number(" -0.5 "), Some(-0.5));
+        assert_eq!(facet_string_as_number("42.0"), Some(42.0));
+        assert_eq!(facet_string_as_number("042"), None);
+        assert_eq!(facet_string_as_number("1e3"), None);
+        assert_eq!(facet_string_as_number("42."), None);
+        assert_eq!(facet_string_as_number("42 cm"), None);
+        assert_eq!(facet_string_as_number("NaN"), None);
+        assert_eq!(facet_string_as_number("inf"), None);
+    }
+
+    #[test]
+    fn displayed_nested_fields() {
+        let index = crate::index::tests::TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "Le Louvre", "address": { "city": "Paris", "zip": "75001" } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let (_, obkv) = index.documents(&rtxn, [0]).unwrap()[0];
+
+        let document =
+            displayed_obkv_to_json(&["name", "address.city"], &fields_ids_map, obkv).unwrap();
+        assert_eq!(
+            Value::Object(document),
+            json!({ "name": "Le Louvre", "address": { "city": "Paris" } })
+        );
+
+        let document = displayed_obkv_to_json(&["address"], &fields_ids_map, obkv).unwrap();
+        assert_eq!(
+            Value::Object(document),
+            json!({ "address": { "city": "Paris", "zip": "75001" } })
+        );
+    }
+
     #[test]
     fn test_relative_position_conversion() {
         assert_eq!((0x0000, 0x0000), relative_from_absolute_position(0x00000000));
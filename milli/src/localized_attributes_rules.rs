@@ -0,0 +1,144 @@
+//! This module provides the `LocalizedAttributesRule` type, associating fields with the
+//! languages they are written in.
+
+use std::collections::HashMap;
+
+use charabia::{Language, Script};
+use serde::{Deserialize, Serialize};
+
+use crate::error::UserError;
+use crate::{is_faceted_by, FieldId, FieldsIdsMap};
+
+/// The fields matching one of the attribute patterns of a rule are tokenized and normalized
+/// for the locales of the rule, instead of the languages detected in their content.
+///
+/// A pattern is a field name, matching this field and its nested fields, or a name starting
+/// and/or ending with a `*` wildcard, e.g. `title_*`, `*_ja` or `*`. The first rule matching
+/// a field applies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedAttributesRule {
+    pub attribute_patterns: Vec<String>,
+    /// The ISO 639-3 codes of the languages, e.g. `jpn` or `cmn`.
+    pub locales: Vec<String>,
+}
+
+impl LocalizedAttributesRule {
+    pub fn new(attribute_patterns: Vec<String>, locales: Vec<String>) -> Self {
+        LocalizedAttributesRule { attribute_patterns, locales }
+    }
+
+    pub fn matches(&self, field: &str) -> bool {
+        self.attribute_patterns.iter().any(|pattern| match_pattern(pattern, field))
+    }
+
+    /// Returns the languages of the locales, or the first locale that is not supported.
+    pub fn languages(&self) -> Result<Vec<Language>, UserError> {
+        self.locales
+            .iter()
+            .map(|locale| {
+                parse_locale(locale)
+                    .ok_or_else(|| UserError::InvalidLocale { locale: locale.clone() })
+            })
+            .collect()
+    }
+}
+
+/// The allow lists of the tokenizer of the localized fields, by field id.
+pub type LocalizedFieldsIds = HashMap<FieldId, HashMap<Script, Vec<Language>>>;
+
+/// Returns the allow lists of the tokenizer of the fields matching a rule.
+pub(crate) fn localized_fields_ids(
+    rules: &[LocalizedAttributesRule],
+    fields_ids_map: &FieldsIdsMap,
+) -> Result<LocalizedFieldsIds, UserError> {
+    let mut localized_fields = HashMap::new();
+    for (field_id, name) in fields_ids_map.iter() {
+        if let Some(rule) = rules.iter().find(|rule| rule.matches(name)) {
+            localized_fields.insert(field_id, allow_list(&rule.languages()?));
+        }
+    }
+    Ok(localized_fields)
+}
+
+/// Returns the language of an ISO 639-3 code, `None` if it isn't supported by the tokenizer.
+pub fn parse_locale(locale: &str) -> Option<Language> {
+    let language = Language::from_name(locale);
+    (language.name() == locale).then_some(language)
+}
+
+/// Returns the allow list of the tokenizer restricting the detection to the given languages.
+pub fn allow_list(languages: &[Language]) -> HashMap<Script, Vec<Language>> {
+    let mut allow_list: HashMap<_, Vec<_>> = HashMap::new();
+    for &language in languages {
+        allow_list.entry(script_of(language)).or_default().push(language);
+    }
+    allow_list
+}
+
+/// Returns the script a language is written in.
+fn script_of(language: Language) -> Script {
+    match language.name() {
+        "cmn" | "jpn" => Script::Cj,
+        "kor" => Script::Hangul,
+        "heb" | "yid" => Script::Hebrew,
+        "tha" => Script::Thai,
+        "ell" => Script::Greek,
+        "ara" | "pes" | "urd" => Script::Arabic,
+        "rus" | "ukr" | "bel" | "bul" | "srp" | "mkd" => Script::Cyrillic,
+        _ => Script::Latin,
+    }
+}
+
+fn match_pattern(pattern: &str, field: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match (pattern.strip_prefix('*'), pattern.strip_suffix('*')) {
+        (Some(_), Some(_)) => field.contains(&pattern[1..pattern.len() - 1]),
+        (Some(suffix), None) => field.ends_with(suffix),
+        (None, Some(prefix)) => field.starts_with(prefix),
+        (None, None) => is_faceted_by(field, pattern),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+
+    #[test]
+    fn match_attribute_patterns() {
+        let rule = LocalizedAttributesRule::new(vec![S("title_*"), S("*_ja"), S("author")], vec![]);
+        assert!(rule.matches("title_fr"));
+        assert!(rule.matches("description_ja"));
+        assert!(rule.matches("author"));
+        assert!(rule.matches("author.name"));
+        assert!(!rule.matches("authors"));
+        assert!(!rule.matches("subtitle"));
+
+        let rule = LocalizedAttributesRule::new(vec![S("*desc*")], vec![]);
+        assert!(rule.matches("short_description"));
+        assert!(LocalizedAttributesRule::new(vec![S("*")], vec![]).matches("anything"));
+    }
+
+    #[test]
+    fn parse_the_locales() {
+        let rule = LocalizedAttributesRule::new(vec![S("*")], vec![S("jpn"), S("eng")]);
+        let languages = rule.languages().unwrap();
+        assert_eq!(languages, vec![Language::Jpn, Language::Eng]);
+        assert_eq!(
+            allow_list(&languages),
+            HashMap::from([
+                (Script::Cj, vec![Language::Jpn]),
+                (Script::Latin, vec![Language::Eng])
+            ])
+        );
+
+        let rule = LocalizedAttributesRule::new(vec![S("*")], vec![S("jpn"), S("japanese")]);
+        assert!(
+            matches!(rule.languages(), Err(UserError::InvalidLocale { locale }) if locale == "japanese")
+        );
+    }
+}
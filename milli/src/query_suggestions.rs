@@ -0,0 +1,141 @@
+//! This module records the queries made by the users and suggests the most
+//! frequent ones starting with a prefix, e.g. to build a search-as-you-type box.
+//!
+//! Nothing is recorded by the engine itself, it is up to the caller to decide which
+//! queries are worth recording, e.g. only the ones that returned results.
+
+use heed::RoTxn;
+use serde::Serialize;
+
+use crate::{normalize_str, Index, Result};
+
+/// The maximum length, in bytes, of a recorded query, longer queries are ignored.
+pub const MAX_QUERY_LENGTH: usize = 256;
+
+const DEFAULT_SUGGESTIONS_LIMIT: usize = 10;
+
+/// Normalizes the query so that the queries only differing by their case, their diacritics
+/// or their whitespaces are counted together, the whitespaces are trimmed and collapsed.
+///
+/// Returns `None` if the query is empty or too long to be recorded.
+pub(crate) fn normalize_query(query: &str) -> Option<String> {
+    let normalized = normalize_str(query).split_whitespace().collect::<Vec<_>>().join(" ");
+    if normalized.is_empty() || normalized.len() > MAX_QUERY_LENGTH {
+        return None;
+    }
+    Some(normalized)
+}
+
+/// Normalizes the prefix like the recorded queries but keeps a trailing whitespace,
+/// it means that the last word is complete.
+fn normalize_prefix(prefix: &str) -> String {
+    let mut normalized = normalize_query(prefix).unwrap_or_default();
+    if !normalized.is_empty() && prefix.ends_with(char::is_whitespace) {
+        normalized.push(' ');
+    }
+    normalized
+}
+
+/// A recorded query along with the number of times it has been recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuerySuggestion {
+    pub query: String,
+    pub count: u64,
+}
+
+/// Retrieves the most frequently recorded queries starting with a prefix.
+pub struct QuerySuggestions<'a> {
+    prefix: String,
+    limit: usize,
+    rtxn: &'a RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> QuerySuggestions<'a> {
+    pub fn new(rtxn: &'a RoTxn, index: &'a Index) -> QuerySuggestions<'a> {
+        QuerySuggestions { prefix: String::new(), limit: DEFAULT_SUGGESTIONS_LIMIT, rtxn, index }
+    }
+
+    pub fn prefix(&mut self, prefix: impl Into<String>) -> &mut Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Returns the suggestions sorted by decreasing count, the queries with the same
+    /// count are sorted alphabetically.
+    pub fn execute(&self) -> Result<Vec<QuerySuggestion>> {
+        let prefix = normalize_prefix(&self.prefix);
+
+        let mut suggestions = Vec::new();
+        for result in self.index.query_suggestions.prefix_iter(self.rtxn, &prefix)? {
+            let (query, count) = result?;
+            suggestions.push(QuerySuggestion { query: query.to_string(), count: count.get() });
+        }
+
+        suggestions
+            .sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+        suggestions.truncate(self.limit);
+
+        Ok(suggestions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn normalization() {
+        assert_eq!(normalize_query("  New   York "), Some(String::from("new york")));
+        assert_eq!(normalize_query("New York"), Some(String::from("new york")));
+        assert_eq!(normalize_query("New\tYork\n"), Some(String::from("new york")));
+        assert_eq!(normalize_query("Crème brûlée"), Some(String::from("creme brulee")));
+        assert_eq!(normalize_query("   "), None);
+        assert_eq!(normalize_query(&"a".repeat(MAX_QUERY_LENGTH + 1)), None);
+
+        assert_eq!(normalize_prefix("  New   York "), "new york ");
+        assert_eq!(normalize_prefix("New York"), "new york");
+        assert_eq!(normalize_prefix("   "), "");
+    }
+
+    #[test]
+    fn suggest_recorded_queries() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        for query in ["new york", "New York", "new delhi", "newcastle", "paris", "  new  york "] {
+            assert!(index.record_query(&mut wtxn, query).unwrap());
+        }
+        assert!(!index.record_query(&mut wtxn, " ").unwrap());
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let suggestions = index.query_suggestions(&rtxn).prefix("NEW").execute().unwrap();
+        let suggestions: Vec<_> = suggestions.iter().map(|s| (s.query.as_str(), s.count)).collect();
+        assert_eq!(suggestions, vec![("new york", 3), ("new delhi", 1), ("newcastle", 1)]);
+
+        let suggestions = index.query_suggestions(&rtxn).prefix("new ").execute().unwrap();
+        let suggestions: Vec<_> = suggestions.iter().map(|s| (s.query.as_str(), s.count)).collect();
+        assert_eq!(suggestions, vec![("new york", 3), ("new delhi", 1)]);
+
+        let suggestions = index.query_suggestions(&rtxn).limit(1).execute().unwrap();
+        assert_eq!(
+            suggestions,
+            vec![QuerySuggestion { query: String::from("new york"), count: 3 }]
+        );
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        index.clear_query_suggestions(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.query_suggestions(&rtxn).execute().unwrap().is_empty());
+    }
+}
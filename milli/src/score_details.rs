@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::distance_between_two_points;
 
@@ -11,8 +11,10 @@ pub enum ScoreDetails {
     Position(Rank),
     ExactAttribute(ExactAttribute),
     Exactness(Rank),
+    Order(Rank),
     Sort(Sort),
     GeoSort(GeoSort),
+    Boost(Boost),
 }
 
 impl ScoreDetails {
@@ -29,13 +31,25 @@ impl ScoreDetails {
             ScoreDetails::Position(details) => Some(*details),
             ScoreDetails::ExactAttribute(details) => Some(details.rank()),
             ScoreDetails::Exactness(details) => Some(*details),
+            ScoreDetails::Order(details) => Some(*details),
             ScoreDetails::Sort(_) => None,
             ScoreDetails::GeoSort(_) => None,
+            ScoreDetails::Boost(_) => None,
         }
     }
 
+    /// The score combining the ranks of the details, multiplied by their boosts.
     pub fn global_score<'a>(details: impl Iterator<Item = &'a Self>) -> f64 {
-        Rank::global_score(details.filter_map(Self::rank))
+        let mut boost = 1.0;
+        let ranks = details.filter_map(|details| match details {
+            ScoreDetails::Boost(details) => {
+                boost *= details.factor;
+                None
+            }
+            details => details.rank(),
+        });
+        let score = Rank::global_score(ranks);
+        score * boost
     }
 
     /// Panics
@@ -135,6 +149,14 @@ impl ScoreDetails {
                     }
                     // do not update the order since this was already done by exactAttribute
                 }
+                ScoreDetails::Order(details) => {
+                    let order_details = serde_json::json!({
+                        "order": order,
+                        "score": details.local_score(),
+                    });
+                    details_map.insert("order".into(), order_details);
+                    order += 1;
+                }
                 ScoreDetails::Sort(details) => {
                     let sort = if details.redacted {
                         format!("<hidden-rule-{order}>")
@@ -174,6 +196,14 @@ impl ScoreDetails {
                     details_map.insert(sort, sort_details);
                     order += 1;
                 }
+                ScoreDetails::Boost(details) => {
+                    let boost_details = serde_json::json!({
+                        "order": order,
+                        "factor": details.factor,
+                    });
+                    details_map.insert("boost".into(), boost_details);
+                    order += 1;
+                }
             }
         }
         details_map
@@ -189,7 +219,7 @@ impl ScoreDetails {
 /// This strategy could feasibly be extended to differentiate between the normalized score and the
 /// detailed scores, but it is not useful today as the normalized score is *derived from* the
 /// detailed scores.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ScoringStrategy {
     /// Don't compute scores
     #[default]
@@ -291,6 +321,12 @@ impl ExactAttribute {
     }
 }
 
+/// The factor the global score of a document is multiplied by, read from the boost field.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Boost {
+    pub factor: f64,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Sort {
     pub field_name: String,
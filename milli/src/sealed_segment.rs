@@ -0,0 +1,440 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::ops::Range;
+
+use charabia::{Language, Script, TokenKind, TokenizerBuilder};
+use fst::{IntoStreamer, Streamer};
+use heed::RoTxn;
+use roaring::RoaringBitmap;
+
+use crate::score_details::{ScoreDetails, Words};
+use crate::search::build_dfa;
+use crate::{DocumentId, FieldsIdsMap, Index, InternalError, Result};
+
+/// The first bytes of a sealed segment, the last one is the version of the format.
+const MAGIC: &[u8; 8] = b"MLSEGMT\x01";
+
+const FIELDS_IDS_MAP: usize = 0;
+const STOP_WORDS: usize = 1;
+const WORDS: usize = 2;
+const WORD_DOCIDS: usize = 3;
+const DOCUMENTS_OFFSETS: usize = 4;
+const DOCUMENTS: usize = 5;
+const SECTIONS_COUNT: usize = 6;
+
+/// The size of an entry of the documents offsets, a document id followed by
+/// the offset of the document in the documents section.
+const DOCUMENT_OFFSET_SIZE: usize = 4 + 8;
+
+/// Builds a [`SealedSegment`], the words must be inserted in lexicographic order
+/// and the documents in the order of their ids.
+pub(crate) struct SealedSegmentBuilder {
+    sections: [Vec<u8>; SECTIONS_COUNT],
+    words: fst::MapBuilder<Vec<u8>>,
+    last_docid: Option<DocumentId>,
+}
+
+impl SealedSegmentBuilder {
+    pub fn new<A: AsRef<[u8]>>(
+        fields_ids_map: &FieldsIdsMap,
+        stop_words: Option<&fst::Set<A>>,
+    ) -> Result<SealedSegmentBuilder> {
+        let mut sections: [Vec<u8>; SECTIONS_COUNT] = Default::default();
+        sections[FIELDS_IDS_MAP] =
+            serde_json::to_vec(fields_ids_map).map_err(InternalError::SerdeJson)?;
+        if let Some(stop_words) = stop_words {
+            sections[STOP_WORDS] = stop_words.as_fst().as_bytes().to_vec();
+        }
+        Ok(SealedSegmentBuilder { sections, words: fst::MapBuilder::memory(), last_docid: None })
+    }
+
+    pub fn insert_word(&mut self, word: &str, docids: &RoaringBitmap) -> Result<()> {
+        let word_docids = &mut self.sections[WORD_DOCIDS];
+        self.words.insert(word, word_docids.len() as u64)?;
+        docids.serialize_into(word_docids)?;
+        Ok(())
+    }
+
+    pub fn insert_document(
+        &mut self,
+        docid: DocumentId,
+        external_id: &str,
+        obkv: obkv::KvReaderU16,
+    ) -> Result<()> {
+        if self.last_docid.map_or(false, |last_docid| last_docid >= docid) {
+            return Err(InternalError::InvalidSealedSegment("unordered documents").into());
+        }
+        self.last_docid = Some(docid);
+
+        let offset = self.sections[DOCUMENTS].len() as u64;
+        let offsets = &mut self.sections[DOCUMENTS_OFFSETS];
+        offsets.extend_from_slice(&docid.to_le_bytes());
+        offsets.extend_from_slice(&offset.to_le_bytes());
+
+        let mut writer = obkv::KvWriterU16::memory();
+        for (field_id, value) in obkv.iter() {
+            writer.insert(field_id, value)?;
+        }
+        let obkv = writer.into_inner()?;
+
+        let documents = &mut self.sections[DOCUMENTS];
+        for bytes in [external_id.as_bytes(), obkv.as_slice()] {
+            documents.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            documents.extend_from_slice(bytes);
+        }
+        Ok(())
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>> {
+        let SealedSegmentBuilder { mut sections, words, last_docid: _ } = self;
+        sections[WORDS] = words.into_inner()?;
+
+        let len = MAGIC.len() + sections.iter().map(|section| 8 + section.len()).sum::<usize>();
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend_from_slice(MAGIC);
+        for section in &sections {
+            bytes.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(section);
+        }
+        Ok(bytes)
+    }
+}
+
+/// A compact and read-only export of documents of an index, created with
+/// [`Index::sealed_segment`](crate::Index::sealed_segment).
+///
+/// It contains the words of the documents, the documents containing each of them and the
+/// documents themselves. Nothing is loaded in memory, the segment is read in place, which
+/// allows to memory-map old documents from a cheaper storage and search them along the index
+/// with a [`TieredSearch`](crate::TieredSearch).
+pub struct SealedSegment<D = memmap2::Mmap> {
+    data: D,
+    sections: [Range<usize>; SECTIONS_COUNT],
+    fields_ids_map: FieldsIdsMap,
+}
+
+/// The documents of a [`SealedSegment`] matching a query.
+#[derive(Debug, Default)]
+pub struct SealedSearchResult {
+    pub candidates: RoaringBitmap,
+    /// The score of the candidates, they all match the same words of the query.
+    pub score: Vec<ScoreDetails>,
+}
+
+/// The tokenizer and typo tolerance settings of the index a [`SealedSegment`] is searched
+/// along, see [`SealedSegment::search_with_settings`].
+///
+/// The default settings don't allow any typo.
+#[derive(Default)]
+pub struct SealedSearchSettings<'t> {
+    pub dictionary: Option<Vec<String>>,
+    pub script_language: HashMap<Script, Vec<Language>>,
+    pub authorize_typos: bool,
+    pub min_word_len_one_typo: u8,
+    pub min_word_len_two_typos: u8,
+    pub exact_words: Option<fst::Set<Cow<'t, [u8]>>>,
+}
+
+impl<'t> SealedSearchSettings<'t> {
+    pub fn from_index(index: &Index, rtxn: &'t RoTxn) -> Result<SealedSearchSettings<'t>> {
+        Ok(SealedSearchSettings {
+            dictionary: index.cached_settings(rtxn)?.dictionary.clone(),
+            script_language: index.script_language(rtxn)?,
+            authorize_typos: index.authorize_typos(rtxn)?,
+            min_word_len_one_typo: index.min_word_len_one_typo(rtxn)?,
+            min_word_len_two_typos: index.min_word_len_two_typos(rtxn)?,
+            exact_words: index.exact_words(rtxn)?,
+        })
+    }
+
+    /// The number of typos allowed in a word of the query, like in the index.
+    fn number_of_typos_allowed(&self, word: &str) -> u8 {
+        if !self.authorize_typos
+            || word.len() < self.min_word_len_one_typo as usize
+            || self.exact_words.as_ref().map_or(false, |fst| fst.contains(word))
+        {
+            0
+        } else if word.len() < self.min_word_len_two_typos as usize {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+impl<D: AsRef<[u8]>> SealedSegment<D> {
+    pub fn new(data: D) -> Result<SealedSegment<D>> {
+        let bytes = data.as_ref();
+        if !bytes.starts_with(MAGIC) {
+            return Err(InternalError::InvalidSealedSegment("invalid header").into());
+        }
+
+        let mut sections: [Range<usize>; SECTIONS_COUNT] = Default::default();
+        let mut offset = MAGIC.len();
+        for section in &mut sections {
+            let len = bytes
+                .get(offset..offset + 8)
+                .map(|len| u64::from_le_bytes(len.try_into().unwrap()) as usize)
+                .ok_or(InternalError::InvalidSealedSegment("truncated section"))?;
+            offset += 8;
+            if bytes.len() - offset < len {
+                return Err(InternalError::InvalidSealedSegment("truncated section").into());
+            }
+            *section = offset..offset + len;
+            offset += len;
+        }
+        if sections[DOCUMENTS_OFFSETS].len() % DOCUMENT_OFFSET_SIZE != 0 {
+            return Err(InternalError::InvalidSealedSegment("invalid documents offsets").into());
+        }
+
+        let fields_ids_map = serde_json::from_slice(&bytes[sections[FIELDS_IDS_MAP].clone()])
+            .map_err(InternalError::SerdeJson)?;
+        let segment = SealedSegment { data, sections, fields_ids_map };
+        // validate the FSTs once, they are read again for every search
+        segment.words()?;
+        segment.stop_words()?;
+        Ok(segment)
+    }
+
+    fn section(&self, section: usize) -> &[u8] {
+        &self.data.as_ref()[self.sections[section].clone()]
+    }
+
+    fn words(&self) -> Result<fst::Map<&[u8]>> {
+        Ok(fst::Map::new(self.section(WORDS))?)
+    }
+
+    fn stop_words(&self) -> Result<Option<fst::Set<&[u8]>>> {
+        match self.section(STOP_WORDS) {
+            [] => Ok(None),
+            bytes => Ok(Some(fst::Set::new(bytes)?)),
+        }
+    }
+
+    pub fn fields_ids_map(&self) -> &FieldsIdsMap {
+        &self.fields_ids_map
+    }
+
+    pub fn number_of_documents(&self) -> u64 {
+        (self.section(DOCUMENTS_OFFSETS).len() / DOCUMENT_OFFSET_SIZE) as u64
+    }
+
+    pub fn documents_ids(&self) -> RoaringBitmap {
+        self.section(DOCUMENTS_OFFSETS)
+            .chunks_exact(DOCUMENT_OFFSET_SIZE)
+            .map(|entry| u32::from_le_bytes(entry[..4].try_into().unwrap()))
+            .collect()
+    }
+
+    /// Returns the external id and the fields of a document, `None` if the segment doesn't contain it.
+    pub fn document(&self, docid: DocumentId) -> Result<Option<(&str, obkv::KvReaderU16)>> {
+        let offsets = self.section(DOCUMENTS_OFFSETS);
+        let count = offsets.len() / DOCUMENT_OFFSET_SIZE;
+        let docid_at = |index: usize| {
+            let start = index * DOCUMENT_OFFSET_SIZE;
+            u32::from_le_bytes(offsets[start..start + 4].try_into().unwrap())
+        };
+
+        // the documents are ordered by id
+        let (mut left, mut right) = (0, count);
+        while left < right {
+            let middle = left + (right - left) / 2;
+            if docid_at(middle) < docid {
+                left = middle + 1;
+            } else {
+                right = middle;
+            }
+        }
+        if left == count || docid_at(left) != docid {
+            return Ok(None);
+        }
+
+        let start = left * DOCUMENT_OFFSET_SIZE + 4;
+        let offset = u64::from_le_bytes(offsets[start..start + 8].try_into().unwrap()) as usize;
+        let documents = self.section(DOCUMENTS);
+        let invalid = || InternalError::InvalidSealedSegment("invalid document");
+        let (external_id, offset) = read_slice(documents, offset).ok_or_else(invalid)?;
+        let (obkv, _) = read_slice(documents, offset).ok_or_else(invalid)?;
+        let external_id = std::str::from_utf8(external_id)?;
+        Ok(Some((external_id, obkv::KvReaderU16::new(obkv))))
+    }
+
+    /// Returns the documents containing the words of the query, the last one as a prefix.
+    ///
+    /// When no document contains all of them, the last words are ignored one by one.
+    /// An empty query matches all the documents.
+    pub fn search(&self, query: &str) -> Result<SealedSearchResult> {
+        self.search_with_settings(query, &SealedSearchSettings::default())
+    }
+
+    /// Like [`Self::search`], the query is tokenized and its words are matched with typos
+    /// like in the index of the settings.
+    pub fn search_with_settings(
+        &self,
+        query: &str,
+        settings: &SealedSearchSettings,
+    ) -> Result<SealedSearchResult> {
+        let stop_words = self.stop_words()?;
+        let mut tokenizer_builder = TokenizerBuilder::default();
+        if let Some(ref stop_words) = stop_words {
+            tokenizer_builder.stop_words(stop_words);
+        }
+        let dictionary: Option<Vec<_>> =
+            settings.dictionary.as_ref().map(|words| words.iter().map(String::as_str).collect());
+        if let Some(ref dictionary) = dictionary {
+            tokenizer_builder.words_dict(dictionary);
+        }
+        if !settings.script_language.is_empty() {
+            tokenizer_builder.allow_list(&settings.script_language);
+        }
+        let tokenizer = tokenizer_builder.build();
+        let words: Vec<_> = tokenizer
+            .tokenize(query)
+            .filter(|token| matches!(token.kind, TokenKind::Word))
+            .map(|token| token.lemma().to_string())
+            .collect();
+
+        if words.is_empty() {
+            return Ok(SealedSearchResult { candidates: self.documents_ids(), score: Vec::new() });
+        }
+
+        let max_matching_words = words.len() as u32;
+        for len in (1..=words.len()).rev() {
+            let candidates = self.words_docids(&words[..len], settings)?;
+            if !candidates.is_empty() {
+                let words = Words { matching_words: len as u32, max_matching_words };
+                return Ok(SealedSearchResult {
+                    candidates,
+                    score: vec![ScoreDetails::Words(words)],
+                });
+            }
+        }
+        Ok(SealedSearchResult::default())
+    }
+
+    /// Returns the documents containing all the words, the last one as a prefix.
+    fn words_docids(
+        &self,
+        words: &[String],
+        settings: &SealedSearchSettings,
+    ) -> Result<RoaringBitmap> {
+        let Some((prefix, words)) = words.split_last() else { return Ok(RoaringBitmap::new()) };
+        let words_fst = self.words()?;
+
+        let mut docids = self.derivations_docids(&words_fst, prefix, true, settings)?;
+        for word in words {
+            if docids.is_empty() {
+                break;
+            }
+            docids &= self.derivations_docids(&words_fst, word, false, settings)?;
+        }
+        Ok(docids)
+    }
+
+    /// Returns the documents containing a word, or one of its typos allowed by the settings.
+    fn derivations_docids(
+        &self,
+        words_fst: &fst::Map<&[u8]>,
+        word: &str,
+        is_prefix: bool,
+        settings: &SealedSearchSettings,
+    ) -> Result<RoaringBitmap> {
+        let dfa = build_dfa(word, settings.number_of_typos_allowed(word), is_prefix);
+        let mut docids = RoaringBitmap::new();
+        let mut stream = words_fst.search(dfa).into_stream();
+        while let Some((_, offset)) = stream.next() {
+            docids |= self.word_docids_at(offset)?;
+        }
+        Ok(docids)
+    }
+
+    fn word_docids_at(&self, offset: u64) -> Result<RoaringBitmap> {
+        let bytes = self
+            .section(WORD_DOCIDS)
+            .get(offset as usize..)
+            .ok_or(InternalError::InvalidSealedSegment("invalid word docids"))?;
+        Ok(RoaringBitmap::deserialize_from(bytes)?)
+    }
+}
+
+/// Reads a slice prefixed by its length at the given offset,
+/// returns the slice and the offset that follows it.
+fn read_slice(bytes: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let len = bytes.get(offset..offset + 4)?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let start = offset + 4;
+    Some((bytes.get(start..start + len)?, start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use big_s::S;
+    use maplit::btreeset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::obkv_to_json;
+
+    #[test]
+    fn export_and_search_a_sealed_segment() {
+        let index = TempIndex::new();
+        index.update_settings(|s| s.set_stop_words(btreeset! { S("the") })).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "The Quick Brown Fox" },
+                { "id": 1, "title": "the lazy dog" },
+                { "id": 2, "title": "a quick dog" },
+                { "id": 3, "title": "a quick cat" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let documents_ids = RoaringBitmap::from_iter([0, 1, 2]);
+        let bytes = index.sealed_segment(&rtxn, &documents_ids).unwrap();
+        let segment = SealedSegment::new(bytes).unwrap();
+        assert_eq!(segment.number_of_documents(), 3);
+        assert_eq!(segment.documents_ids(), documents_ids);
+
+        let result = segment.search("QUICK").unwrap();
+        assert_eq!(result.candidates, RoaringBitmap::from_iter([0, 2]));
+        let result = segment.search("the quick do").unwrap();
+        assert_eq!(result.candidates, RoaringBitmap::from_iter([2]));
+        // the last words are ignored when no document contains all of them
+        let result = segment.search("lazy unicorn").unwrap();
+        assert_eq!(result.candidates, RoaringBitmap::from_iter([1]));
+        assert_eq!(
+            result.score,
+            vec![ScoreDetails::Words(Words { matching_words: 1, max_matching_words: 2 })]
+        );
+        assert!(segment.search("cat").unwrap().candidates.is_empty());
+        assert_eq!(segment.search("").unwrap().candidates, documents_ids);
+
+        // the typos are only allowed with the settings of the index
+        assert!(segment.search("qvick").unwrap().candidates.is_empty());
+        let settings = SealedSearchSettings::from_index(&index, &rtxn).unwrap();
+        let result = segment.search_with_settings("qvick", &settings).unwrap();
+        assert_eq!(result.candidates, RoaringBitmap::from_iter([0, 2]));
+
+        let (external_id, obkv) = segment.document(1).unwrap().unwrap();
+        assert_eq!(external_id, "1");
+        let all_fields: Vec<_> = segment.fields_ids_map().ids().collect();
+        let document = obkv_to_json(&all_fields, segment.fields_ids_map(), obkv).unwrap();
+        assert_eq!(document["title"], "the lazy dog");
+        assert!(segment.document(3).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_sealed_segment() {
+        assert!(SealedSegment::new(b"not a segment".to_vec()).is_err());
+
+        let index = TempIndex::new();
+        index.add_documents(documents!([{ "id": 0, "title": "hello" }])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let mut bytes = index.sealed_segment(&rtxn, &RoaringBitmap::from_iter([0])).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(SealedSegment::new(bytes).is_err());
+    }
+}
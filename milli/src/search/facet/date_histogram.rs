@@ -0,0 +1,307 @@
+use std::ops::Bound;
+
+use roaring::RoaringBitmap;
+use time::{Duration, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+use super::facet_range_search::find_docids_of_facet_within_bounds;
+use super::{facet_max_value, facet_min_value};
+use crate::error::UserError;
+use crate::{Index, Result};
+
+/// The default maximum number of buckets of a [`DateHistogram`].
+pub const DEFAULT_MAX_BUCKETS: usize = 1_000;
+
+/// The width of the buckets of a [`DateHistogram`], in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarInterval {
+    Day,
+    /// The weeks start on mondays.
+    Week,
+    Month,
+}
+
+impl CalendarInterval {
+    /// Returns the start of the bucket containing the date.
+    fn bucket_start(self, date: OffsetDateTime) -> OffsetDateTime {
+        let day = date.replace_time(Time::MIDNIGHT);
+        match self {
+            CalendarInterval::Day => day,
+            CalendarInterval::Week => {
+                let days = Duration::days(day.weekday().number_days_from_monday().into());
+                day.checked_sub(days).unwrap_or(day)
+            }
+            CalendarInterval::Month => day.replace_day(1).unwrap(),
+        }
+    }
+
+    /// Returns the start of the bucket following the one starting at the given date,
+    /// or `None` if it can't be represented.
+    fn next_bucket_start(self, start: OffsetDateTime) -> Option<OffsetDateTime> {
+        match self {
+            CalendarInterval::Day => start.checked_add(Duration::days(1)),
+            CalendarInterval::Week => start.checked_add(Duration::weeks(1)),
+            CalendarInterval::Month => match start.month() {
+                Month::December => {
+                    start.replace_year(start.year() + 1).ok()?.replace_month(Month::January).ok()
+                }
+                month => start.replace_month(month.next()).ok(),
+            },
+        }
+    }
+}
+
+/// The number of documents whose date is in `[start, start + interval)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateHistogramBucket {
+    pub start: OffsetDateTime,
+    pub count: u64,
+}
+
+/// Counts the documents by period of time of a date facet.
+///
+/// The dates are the numbers of the facet, interpreted as UNIX timestamps in seconds.
+/// Every bucket is computed with a range search on the facet levels.
+pub struct DateHistogram<'a> {
+    field: String,
+    interval: CalendarInterval,
+    candidates: Option<RoaringBitmap>,
+    max_buckets: usize,
+    rtxn: &'a heed::RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> DateHistogram<'a> {
+    pub fn new(
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+        field: impl Into<String>,
+        interval: CalendarInterval,
+    ) -> DateHistogram<'a> {
+        DateHistogram {
+            field: field.into(),
+            interval,
+            candidates: None,
+            max_buckets: DEFAULT_MAX_BUCKETS,
+            rtxn,
+            index,
+        }
+    }
+
+    /// Restricts the histogram to the given documents, all the documents are counted by default.
+    pub fn candidates(&mut self, candidates: RoaringBitmap) -> &mut Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    /// Sets the maximum number of buckets, the histograms spanning more buckets are refused.
+    pub fn max_buckets(&mut self, max: usize) -> &mut Self {
+        self.max_buckets = max;
+        self
+    }
+
+    /// Returns the buckets from the oldest to the most recent date of the documents,
+    /// the buckets without any document in between are returned with a count of zero.
+    ///
+    /// The dates before the year -9999 are not counted and the ones after the year 9999
+    /// are counted in the last bucket. Returns an error if there would be more buckets
+    /// than the maximum, a wider interval must then be used.
+    pub fn execute(&self) -> Result<Vec<DateHistogramBucket>> {
+        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
+        if !crate::is_faceted(&self.field, &filterable_fields) {
+            return Err(UserError::InvalidFacetsDistribution {
+                invalid_facets_name: [self.field.clone()].into(),
+                valid_facets_name: filterable_fields.into_iter().collect(),
+            }
+            .into());
+        }
+
+        let field_id = match self.index.fields_ids_map(self.rtxn)?.id(&self.field) {
+            Some(field_id) => field_id,
+            None => return Ok(Vec::new()),
+        };
+        let candidates = match &self.candidates {
+            Some(candidates) => candidates.clone(),
+            None => self.index.documents_ids(self.rtxn)?,
+        };
+
+        let (min, max) = match (
+            facet_min_value(self.index, self.rtxn, field_id, candidates.clone())?,
+            facet_max_value(self.index, self.rtxn, field_id, candidates.clone())?,
+        ) {
+            (Some(min), Some(max)) => (to_date(min), to_date(max)),
+            _ => return Ok(Vec::new()),
+        };
+
+        let db = self.index.facet_id_f64_docids;
+        let mut buckets = Vec::new();
+        let mut start = self.interval.bucket_start(min);
+        while start <= max {
+            if buckets.len() == self.max_buckets {
+                return Err(UserError::TooManyDateHistogramBuckets {
+                    field: self.field.clone(),
+                    max_buckets: self.max_buckets,
+                }
+                .into());
+            }
+            let next_start = self.interval.next_bucket_start(start);
+            let left = Bound::Included(start.unix_timestamp() as f64);
+            let right = match next_start {
+                Some(next_start) => Bound::Excluded(next_start.unix_timestamp() as f64),
+                None => Bound::Unbounded,
+            };
+            let mut docids = RoaringBitmap::new();
+            find_docids_of_facet_within_bounds(
+                self.rtxn,
+                db,
+                field_id,
+                &left,
+                &right,
+                &mut docids,
+            )?;
+            buckets
+                .push(DateHistogramBucket { start, count: docids.intersection_len(&candidates) });
+
+            match next_start {
+                Some(next_start) => start = next_start,
+                None => break,
+            }
+        }
+
+        Ok(buckets)
+    }
+}
+
+/// Converts a timestamp to a date, clamped to the dates that can be represented.
+fn to_date(timestamp: f64) -> OffsetDateTime {
+    let min = PrimitiveDateTime::MIN.assume_utc();
+    let max = PrimitiveDateTime::MAX.assume_utc();
+    let timestamp = timestamp.clamp(min.unix_timestamp() as f64, max.unix_timestamp() as f64);
+    OffsetDateTime::from_unix_timestamp(timestamp.floor() as i64).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::Filter;
+
+    fn create_index() -> TempIndex {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("date"), S("kind") })
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "date": 1675072800, "kind": "post" },    // 2023-01-30T10:00:00
+                { "id": 1, "date": 1675209599, "kind": "comment" }, // 2023-01-31T23:59:59
+                { "id": 2, "date": 1675209600, "kind": "post" },    // 2023-02-01T00:00:00
+                { "id": 3, "date": 1678881600, "kind": "post" },    // 2023-03-15T12:00:00
+                { "id": 4, "kind": "comment" },
+            ]))
+            .unwrap();
+        index
+    }
+
+    fn counts(buckets: Vec<DateHistogramBucket>) -> Vec<(OffsetDateTime, u64)> {
+        buckets.into_iter().map(|DateHistogramBucket { start, count }| (start, count)).collect()
+    }
+
+    #[test]
+    fn histogram_by_interval() {
+        let index = create_index();
+        let rtxn = index.read_txn().unwrap();
+
+        let buckets =
+            DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Month).execute().unwrap();
+        assert_eq!(
+            counts(buckets),
+            vec![
+                (datetime!(2023-01-01 0:00 UTC), 2),
+                (datetime!(2023-02-01 0:00 UTC), 1),
+                (datetime!(2023-03-01 0:00 UTC), 1),
+            ]
+        );
+
+        let buckets =
+            DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Week).execute().unwrap();
+        let buckets = counts(buckets);
+        assert_eq!(buckets.len(), 7);
+        assert_eq!(buckets[0], (datetime!(2023-01-30 0:00 UTC), 3));
+        assert!(buckets[1..6].iter().all(|(_, count)| *count == 0));
+        assert_eq!(buckets[6], (datetime!(2023-03-13 0:00 UTC), 1));
+
+        let buckets =
+            DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Day).execute().unwrap();
+        let buckets = counts(buckets);
+        assert_eq!(buckets.len(), 45);
+        assert_eq!(
+            buckets[..3],
+            [
+                (datetime!(2023-01-30 0:00 UTC), 1),
+                (datetime!(2023-01-31 0:00 UTC), 1),
+                (datetime!(2023-02-01 0:00 UTC), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn histogram_of_candidates() {
+        let index = create_index();
+        let rtxn = index.read_txn().unwrap();
+
+        let candidates =
+            Filter::from_str("kind = post").unwrap().unwrap().evaluate(&rtxn, &index).unwrap();
+        let buckets = DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Month)
+            .candidates(candidates)
+            .execute()
+            .unwrap();
+        assert_eq!(
+            counts(buckets),
+            vec![
+                (datetime!(2023-01-01 0:00 UTC), 1),
+                (datetime!(2023-02-01 0:00 UTC), 1),
+                (datetime!(2023-03-01 0:00 UTC), 1),
+            ]
+        );
+
+        let buckets = DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Month)
+            .candidates(RoaringBitmap::from_iter([4]))
+            .execute()
+            .unwrap();
+        assert!(buckets.is_empty());
+
+        let error =
+            DateHistogram::new(&rtxn, &index, "id", CalendarInterval::Day).execute().unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::UserError(UserError::InvalidFacetsDistribution { .. })
+        ));
+    }
+
+    #[test]
+    fn histogram_with_too_many_buckets() {
+        let index = create_index();
+        let rtxn = index.read_txn().unwrap();
+
+        let buckets = DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Month)
+            .max_buckets(3)
+            .execute()
+            .unwrap();
+        assert_eq!(buckets.len(), 3);
+
+        let error = DateHistogram::new(&rtxn, &index, "date", CalendarInterval::Week)
+            .max_buckets(3)
+            .execute()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::UserError(UserError::TooManyDateHistogramBuckets { max_buckets: 3, .. })
+        ));
+    }
+}
@@ -178,7 +178,8 @@ impl<'a> FacetDistribution<'a> {
             field_id,
             candidates,
             |facet_key, nbr_docids, _| {
-                let facet_key = OrderedF64Codec::bytes_decode(facet_key).unwrap();
+                let facet_key =
+                    OrderedF64Codec::bytes_decode(facet_key).ok_or(heed::Error::Decoding)?;
                 distribution.insert(facet_key.to_string(), nbr_docids);
                 if distribution.len() == self.max_values_per_facet {
                     Ok(ControlFlow::Break(()))
@@ -209,14 +210,20 @@ impl<'a> FacetDistribution<'a> {
             field_id,
             candidates,
             |facet_key, nbr_docids, any_docid| {
-                let facet_key = StrRefCodec::bytes_decode(facet_key).unwrap();
+                // the numbers may have already filled the distribution
+                if distribution.len() >= self.max_values_per_facet {
+                    return Ok(ControlFlow::Break(()));
+                }
+                let facet_key =
+                    StrRefCodec::bytes_decode(facet_key).ok_or(heed::Error::Decoding)?;
 
                 let key: (FieldId, _, &str) = (field_id, any_docid, facet_key);
+                // fallback on the normalized value if the original one is missing
                 let original_string = self
                     .index
                     .field_id_docid_facet_strings
                     .get(self.rtxn, &key)?
-                    .unwrap()
+                    .unwrap_or(facet_key)
                     .to_owned();
 
                 distribution.insert(original_string, nbr_docids);
@@ -402,7 +409,7 @@ mod tests {
 
     use crate::documents::documents_batch_reader_from_objects;
     use crate::index::tests::TempIndex;
-    use crate::{milli_snap, FacetDistribution, OrderBy};
+    use crate::{milli_snap, FacetDistribution, Filter, OrderBy, Search};
 
     #[test]
     fn few_candidates_few_facet_values() {
@@ -479,6 +486,52 @@ mod tests {
         milli_snap!(format!("{map:?}"), @r###"{"colour": {"Blue": 2}}"###);
     }
 
+    #[test]
+    fn facet_distribution_of_a_search() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.autogenerate_docids = true;
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("colour"), S("size") })
+            })
+            .unwrap();
+
+        let documents = documents!([
+            { "title": "shirt", "colour": "Blue", "size": 1 },
+            { "title": "shirt", "colour": "RED", "size": 2 },
+            { "title": "shirt", "colour": "RED", "size": 3 },
+            { "title": "trousers", "colour": "RED", "size": 3 },
+        ]);
+
+        index.add_documents(documents).unwrap();
+
+        let txn = index.read_txn().unwrap();
+
+        let mut search = Search::new(&txn, &index);
+        search.query("shirt");
+        let map = search
+            .facet_distribution()
+            .unwrap()
+            .facets(iter::once(("colour", OrderBy::default())))
+            .execute()
+            .unwrap();
+
+        milli_snap!(format!("{map:?}"), @r###"{"colour": {"Blue": 1, "RED": 2}}"###);
+
+        let mut search = Search::new(&txn, &index);
+        search.query("shirt");
+        search.filter(Filter::from_str("size > 1").unwrap().unwrap());
+        let map = search
+            .facet_distribution()
+            .unwrap()
+            .facets(iter::once(("colour", OrderBy::default())))
+            .execute()
+            .unwrap();
+
+        milli_snap!(format!("{map:?}"), @r###"{"colour": {"RED": 2}}"###);
+    }
+
     #[test]
     fn many_candidates_few_facet_values() {
         let mut index = TempIndex::new_with_map_size(4096 * 10_000);
@@ -598,7 +651,7 @@ mod tests {
             .execute()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "no_candidates", @"ac9229ed5964d893af96a7076e2f8af5");
+        milli_snap!(format!("{map:?}"), "no_candidates", @"892d38bb69b7dacb28087777907b419b");
 
         let map = FacetDistribution::new(&txn, &index)
             .facets(iter::once(("colour", OrderBy::default())))
@@ -614,7 +667,7 @@ mod tests {
             .execute()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "candidates_0_10_000", @"ac9229ed5964d893af96a7076e2f8af5");
+        milli_snap!(format!("{map:?}"), "candidates_0_10_000", @"892d38bb69b7dacb28087777907b419b");
 
         let map = FacetDistribution::new(&txn, &index)
             .facets(iter::once(("colour", OrderBy::default())))
@@ -622,7 +675,7 @@ mod tests {
             .execute()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "candidates_0_5_000", @"825f23a4090d05756f46176987b7d992");
+        milli_snap!(format!("{map:?}"), "candidates_0_5_000", @"42517241c1e06a39cb8a30b8371ef596");
     }
 
     #[test]
@@ -770,7 +823,7 @@ mod tests {
             .compute_stats()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "candidates_0_1000", @r###"{"colour": (0.0, 999.0)}"###);
+        milli_snap!(format!("{map:?}"), "candidates_0_1000", @r###"{"colour": (0.0, 1999.0)}"###);
 
         let map = FacetDistribution::new(&txn, &index)
             .facets(iter::once(("colour", OrderBy::default())))
@@ -778,7 +831,7 @@ mod tests {
             .compute_stats()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "candidates_217_777", @r###"{"colour": (217.0, 776.0)}"###);
+        milli_snap!(format!("{map:?}"), "candidates_217_777", @r###"{"colour": (217.0, 1776.0)}"###);
     }
 
     #[test]
@@ -826,7 +879,7 @@ mod tests {
             .compute_stats()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "candidates_0_1000", @r###"{"colour": (0.0, 1998.0)}"###);
+        milli_snap!(format!("{map:?}"), "candidates_0_1000", @r###"{"colour": (0.0, 10999.0)}"###);
 
         let map = FacetDistribution::new(&txn, &index)
             .facets(iter::once(("colour", OrderBy::default())))
@@ -834,6 +887,6 @@ mod tests {
             .compute_stats()
             .unwrap();
 
-        milli_snap!(format!("{map:?}"), "candidates_217_777", @r###"{"colour": (218.0, 1776.0)}"###);
+        milli_snap!(format!("{map:?}"), "candidates_217_777", @r###"{"colour": (218.0, 10775.0)}"###);
     }
 }
@@ -1,6 +1,8 @@
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::fmt::{Debug, Display};
-use std::ops::Bound::{self, Excluded, Included};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::time::Instant;
 
 use either::Either;
 pub use filter_parser::{Condition, Error as FPError, FilterCondition, Span, Token};
@@ -12,7 +14,11 @@ use crate::error::{Error, UserError};
 use crate::heed_codec::facet::{
     FacetGroupKey, FacetGroupKeyCodec, FacetGroupValueCodec, OrderedF64Codec,
 };
-use crate::{distance_between_two_points, lat_lng_to_xyz, FieldId, Index, Result};
+use crate::heed_codec::StrRefCodec;
+use crate::{
+    distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapLenCodec, FieldId, Index, Result,
+    BEU16,
+};
 
 /// The maximum number of filters the filter AST can process.
 const MAX_FILTER_DEPTH: usize = 2000;
@@ -22,6 +28,21 @@ pub struct Filter<'a> {
     condition: FilterCondition<'a>,
 }
 
+/// The instant after which the conditions of a filter are not evaluated anymore.
+struct Deadline {
+    instant: Option<Instant>,
+    reached: Cell<bool>,
+}
+
+impl Deadline {
+    fn is_reached(&self) -> bool {
+        if !self.reached.get() && self.instant.map_or(false, |instant| Instant::now() >= instant) {
+            self.reached.set(true);
+        }
+        self.reached.get()
+    }
+}
+
 #[derive(Debug)]
 pub enum BadGeoError {
     Lat(f64),
@@ -52,44 +73,21 @@ impl Display for BadGeoError {
 }
 
 #[derive(Debug)]
-enum FilterError<'a> {
-    AttributeNotFilterable { attribute: &'a str, filterable_fields: HashSet<String> },
+enum FilterError {
     ParseGeoError(BadGeoError),
     TooDeep,
 }
-impl<'a> std::error::Error for FilterError<'a> {}
+impl std::error::Error for FilterError {}
 
-impl<'a> From<BadGeoError> for FilterError<'a> {
+impl From<BadGeoError> for FilterError {
     fn from(geo_error: BadGeoError) -> Self {
         FilterError::ParseGeoError(geo_error)
     }
 }
 
-impl<'a> Display for FilterError<'a> {
+impl Display for FilterError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::AttributeNotFilterable { attribute, filterable_fields } => {
-                if filterable_fields.is_empty() {
-                    write!(
-                        f,
-                        "Attribute `{}` is not filterable. This index does not have configured filterable attributes.",
-                        attribute,
-                    )
-                } else {
-                    let filterables_list = filterable_fields
-                        .iter()
-                        .map(AsRef::as_ref)
-                        .collect::<Vec<&str>>()
-                        .join(" ");
-
-                    write!(
-                        f,
-                        "Attribute `{}` is not filterable. Available filterable attributes are: `{}`.",
-                        attribute,
-                        filterables_list,
-                    )
-                }
-            }
             Self::TooDeep => write!(
                 f,
                 "Too many filter conditions, can't process more than {} filters.",
@@ -218,17 +216,82 @@ impl<'a> Filter<'a> {
 
         Ok(Some(Self { condition }))
     }
+
+    /// Combines this filter with another one, only the documents matching both are selected.
+    pub fn and(self, other: Filter<'a>) -> Filter<'a> {
+        Filter { condition: FilterCondition::And(vec![self.condition, other.condition]) }
+    }
 }
 
 impl<'a> Filter<'a> {
     pub fn evaluate(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<RoaringBitmap> {
+        self.evaluate_before(rtxn, index, None).map(Option::unwrap_or_default)
+    }
+
+    /// Evaluates the filter unless the deadline is reached first, `None` is returned in this case.
+    ///
+    /// The deadline is checked before every condition of the filter.
+    pub fn evaluate_before(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        deadline: Option<Instant>,
+    ) -> Result<Option<RoaringBitmap>> {
         // to avoid doing this for each recursive call we're going to do it ONCE ahead of time
         let soft_deleted_documents = index.soft_deleted_documents_ids(rtxn)?;
-        let filterable_fields = index.filterable_fields(rtxn)?;
+        let settings = index.cached_settings(rtxn)?;
+        let deadline = Deadline { instant: deadline, reached: Cell::new(false) };
 
         // and finally we delete all the soft_deleted_documents, again, only once at the very end
-        self.inner_evaluate(rtxn, index, &filterable_fields)
-            .map(|result| result - soft_deleted_documents)
+        let result = self.inner_evaluate(rtxn, index, &settings.filterable_fields, &deadline)?;
+        if deadline.reached.get() {
+            Ok(None)
+        } else {
+            Ok(Some(result - soft_deleted_documents))
+        }
+    }
+
+    /// Estimates the cost of the evaluation of the filter as the number of documents
+    /// having a value for the fields it uses, the documents ids read from the facet databases.
+    ///
+    /// Only the lengths of the bitmaps are decoded. Returns `None` if the filter contains a
+    /// geo condition, their cost is not estimated.
+    pub(crate) fn estimate_cost(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<Option<u64>> {
+        let mut fields = Vec::new();
+        if !collect_fields(&self.condition, &mut fields) {
+            return Ok(None);
+        }
+
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let exists_docids_len =
+            index.facet_id_exists_docids.remap_data_type::<CboRoaringBitmapLenCodec>();
+        let mut cost = 0;
+        for field in fields {
+            if let Some(field_id) = fields_ids_map.id(field.value()) {
+                cost += exists_docids_len.get(rtxn, &BEU16::new(field_id))?.unwrap_or_default();
+            }
+        }
+        Ok(Some(cost))
+    }
+
+    /// Returns the user errors of the fields and numbers of the filter without evaluating it,
+    /// e.g. to validate a user filter when it is received. Like the parsing errors, the errors
+    /// end with the span of the offending token.
+    ///
+    /// The geo conditions are not checked. The primary key doesn't have to be filterable
+    /// when it is used with the `=`, `!=` and `IN` operators.
+    pub fn check(&self, rtxn: &heed::RoTxn, index: &Index) -> Result<()> {
+        let settings = index.cached_settings(rtxn)?;
+        let filterable_fields = &settings.filterable_fields;
+        let primary_key = index.primary_key(rtxn)?;
+        let mut fields = Vec::new();
+        collect_filterable_fields(&self.condition, primary_key, &mut fields);
+        for field in fields {
+            if !crate::is_faceted(field.value(), filterable_fields) {
+                return Err(attribute_not_filterable(field, field.value(), filterable_fields));
+            }
+        }
+        check_numbers(&self.condition)
     }
 
     fn evaluate_operator(
@@ -271,13 +334,14 @@ impl<'a> Filter<'a> {
                 return Ok(exist);
             }
             Condition::Equal(val) => {
+                let normalization = index.facet_normalization(rtxn)?;
                 let string_docids = strings_db
                     .get(
                         rtxn,
                         &FacetGroupKey {
                             field_id,
                             level: 0,
-                            left_bound: &crate::normalize_facet(val.value()),
+                            left_bound: &crate::normalize_facet(val.value(), normalization),
                         },
                     )?
                     .map(|v| v.bitmap)
@@ -298,6 +362,28 @@ impl<'a> Filter<'a> {
                 let all_ids = index.documents_ids(rtxn)?;
                 return Ok(all_ids - docids);
             }
+            Condition::StartsWith(val) => {
+                // The values starting with the prefix are the ones between the prefix and its
+                // successor, the range is resolved with the groups of the upper levels.
+                let normalization = index.facet_normalization(rtxn)?;
+                let prefix = crate::normalize_facet(val.value(), normalization);
+                let successor = prefix_successor(&prefix);
+                let left = Included(prefix.as_str());
+                let right = match &successor {
+                    Some(successor) => Excluded(successor.as_str()),
+                    None => Unbounded,
+                };
+                let mut output = RoaringBitmap::new();
+                facet_range_search::find_docids_of_facet_within_bounds::<StrRefCodec>(
+                    rtxn,
+                    strings_db,
+                    field_id,
+                    &left,
+                    &right,
+                    &mut output,
+                )?;
+                return Ok(output);
+            }
         };
 
         let mut output = RoaringBitmap::new();
@@ -305,6 +391,30 @@ impl<'a> Filter<'a> {
         Ok(output)
     }
 
+    /// Evaluates an `=` or `!=` condition on the primary key with the external documents ids,
+    /// the primary key doesn't have to be filterable. Returns `None` if the field is not the
+    /// primary key or if the operator can't be evaluated on it.
+    fn evaluate_primary_key_operator(
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        field: &str,
+        operator: &Condition<'a>,
+    ) -> Result<Option<RoaringBitmap>> {
+        if index.primary_key(rtxn)? != Some(field) {
+            return Ok(None);
+        }
+        match operator {
+            Condition::Equal(value) => {
+                primary_key_docids(rtxn, index, std::slice::from_ref(value)).map(Some)
+            }
+            Condition::NotEqual(value) => {
+                let docids = primary_key_docids(rtxn, index, std::slice::from_ref(value))?;
+                Ok(Some(index.documents_ids(rtxn)? - docids))
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Aggregates the documents ids that are part of the specified range automatically
     /// going deeper through the levels.
     fn explore_facet_number_levels(
@@ -335,7 +445,13 @@ impl<'a> Filter<'a> {
         rtxn: &heed::RoTxn,
         index: &Index,
         filterable_fields: &HashSet<String>,
+        deadline: &Deadline,
     ) -> Result<RoaringBitmap> {
+        // The result is discarded by `evaluate_before` once the deadline is reached.
+        if deadline.is_reached() {
+            return Ok(RoaringBitmap::new());
+        }
+
         match &self.condition {
             FilterCondition::Not(f) => {
                 let all_ids = index.documents_ids(rtxn)?;
@@ -344,6 +460,7 @@ impl<'a> Filter<'a> {
                     rtxn,
                     index,
                     filterable_fields,
+                    deadline,
                 )?;
                 Ok(all_ids - selected)
             }
@@ -363,11 +480,10 @@ impl<'a> Filter<'a> {
                     } else {
                         Ok(RoaringBitmap::new())
                     }
+                } else if index.primary_key(rtxn)? == Some(fid.value()) {
+                    primary_key_docids(rtxn, index, els)
                 } else {
-                    Err(fid.as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: fid.value(),
-                        filterable_fields: filterable_fields.clone(),
-                    }))?
+                    Err(attribute_not_filterable(fid, fid.value(), filterable_fields))
                 }
             }
             FilterCondition::Condition { fid, op } => {
@@ -378,18 +494,24 @@ impl<'a> Filter<'a> {
                     } else {
                         Ok(RoaringBitmap::new())
                     }
+                } else if let Some(docids) =
+                    Self::evaluate_primary_key_operator(rtxn, index, fid.value(), op)?
+                {
+                    Ok(docids)
                 } else {
-                    Err(fid.as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: fid.value(),
-                        filterable_fields: filterable_fields.clone(),
-                    }))?
+                    Err(attribute_not_filterable(fid, fid.value(), filterable_fields))
                 }
             }
             FilterCondition::Or(subfilters) => {
                 let mut bitmap = RoaringBitmap::new();
                 for f in subfilters {
-                    bitmap |=
-                        Self::inner_evaluate(&(f.clone()).into(), rtxn, index, filterable_fields)?;
+                    bitmap |= Self::inner_evaluate(
+                        &(f.clone()).into(),
+                        rtxn,
+                        index,
+                        filterable_fields,
+                        deadline,
+                    )?;
                 }
                 Ok(bitmap)
             }
@@ -401,6 +523,7 @@ impl<'a> Filter<'a> {
                         rtxn,
                         index,
                         filterable_fields,
+                        deadline,
                     )?;
                     for f in subfilters_iter {
                         if bitmap.is_empty() {
@@ -411,6 +534,7 @@ impl<'a> Filter<'a> {
                             rtxn,
                             index,
                             filterable_fields,
+                            deadline,
                         )?;
                     }
                     Ok(bitmap)
@@ -447,10 +571,7 @@ impl<'a> Filter<'a> {
 
                     Ok(result)
                 } else {
-                    Err(point[0].as_external_error(FilterError::AttributeNotFilterable {
-                        attribute: "_geo",
-                        filterable_fields: filterable_fields.clone(),
-                    }))?
+                    Err(attribute_not_filterable(&point[0], "_geo", filterable_fields))
                 }
             }
             FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
@@ -575,18 +696,119 @@ impl<'a> Filter<'a> {
 
                     Ok(selected_lat & selected_lng)
                 } else {
-                    Err(top_right_point[0].as_external_error(
-                        FilterError::AttributeNotFilterable {
-                            attribute: "_geo",
-                            filterable_fields: filterable_fields.clone(),
-                        },
-                    ))?
+                    Err(attribute_not_filterable(&top_right_point[0], "_geo", filterable_fields))
                 }
             }
         }
     }
 }
 
+/// Collects the fields used by the condition, returns `false` if it contains a geo condition.
+fn collect_fields<'f, 'a>(
+    condition: &'f FilterCondition<'a>,
+    fields: &mut Vec<&'f Token<'a>>,
+) -> bool {
+    match condition {
+        FilterCondition::Not(condition) => collect_fields(condition, fields),
+        FilterCondition::Condition { fid, .. } | FilterCondition::In { fid, .. } => {
+            fields.push(fid);
+            true
+        }
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => conditions
+            .iter()
+            .fold(true, |no_geo, condition| collect_fields(condition, fields) && no_geo),
+        FilterCondition::GeoLowerThan { .. } | FilterCondition::GeoBoundingBox { .. } => false,
+    }
+}
+
+/// Collects the fields that must be filterable, the conditions on the primary key that
+/// are evaluated with the external documents ids are skipped.
+fn collect_filterable_fields<'f, 'a>(
+    condition: &'f FilterCondition<'a>,
+    primary_key: Option<&str>,
+    fields: &mut Vec<&'f Token<'a>>,
+) {
+    match condition {
+        FilterCondition::Not(condition) => {
+            collect_filterable_fields(condition, primary_key, fields)
+        }
+        FilterCondition::In { fid, .. }
+        | FilterCondition::Condition { fid, op: Condition::Equal(_) | Condition::NotEqual(_) }
+            if primary_key == Some(fid.value()) => {}
+        FilterCondition::Condition { fid, .. } | FilterCondition::In { fid, .. } => {
+            fields.push(fid)
+        }
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            for condition in conditions {
+                collect_filterable_fields(condition, primary_key, fields);
+            }
+        }
+        FilterCondition::GeoLowerThan { .. } | FilterCondition::GeoBoundingBox { .. } => (),
+    }
+}
+
+/// Returns the error of a filter using a field that is not filterable, it points at the token.
+fn attribute_not_filterable(
+    token: &Token,
+    attribute: &str,
+    filterable_fields: &HashSet<String>,
+) -> Error {
+    let span = token.original_span();
+    let start = span.get_utf8_column();
+    Error::UserError(UserError::InvalidFilterableAttribute {
+        field: attribute.to_string(),
+        valid_fields: filterable_fields.iter().cloned().collect(),
+        position: start..start + span.fragment().chars().count(),
+        filter: span.extra.to_string(),
+    })
+}
+
+/// Returns the internal ids of the documents with the given external ids.
+fn primary_key_docids(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    values: &[Token],
+) -> Result<RoaringBitmap> {
+    let external_documents_ids = index.external_documents_ids(rtxn)?;
+    Ok(values.iter().filter_map(|value| external_documents_ids.get(value.value())).collect())
+}
+
+/// Returns the smallest string greater than all the strings starting with the prefix,
+/// or `None` if there is none, e.g. `"abd"` for `"abc"`.
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// Parses the numbers of the range conditions like their evaluation does.
+fn check_numbers(condition: &FilterCondition) -> Result<()> {
+    match condition {
+        FilterCondition::Not(condition) => check_numbers(condition),
+        FilterCondition::Condition { op, .. } => match op {
+            Condition::GreaterThan(value)
+            | Condition::GreaterThanOrEqual(value)
+            | Condition::LowerThan(value)
+            | Condition::LowerThanOrEqual(value) => value.parse_finite_float().map(drop),
+            Condition::Between { from, to } => {
+                from.parse_finite_float()?;
+                to.parse_finite_float().map(drop)
+            }
+            _ => Ok(()),
+        }
+        .map_err(Into::into),
+        FilterCondition::Or(conditions) | FilterCondition::And(conditions) => {
+            conditions.iter().try_for_each(check_numbers)
+        }
+        _ => Ok(()),
+    }
+}
+
 impl<'a> From<FilterCondition<'a>> for Filter<'a> {
     fn from(fc: FilterCondition<'a>) -> Self {
         Self { condition: fc }
@@ -600,11 +822,12 @@ mod tests {
 
     use big_s::S;
     use either::Either;
-    use maplit::hashset;
+    use maplit::{btreeset, hashset};
     use roaring::RoaringBitmap;
 
+    use crate::error::{Error, UserError};
     use crate::index::tests::TempIndex;
-    use crate::Filter;
+    use crate::{FacetNormalization, Filter};
 
     #[test]
     fn empty_db() {
@@ -789,6 +1012,223 @@ mod tests {
         assert_eq!(documents_ids, vec![2]);
     }
 
+    #[test]
+    fn filter_ignores_diacritics_and_case() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("sector")));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "sector": "Énergie" },
+                { "id": 1, "sector": "energie" },
+                { "id": 2, "sector": "Énergies" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.filter(Filter::from_str("sector = ENERGIE").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+
+        search.filter(Filter::from_str("sector = énergie").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn filter_keeps_the_normalization_of_the_existing_facets() {
+        let index = TempIndex::new();
+
+        // an index created before the facets were normalized like the words
+        let mut wtxn = index.write_txn().unwrap();
+        index.put_facet_normalization(&mut wtxn, FacetNormalization::Lowercase).unwrap();
+        wtxn.commit().unwrap();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("sector")));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "sector": "Énergie" },
+                { "id": 1, "sector": "energie" },
+            ]))
+            .unwrap();
+
+        let search = |filter: &str| {
+            let rtxn = index.read_txn().unwrap();
+            let mut search = crate::Search::new(&rtxn, &index);
+            search.filter(Filter::from_str(filter).unwrap().unwrap());
+            search.execute().unwrap().documents_ids
+        };
+        assert_eq!(search("sector = ÉNERGIE"), vec![0]);
+        assert_eq!(search("sector = energie"), vec![1]);
+
+        // the facets are normalized like the words once the documents are reindexed
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("sector"), S("id")));
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.facet_normalization(&rtxn).unwrap(), FacetNormalization::Words);
+        drop(rtxn);
+        assert_eq!(search("sector = ÉNERGIE"), vec![0, 1]);
+        assert_eq!(search("sector = energie"), vec![0, 1]);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+
+        let error = Filter::from_str("price > 10 AND genre horror").unwrap_err();
+        assert!(error.to_string().ends_with("16:28 price > 10 AND genre horror"), "{error}");
+
+        let filter = Filter::from_str("price > 10 AND genre = horror").unwrap().unwrap();
+        let error = filter.check(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `genre` is not filterable."));
+        assert!(error.to_string().ends_with("16:21 price > 10 AND genre = horror"), "{error}");
+
+        let filter = Filter::from_str("price > ten").unwrap().unwrap();
+        let error = filter.check(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().ends_with("9:12 price > ten"), "{error}");
+
+        let filter =
+            Filter::from_array([Either::Left(["price = 1", "price > 2"])]).unwrap().unwrap();
+        filter.check(&rtxn, &index).unwrap();
+    }
+
+    #[test]
+    fn not_filterable_error_is_structured() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("year"), S("price") });
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("price > 10 AND genre = \"hörror\"").unwrap().unwrap();
+        let error = filter.check(&rtxn, &index).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Attribute `genre` is not filterable. Available filterable attributes are: `price year`.\n16:21 price > 10 AND genre = \"hörror\""
+        );
+        match error {
+            Error::UserError(UserError::InvalidFilterableAttribute {
+                field,
+                valid_fields,
+                position,
+                filter,
+            }) => {
+                assert_eq!(field, "genre");
+                assert_eq!(valid_fields, btreeset! { S("price"), S("year") });
+                assert_eq!(position, 16..21);
+                assert_eq!(filter, "price > 10 AND genre = \"hörror\"");
+            }
+            error => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn filter_on_the_primary_key() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": "abc", "title": "first" },
+                { "id": "def", "title": "second" },
+                { "id": 42, "title": "third" },
+            ]))
+            .unwrap();
+
+        // the primary key is not filterable, the ids are resolved with the external ids
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.filter(Filter::from_str("id = abc").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+
+        search.filter(Filter::from_str("id IN [def, 42, unknown]").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 2]);
+
+        search.filter(Filter::from_str("id != def AND NOT id = 42").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+
+        // the other operators require the primary key to be filterable
+        let filter = Filter::from_str("id > 10").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `id` is not filterable."));
+        let error = filter.check(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with("Attribute `id` is not filterable."));
+        let filter = Filter::from_str("id = abc OR id EXISTS").unwrap().unwrap();
+        assert!(filter.check(&rtxn, &index).is_err());
+        let filter = Filter::from_str("id IN [abc, def] AND NOT id != 42").unwrap().unwrap();
+        filter.check(&rtxn, &index).unwrap();
+    }
+
+    #[test]
+    fn filter_numbers_sent_as_strings() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("price")));
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "price": 42 },
+                { "id": 1, "price": "42" },
+                { "id": 2, "price": " 42.0 " },
+                { "id": 3, "price": "12" },
+                { "id": 4, "price": "42 euros" },
+                // the leading zeros are kept, it stays a string
+                { "id": 5, "price": "042" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.filter(Filter::from_str("price = 42").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2]);
+
+        search.filter(Filter::from_str("price = \"42.0\"").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2]);
+
+        search.filter(Filter::from_str("price > 20").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2]);
+
+        search.filter(Filter::from_str("price 10 TO 20").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![3]);
+
+        search.filter(Filter::from_str("price = \"42 euros\"").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![4]);
+
+        search.filter(Filter::from_str("price STARTS WITH \"04\"").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![5]);
+    }
+
     #[test]
     fn zero_radius() {
         let index = TempIndex::new();
@@ -1131,4 +1571,274 @@ mod tests {
         let result = filter.evaluate(&rtxn, &index).unwrap();
         assert_eq!(result, RoaringBitmap::from_iter((0..100).filter(|x| x % 10 != 0)));
     }
+
+    #[test]
+    fn filter_boolean_combinators() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 1999 },
+                { "id": 1, "genre": "horror", "year": 2005 },
+                { "id": 2, "genre": "thriller", "year": 2010 },
+                { "id": 3, "genre": "comedy", "year": 2012 },
+                { "id": 4, "genre": "thriller", "year": 1980 },
+                { "id": 5, "genre": "horror" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("(genre = horror OR genre = thriller) AND year > 2000", vec![1, 2]),
+            ("genre = horror OR genre = thriller AND year > 2000", vec![0, 1, 2, 5]),
+            ("NOT (genre = horror OR genre = thriller)", vec![3]),
+            ("NOT genre = horror AND NOT year < 2000", vec![2, 3]),
+            ("(genre = comedy OR (genre = horror AND NOT year EXISTS))", vec![3, 5]),
+        ] {
+            let result = Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+            assert_eq!(result.unwrap(), RoaringBitmap::from_iter(expected), "{filter}");
+        }
+    }
+
+    #[test]
+    fn filter_in() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genre": "horror", "year": 1999 },
+                { "id": 1, "genre": "Horror", "year": 2005 },
+                { "id": 2, "genre": "thriller", "year": 2010 },
+                { "id": 3, "genre": "comedy", "year": 2012 },
+                { "id": 4, "genre": ["thriller", "drama"], "year": 1980 },
+                { "id": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("genre IN [horror, thriller]", vec![0, 1, 2, 4]),
+            ("genre IN [drama]", vec![4]),
+            ("genre IN [western]", vec![]),
+            ("year IN [1999, 2012, 3000]", vec![0, 3]),
+            ("genre NOT IN [horror, thriller]", vec![3, 5]),
+            ("genre IN [comedy, drama] AND year IN [1980]", vec![4]),
+        ] {
+            let result = Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+            assert_eq!(result.unwrap(), RoaringBitmap::from_iter(expected), "{filter}");
+        }
+
+        // An IN is evaluated like the equivalent chain of ORs.
+        let left = Filter::from_str("genre IN [comedy, drama, horror]").unwrap().unwrap();
+        let right =
+            Filter::from_str("genre = comedy OR genre = drama OR genre = horror").unwrap().unwrap();
+        assert_eq!(left.evaluate(&rtxn, &index).unwrap(), right.evaluate(&rtxn, &index).unwrap());
+    }
+
+    #[test]
+    fn filter_array_values() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("genres"), S("ratings") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "genres": ["horror", "comedy"], "ratings": [3, 5] },
+                { "id": 1, "genres": ["Comedy"], "ratings": [4] },
+                { "id": 2, "genres": [["drama"], ["horror", "thriller"]], "ratings": [] },
+                { "id": 3, "genres": "horror", "ratings": 2 },
+                { "id": 4, "genres": ["action", 2010, true] },
+                { "id": 5 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("genres = horror", vec![0, 2, 3]),
+            ("genres = comedy", vec![0, 1]),
+            // the nested arrays are flattened
+            ("genres = drama", vec![2]),
+            ("genres != horror", vec![1, 4, 5]),
+            // every element of a mixed array is indexed with its own type
+            ("genres = 2010", vec![4]),
+            ("genres > 2000", vec![4]),
+            ("genres = true", vec![4]),
+            // a range matches a document if any of its numbers is in the range
+            ("ratings > 4", vec![0]),
+            ("ratings < 4", vec![0, 3]),
+            ("ratings 4 TO 5", vec![0, 1]),
+            ("ratings IS EMPTY", vec![2]),
+            ("genres = horror AND ratings > 4", vec![0]),
+        ] {
+            let result = Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+            assert_eq!(result.unwrap(), RoaringBitmap::from_iter(expected), "{filter}");
+        }
+    }
+
+    #[test]
+    fn filter_starts_with() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("tags") });
+            })
+            .unwrap();
+
+        // enough values for the facet database to have several levels
+        let mut docs = vec![];
+        for i in 0..1000 {
+            docs.push(serde_json::json!({ "id": i, "tags": [format!("tag-{i:04}"), format!("Group-{}", i % 7)] }));
+        }
+        docs.push(serde_json::json!({ "id": 1000, "tags": "tag" }));
+        docs.push(serde_json::json!({ "id": 1001, "tags": ["tah", 42] }));
+        index.add_documents(documents!(docs)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for (filter, expected) in [
+            ("tags STARTS WITH tag-00", RoaringBitmap::from_iter(0..100)),
+            // the prefix is normalized like the facet values
+            ("tags STARTS WITH TAG-099", RoaringBitmap::from_iter(990..1000)),
+            ("tags STARTS WITH 'group-3'", (0..1000).filter(|i| i % 7 == 3).collect()),
+            ("tags STARTS WITH tag", RoaringBitmap::from_iter(0..1001)),
+            ("tags STARTS WITH 4", RoaringBitmap::new()),
+            ("tags STARTS WITH zzz", RoaringBitmap::new()),
+            ("NOT tags STARTS WITH tag", RoaringBitmap::from_iter([1001])),
+        ] {
+            let result = Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+            assert_eq!(result.unwrap(), expected, "{filter}");
+        }
+    }
+
+    #[test]
+    fn prefix_successor() {
+        assert_eq!(super::prefix_successor("abc").as_deref(), Some("abd"));
+        assert_eq!(super::prefix_successor("a\u{D7FF}").as_deref(), Some("a\u{E000}"));
+        assert_eq!(super::prefix_successor("a\u{10FFFF}").as_deref(), Some("b"));
+        assert_eq!(super::prefix_successor("\u{10FFFF}"), None);
+        assert_eq!(super::prefix_successor(""), None);
+    }
+
+    #[test]
+    fn range_filters_union_the_groups_of_the_upper_levels() {
+        use crate::heed_codec::facet::FacetGroupKey;
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+
+        let mut docs = vec![];
+        for i in 0..1000 {
+            docs.push(serde_json::json!({ "id": i, "price": i }));
+        }
+        index.add_documents(documents!(docs)).unwrap();
+
+        // the level 0 entry of a value in the middle of the range is removed, the documents of
+        // this value can only be found by the range through the groups of the upper levels.
+        let mut wtxn = index.write_txn().unwrap();
+        let field_id = index.fields_ids_map(&wtxn).unwrap().id("price").unwrap();
+        let key = FacetGroupKey { field_id, level: 0, left_bound: 250.0 };
+        assert!(index.facet_id_f64_docids.delete(&mut wtxn, &key).unwrap());
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter| Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+        assert_eq!(evaluate("price = 250").unwrap(), RoaringBitmap::new());
+        assert_eq!(
+            evaluate("price >= 10 AND price < 500").unwrap(),
+            RoaringBitmap::from_iter(10..500)
+        );
+        assert_eq!(evaluate("price 10 TO 499").unwrap(), RoaringBitmap::from_iter(10..500));
+        // the boundaries are resolved on the level 0
+        assert_eq!(evaluate("price 250 TO 250").unwrap(), RoaringBitmap::new());
+    }
+
+    #[test]
+    fn filter_exists_null_empty() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key("id".to_owned());
+                settings.set_filterable_fields(hashset! { S("tag") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "tag": "a" },
+                { "id": 1, "tag": null },
+                { "id": 2, "tag": [] },
+                { "id": 3, "tag": "" },
+                { "id": 4, "tag": {} },
+                { "id": 5 },
+            ]))
+            .unwrap();
+
+        // The documents are replaced under new internal ids, we compare the external ones.
+        let check = |expected: &[(&str, &[u32])]| {
+            let rtxn = index.read_txn().unwrap();
+            let external_ids = index.external_documents_ids(&rtxn).unwrap().to_hash_map();
+            for (filter, ids) in expected {
+                let result = Filter::from_str(filter).unwrap().unwrap().evaluate(&rtxn, &index);
+                let result = result.unwrap();
+                let mut found: Vec<u32> = external_ids
+                    .iter()
+                    .filter(|(_, docid)| result.contains(**docid))
+                    .map(|(external_id, _)| external_id.parse().unwrap())
+                    .collect();
+                found.sort_unstable();
+                assert_eq!(found, *ids, "{filter}");
+            }
+        };
+
+        check(&[
+            ("tag EXISTS", &[0, 1, 2, 3, 4]),
+            ("tag NOT EXISTS", &[5]),
+            ("tag IS NULL", &[1]),
+            ("tag IS NOT NULL", &[0, 2, 3, 4, 5]),
+            ("tag IS EMPTY", &[2, 3, 4]),
+            ("tag IS NOT EMPTY", &[0, 1, 5]),
+        ]);
+
+        // The bitmaps follow the updates of the documents.
+        index
+            .add_documents(documents!([
+                { "id": 1, "tag": "b" },
+                { "id": 2 },
+                { "id": 5, "tag": null },
+            ]))
+            .unwrap();
+
+        check(&[
+            ("tag EXISTS", &[0, 1, 3, 4, 5]),
+            ("tag IS NULL", &[5]),
+            ("tag IS EMPTY", &[3, 4]),
+        ]);
+    }
 }
@@ -4,11 +4,13 @@ use heed::types::{ByteSlice, DecodeIgnore};
 use heed::{BytesDecode, RoTxn};
 use roaring::RoaringBitmap;
 
+pub use self::date_histogram::{CalendarInterval, DateHistogram, DateHistogramBucket};
 pub use self::facet_distribution::{FacetDistribution, OrderBy, DEFAULT_VALUES_PER_FACET};
 pub use self::filter::{BadGeoError, Filter};
 use crate::heed_codec::facet::{FacetGroupKeyCodec, FacetGroupValueCodec, OrderedF64Codec};
 use crate::heed_codec::ByteSliceRefCodec;
 use crate::{Index, Result};
+mod date_histogram;
 mod facet_distribution;
 mod facet_distribution_iter;
 mod facet_range_search;
@@ -105,16 +107,16 @@ pub(crate) fn get_highest_level<'t>(
     field_id: u16,
 ) -> heed::Result<u8> {
     let field_id_prefix = &field_id.to_be_bytes();
-    Ok(db
-        .as_polymorph()
-        .rev_prefix_iter::<_, ByteSlice, DecodeIgnore>(txn, field_id_prefix)?
-        .next()
-        .map(|el| {
-            let (key, _) = el.unwrap();
-            let key = FacetGroupKeyCodec::<ByteSliceRefCodec>::bytes_decode(key).unwrap();
-            key.level
-        })
-        .unwrap_or(0))
+    let mut highest_iter =
+        db.as_polymorph().rev_prefix_iter::<_, ByteSlice, DecodeIgnore>(txn, field_id_prefix)?;
+    if let Some(highest) = highest_iter.next() {
+        let (key, _) = highest?;
+        let key = FacetGroupKeyCodec::<ByteSliceRefCodec>::bytes_decode(key)
+            .ok_or(heed::Error::Decoding)?;
+        Ok(key.level)
+    } else {
+        Ok(0)
+    }
 }
 
 #[cfg(test)]
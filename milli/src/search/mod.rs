@@ -1,21 +1,35 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::time::{Duration, Instant};
 
-use fst::automaton::{Automaton, Str};
+use charabia::{Language, TokenizerBuilder};
 use fst::{IntoStreamer, Streamer};
 use levenshtein_automata::{LevenshteinAutomatonBuilder as LevBuilder, DFA};
 use log::error;
 use once_cell::sync::Lazy;
+use rayon::ThreadPool;
 use roaring::bitmap::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 
-pub use self::facet::{FacetDistribution, Filter, OrderBy, DEFAULT_VALUES_PER_FACET};
-pub use self::new::matches::{FormatOptions, MatchBounds, Matcher, MatcherBuilder, MatchingWords};
+pub use self::facet::{
+    CalendarInterval, DateHistogram, DateHistogramBucket, FacetDistribution, Filter, OrderBy,
+    DEFAULT_VALUES_PER_FACET,
+};
+pub use self::new::matches::{
+    FormatOptions, Formatter, FoundWord, MatchBounds, MatchPosition, Matcher, MatcherBuilder,
+    MatchingWordKind, MatchingWords,
+};
 use self::new::PartialSearchResult;
-use crate::error::UserError;
+use self::query_rewriter::rewrite_query;
+pub use self::query_rewriter::{ExpandAbbreviations, QueryRewriter};
+pub use self::sharded::{ShardedHit, ShardedSearch, ShardedSearchResult};
+pub use self::tiered::{Tier, TieredHit, TieredSearch, TieredSearchResult};
+use crate::error::{InternalError, UserError};
 use crate::heed_codec::facet::{FacetGroupKey, FacetGroupValue};
 use crate::score_details::{ScoreDetails, ScoringStrategy};
 use crate::{
     execute_search, normalize_facet, AscDesc, DefaultSearchLogger, DocumentId, FieldId, Index,
-    Result, SearchContext, BEU16,
+    Result, SearchContext, View, BEU16,
 };
 
 // Building these factories is not free.
@@ -26,15 +40,27 @@ static LEVDIST2: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(2, true));
 /// The maximum number of facets returned by the facet search route.
 const MAX_NUMBER_OF_FACETS: usize = 100;
 
+/// The number of interned values after which a batch of searches stops sharing its
+/// search context, interners can't contain more than `u16::MAX` values.
+const MAX_SHARED_INTERNED_VALUES: usize = u16::MAX as usize / 2;
+
+/// The number of cached database entries after which a batch of searches stops sharing
+/// its search context, bounds the memory retained between the searches of a batch.
+const MAX_SHARED_CACHED_ENTRIES: usize = 100_000;
+
 pub mod facet;
 mod fst_utils;
 pub mod new;
+mod query_rewriter;
+mod sharded;
+mod tiered;
 
 pub struct Search<'a> {
     query: Option<String>,
     vector: Option<Vec<f32>>,
     // this should be linked to the String in the query
     filter: Option<Filter<'a>>,
+    view_filter: Option<Filter<'a>>,
     offset: usize,
     limit: usize,
     sort_criteria: Option<Vec<AscDesc>>,
@@ -43,7 +69,17 @@ pub struct Search<'a> {
     terms_matching_strategy: TermsMatchingStrategy,
     scoring_strategy: ScoringStrategy,
     words_limit: usize,
+    typo_derivations_limit: Option<usize>,
+    derivations_thread_pool: Option<&'a ThreadPool>,
+    sort_in_memory_threshold: Option<usize>,
     exhaustive_number_hits: bool,
+    time_budget: Option<Duration>,
+    locales: Option<Vec<Language>>,
+    all_words_as_prefix: bool,
+    autocomplete: bool,
+    stop_words: BTreeSet<String>,
+    tie_breaker: TieBreaker,
+    query_rewriters: Vec<&'a dyn QueryRewriter>,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
 }
@@ -54,6 +90,7 @@ impl<'a> Search<'a> {
             query: None,
             vector: None,
             filter: None,
+            view_filter: None,
             offset: 0,
             limit: 20,
             sort_criteria: None,
@@ -63,6 +100,16 @@ impl<'a> Search<'a> {
             scoring_strategy: Default::default(),
             exhaustive_number_hits: false,
             words_limit: 10,
+            typo_derivations_limit: None,
+            derivations_thread_pool: None,
+            sort_in_memory_threshold: None,
+            time_budget: None,
+            locales: None,
+            all_words_as_prefix: false,
+            autocomplete: true,
+            stop_words: BTreeSet::new(),
+            tie_breaker: TieBreaker::default(),
+            query_rewriters: Vec::new(),
             rtxn,
             index,
         }
@@ -108,16 +155,55 @@ impl<'a> Search<'a> {
         self
     }
 
+    /// Whether the score details of every returned document must be computed precisely,
+    /// a shorthand for the [`Detailed`](ScoringStrategy::Detailed) scoring strategy.
+    pub fn compute_scores(&mut self, value: bool) -> &mut Search<'a> {
+        self.scoring_strategy =
+            if value { ScoringStrategy::Detailed } else { ScoringStrategy::Skip };
+        self
+    }
+
     pub fn words_limit(&mut self, value: usize) -> &mut Search<'a> {
         self.words_limit = value;
         self
     }
 
+    /// Sets the maximum number of typo derivations kept for every word of the query,
+    /// the ones that appear in the most documents are kept. Overrides the limit set in the
+    /// settings of the index.
+    pub fn typo_derivations_limit(&mut self, value: usize) -> &mut Search<'a> {
+        self.typo_derivations_limit = Some(value);
+        self
+    }
+
+    /// Finds the typo derivations of the words of the query in parallel on the given thread
+    /// pool, instead of one word after the other on the thread of the search.
+    pub fn derivations_thread_pool(&mut self, thread_pool: &'a ThreadPool) -> &mut Search<'a> {
+        self.derivations_thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// Sets the maximum number of candidates of a sort ranking rule that are sorted in memory
+    /// from the facet values of each document, the facet levels are walked above it.
+    pub fn sort_in_memory_threshold(&mut self, value: usize) -> &mut Search<'a> {
+        self.sort_in_memory_threshold = Some(value);
+        self
+    }
+
     pub fn filter(&mut self, condition: Filter<'a>) -> &mut Search<'a> {
         self.filter = Some(condition);
         self
     }
 
+    /// Restricts the search to the documents that are part of the given view.
+    ///
+    /// The view filter is combined with the filter of the search, if any. The documents
+    /// must be retrieved with [`Index::view_documents`] to only display the fields of the view.
+    pub fn view(&mut self, view: &'a View) -> Result<&mut Search<'a>> {
+        self.view_filter = view.filter()?;
+        Ok(self)
+    }
+
     #[cfg(test)]
     pub fn geo_sort_strategy(&mut self, strategy: new::GeoSortStrategy) -> &mut Search<'a> {
         self.geo_strategy = strategy;
@@ -131,30 +217,110 @@ impl<'a> Search<'a> {
         self
     }
 
+    /// Stops ranking the documents once the search has been running for the given duration.
+    ///
+    /// The deadline is checked while the ranking rules compute their buckets, once it is reached
+    /// the documents that are not ranked yet are returned in the order of the tie-breaker and the
+    /// result is marked as [`degraded`](SearchResult::degraded). When it is reached during the
+    /// evaluation of the filter, no document is returned.
+    pub fn terminate_after(&mut self, time_budget: Duration) -> &mut Search<'a> {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    /// Tokenizes the query for the given languages instead of the languages detected in
+    /// the documents, e.g. the languages of the localized attributes that are searched.
+    pub fn locales(&mut self, locales: Vec<Language>) -> &mut Search<'a> {
+        self.locales = Some(locales);
+        self
+    }
+
+    /// Considers every word of the query as a prefix, not only the last one, e.g. for the
+    /// search-as-you-type interfaces where the words in the middle of the query can be partial.
+    pub fn all_words_as_prefix(&mut self, value: bool) -> &mut Search<'a> {
+        self.all_words_as_prefix = value;
+        self
+    }
+
+    /// Whether the last word of the query is a prefix, `true` by default. The backends that
+    /// search for complete words, e.g. to score documents in batch, can disable it.
+    pub fn autocomplete(&mut self, value: bool) -> &mut Search<'a> {
+        self.autocomplete = value;
+        self
+    }
+
+    /// Ignores the given words like stop words for this query only, e.g. the boilerplate terms
+    /// added by an interface. They are compared to the normalized words of the query.
+    pub fn stop_words(&mut self, words: BTreeSet<String>) -> &mut Search<'a> {
+        self.stop_words = words;
+        self
+    }
+
+    /// Sets the order of the documents that tie on every ranking rule, see [`TieBreaker`].
+    pub fn tie_breaker(&mut self, tie_breaker: TieBreaker) -> &mut Search<'a> {
+        self.tie_breaker = tie_breaker;
+        self
+    }
+
+    /// Adds a rewriter of the query, applied before the query is tokenized
+    /// after the rewriters that were added before it.
+    pub fn query_rewriter(&mut self, rewriter: &'a dyn QueryRewriter) -> &mut Search<'a> {
+        self.query_rewriters.push(rewriter);
+        self
+    }
+
     pub fn execute(&self) -> Result<SearchResult> {
         let mut ctx = SearchContext::new(self.index, self.rtxn);
+        ctx.deadline = self.time_budget.map(|time_budget| Instant::now() + time_budget);
+        ctx.locales = self.locales.clone();
+        ctx.all_words_as_prefix = self.all_words_as_prefix;
+        ctx.autocomplete = self.autocomplete;
+        ctx.stop_words = self.stop_words.clone();
+        ctx.tie_breaker = self.tie_breaker;
 
         if let Some(searchable_attributes) = self.searchable_attributes {
             ctx.searchable_attributes(searchable_attributes)?;
         }
+        ctx.typo_derivations_limit = match self.typo_derivations_limit {
+            Some(limit) => Some(limit),
+            None => self.index.typo_derivations_limit(self.rtxn)?,
+        };
+        ctx.derivations_thread_pool = self.derivations_thread_pool;
+        if let Some(sort_in_memory_threshold) = self.sort_in_memory_threshold {
+            ctx.sort_in_memory_threshold = sort_in_memory_threshold;
+        }
 
-        let PartialSearchResult { located_query_terms, candidates, documents_ids, document_scores } =
-            execute_search(
-                &mut ctx,
-                &self.query,
-                &self.vector,
-                self.terms_matching_strategy,
-                self.scoring_strategy,
-                self.exhaustive_number_hits,
-                &self.filter,
-                &self.sort_criteria,
-                self.geo_strategy,
-                self.offset,
-                self.limit,
-                Some(self.words_limit),
-                &mut DefaultSearchLogger,
-                &mut DefaultSearchLogger,
-            )?;
+        let filter = match (&self.filter, &self.view_filter) {
+            (Some(filter), Some(view_filter)) => Some(filter.clone().and(view_filter.clone())),
+            (filter, view_filter) => filter.clone().or_else(|| view_filter.clone()),
+        };
+        let query = self
+            .query
+            .as_deref()
+            .map(|query| rewrite_query(&self.query_rewriters, query).into_owned());
+
+        let PartialSearchResult {
+            located_query_terms,
+            candidates,
+            documents_ids,
+            document_scores,
+            degraded,
+        } = execute_search(
+            &mut ctx,
+            &query,
+            &self.vector,
+            self.terms_matching_strategy,
+            self.scoring_strategy,
+            self.exhaustive_number_hits,
+            &filter,
+            &self.sort_criteria,
+            self.geo_strategy,
+            self.offset,
+            self.limit,
+            Some(self.words_limit),
+            &mut DefaultSearchLogger,
+            &mut DefaultSearchLogger,
+        )?;
 
         // consume context and located_query_terms to build MatchingWords.
         let matching_words = match located_query_terms {
@@ -162,7 +328,243 @@ impl<'a> Search<'a> {
             None => MatchingWords::default(),
         };
 
-        Ok(SearchResult { matching_words, candidates, document_scores, documents_ids })
+        Ok(SearchResult { matching_words, candidates, document_scores, documents_ids, degraded })
+    }
+
+    /// Returns a [`FacetDistribution`] restricted to the documents matching
+    /// the query and the filter of this search.
+    ///
+    /// The offset and the limit of the search are ignored, the counts are
+    /// computed on all the matching documents.
+    pub fn facet_distribution(&self) -> Result<FacetDistribution<'a>> {
+        let SearchResult { candidates, .. } = self.execute()?;
+        let mut distribution = FacetDistribution::new(self.rtxn, self.index);
+        distribution.candidates(candidates);
+        Ok(distribution)
+    }
+
+    /// Executes a batch of searches using the same read transaction.
+    ///
+    /// The words FST, the database cache and the interners are shared between the searches
+    /// of the batch, which is much faster than executing them one by one when the queries
+    /// have words in common, e.g. the different widgets of a dashboard. The context is
+    /// reset when its interners or caches grow too large.
+    pub fn execute_many(
+        rtxn: &'a heed::RoTxn<'a>,
+        index: &'a Index,
+        queries: Vec<SearchQuery>,
+    ) -> Result<Vec<SearchResult>> {
+        let typo_derivations_limit = index.typo_derivations_limit(rtxn)?;
+        let mut ctx = SearchContext::new(index, rtxn);
+        let mut results = Vec::with_capacity(queries.len());
+
+        for search_query in &queries {
+            if must_reset_shared_context(&ctx) {
+                ctx = SearchContext::new(index, rtxn);
+            }
+            ctx.typo_derivations_limit = typo_derivations_limit;
+            ctx.deadline = search_query.time_budget.map(|time_budget| Instant::now() + time_budget);
+
+            ctx.restricted_fids = None;
+            if let Some(searchable_attributes) = &search_query.searchable_attributes {
+                ctx.searchable_attributes(searchable_attributes)?;
+            }
+
+            let filter = match &search_query.filter {
+                Some(filter) => Filter::from_str(filter)?,
+                None => None,
+            };
+
+            let PartialSearchResult {
+                located_query_terms,
+                candidates,
+                documents_ids,
+                document_scores,
+                degraded,
+            } = execute_search(
+                &mut ctx,
+                &search_query.query,
+                &None,
+                search_query.terms_matching_strategy,
+                search_query.scoring_strategy,
+                search_query.exhaustive_number_hits,
+                &filter,
+                &search_query.sort_criteria,
+                new::GeoSortStrategy::default(),
+                search_query.offset,
+                search_query.limit,
+                Some(search_query.words_limit),
+                &mut DefaultSearchLogger,
+                &mut DefaultSearchLogger,
+            )?;
+
+            let matching_words = match located_query_terms {
+                Some(located_query_terms) => MatchingWords::from_ctx_ref(&ctx, located_query_terms),
+                None => MatchingWords::default(),
+            };
+
+            results.push(SearchResult {
+                matching_words,
+                candidates,
+                document_scores,
+                documents_ids,
+                degraded,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// An owned description of a search, which can be stored and executed later on any index.
+///
+/// It is built by value, e.g. `SearchQuery::new().query("dog").limit(10)`, and is
+/// also used to execute many searches in a batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    pub query: Option<String>,
+    pub filter: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+    pub sort_criteria: Option<Vec<AscDesc>>,
+    pub searchable_attributes: Option<Vec<String>>,
+    pub terms_matching_strategy: TermsMatchingStrategy,
+    pub scoring_strategy: ScoringStrategy,
+    pub words_limit: usize,
+    pub exhaustive_number_hits: bool,
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        SearchQuery {
+            query: None,
+            filter: None,
+            offset: 0,
+            limit: 20,
+            sort_criteria: None,
+            searchable_attributes: None,
+            terms_matching_strategy: TermsMatchingStrategy::default(),
+            scoring_strategy: ScoringStrategy::default(),
+            words_limit: 10,
+            exhaustive_number_hits: false,
+            time_budget: None,
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn new() -> SearchQuery {
+        SearchQuery::default()
+    }
+
+    pub fn query(mut self, query: impl Into<String>) -> SearchQuery {
+        self.query = Some(query.into());
+        self
+    }
+
+    /// Sets the filter expression, it is parsed when the query is executed.
+    pub fn filter(mut self, filter: impl Into<String>) -> SearchQuery {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> SearchQuery {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> SearchQuery {
+        self.limit = limit;
+        self
+    }
+
+    pub fn sort_criteria(mut self, criteria: Vec<AscDesc>) -> SearchQuery {
+        self.sort_criteria = Some(criteria);
+        self
+    }
+
+    pub fn searchable_attributes(mut self, searchable: Vec<String>) -> SearchQuery {
+        self.searchable_attributes = Some(searchable);
+        self
+    }
+
+    pub fn terms_matching_strategy(mut self, value: TermsMatchingStrategy) -> SearchQuery {
+        self.terms_matching_strategy = value;
+        self
+    }
+
+    pub fn scoring_strategy(mut self, value: ScoringStrategy) -> SearchQuery {
+        self.scoring_strategy = value;
+        self
+    }
+
+    pub fn words_limit(mut self, value: usize) -> SearchQuery {
+        self.words_limit = value;
+        self
+    }
+
+    pub fn exhaustive_number_hits(mut self, exhaustive_number_hits: bool) -> SearchQuery {
+        self.exhaustive_number_hits = exhaustive_number_hits;
+        self
+    }
+
+    /// Sets the time budget of the query, see [`Search::terminate_after`].
+    pub fn terminate_after(mut self, time_budget: Duration) -> SearchQuery {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
+    /// Executes the query on the index, see [`Search::execute`].
+    pub fn execute(self, index: &Index, rtxn: &heed::RoTxn) -> Result<SearchResult> {
+        self.search(rtxn, index)?.execute()
+    }
+
+    /// Returns the [`Search`] described by this query, its filter is parsed.
+    pub(crate) fn search<'a>(
+        &'a self,
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+    ) -> Result<Search<'a>> {
+        let SearchQuery {
+            query,
+            filter,
+            offset,
+            limit,
+            sort_criteria,
+            searchable_attributes,
+            terms_matching_strategy,
+            scoring_strategy,
+            words_limit,
+            exhaustive_number_hits,
+            time_budget,
+        } = self;
+
+        let mut search = Search::new(rtxn, index);
+        if let Some(query) = query {
+            search.query(query);
+        }
+        if let Some(filter) = filter.as_deref().map(Filter::from_str).transpose()?.flatten() {
+            search.filter(filter);
+        }
+        if let Some(sort_criteria) = sort_criteria {
+            search.sort_criteria(sort_criteria.clone());
+        }
+        if let Some(searchable_attributes) = searchable_attributes {
+            search.searchable_attributes(searchable_attributes);
+        }
+        if let Some(time_budget) = time_budget {
+            search.terminate_after(*time_budget);
+        }
+        search
+            .offset(*offset)
+            .limit(*limit)
+            .terms_matching_strategy(*terms_matching_strategy)
+            .scoring_strategy(*scoring_strategy)
+            .words_limit(*words_limit)
+            .exhaustive_number_hits(*exhaustive_number_hits);
+        Ok(search)
     }
 }
 
@@ -172,6 +574,7 @@ impl fmt::Debug for Search<'_> {
             query,
             vector: _,
             filter,
+            view_filter,
             offset,
             limit,
             sort_criteria,
@@ -180,7 +583,17 @@ impl fmt::Debug for Search<'_> {
             terms_matching_strategy,
             scoring_strategy,
             words_limit,
+            typo_derivations_limit,
+            derivations_thread_pool,
+            sort_in_memory_threshold,
             exhaustive_number_hits,
+            time_budget,
+            locales,
+            all_words_as_prefix,
+            autocomplete,
+            stop_words,
+            tie_breaker,
+            query_rewriters,
             rtxn: _,
             index: _,
         } = self;
@@ -188,6 +601,7 @@ impl fmt::Debug for Search<'_> {
             .field("query", query)
             .field("vector", &"[...]")
             .field("filter", filter)
+            .field("view_filter", view_filter)
             .field("offset", offset)
             .field("limit", limit)
             .field("sort_criteria", sort_criteria)
@@ -196,24 +610,119 @@ impl fmt::Debug for Search<'_> {
             .field("scoring_strategy", scoring_strategy)
             .field("exhaustive_number_hits", exhaustive_number_hits)
             .field("words_limit", words_limit)
+            .field("typo_derivations_limit", typo_derivations_limit)
+            .field(
+                "derivations_thread_pool",
+                &derivations_thread_pool.map(|pool| pool.current_num_threads()),
+            )
+            .field("sort_in_memory_threshold", sort_in_memory_threshold)
+            .field("time_budget", time_budget)
+            .field("locales", locales)
+            .field("all_words_as_prefix", all_words_as_prefix)
+            .field("autocomplete", autocomplete)
+            .field("stop_words", stop_words)
+            .field("tie_breaker", tie_breaker)
+            .field("query_rewriters", &query_rewriters.len())
             .finish()
     }
 }
 
+/// Whether the search context shared by a batch of searches grew too large to be reused.
+fn must_reset_shared_context(ctx: &SearchContext) -> bool {
+    let interned_values =
+        [ctx.word_interner.len(), ctx.phrase_interner.len(), ctx.term_interner.len() as usize];
+    let cached_entries = ctx.db_cache.entries_count() + ctx.phrase_docids.cache.len();
+
+    interned_values.iter().any(|&len| len > MAX_SHARED_INTERNED_VALUES)
+        || cached_entries > MAX_SHARED_CACHED_ENTRIES
+}
+
 #[derive(Default, Debug)]
 pub struct SearchResult {
     pub matching_words: MatchingWords,
     pub candidates: RoaringBitmap,
     pub documents_ids: Vec<DocumentId>,
     pub document_scores: Vec<Vec<ScoreDetails>>,
+    /// Whether the time budget of the search was exhausted before the documents were ranked,
+    /// see [`Search::terminate_after`].
+    pub degraded: bool,
+}
+
+impl SearchResult {
+    /// Returns the ranking score of every returned document, between 0 and 1,
+    /// derived from its [`document_scores`](Self::document_scores).
+    pub fn ranking_scores(&self) -> Vec<f64> {
+        self.document_scores
+            .iter()
+            .map(|details| ScoreDetails::global_score(details.iter()))
+            .collect()
+    }
+
+    /// Returns, for every returned document, the bounds of the words matching the query
+    /// in each of its searchable fields.
+    ///
+    /// The start of the bounds is a byte offset in the string value of the field, only the fields
+    /// that are strings at the top level of the document are considered.
+    pub fn matches_positions(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+    ) -> Result<Vec<BTreeMap<String, Vec<MatchBounds>>>> {
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let searchable_fields_ids = index.searchable_fields_ids(rtxn)?;
+
+        // The tokenizer must be built like in the search to match the same words.
+        let mut tokenizer_builder = TokenizerBuilder::default();
+        let stop_words = index.stop_words(rtxn)?;
+        if let Some(ref stop_words) = stop_words {
+            tokenizer_builder.stop_words(stop_words);
+        }
+        let settings = index.cached_settings(rtxn)?;
+        let dictionary = settings.words_dict();
+        if let Some(ref dictionary) = dictionary {
+            tokenizer_builder.words_dict(dictionary);
+        }
+        let script_lang_map = index.script_language(rtxn)?;
+        if !script_lang_map.is_empty() {
+            tokenizer_builder.allow_list(&script_lang_map);
+        }
+        let tokenizer = tokenizer_builder.build();
+
+        let mut documents_matches = Vec::with_capacity(self.documents_ids.len());
+        for result in index.iter_documents(rtxn, self.documents_ids.iter().copied())? {
+            let (_id, obkv) = result?;
+            let mut fields_matches = BTreeMap::new();
+            for (field_id, value) in obkv.iter() {
+                let is_searchable =
+                    searchable_fields_ids.as_ref().map_or(true, |ids| ids.contains(&field_id));
+                let name = match fields_ids_map.name(field_id) {
+                    Some(name) if is_searchable => name,
+                    _ => continue,
+                };
+                let value: serde_json::Value =
+                    serde_json::from_slice(value).map_err(InternalError::SerdeJson)?;
+                if let serde_json::Value::String(text) = value {
+                    let matches = self.matching_words.match_bounds(&tokenizer, &text);
+                    if !matches.is_empty() {
+                        fields_matches.insert(name.to_string(), matches);
+                    }
+                }
+            }
+            documents_matches.push(fields_matches);
+        }
+
+        Ok(documents_matches)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TermsMatchingStrategy {
     // remove last word first
     Last,
     // all words are mandatory
     All,
+    // remove the word contained in the fewest documents first
+    Frequency,
 }
 
 impl Default for TermsMatchingStrategy {
@@ -222,6 +731,25 @@ impl Default for TermsMatchingStrategy {
     }
 }
 
+/// The order of the documents that tie on every ranking rule.
+///
+/// The order of the results of identical queries is the same as long as the index doesn't
+/// change, which makes the pagination stable. The internal ids of the documents are reassigned
+/// when they are reindexed, sorting by external id keeps the same order afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TieBreaker {
+    /// The documents are sorted by increasing internal id, the cheapest order.
+    InternalId,
+    /// The documents are sorted by external id, compared as strings: `"10"` comes before `"9"`.
+    ExternalId,
+}
+
+impl Default for TieBreaker {
+    fn default() -> Self {
+        Self::InternalId
+    }
+}
+
 fn get_first(s: &str) -> &str {
     match s.chars().next() {
         Some(c) => &s[..c.len_utf8()],
@@ -247,11 +775,12 @@ pub struct SearchForFacetValues<'a> {
     query: Option<String>,
     facet: String,
     search_query: Search<'a>,
+    max_values: usize,
 }
 
 impl<'a> SearchForFacetValues<'a> {
     pub fn new(facet: String, search_query: Search<'a>) -> SearchForFacetValues<'a> {
-        SearchForFacetValues { query: None, facet, search_query }
+        SearchForFacetValues { query: None, facet, search_query, max_values: MAX_NUMBER_OF_FACETS }
     }
 
     pub fn query(&mut self, query: impl Into<String>) -> &mut Self {
@@ -259,6 +788,12 @@ impl<'a> SearchForFacetValues<'a> {
         self
     }
 
+    /// Sets the maximum number of facet values returned, defaults to 100.
+    pub fn max_values(&mut self, max: usize) -> &mut Self {
+        self.max_values = max;
+        self
+    }
+
     fn one_original_value_of(
         &self,
         field_id: FieldId,
@@ -301,75 +836,34 @@ impl<'a> SearchForFacetValues<'a> {
 
         match self.query.as_ref() {
             Some(query) => {
-                let query = normalize_facet(query);
+                let normalization = self.search_query.index.facet_normalization(rtxn)?;
+                let query = normalize_facet(query, normalization);
                 let query = query.as_str();
                 let authorize_typos = self.search_query.index.authorize_typos(rtxn)?;
                 let field_authorizes_typos =
                     !self.search_query.index.exact_attributes_ids(rtxn)?.contains(&fid);
 
-                if authorize_typos && field_authorizes_typos {
+                // An exact word doesn't tolerate typos but can still be a prefix,
+                // like the words of the query of a search.
+                let exact_words_fst = self.search_query.index.exact_words(rtxn)?;
+                let is_exact_word = exact_words_fst.map_or(false, |fst| fst.contains(query));
+
+                if authorize_typos && field_authorizes_typos && !is_exact_word {
                     let mut results = vec![];
 
-                    let exact_words_fst = self.search_query.index.exact_words(rtxn)?;
-                    if exact_words_fst.map_or(false, |fst| fst.contains(query)) {
-                        let key = FacetGroupKey { field_id: fid, level: 0, left_bound: query };
-                        if let Some(FacetGroupValue { bitmap, .. }) =
-                            index.facet_id_string_docids.get(rtxn, &key)?
-                        {
-                            let count = search_candidates.intersection_len(&bitmap);
-                            if count != 0 {
-                                let value = self
-                                    .one_original_value_of(fid, query, bitmap.min().unwrap())?
-                                    .unwrap_or_else(|| query.to_string());
-                                results.push(FacetValueHit { value, count });
-                            }
-                        }
-                    } else {
-                        let one_typo = self.search_query.index.min_word_len_one_typo(rtxn)?;
-                        let two_typos = self.search_query.index.min_word_len_two_typos(rtxn)?;
-
-                        let is_prefix = true;
-                        let automaton = if query.len() < one_typo as usize {
-                            build_dfa(query, 0, is_prefix)
-                        } else if query.len() < two_typos as usize {
-                            build_dfa(query, 1, is_prefix)
-                        } else {
-                            build_dfa(query, 2, is_prefix)
-                        };
+                    let one_typo = self.search_query.index.min_word_len_one_typo(rtxn)?;
+                    let two_typos = self.search_query.index.min_word_len_two_typos(rtxn)?;
 
-                        let mut stream = fst.search(automaton).into_stream();
-                        let mut length = 0;
-                        while let Some(facet_value) = stream.next() {
-                            let value = std::str::from_utf8(facet_value)?;
-                            let key = FacetGroupKey { field_id: fid, level: 0, left_bound: value };
-                            let docids = match index.facet_id_string_docids.get(rtxn, &key)? {
-                                Some(FacetGroupValue { bitmap, .. }) => bitmap,
-                                None => {
-                                    error!(
-                                        "the facet value is missing from the facet database: {key:?}"
-                                    );
-                                    continue;
-                                }
-                            };
-                            let count = search_candidates.intersection_len(&docids);
-                            if count != 0 {
-                                let value = self
-                                    .one_original_value_of(fid, value, docids.min().unwrap())?
-                                    .unwrap_or_else(|| query.to_string());
-                                results.push(FacetValueHit { value, count });
-                                length += 1;
-                            }
-                            if length >= MAX_NUMBER_OF_FACETS {
-                                break;
-                            }
-                        }
-                    }
+                    let is_prefix = true;
+                    let automaton = if query.len() < one_typo as usize {
+                        build_dfa(query, 0, is_prefix)
+                    } else if query.len() < two_typos as usize {
+                        build_dfa(query, 1, is_prefix)
+                    } else {
+                        build_dfa(query, 2, is_prefix)
+                    };
 
-                    Ok(results)
-                } else {
-                    let automaton = Str::new(query).starts_with();
                     let mut stream = fst.search(automaton).into_stream();
-                    let mut results = vec![];
                     let mut length = 0;
                     while let Some(facet_value) = stream.next() {
                         let value = std::str::from_utf8(facet_value)?;
@@ -387,40 +881,53 @@ impl<'a> SearchForFacetValues<'a> {
                         if count != 0 {
                             let value = self
                                 .one_original_value_of(fid, value, docids.min().unwrap())?
-                                .unwrap_or_else(|| query.to_string());
+                                .unwrap_or_else(|| value.to_string());
                             results.push(FacetValueHit { value, count });
                             length += 1;
                         }
-                        if length >= MAX_NUMBER_OF_FACETS {
+                        if length >= self.max_values {
                             break;
                         }
                     }
 
                     Ok(results)
+                } else {
+                    self.facet_values_starting_with(fid, query, &search_candidates)
                 }
             }
-            None => {
-                let mut results = vec![];
-                let mut length = 0;
-                let prefix = FacetGroupKey { field_id: fid, level: 0, left_bound: "" };
-                for result in index.facet_id_string_docids.prefix_iter(rtxn, &prefix)? {
-                    let (FacetGroupKey { left_bound, .. }, FacetGroupValue { bitmap, .. }) =
-                        result?;
-                    let count = search_candidates.intersection_len(&bitmap);
-                    if count != 0 {
-                        let value = self
-                            .one_original_value_of(fid, left_bound, bitmap.min().unwrap())?
-                            .unwrap_or_else(|| left_bound.to_string());
-                        results.push(FacetValueHit { value, count });
-                        length += 1;
-                    }
-                    if length >= MAX_NUMBER_OF_FACETS {
-                        break;
-                    }
-                }
-                Ok(results)
+            None => self.facet_values_starting_with(fid, "", &search_candidates),
+        }
+    }
+
+    /// Returns the facet values starting with the normalized prefix in a single range scan
+    /// of the level 0 of the facet database, where they are sorted lexicographically.
+    fn facet_values_starting_with(
+        &self,
+        fid: FieldId,
+        prefix: &str,
+        search_candidates: &RoaringBitmap,
+    ) -> Result<Vec<FacetValueHit>> {
+        let index = self.search_query.index;
+        let rtxn = self.search_query.rtxn;
+
+        let mut results = vec![];
+        let mut length = 0;
+        let prefix = FacetGroupKey { field_id: fid, level: 0, left_bound: prefix };
+        for result in index.facet_id_string_docids.prefix_iter(rtxn, &prefix)? {
+            let (FacetGroupKey { left_bound, .. }, FacetGroupValue { bitmap, .. }) = result?;
+            let count = search_candidates.intersection_len(&bitmap);
+            if count != 0 {
+                let value = self
+                    .one_original_value_of(fid, left_bound, bitmap.min().unwrap())?
+                    .unwrap_or_else(|| left_bound.to_string());
+                results.push(FacetValueHit { value, count });
+                length += 1;
+            }
+            if length >= self.max_values {
+                break;
             }
         }
+        Ok(results)
     }
 }
 
@@ -437,6 +944,218 @@ mod test {
     #[allow(unused_imports)]
     use super::*;
 
+    #[test]
+    fn execute_many_matches_individual_searches() {
+        use big_s::S;
+        use maplit::hashset;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("color") }))
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox", "color": "brown" },
+                { "id": 1, "title": "the lazy dog", "color": "white" },
+                { "id": 2, "title": "the quick white rabbit", "color": "white" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let queries = vec![
+            SearchQuery { query: Some(S("quick")), ..Default::default() },
+            SearchQuery {
+                query: Some(S("the")),
+                filter: Some(S("color = white")),
+                ..Default::default()
+            },
+            SearchQuery { limit: 1, ..Default::default() },
+        ];
+
+        let results = Search::execute_many(&rtxn, &index, queries.clone()).unwrap();
+        assert_eq!(results.len(), queries.len());
+
+        for (query, result) in queries.iter().zip(results) {
+            let mut search = Search::new(&rtxn, &index);
+            if let Some(q) = &query.query {
+                search.query(q);
+            }
+            if let Some(filter) = &query.filter {
+                search.filter(Filter::from_str(filter).unwrap().unwrap());
+            }
+            search.limit(query.limit);
+            let expected = search.execute().unwrap();
+            assert_eq!(result.documents_ids, expected.documents_ids);
+            assert_eq!(result.candidates, expected.candidates);
+        }
+    }
+
+    #[test]
+    fn search_for_facet_values() {
+        use big_s::S;
+        use maplit::hashset;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("brand"), S("color") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "brand": "Samsung", "color": "black" },
+                { "id": 1, "brand": "Sony", "color": "black" },
+                { "id": 2, "brand": "samsung", "color": "white" },
+                { "id": 3, "brand": "Sonos", "color": "white" },
+                { "id": 4, "brand": "Apple", "color": "white" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let hits = |query: &str, filter: Option<&str>, max_values: usize| {
+            let mut search = Search::new(&rtxn, &index);
+            if let Some(filter) = filter {
+                search.filter(Filter::from_str(filter).unwrap().unwrap());
+            }
+            let mut facet_search = SearchForFacetValues::new(S("brand"), search);
+            facet_search.query(query).max_values(max_values);
+            let hits = facet_search.execute().unwrap();
+            hits.into_iter().map(|hit| (hit.value, hit.count)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(hits("son", None, 10), vec![(S("Sonos"), 1), (S("Sony"), 1)]);
+        // a typo is tolerated from the fifth letter
+        assert_eq!(hits("samsang", None, 10), vec![(S("Samsung"), 2)]);
+        // the counts are restricted to the filtered documents
+        assert_eq!(hits("samsung", Some("color = white"), 10), vec![(S("Samsung"), 1)]);
+        assert_eq!(hits("so", None, 1), vec![(S("Sonos"), 1)]);
+
+        drop(hits);
+        drop(rtxn);
+
+        // the exact words don't tolerate typos but are still prefixes
+        index
+            .update_settings(|settings| {
+                settings
+                    .set_exact_words(["samsang", "sams"].iter().map(ToString::to_string).collect());
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let hits = |query: &str| {
+            let search = Search::new(&rtxn, &index);
+            let mut facet_search = SearchForFacetValues::new(S("brand"), search);
+            facet_search.query(query);
+            let hits = facet_search.execute().unwrap();
+            hits.into_iter().map(|hit| (hit.value, hit.count)).collect::<Vec<_>>()
+        };
+        assert_eq!(hits("samsang"), vec![]);
+        assert_eq!(hits("sams"), vec![(S("Samsung"), 2)]);
+
+        let search = Search::new(&rtxn, &index);
+        let facet_search = SearchForFacetValues::new(S("title"), search);
+        assert!(facet_search.execute().is_err());
+    }
+
+    #[test]
+    fn execute_a_stored_search_query() {
+        use big_s::S;
+        use maplit::hashset;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("color") });
+                settings.set_sortable_fields(hashset! { S("id") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox", "color": "brown" },
+                { "id": 1, "title": "the lazy dog", "color": "white" },
+                { "id": 2, "title": "the quick white rabbit", "color": "white" },
+            ]))
+            .unwrap();
+
+        let query = SearchQuery::new()
+            .query("the")
+            .filter("color = white")
+            .sort_criteria(vec!["id:desc".parse().unwrap()])
+            .terms_matching_strategy(TermsMatchingStrategy::All)
+            .limit(1);
+
+        // The query can be stored and replayed later.
+        let stored = serde_json::to_string(&query).unwrap();
+        let replayed: SearchQuery = serde_json::from_str(&stored).unwrap();
+        assert_eq!(replayed, query);
+        let partial: SearchQuery = serde_json::from_str(r#"{ "limit": 5 }"#).unwrap();
+        assert_eq!(partial, SearchQuery::new().limit(5));
+
+        let rtxn = index.read_txn().unwrap();
+        let result = replayed.execute(&index, &rtxn).unwrap();
+        assert_eq!(result.documents_ids, vec![2]);
+        assert_eq!(result.candidates, RoaringBitmap::from_iter([1, 2]));
+
+        let error = SearchQuery::new().filter("title = dog").execute(&index, &rtxn).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::UserError(UserError::InvalidFilterableAttribute { .. })
+        ));
+    }
+
+    #[test]
+    fn matches_positions() {
+        use big_s::S;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("description")])
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox", "description": "a fox", "color": "fox" },
+                { "id": 1, "title": "the lazy dog", "description": "not a quack fox" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("quick fo");
+        search.terms_matching_strategy(TermsMatchingStrategy::Last);
+        let result = search.execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0, 1]);
+
+        let matches = result.matches_positions(&rtxn, &index).unwrap();
+        // the non-searchable `color` field doesn't have any match
+        assert_eq!(
+            matches[0],
+            BTreeMap::from([
+                (S("description"), vec![MatchBounds { start: 2, length: 2 }]),
+                (
+                    S("title"),
+                    vec![MatchBounds { start: 4, length: 5 }, MatchBounds { start: 16, length: 2 }]
+                ),
+            ])
+        );
+        // `quack` is matched with a typo and `fox` as a prefix
+        assert_eq!(
+            matches[1],
+            BTreeMap::from([(
+                S("description"),
+                vec![MatchBounds { start: 6, length: 5 }, MatchBounds { start: 12, length: 2 }]
+            )])
+        );
+    }
+
     #[cfg(feature = "japanese")]
     #[test]
     fn test_kanji_language_detection() {
@@ -460,4 +1179,98 @@ mod test {
 
         assert_eq!(documents_ids, vec![1]);
     }
+
+    #[test]
+    fn terminate_after() {
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        let documents: Vec<_> = (0..100)
+            .map(|id| serde_json::json!({ "id": id, "title": format!("the {} fox", id % 7) }))
+            .collect();
+        index.add_documents(documents!(documents)).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("the fox").limit(10);
+        let ranked = search.execute().unwrap();
+        assert!(!ranked.degraded);
+
+        // Without any budget the documents are returned in the order of their ids.
+        search.terminate_after(Duration::ZERO);
+        let degraded = search.execute().unwrap();
+        assert!(degraded.degraded);
+        assert_eq!(degraded.candidates, ranked.candidates);
+        assert_eq!(degraded.documents_ids, (0..10).collect::<Vec<_>>());
+
+        search.terminate_after(Duration::from_secs(3600));
+        let result = search.execute().unwrap();
+        assert!(!result.degraded);
+        assert_eq!(result.documents_ids, ranked.documents_ids);
+
+        // The budget of the queries executed in a batch is respected too.
+        let queries = vec![
+            SearchQuery::new().query("the fox").limit(10).terminate_after(Duration::ZERO),
+            SearchQuery::new().query("the fox").limit(10),
+        ];
+        let results = Search::execute_many(&rtxn, &index, queries).unwrap();
+        assert!(results[0].degraded);
+        assert_eq!(results[0].documents_ids, degraded.documents_ids);
+        assert!(!results[1].degraded);
+        assert_eq!(results[1].documents_ids, ranked.documents_ids);
+    }
+
+    #[test]
+    fn terminate_after_during_the_filter_evaluation() {
+        use big_s::S;
+        use maplit::hashset;
+
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index.update_settings(|s| s.set_filterable_fields(hashset! { S("color") })).unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox", "color": "brown" },
+                { "id": 1, "title": "the lazy dog", "color": "white" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let filter = Filter::from_str("color = white OR color = brown").unwrap().unwrap();
+        assert_eq!(filter.evaluate_before(&rtxn, &index, Some(Instant::now())).unwrap(), None);
+
+        // A partially evaluated filter could select documents it must not, none is returned.
+        let mut search = Search::new(&rtxn, &index);
+        search.filter(filter).terminate_after(Duration::ZERO);
+        let result = search.execute().unwrap();
+        assert!(result.degraded);
+        assert!(result.documents_ids.is_empty());
+    }
+
+    #[test]
+    fn ranking_scores() {
+        use crate::index::tests::TempIndex;
+
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox" },
+                { "id": 1, "title": "the quick fox" },
+                { "id": 2, "title": "the brown dog" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("quick brown fox").compute_scores(true);
+        let result = search.execute().unwrap();
+        // the last words of the query are removed first, `quick` is always required
+        assert_eq!(result.documents_ids, vec![0, 1]);
+
+        let scores = result.ranking_scores();
+        assert_eq!(scores.len(), result.document_scores.len());
+        assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+    }
 }
@@ -0,0 +1,42 @@
+use super::bucket_sort::BucketSortOutput;
+use super::sort::facet_number_values;
+use super::SearchContext;
+use crate::score_details::{self, ScoreDetails};
+use crate::{FieldId, Result};
+
+/// Reorders the ranked documents by their global score multiplied by their boost
+/// and returns the requested page.
+///
+/// The boost of a document is the first number of its boost field, documents without
+/// any keep their score and negative boosts are considered null. The boost is added to
+/// the score details of the documents, their global score is the boosted one.
+pub fn apply_boost(
+    ctx: &SearchContext,
+    boost_fid: FieldId,
+    output: BucketSortOutput,
+    from: usize,
+    length: usize,
+) -> Result<BucketSortOutput> {
+    let BucketSortOutput { docids, scores, all_candidates, degraded } = output;
+
+    let mut boosted = Vec::with_capacity(docids.len());
+    for (docid, mut scores) in docids.into_iter().zip(scores) {
+        if let Some(result) = facet_number_values(docid, boost_fid, ctx.index, ctx.txn)?.next() {
+            let ((_, _, boost), ()) = result?;
+            scores.push(ScoreDetails::Boost(score_details::Boost { factor: boost.max(0.0) }));
+        }
+        let score = ScoreDetails::global_score(scores.iter());
+        boosted.push((score, docid, scores));
+    }
+
+    // the sort is stable, the documents with the same boosted score keep their ranking order
+    boosted.sort_by(|(left, ..), (right, ..)| right.total_cmp(left));
+    let (docids, scores) = boosted
+        .into_iter()
+        .skip(from)
+        .take(length)
+        .map(|(_, docid, scores)| (docid, scores))
+        .unzip();
+
+    Ok(BucketSortOutput { docids, scores, all_candidates, degraded })
+}
@@ -1,3 +1,4 @@
+use fst::Streamer;
 use roaring::RoaringBitmap;
 
 use super::logger::SearchLogger;
@@ -5,12 +6,14 @@ use super::ranking_rules::{BoxRankingRule, RankingRuleQueryTrait};
 use super::SearchContext;
 use crate::score_details::{ScoreDetails, ScoringStrategy};
 use crate::search::new::distinct::{apply_distinct_rule, distinct_single_docid, DistinctOutput};
-use crate::Result;
+use crate::{Result, TieBreaker};
 
 pub struct BucketSortOutput {
     pub docids: Vec<u32>,
     pub scores: Vec<Vec<ScoreDetails>>,
     pub all_candidates: RoaringBitmap,
+    /// Whether the deadline of the search was reached before the documents were fully ranked.
+    pub degraded: bool,
 }
 
 // TODO: would probably be good to regroup some of these inside of a struct?
@@ -40,13 +43,20 @@ pub fn bucket_sort<'ctx, Q: RankingRuleQueryTrait>(
             docids: vec![],
             scores: vec![],
             all_candidates: universe.clone(),
+            degraded: false,
         });
     }
     if ranking_rules.is_empty() {
         if let Some(distinct_fid) = distinct_fid {
             let mut excluded = RoaringBitmap::new();
             let mut results = vec![];
-            for docid in universe.iter() {
+            let ordered_universe: Box<dyn Iterator<Item = u32>> = match ctx.tie_breaker {
+                TieBreaker::InternalId => Box::new(universe.iter()),
+                TieBreaker::ExternalId => {
+                    Box::new(tie_break(ctx, universe, universe.len() as usize)?.into_iter())
+                }
+            };
+            for docid in ordered_universe {
                 if results.len() >= from + length {
                     break;
                 }
@@ -62,13 +72,18 @@ pub fn bucket_sort<'ctx, Q: RankingRuleQueryTrait>(
                 scores: vec![Default::default(); results.len()],
                 docids: results,
                 all_candidates,
+                degraded: false,
             });
         } else {
-            let docids: Vec<u32> = universe.iter().skip(from).take(length).collect();
+            let docids: Vec<u32> = tie_break(ctx, universe, from.saturating_add(length))?
+                .into_iter()
+                .skip(from)
+                .collect();
             return Ok(BucketSortOutput {
                 scores: vec![Default::default(); docids.len()],
                 docids,
                 all_candidates: universe.clone(),
+                degraded: false,
             });
         };
     }
@@ -118,6 +133,7 @@ pub fn bucket_sort<'ctx, Q: RankingRuleQueryTrait>(
     let mut valid_docids = vec![];
     let mut valid_scores = vec![];
     let mut cur_offset = 0usize;
+    let mut degraded = false;
 
     macro_rules! maybe_add_to_results {
         ($candidates:expr) => {
@@ -141,6 +157,31 @@ pub fn bucket_sort<'ctx, Q: RankingRuleQueryTrait>(
     }
 
     while valid_docids.len() < length {
+        if ctx.deadline_reached() {
+            // The buckets that are not ranked yet are returned as they are, in the order
+            // of the ids of their documents, from the deepest ranking rule to the first one.
+            let deepest_ranking_rule_index = cur_ranking_rule_index;
+            loop {
+                let bucket = std::mem::take(&mut ranking_rule_universes[cur_ranking_rule_index]);
+                maybe_add_to_results!(bucket);
+                if cur_ranking_rule_index == 0 || valid_docids.len() >= length {
+                    break;
+                }
+                cur_ranking_rule_index -= 1;
+                ranking_rule_scores.truncate(cur_ranking_rule_index);
+            }
+            for index in (0..=deepest_ranking_rule_index).rev() {
+                logger.end_iteration_ranking_rule(
+                    index,
+                    ranking_rules[index].as_ref(),
+                    &ranking_rule_universes[index],
+                );
+                ranking_rules[index].end_iteration(ctx, logger);
+            }
+            degraded = true;
+            break;
+        }
+
         // The universe for this bucket is zero, so we don't need to sort
         // anything, just go back to the parent ranking rule.
         if ranking_rule_universes[cur_ranking_rule_index].is_empty()
@@ -202,7 +243,7 @@ pub fn bucket_sort<'ctx, Q: RankingRuleQueryTrait>(
         )?;
     }
 
-    Ok(BucketSortOutput { docids: valid_docids, scores: valid_scores, all_candidates })
+    Ok(BucketSortOutput { docids: valid_docids, scores: valid_scores, all_candidates, degraded })
 }
 
 /// Add the candidates to the results. Take `distinct`, `from`, `length`, and `cur_offset`
@@ -259,9 +300,12 @@ fn maybe_add_to_results<'ctx, Q: RankingRuleQueryTrait>(
                 &candidates,
             );
         } else {
-            // otherwise, skip some of the documents and add some of the rest, in order of ids
-            let candidates_vec = candidates.iter().collect::<Vec<_>>();
-            let (skipped_candidates, candidates) = candidates_vec.split_at(from - *cur_offset);
+            // otherwise, skip some of the documents and add some of the rest, in the order
+            // of the tie-breaker
+            let skipped = from - *cur_offset;
+            let candidates_vec =
+                tie_break(ctx, &candidates, skipped.saturating_add(length - valid_docids.len()))?;
+            let (skipped_candidates, candidates) = candidates_vec.split_at(skipped);
 
             logger.skip_bucket_ranking_rule(
                 cur_ranking_rule_index,
@@ -277,7 +321,7 @@ fn maybe_add_to_results<'ctx, Q: RankingRuleQueryTrait>(
         }
     } else {
         // if we have passed the offset already, add some of the documents (up to the limit)
-        let candidates = candidates.iter().take(length - valid_docids.len()).collect::<Vec<u32>>();
+        let candidates = tie_break(ctx, &candidates, length - valid_docids.len())?;
         logger.add_to_results(&candidates);
         valid_docids.extend_from_slice(&candidates);
         valid_scores
@@ -287,3 +331,42 @@ fn maybe_add_to_results<'ctx, Q: RankingRuleQueryTrait>(
     *cur_offset += candidates.len() as usize;
     Ok(())
 }
+
+/// Returns the first `limit` candidates in the order of the tie-breaker of the search.
+fn tie_break(ctx: &SearchContext, candidates: &RoaringBitmap, limit: usize) -> Result<Vec<u32>> {
+    if ctx.tie_breaker == TieBreaker::InternalId {
+        return Ok(candidates.iter().take(limit).collect());
+    }
+
+    // The external ids of a few documents are read from the documents themselves,
+    // otherwise the external ids FST is streamed in order until enough candidates are found.
+    let mut docids = Vec::with_capacity(limit.min(candidates.len() as usize));
+    if candidates.len() <= ctx.sort_in_memory_threshold as u64 {
+        let mut documents = Vec::with_capacity(candidates.len() as usize);
+        for docid in candidates {
+            documents.push((ctx.index.external_id_of(ctx.txn, docid)?, docid));
+        }
+        // The documents without a valid external id come last.
+        documents.sort_unstable_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        docids.extend(documents.into_iter().map(|(_, docid)| docid).take(limit));
+    } else {
+        let external_documents_ids = ctx.index.external_documents_ids(ctx.txn)?;
+        let external_documents_ids = external_documents_ids.to_fst()?;
+        let mut remaining = candidates.clone();
+        let mut stream = external_documents_ids.stream();
+        while let Some((_, docid)) = stream.next() {
+            if docids.len() >= limit {
+                break;
+            }
+            let docid = docid as u32;
+            if remaining.remove(docid) {
+                docids.push(docid);
+            }
+        }
+        docids.extend(remaining.iter().take(limit - docids.len()));
+    }
+    Ok(docids)
+}
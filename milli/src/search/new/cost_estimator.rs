@@ -0,0 +1,198 @@
+//! Chooses the order in which the filter and the query words of a search are evaluated.
+//!
+//! The filter is evaluated first by default, the query words are then only resolved on the
+//! filtered documents. When the query words are expected to match fewer documents than the
+//! documents ids read to evaluate the filter, the words are resolved first and the filter is
+//! skipped if they don't match any document.
+
+use super::query_term::LocatedQueryTerm;
+use super::SearchContext;
+use crate::{Filter, Result, TermsMatchingStrategy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvaluationOrder {
+    FilterFirst,
+    WordsFirst,
+}
+
+/// Returns the cheapest order to evaluate the filter and the query terms in.
+pub fn evaluation_order(
+    ctx: &SearchContext,
+    filter: &Filter,
+    query_terms: &[LocatedQueryTerm],
+    matching_strategy: TermsMatchingStrategy,
+) -> Result<EvaluationOrder> {
+    let filter_cost = match filter.estimate_cost(ctx.txn, ctx.index)? {
+        Some(cost) => cost,
+        None => return Ok(EvaluationOrder::FilterFirst),
+    };
+    let words_candidates = estimate_words_candidates(ctx, query_terms, matching_strategy)?;
+    if words_candidates < filter_cost {
+        Ok(EvaluationOrder::WordsFirst)
+    } else {
+        Ok(EvaluationOrder::FilterFirst)
+    }
+}
+
+/// Estimates the number of documents matching the query terms from the document frequencies
+/// of the words derived so far.
+///
+/// Only the terms that can't be removed by the matching strategy are taken into account.
+pub fn estimate_words_candidates(
+    ctx: &SearchContext,
+    query_terms: &[LocatedQueryTerm],
+    matching_strategy: TermsMatchingStrategy,
+) -> Result<u64> {
+    let required_terms = match matching_strategy {
+        // The first term is never removed by the matching strategy.
+        TermsMatchingStrategy::Last => &query_terms[..query_terms.len().min(1)],
+        TermsMatchingStrategy::All => query_terms,
+        // The most frequent term is never removed by the matching strategy.
+        TermsMatchingStrategy::Frequency => {
+            let mut estimation = 0;
+            for located_term in query_terms {
+                estimation = estimation.max(estimate_term_candidates(ctx, located_term)?);
+            }
+            return Ok(estimation.min(ctx.index.number_of_documents(ctx.txn)?));
+        }
+    };
+
+    let mut estimation = ctx.index.number_of_documents(ctx.txn)?;
+    for located_term in required_terms {
+        estimation = estimation.min(estimate_term_candidates(ctx, located_term)?);
+    }
+    Ok(estimation)
+}
+
+/// Sums the document frequencies of the words and phrases derived from the term.
+fn estimate_term_candidates(ctx: &SearchContext, located_term: &LocatedQueryTerm) -> Result<u64> {
+    let term = ctx.term_interner.get(located_term.value);
+    let (words, phrases) = term.all_computed_derivations();
+
+    let mut count = 0;
+    if term.is_cached_prefix() {
+        let prefix = term.original_word(ctx);
+        count += ctx.index.word_prefix_documents_count(ctx.txn, &prefix)?.unwrap_or_default();
+    }
+    for word in words {
+        let word = ctx.word_interner.get(word);
+        count += ctx.index.word_documents_count(ctx.txn, word)?.unwrap_or_default();
+    }
+    for phrase in phrases {
+        // A phrase can't match more documents than its rarest word.
+        let mut phrase_count = None;
+        for word in ctx.phrase_interner.get(phrase).words.iter().flatten() {
+            let word = ctx.word_interner.get(*word);
+            let word_count = ctx.index.word_documents_count(ctx.txn, word)?.unwrap_or_default();
+            phrase_count = Some(phrase_count.map_or(word_count, |c: u64| c.min(word_count)));
+        }
+        count += phrase_count.unwrap_or_default();
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use charabia::TokenizerBuilder;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::search::new::query_term::{located_query_terms_from_tokens, ExtractedTokens};
+
+    fn create_index() -> TempIndex {
+        let index = TempIndex::new();
+        index
+            .update_settings(|s| {
+                s.set_primary_key(S("id"));
+                s.set_searchable_fields(vec![S("text")]);
+                s.set_filterable_fields(hashset! { S("id"), S("color") });
+            })
+            .unwrap();
+
+        let documents: Vec<_> = (0..100)
+            .map(|id| {
+                let text = if id == 42 { "the unique dog" } else { "the common dog" };
+                let color = if id % 2 == 0 { "red" } else { "blue" };
+                serde_json::json!({ "id": id, "text": text, "color": color })
+            })
+            .map(|document| match document {
+                serde_json::Value::Object(object) => object,
+                _ => unreachable!(),
+            })
+            .collect();
+        index
+            .add_documents(crate::documents::documents_batch_reader_from_objects(documents))
+            .unwrap();
+        index
+    }
+
+    fn order(
+        index: &TempIndex,
+        query: &str,
+        filter: &str,
+        strategy: TermsMatchingStrategy,
+    ) -> (u64, EvaluationOrder) {
+        let txn = index.read_txn().unwrap();
+        let mut ctx = SearchContext::new(index, &txn);
+        let tokenizer = TokenizerBuilder::default().into_tokenizer();
+        let ExtractedTokens { query_terms, .. } =
+            located_query_terms_from_tokens(&mut ctx, tokenizer.tokenize(query), None).unwrap();
+        let filter = Filter::from_str(filter).unwrap().unwrap();
+        let estimation = estimate_words_candidates(&ctx, &query_terms, strategy).unwrap();
+        (estimation, evaluation_order(&ctx, &filter, &query_terms, strategy).unwrap())
+    }
+
+    #[test]
+    fn estimate_from_word_frequencies() {
+        let index = create_index();
+        let all = TermsMatchingStrategy::All;
+        let last = TermsMatchingStrategy::Last;
+
+        // `dog` is in every document, `unique` in one.
+        assert_eq!(order(&index, "the unique dog", "id > 10", all).0, 1);
+        assert_eq!(order(&index, "the unique dog", "id > 10", last).0, 100);
+        let frequency = TermsMatchingStrategy::Frequency;
+        assert_eq!(order(&index, "unique dog", "id > 10", frequency).0, 100);
+        assert_eq!(order(&index, "\"unique dog\"", "id > 10", all).0, 1);
+        assert_eq!(order(&index, "missing dog", "id > 10", all).0, 0);
+    }
+
+    #[test]
+    fn choose_evaluation_order() {
+        let index = create_index();
+        let all = TermsMatchingStrategy::All;
+
+        // The rare words are resolved before the filter on a field with many values.
+        assert_eq!(order(&index, "unique", "id > 10", all).1, EvaluationOrder::WordsFirst);
+        assert_eq!(order(&index, "the", "id > 10", all).1, EvaluationOrder::FilterFirst);
+        // The common words are not cheaper to resolve than a filter on a field of every document.
+        assert_eq!(order(&index, "the dog", "color = red", all).1, EvaluationOrder::FilterFirst);
+        // The cost of the geo filters is not estimated.
+        assert_eq!(
+            order(&index, "unique", "_geoRadius(0, 0, 10) OR id > 10", all).1,
+            EvaluationOrder::FilterFirst
+        );
+    }
+
+    #[test]
+    fn words_first_search() {
+        let index = create_index();
+        let txn = index.read_txn().unwrap();
+
+        let search = |query: &str, filter: &str| {
+            let mut search = crate::Search::new(&txn, &index);
+            search.query(query);
+            search.filter(Filter::from_str(filter).unwrap().unwrap());
+            search.execute().map(|result| result.documents_ids)
+        };
+        assert_eq!(search("unique", "id > 10").unwrap(), vec![42]);
+        assert!(search("unique", "id < 10").unwrap().is_empty());
+
+        // The errors of the filter are returned even if it is not evaluated.
+        assert!(search("missing", "id > 10").unwrap().is_empty());
+        assert!(search("missing", "text = dog").is_err());
+        assert!(search("missing", "id > ten").is_err());
+    }
+}
@@ -4,15 +4,16 @@ use std::hash::Hash;
 
 use fxhash::FxHashMap;
 use heed::types::ByteSlice;
-use heed::{BytesEncode, Database, RoTxn};
+use heed::{BytesDecode, BytesEncode, Database, RoTxn};
 use roaring::RoaringBitmap;
 
 use super::interner::Interned;
 use super::Word;
 use crate::heed_codec::{BytesDecodeOwned, StrBEU16Codec};
-use crate::update::{merge_cbo_roaring_bitmaps, MergeFn};
+use crate::index::db_name;
 use crate::{
-    CboRoaringBitmapCodec, CboRoaringBitmapLenCodec, Result, RoaringBitmapCodec, SearchContext,
+    CboRoaringBitmapCodec, CboRoaringBitmapLenCodec, Index, Result, RoaringBitmapCodec,
+    SearchContext,
 };
 
 /// A cache storing pointers to values in the LMDB databases.
@@ -33,7 +34,9 @@ pub struct DatabaseCache<'ctx> {
     pub word_prefix_docids: FxHashMap<Interned<String>, Option<Cow<'ctx, [u8]>>>,
     pub exact_word_prefix_docids: FxHashMap<Interned<String>, Option<Cow<'ctx, [u8]>>>,
 
+    /// The words FST borrowed from the LMDB memory map, it is never copied.
     pub words_fst: Option<fst::Set<Cow<'ctx, [u8]>>>,
+    pub words_fst_metrics: CacheMetrics,
     pub word_position_docids: FxHashMap<(Interned<String>, u16), Option<Cow<'ctx, [u8]>>>,
     pub word_prefix_position_docids: FxHashMap<(Interned<String>, u16), Option<Cow<'ctx, [u8]>>>,
     pub word_positions: FxHashMap<Interned<String>, Vec<u16>>,
@@ -44,13 +47,61 @@ pub struct DatabaseCache<'ctx> {
     pub word_fids: FxHashMap<Interned<String>, Vec<u16>>,
     pub word_prefix_fids: FxHashMap<Interned<String>, Vec<u16>>,
 }
+/// The number of lookups answered by a cache and by the database.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+}
+
 impl<'ctx> DatabaseCache<'ctx> {
+    /// The number of database entries retained by the cache, the words FST is not counted.
+    pub fn entries_count(&self) -> usize {
+        let DatabaseCache {
+            word_pair_proximity_docids,
+            word_prefix_pair_proximity_docids,
+            prefix_word_pair_proximity_docids,
+            word_docids,
+            exact_word_docids,
+            word_prefix_docids,
+            exact_word_prefix_docids,
+            words_fst: _,
+            words_fst_metrics: _,
+            word_position_docids,
+            word_prefix_position_docids,
+            word_positions,
+            word_prefix_positions,
+            word_fid_docids,
+            word_prefix_fid_docids,
+            word_fids,
+            word_prefix_fids,
+        } = self;
+
+        word_pair_proximity_docids.len()
+            + word_prefix_pair_proximity_docids.len()
+            + prefix_word_pair_proximity_docids.len()
+            + word_docids.len()
+            + exact_word_docids.len()
+            + word_prefix_docids.len()
+            + exact_word_prefix_docids.len()
+            + word_position_docids.len()
+            + word_prefix_position_docids.len()
+            + word_positions.len()
+            + word_prefix_positions.len()
+            + word_fid_docids.len()
+            + word_prefix_fid_docids.len()
+            + word_fids.len()
+            + word_prefix_fids.len()
+    }
+
     fn get_value<'v, K1, KC, DC>(
         txn: &'ctx RoTxn,
         cache_key: K1,
         db_key: &'v KC::EItem,
         cache: &mut FxHashMap<K1, Option<Cow<'ctx, [u8]>>>,
         db: Database<KC, ByteSlice>,
+        index: &Index,
+        name: &str,
     ) -> Result<Option<DC::DItem>>
     where
         K1: Copy + Eq + Hash,
@@ -62,24 +113,22 @@ impl<'ctx> DatabaseCache<'ctx> {
             entry.insert(bitmap_ptr);
         }
 
-        match cache.get(&cache_key).unwrap() {
-            Some(Cow::Borrowed(bytes)) => {
-                DC::bytes_decode_owned(bytes).ok_or(heed::Error::Decoding.into()).map(Some)
-            }
-            Some(Cow::Owned(bytes)) => {
-                DC::bytes_decode_owned(bytes).ok_or(heed::Error::Decoding.into()).map(Some)
+        Ok(Self::decode_value::<DC>(cache.get(&cache_key).unwrap(), || {
+            if let Some(key) = KC::bytes_encode(db_key) {
+                index.record_skipped_corrupted_entry(name, &key);
             }
-            None => Ok(None),
-        }
+        }))
     }
 
+    /// The values of the keys are CBO roaring bitmaps, merged in a single value.
     fn get_value_from_keys<'v, K1, KC, DC>(
         txn: &'ctx RoTxn,
         cache_key: K1,
         db_keys: &'v [KC::EItem],
         cache: &mut FxHashMap<K1, Option<Cow<'ctx, [u8]>>>,
         db: Database<KC, ByteSlice>,
-        merger: MergeFn,
+        index: &Index,
+        name: &str,
     ) -> Result<Option<DC::DItem>>
     where
         K1: Copy + Eq + Hash,
@@ -101,7 +150,22 @@ impl<'ctx> DatabaseCache<'ctx> {
                     if bitmaps.is_empty() {
                         None
                     } else {
-                        Some(merger(&[], &bitmaps[..])?)
+                        let mut merged = Vec::new();
+                        if CboRoaringBitmapCodec::merge_into(&bitmaps, &mut merged).is_err() {
+                            // One of the bitmaps is corrupted, it is skipped and recorded.
+                            let mut decodable = Vec::with_capacity(bitmaps.len());
+                            for key in keys {
+                                let Some(bitmap) = db.get(txn, key)? else { continue };
+                                if CboRoaringBitmapCodec::bytes_decode(bitmap).is_some() {
+                                    decodable.push(Cow::Borrowed(bitmap));
+                                } else if let Some(key) = KC::bytes_encode(key) {
+                                    index.record_skipped_corrupted_entry(name, &key);
+                                }
+                            }
+                            merged.clear();
+                            CboRoaringBitmapCodec::merge_into(&decodable, &mut merged)?;
+                        }
+                        Some(Cow::Owned(merged))
                     }
                 }
             };
@@ -109,23 +173,37 @@ impl<'ctx> DatabaseCache<'ctx> {
             entry.insert(bitmap_ptr);
         }
 
-        match cache.get(&cache_key).unwrap() {
-            Some(Cow::Borrowed(bytes)) => {
-                DC::bytes_decode_owned(bytes).ok_or(heed::Error::Decoding.into()).map(Some)
-            }
-            Some(Cow::Owned(bytes)) => {
-                DC::bytes_decode_owned(bytes).ok_or(heed::Error::Decoding.into()).map(Some)
+        Ok(Self::decode_value::<DC>(cache.get(&cache_key).unwrap(), || {
+            for key in db_keys {
+                if let Some(key) = KC::bytes_encode(key) {
+                    index.record_skipped_corrupted_entry(name, &key);
+                }
             }
-            None => Ok(None),
+        }))
+    }
+
+    /// Decodes a cached value, the values that can't be decoded are skipped as if they were
+    /// missing, after being reported with `on_corrupted`.
+    fn decode_value<DC: BytesDecodeOwned>(
+        value: &Option<Cow<'ctx, [u8]>>,
+        on_corrupted: impl FnOnce(),
+    ) -> Option<DC::DItem> {
+        let bytes = value.as_deref()?;
+        let decoded = DC::bytes_decode_owned(bytes);
+        if decoded.is_none() {
+            on_corrupted();
         }
+        decoded
     }
 }
 
 impl<'ctx> SearchContext<'ctx> {
     pub fn get_words_fst(&mut self) -> Result<fst::Set<Cow<'ctx, [u8]>>> {
         if let Some(fst) = self.db_cache.words_fst.clone() {
+            self.db_cache.words_fst_metrics.hits += 1;
             Ok(fst)
         } else {
+            self.db_cache.words_fst_metrics.misses += 1;
             let fst = self.index.words_fst(self.txn)?;
             self.db_cache.words_fst = Some(fst.clone());
             Ok(fst)
@@ -165,7 +243,8 @@ impl<'ctx> SearchContext<'ctx> {
                     &keys[..],
                     &mut self.db_cache.word_docids,
                     self.index.word_fid_docids.remap_data_type::<ByteSlice>(),
-                    merge_cbo_roaring_bitmaps,
+                    self.index,
+                    db_name::WORD_FIELD_ID_DOCIDS,
                 )
             }
             None => DatabaseCache::get_value::<_, _, RoaringBitmapCodec>(
@@ -174,6 +253,8 @@ impl<'ctx> SearchContext<'ctx> {
                 self.word_interner.get(word).as_str(),
                 &mut self.db_cache.word_docids,
                 self.index.word_docids.remap_data_type::<ByteSlice>(),
+                self.index,
+                db_name::WORD_DOCIDS,
             ),
         }
     }
@@ -188,6 +269,8 @@ impl<'ctx> SearchContext<'ctx> {
             self.word_interner.get(word).as_str(),
             &mut self.db_cache.exact_word_docids,
             self.index.exact_word_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::EXACT_WORD_DOCIDS,
         )
     }
 
@@ -227,7 +310,8 @@ impl<'ctx> SearchContext<'ctx> {
                     &keys[..],
                     &mut self.db_cache.word_prefix_docids,
                     self.index.word_prefix_fid_docids.remap_data_type::<ByteSlice>(),
-                    merge_cbo_roaring_bitmaps,
+                    self.index,
+                    db_name::WORD_PREFIX_FIELD_ID_DOCIDS,
                 )
             }
             None => DatabaseCache::get_value::<_, _, RoaringBitmapCodec>(
@@ -236,6 +320,8 @@ impl<'ctx> SearchContext<'ctx> {
                 self.word_interner.get(prefix).as_str(),
                 &mut self.db_cache.word_prefix_docids,
                 self.index.word_prefix_docids.remap_data_type::<ByteSlice>(),
+                self.index,
+                db_name::WORD_PREFIX_DOCIDS,
             ),
         }
     }
@@ -250,6 +336,8 @@ impl<'ctx> SearchContext<'ctx> {
             self.word_interner.get(prefix).as_str(),
             &mut self.db_cache.exact_word_prefix_docids,
             self.index.exact_word_prefix_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::EXACT_WORD_PREFIX_DOCIDS,
         )
     }
 
@@ -269,6 +357,8 @@ impl<'ctx> SearchContext<'ctx> {
             ),
             &mut self.db_cache.word_pair_proximity_docids,
             self.index.word_pair_proximity_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_PAIR_PROXIMITY_DOCIDS,
         )
     }
 
@@ -288,6 +378,8 @@ impl<'ctx> SearchContext<'ctx> {
             ),
             &mut self.db_cache.word_pair_proximity_docids,
             self.index.word_pair_proximity_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_PAIR_PROXIMITY_DOCIDS,
         )
     }
 
@@ -307,6 +399,8 @@ impl<'ctx> SearchContext<'ctx> {
             ),
             &mut self.db_cache.word_prefix_pair_proximity_docids,
             self.index.word_prefix_pair_proximity_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_PREFIX_PAIR_PROXIMITY_DOCIDS,
         )
     }
     pub fn get_db_prefix_word_pair_proximity_docids(
@@ -325,6 +419,8 @@ impl<'ctx> SearchContext<'ctx> {
             ),
             &mut self.db_cache.prefix_word_pair_proximity_docids,
             self.index.prefix_word_pair_proximity_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::PREFIX_WORD_PAIR_PROXIMITY_DOCIDS,
         )
     }
 
@@ -344,6 +440,8 @@ impl<'ctx> SearchContext<'ctx> {
             &(self.word_interner.get(word).as_str(), fid),
             &mut self.db_cache.word_fid_docids,
             self.index.word_fid_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_FIELD_ID_DOCIDS,
         )
     }
 
@@ -363,6 +461,8 @@ impl<'ctx> SearchContext<'ctx> {
             &(self.word_interner.get(word_prefix).as_str(), fid),
             &mut self.db_cache.word_prefix_fid_docids,
             self.index.word_prefix_fid_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_PREFIX_FIELD_ID_DOCIDS,
         )
     }
 
@@ -431,6 +531,8 @@ impl<'ctx> SearchContext<'ctx> {
             &(self.word_interner.get(word).as_str(), position),
             &mut self.db_cache.word_position_docids,
             self.index.word_position_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_POSITION_DOCIDS,
         )
     }
 
@@ -445,6 +547,8 @@ impl<'ctx> SearchContext<'ctx> {
             &(self.word_interner.get(word_prefix).as_str(), position),
             &mut self.db_cache.word_prefix_position_docids,
             self.index.word_prefix_position_docids.remap_data_type::<ByteSlice>(),
+            self.index,
+            db_name::WORD_PREFIX_POSITION_DOCIDS,
         )
     }
 
@@ -507,3 +611,30 @@ impl<'ctx> SearchContext<'ctx> {
         Ok(positions)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use heed::types::Str;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn words_fst_is_borrowed_from_the_database() {
+        let index = TempIndex::new();
+        index.add_documents(documents!([{ "id": 0, "text": "the quick brown fox" }])).unwrap();
+
+        let txn = index.read_txn().unwrap();
+        let mut ctx = SearchContext::new(&index, &txn);
+        let fst = ctx.get_words_fst().unwrap();
+        assert!(fst.contains("quick"));
+        assert!(matches!(fst.as_fst().as_inner(), Cow::Borrowed(_)));
+
+        let bytes = index.main.get::<_, Str, ByteSlice>(&txn, "words-fst").unwrap().unwrap();
+        assert_eq!(fst.as_fst().as_bytes().as_ptr(), bytes.as_ptr());
+
+        ctx.get_words_fst().unwrap();
+        ctx.get_words_fst().unwrap();
+        assert_eq!(ctx.db_cache.words_fst_metrics, CacheMetrics { hits: 2, misses: 1 });
+    }
+}
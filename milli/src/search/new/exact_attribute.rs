@@ -185,8 +185,8 @@ impl State {
         }
 
         let searchable_fields_ids = {
-            if let Some(fids) = ctx.index.searchable_fields_ids(ctx.txn)? {
-                fids
+            if let Some(fids) = &ctx.index.cached_settings(ctx.txn)?.searchable_fields_ids {
+                fids.clone()
             } else {
                 ctx.index.fields_ids_map(ctx.txn)?.ids().collect()
             }
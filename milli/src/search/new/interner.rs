@@ -68,6 +68,14 @@ where
     pub fn get(&self, interned: Interned<T>) -> &T {
         &self.stable_store[interned.idx as usize]
     }
+    /// Returns the number of values stored in the dedup-interner.
+    pub fn len(&self) -> usize {
+        self.stable_store.len()
+    }
+    /// Returns `true` if the dedup-interner doesn't contain any value.
+    pub fn is_empty(&self) -> bool {
+        self.stable_store.is_empty()
+    }
 }
 
 /// A fixed-length store for values of type `T`, where each value is identified
@@ -8,6 +8,14 @@ pub const MAX_ONE_TYPO_COUNT: usize = 150;
 /// Maximum number of words that can be derived from a single word with a distance of two to that word.
 pub const MAX_TWO_TYPOS_COUNT: usize = 50;
 
+/// Maximum length, in bytes, of a word that can be split into three consecutive words.
+///
+/// The number of splits grows with the square of the length of the word.
+pub const MAX_THREE_WAY_SPLIT_WORD_LENGTH: usize = 32;
+/// Maximum number of splits of a word into three consecutive words whose frequency is looked up
+/// in the database, the splits are only candidates when their three words are in the index.
+pub const MAX_THREE_WAY_SPLIT_CANDIDATES: usize = 20;
+
 /// Maximum amount of synonym phrases that can be derived from a single word.
 pub const MAX_SYNONYM_PHRASE_COUNT: usize = 50;
 
@@ -15,3 +23,11 @@ pub const MAX_SYNONYM_PHRASE_COUNT: usize = 50;
 ///
 /// This limit is meant to gracefully handle the case where a word would have very long phrases as synonyms.
 pub const MAX_SYNONYM_WORD_COUNT: usize = 100;
+
+/// Default maximum number of candidates sorted in memory by the sort ranking rules,
+/// instead of walking the facet levels.
+pub const SORT_IN_MEMORY_THRESHOLD: usize = 1_000;
+
+/// Number of best ranked documents that are reordered by their boosted score
+/// when the index has a boost field.
+pub const BOOST_WINDOW_SIZE: usize = 1_000;
@@ -0,0 +1,306 @@
+//! Formats the documents returned by a search, like the `_formatted` field of the search route.
+
+use std::collections::BTreeMap;
+
+use charabia::Tokenizer;
+use serde_json::Value;
+
+use super::{FormatOptions, MatchPosition, MatcherBuilder, MatchingWords};
+use crate::{is_faceted_by, Object};
+
+/// Highlights and crops the attributes of documents with configurable tags and crop marker.
+pub struct Formatter<'m> {
+    builder: MatcherBuilder<'m>,
+    attributes: BTreeMap<String, FormatOptions>,
+}
+
+impl<'m> Formatter<'m> {
+    /// The tokenizer must be built like the one of the search to match the same words.
+    pub fn new(matching_words: MatchingWords, tokenizer: Tokenizer<'m>) -> Self {
+        Self {
+            builder: MatcherBuilder::new(matching_words, tokenizer),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    pub fn highlight_pre_tag(&mut self, tag: String) -> &mut Self {
+        self.builder.highlight_prefix(tag);
+        self
+    }
+
+    pub fn highlight_post_tag(&mut self, tag: String) -> &mut Self {
+        self.builder.highlight_suffix(tag);
+        self
+    }
+
+    pub fn crop_marker(&mut self, marker: String) -> &mut Self {
+        self.builder.crop_marker(marker);
+        self
+    }
+
+    /// Formats the attribute, and all the fields nested under it, with the given options,
+    /// `*` selects all the attributes of the documents.
+    ///
+    /// The options given several times for the same attribute are merged.
+    pub fn format_attribute(
+        &mut self,
+        attribute: impl Into<String>,
+        options: FormatOptions,
+    ) -> &mut Self {
+        self.attributes
+            .entry(attribute.into())
+            .and_modify(|current| *current = current.merge(options))
+            .or_insert(options);
+        self
+    }
+
+    /// Highlights the given attributes, `*` selects all the attributes of the documents.
+    pub fn attributes_to_highlight(&mut self, attributes: &[String]) -> &mut Self {
+        for attribute in attributes {
+            self.format_attribute(attribute, FormatOptions { highlight: true, crop: None });
+        }
+        self
+    }
+
+    /// Crops the given attributes to `crop_length` words, `*` selects all the attributes
+    /// of the documents.
+    ///
+    /// The crop length of an attribute can be given with the `attribute:length` syntax,
+    /// an invalid length is replaced by `crop_length`.
+    pub fn attributes_to_crop(&mut self, attributes: &[String], crop_length: usize) -> &mut Self {
+        for attribute in attributes {
+            let mut split = attribute.rsplitn(2, ':');
+            let (attribute, crop_length) = match split.next().zip(split.next()) {
+                Some((length, attribute)) => (attribute, length.parse().unwrap_or(crop_length)),
+                None => (attribute.as_str(), crop_length),
+            };
+            self.format_attribute(
+                attribute,
+                FormatOptions { highlight: false, crop: Some(crop_length) },
+            );
+        }
+        self
+    }
+
+    /// Returns the formatted version of the document, only the attributes to format are kept.
+    ///
+    /// The options of a nested field are merged with the ones of its parents, the numbers
+    /// are formatted as strings and the values of arrays are never cropped.
+    pub fn format(&self, document: &Object) -> Object {
+        self.format_object(document, None)
+    }
+
+    /// Returns the positions of the words matching the query in the strings and numbers
+    /// of the document, by attribute, the nested fields are named with dots.
+    ///
+    /// The positions of the values of an array are relative to each value.
+    pub fn matches_position(&self, document: &Object) -> BTreeMap<String, Vec<MatchPosition>> {
+        let mut matches_position = BTreeMap::new();
+        for (key, value) in document {
+            self.match_positions(value, key, &mut matches_position);
+        }
+        matches_position
+    }
+
+    fn match_positions(
+        &self,
+        value: &Value,
+        name: &str,
+        matches_position: &mut BTreeMap<String, Vec<MatchPosition>>,
+    ) {
+        let positions = match value {
+            Value::String(text) => self.builder.build(text).match_positions(),
+            Value::Number(number) => self.builder.build(&number.to_string()).match_positions(),
+            Value::Array(values) => {
+                for value in values {
+                    self.match_positions(value, name, matches_position);
+                }
+                return;
+            }
+            Value::Object(object) => {
+                for (key, value) in object {
+                    self.match_positions(value, &format!("{name}.{key}"), matches_position);
+                }
+                return;
+            }
+            _ => return,
+        };
+        if !positions.is_empty() {
+            matches_position.entry(name.to_string()).or_default().extend(positions);
+        }
+    }
+
+    fn format_object(&self, object: &Object, parent: Option<&str>) -> Object {
+        let mut formatted = Object::new();
+        for (key, value) in object {
+            let name = match parent {
+                Some(parent) => format!("{parent}.{key}"),
+                None => key.clone(),
+            };
+            let options = self
+                .attributes
+                .iter()
+                .filter(|(attribute, _)| *attribute == "*" || is_faceted_by(&name, attribute))
+                .map(|(_, options)| *options)
+                .reduce(FormatOptions::merge);
+
+            match (options, value) {
+                (Some(options), value) => {
+                    formatted.insert(key.clone(), self.format_value(value, &name, options));
+                }
+                // One of the fields nested under this object must be formatted.
+                (None, Value::Object(object))
+                    if self.attributes.keys().any(|attribute| is_faceted_by(attribute, &name)) =>
+                {
+                    formatted.insert(
+                        key.clone(),
+                        Value::Object(self.format_object(object, Some(&name))),
+                    );
+                }
+                (None, _) => (),
+            }
+        }
+        formatted
+    }
+
+    fn format_value(&self, value: &Value, name: &str, options: FormatOptions) -> Value {
+        match value {
+            Value::String(text) => {
+                let mut matcher = self.builder.build(text);
+                Value::String(matcher.format(options).into_owned())
+            }
+            Value::Number(number) => {
+                let text = number.to_string();
+                let mut matcher = self.builder.build(&text);
+                Value::String(matcher.format(options).into_owned())
+            }
+            Value::Array(values) => {
+                let options = FormatOptions { crop: None, ..options };
+                Value::Array(values.iter().map(|v| self.format_value(v, name, options)).collect())
+            }
+            Value::Object(object) => Value::Object(self.format_object(object, Some(name))),
+            value => value.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::search::new::matches::matching_words::tests::temp_index_with_documents;
+
+    fn formatter(rtxn: &heed::RoTxn, index: &TempIndex, query: &str) -> Formatter<'static> {
+        let MatcherBuilder { matching_words, .. } = MatcherBuilder::new_test(rtxn, index, query);
+        Formatter::new(matching_words, charabia::TokenizerBuilder::default().into_tokenizer())
+    }
+
+    fn document() -> Object {
+        match json!({
+            "id": 1,
+            "title": "Natalie risk her future to build a world with the boy she loves.",
+            "author": { "name": "Emily Henry", "bio": "She split the world in two" },
+            "tags": ["world", "split books"],
+            "year": 2020,
+        }) {
+            Value::Object(object) => object,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn format_selected_attributes() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let mut formatter = formatter(&rtxn, &temp_index, "split the world");
+        formatter
+            .format_attribute("title", FormatOptions { highlight: true, crop: None })
+            .format_attribute("author.bio", FormatOptions { highlight: false, crop: Some(3) })
+            .format_attribute("tags", FormatOptions { highlight: true, crop: Some(1) })
+            .format_attribute("year", FormatOptions { highlight: false, crop: None });
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&formatter.format(&document())).unwrap(), @r###"
+        {
+          "title": "Natalie risk her future to build a <em>world</em> with <em>the</em> boy she loves.",
+          "author": {
+            "bio": "…split the world…"
+          },
+          "tags": [
+            "<em>world</em>",
+            "<em>split</em> books"
+          ],
+          "year": "2020"
+        }
+        "###);
+    }
+
+    #[test]
+    fn format_with_custom_tags() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let mut formatter = formatter(&rtxn, &temp_index, "split the world");
+        formatter
+            .highlight_pre_tag(String::from("<mark>"))
+            .highlight_post_tag(String::from("</mark>"))
+            .crop_marker(String::from("[...]"))
+            .format_attribute("author", FormatOptions { highlight: true, crop: None })
+            .format_attribute("author.bio", FormatOptions { highlight: false, crop: Some(3) });
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&formatter.format(&document())).unwrap(), @r###"
+        {
+          "author": {
+            "name": "Emily Henry",
+            "bio": "[...]<mark>split</mark> <mark>the</mark> <mark>world</mark>[...]"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn attributes_to_highlight_and_crop() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let mut formatter = formatter(&rtxn, &temp_index, "split the world");
+        formatter
+            .attributes_to_highlight(&[String::from("*")])
+            .attributes_to_crop(&[String::from("title"), String::from("author.bio:3")], 4);
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&formatter.format(&document())).unwrap(), @r###"
+        {
+          "id": "1",
+          "title": "…a <em>world</em> with <em>the</em>…",
+          "author": {
+            "name": "Emily Henry",
+            "bio": "…<em>split</em> <em>the</em> <em>world</em>…"
+          },
+          "tags": [
+            "<em>world</em>",
+            "<em>split</em> books"
+          ],
+          "year": "2020"
+        }
+        "###);
+
+        // Only the cropped attributes are returned and an invalid length is ignored.
+        let mut formatter = formatter(&rtxn, &temp_index, "split the world");
+        formatter.attributes_to_crop(&[String::from("author.bio:many")], 3);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&formatter.format(&document())).unwrap(), @r###"
+        {
+          "author": {
+            "bio": "…split the world…"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn matches_position() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let formatter = formatter(&rtxn, &temp_index, "split the world");
+
+        insta::assert_snapshot!(serde_json::to_string(&formatter.matches_position(&document())).unwrap(), @r###"{"author.bio":[{"byteStart":4,"byteLength":5,"charStart":4,"charLength":5},{"byteStart":10,"byteLength":3,"charStart":10,"charLength":3},{"byteStart":14,"byteLength":5,"charStart":14,"charLength":5}],"tags":[{"byteStart":0,"byteLength":5,"charStart":0,"charLength":5},{"byteStart":0,"byteLength":5,"charStart":0,"charLength":5}],"title":[{"byteStart":35,"byteLength":5,"charStart":35,"charLength":5},{"byteStart":46,"byteLength":3,"charStart":46,"charLength":3}]}"###);
+    }
+}
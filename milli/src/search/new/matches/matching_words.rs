@@ -3,6 +3,7 @@ use std::fmt;
 use std::ops::RangeInclusive;
 
 use charabia::Token;
+use serde::Serialize;
 
 use super::super::interner::Interned;
 use super::super::query_term::LocatedQueryTerm;
@@ -11,16 +12,46 @@ use crate::SearchContext;
 
 pub struct LocatedMatchingPhrase {
     pub value: Interned<Phrase>,
+    pub kind: MatchingWordKind,
     pub positions: RangeInclusive<WordId>,
 }
 
 pub struct LocatedMatchingWords {
-    pub value: Vec<Interned<String>>,
+    pub value: Vec<(Interned<String>, MatchingWordKind)>,
     pub positions: RangeInclusive<WordId>,
     pub is_prefix: bool,
     pub original_char_count: usize,
 }
 
+/// The reason why a word, or a phrase, matches a term of the query.
+///
+/// The variants are ordered from the most to the least precise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchingWordKind {
+    /// The word, or phrase, written by the end user.
+    Original,
+    /// A word that starts with the last word of the query.
+    PrefixCompletion,
+    /// A word that is one or two typos away from the one written by the end user,
+    /// or the word written by the end user split in two.
+    Typo,
+    /// A synonym of the word, or phrase, written by the end user.
+    Synonym,
+}
+
+/// A word, or a phrase, that matches a term of the query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoundWord {
+    /// The matching words, a phrase is represented by its words joined by spaces
+    /// and where the stop words are omitted.
+    pub word: String,
+    pub kind: MatchingWordKind,
+    /// The positions of the query words that are matched.
+    pub positions: RangeInclusive<WordId>,
+}
+
 /// Structure created from a query tree
 /// referencing words that match the given query tree.
 #[derive(Default)]
@@ -33,6 +64,31 @@ pub struct MatchingWords {
 
 impl MatchingWords {
     pub fn new(ctx: SearchContext, located_terms: Vec<LocatedQueryTerm>) -> Self {
+        let (phrases, words) = Self::extract_located_matchings(&ctx, located_terms);
+        Self {
+            phrases,
+            words,
+            word_interner: ctx.word_interner,
+            phrase_interner: ctx.phrase_interner,
+        }
+    }
+
+    /// Identical to [`MatchingWords::new`] but doesn't consume the [`SearchContext`],
+    /// the words and phrases interners are cloned instead.
+    pub fn from_ctx_ref(ctx: &SearchContext, located_terms: Vec<LocatedQueryTerm>) -> Self {
+        let (phrases, words) = Self::extract_located_matchings(ctx, located_terms);
+        Self {
+            phrases,
+            words,
+            word_interner: ctx.word_interner.clone(),
+            phrase_interner: ctx.phrase_interner.clone(),
+        }
+    }
+
+    fn extract_located_matchings(
+        ctx: &SearchContext,
+        located_terms: Vec<LocatedQueryTerm>,
+    ) -> (Vec<LocatedMatchingPhrase>, Vec<LocatedMatchingWords>) {
         let mut phrases = Vec::new();
         let mut words = Vec::new();
 
@@ -40,11 +96,12 @@ impl MatchingWords {
         // and wrap them in dedicated structures.
         for located_term in located_terms {
             let term = ctx.term_interner.get(located_term.value);
-            let (matching_words, matching_phrases) = term.all_computed_derivations();
+            let (matching_words, matching_phrases) = term.all_computed_derivations_with_kind();
 
-            for matching_phrase in matching_phrases {
+            for (matching_phrase, kind) in matching_phrases {
                 phrases.push(LocatedMatchingPhrase {
                     value: matching_phrase,
+                    kind,
                     positions: located_term.positions.clone(),
                 });
             }
@@ -53,19 +110,47 @@ impl MatchingWords {
                 value: matching_words,
                 positions: located_term.positions.clone(),
                 is_prefix: term.is_prefix(),
-                original_char_count: term.original_word(&ctx).chars().count(),
+                original_char_count: term.original_word(ctx).chars().count(),
             });
         }
 
         // Sort word to put prefixes at the bottom prioritizing the exact matches.
         words.sort_unstable_by_key(|lmw| (lmw.is_prefix, Reverse(lmw.positions.len())));
 
-        Self {
-            phrases,
-            words,
-            word_interner: ctx.word_interner,
-            phrase_interner: ctx.phrase_interner,
-        }
+        (phrases, words)
+    }
+
+    /// Returns all the words and phrases that match the query, associated with
+    /// the reason why they match it.
+    ///
+    /// The words are sorted by kind, from the most to the least precise.
+    pub fn found_words(&self) -> Vec<FoundWord> {
+        let phrases = self.phrases.iter().map(|located_phrase| {
+            let phrase = self.phrase_interner.get(located_phrase.value);
+            let words: Vec<_> = phrase
+                .words
+                .iter()
+                .flatten()
+                .map(|word| self.word_interner.get(*word).as_str())
+                .collect();
+            FoundWord {
+                word: words.join(" "),
+                kind: located_phrase.kind,
+                positions: located_phrase.positions.clone(),
+            }
+        });
+
+        let words = self.words.iter().flat_map(|located_words| {
+            located_words.value.iter().map(|(word, kind)| FoundWord {
+                word: self.word_interner.get(*word).clone(),
+                kind: *kind,
+                positions: located_words.positions.clone(),
+            })
+        });
+
+        let mut found_words: Vec<_> = phrases.chain(words).collect();
+        found_words.sort_by_key(|found| found.kind);
+        found_words
     }
 
     /// Returns an iterator over terms that match or partially match the given token.
@@ -76,7 +161,7 @@ impl MatchingWords {
     /// Try to match the token with one of the located_words.
     fn match_unique_words<'a>(&'a self, token: &Token) -> Option<MatchType<'a>> {
         for located_words in &self.words {
-            for word in &located_words.value {
+            for (word, _kind) in &located_words.value {
                 let word = self.word_interner.get(*word);
                 // if the word is a prefix we match using starts_with.
                 if located_words.is_prefix && token.lemma().starts_with(word) {
@@ -130,9 +215,22 @@ impl<'a> Iterator for MatchesIter<'a, '_> {
                         word.map(|word| self.matching_words.word_interner.get(word).as_str())
                     })
                     .collect();
-                let partial = PartialMatch { matching_words: words, ids, char_len: 0 };
-
-                partial.match_token(self.token).or_else(|| self.next())
+                let partial = PartialMatch {
+                    matching_words: words,
+                    ids,
+                    char_len: 0,
+                    slop: 0,
+                    skipped: false,
+                };
+
+                // the slop only allows to skip words once the first word of the phrase matched.
+                match partial.match_token(self.token) {
+                    Some(MatchType::Partial(partial)) => {
+                        Some(MatchType::Partial(PartialMatch { slop: phrase.slop, ..partial }))
+                    }
+                    Some(full) => Some(full),
+                    None => self.next(),
+                }
             }
             // If no phrases matches, try to match uiques words.
             None => self.matching_words.match_unique_words(self.token),
@@ -159,15 +257,20 @@ pub struct PartialMatch<'a> {
     matching_words: Vec<Option<&'a str>>,
     ids: &'a RangeInclusive<WordId>,
     char_len: usize,
+    /// The number of words that can still be skipped between the words of a phrase.
+    slop: u8,
+    /// Whether the last token was skipped instead of matched.
+    skipped: bool,
 }
 
 impl<'a> PartialMatch<'a> {
     /// Returns:
     /// - None if the given token breaks the partial match
-    /// - Partial if the given token matches the partial match but doesn't complete it
+    /// - Partial if the given token matches the partial match but doesn't complete it,
+    ///   or if it is skipped thanks to the slop of the phrase
     /// - Full if the given token completes the partial match
     pub fn match_token(self, token: &Token) -> Option<MatchType<'a>> {
-        let Self { mut matching_words, ids, .. } = self;
+        let Self { mut matching_words, ids, slop, .. } = self;
 
         let is_matching = match matching_words.first()? {
             Some(word) => &token.lemma() == word,
@@ -181,11 +284,27 @@ impl<'a> PartialMatch<'a> {
         // return a new Partial match allowing the highlighter to continue.
         if is_matching && matching_words.len() > 1 {
             matching_words.remove(0);
-            Some(MatchType::Partial(PartialMatch { matching_words, ids, char_len }))
+            Some(MatchType::Partial(PartialMatch {
+                matching_words,
+                ids,
+                char_len,
+                slop,
+                skipped: false,
+            }))
         // if there is no remaining word to match in the phrase and the current token is matching,
         // return a Full match.
         } else if is_matching {
             Some(MatchType::Full { char_len, ids })
+        // if the current token doesn't match but the slop of the phrase allows to skip it,
+        // return a Partial match that is not highlighted, in the same way the phrase is resolved.
+        } else if slop > 0 {
+            Some(MatchType::Partial(PartialMatch {
+                matching_words,
+                ids,
+                char_len: 0,
+                slop: slop - 1,
+                skipped: true,
+            }))
         // if the current token doesn't match, return None to break the match sequence.
         } else {
             None
@@ -195,6 +314,11 @@ impl<'a> PartialMatch<'a> {
     pub fn char_len(&self) -> usize {
         self.char_len
     }
+
+    /// Whether the last token was skipped thanks to the slop of the phrase.
+    pub fn is_skipped(&self) -> bool {
+        self.skipped
+    }
 }
 
 impl fmt::Debug for MatchingWords {
@@ -222,7 +346,7 @@ impl fmt::Debug for MatchingWords {
             .flat_map(|w| {
                 w.value
                     .iter()
-                    .map(|s| (word_interner.get(*s), w.positions.clone(), w.is_prefix))
+                    .map(|(s, _)| (word_interner.get(*s), w.positions.clone(), w.is_prefix))
                     .collect::<Vec<_>>()
             })
             .collect();
@@ -237,7 +361,7 @@ pub(crate) mod tests {
 
     use charabia::{TokenKind, TokenizerBuilder};
 
-    use super::super::super::located_query_terms_from_tokens;
+    use super::super::super::{located_query_terms_from_tokens, ExtractedTokens};
     use super::*;
     use crate::index::tests::TempIndex;
 
@@ -261,7 +385,8 @@ pub(crate) mod tests {
         let mut builder = TokenizerBuilder::default();
         let tokenizer = builder.build();
         let tokens = tokenizer.tokenize("split this world");
-        let query_terms = located_query_terms_from_tokens(&mut ctx, tokens, None).unwrap();
+        let ExtractedTokens { query_terms, .. } =
+            located_query_terms_from_tokens(&mut ctx, tokens, None).unwrap();
         let matching_words = MatchingWords::new(ctx, query_terms);
 
         assert_eq!(
@@ -325,4 +450,40 @@ pub(crate) mod tests {
             None
         );
     }
+
+    #[test]
+    fn found_words() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let mut ctx = SearchContext::new(&temp_index, &rtxn);
+        let mut builder = TokenizerBuilder::default();
+        let tokenizer = builder.build();
+        let tokens = tokenizer.tokenize("split this world");
+        let ExtractedTokens { query_terms, .. } =
+            located_query_terms_from_tokens(&mut ctx, tokens, None).unwrap();
+        let matching_words = MatchingWords::new(ctx, query_terms);
+        let found_words = matching_words.found_words();
+
+        let split = FoundWord {
+            word: String::from("split"),
+            kind: MatchingWordKind::Original,
+            positions: 0..=0,
+        };
+        assert!(found_words.contains(&split));
+        let world = FoundWord {
+            word: String::from("world"),
+            kind: MatchingWordKind::Original,
+            positions: 2..=2,
+        };
+        assert!(found_words.contains(&world));
+
+        // only the last word of the query can be completed
+        for found in &found_words {
+            if found.kind == MatchingWordKind::PrefixCompletion {
+                assert!(found.word.starts_with("world"));
+                assert_eq!(found.positions, 2..=2);
+            }
+        }
+        assert!(found_words.windows(2).all(|w| w[0].kind <= w[1].kind));
+    }
 }
@@ -1,10 +1,12 @@
 use std::borrow::Cow;
 
 use charabia::{SeparatorKind, Token, Tokenizer};
-pub use matching_words::MatchingWords;
+pub use formatter::Formatter;
+pub use matching_words::{FoundWord, MatchingWordKind, MatchingWords};
 use matching_words::{MatchType, PartialMatch, WordId};
 use serde::Serialize;
 
+mod formatter;
 pub mod matching_words;
 
 const DEFAULT_CROP_MARKER: &str = "…";
@@ -72,6 +74,23 @@ impl<'m> MatcherBuilder<'m> {
     }
 }
 
+impl MatchingWords {
+    /// Returns the bounds of the words of the text that match the query, the typos
+    /// and prefixes are matched exactly like during the search.
+    pub fn match_bounds(&self, tokenizer: &Tokenizer, text: &str) -> Vec<MatchBounds> {
+        let mut matcher = Matcher {
+            text,
+            matching_words: self,
+            tokenizer,
+            crop_marker: DEFAULT_CROP_MARKER,
+            highlight_prefix: DEFAULT_HIGHLIGHT_PREFIX,
+            highlight_suffix: DEFAULT_HIGHLIGHT_SUFFIX,
+            matches: None,
+        };
+        matcher.matches()
+    }
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct FormatOptions {
     pub highlight: bool,
@@ -101,6 +120,16 @@ pub struct MatchBounds {
     pub length: usize,
 }
 
+/// The position of a word matching the query, both in bytes and in characters.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchPosition {
+    pub byte_start: usize,
+    pub byte_length: usize,
+    pub char_start: usize,
+    pub char_length: usize,
+}
+
 /// Structure used to analize a string, compute words that match,
 /// and format the source string, returning a highlighted and cropped sub-string.
 pub struct Matcher<'t, 'm> {
@@ -130,9 +159,16 @@ impl<'t> Matcher<'t, '_> {
             for (token_position, word_position, word) in words_positions {
                 partial = match partial.match_token(word) {
                     // token matches the partial match, but the match is not full,
-                    // we temporarly save the current token then we try to match the next one.
+                    // we temporarly save the current token then we try to match the next one,
+                    // a token skipped thanks to the slop of a phrase is not saved.
                     Some(MatchType::Partial(partial)) => {
-                        potential_matches.push((token_position, word_position, partial.char_len()));
+                        if !partial.is_skipped() {
+                            potential_matches.push((
+                                token_position,
+                                word_position,
+                                partial.char_len(),
+                            ));
+                        }
                         partial
                     }
                     // partial match is now full, we keep this matches and we advance positions
@@ -239,6 +275,30 @@ impl<'t> Matcher<'t, '_> {
         }
     }
 
+    /// Returns the positions of the words that match the query.
+    ///
+    /// The length of a prefix match is the length of the matching part of the word.
+    pub fn match_positions(&mut self) -> Vec<MatchPosition> {
+        match &self.matches {
+            None => self.compute_matches().match_positions(),
+            Some((tokens, matches)) => matches
+                .iter()
+                .map(|m| {
+                    let token = &tokens[m.token_position];
+                    let word = &self.text[token.byte_start..token.byte_end];
+                    let byte_length =
+                        word.char_indices().nth(m.match_len).map_or(word.len(), |(i, _)| i);
+                    MatchPosition {
+                        byte_start: token.byte_start,
+                        byte_length,
+                        char_start: token.char_start,
+                        char_length: m.match_len,
+                    }
+                })
+                .collect(),
+        }
+    }
+
     /// Returns the bounds in byte index of the crop window.
     fn crop_bounds(&self, tokens: &[Token], matches: &[Match], crop_size: usize) -> (usize, usize) {
         // if there is no match, we start from the beginning of the string by default.
@@ -504,7 +564,7 @@ mod tests {
     use crate::{execute_search, SearchContext};
 
     impl<'a> MatcherBuilder<'a> {
-        fn new_test(rtxn: &'a heed::RoTxn, index: &'a TempIndex, query: &str) -> Self {
+        pub(super) fn new_test(rtxn: &'a heed::RoTxn, index: &'a TempIndex, query: &str) -> Self {
             let mut ctx = SearchContext::new(index, rtxn);
             let crate::search::PartialSearchResult { located_query_terms, .. } = execute_search(
                 &mut ctx,
@@ -604,6 +664,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn match_positions_unicode() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let builder = MatcherBuilder::new_test(&rtxn, &temp_index, "world");
+
+        // The byte and char offsets differ after the non-ascii characters.
+        let text = "a Westfália, Ŵôřlḑ and Ŵôřlḑôle";
+        let mut matcher = builder.build(text);
+        assert_eq!(
+            matcher.match_positions(),
+            vec![
+                MatchPosition { byte_start: 14, byte_length: 10, char_start: 13, char_length: 5 },
+                MatchPosition { byte_start: 29, byte_length: 10, char_start: 23, char_length: 5 },
+            ]
+        );
+        // The bounds use a byte start and a char length.
+        assert_eq!(
+            matcher.matches(),
+            vec![MatchBounds { start: 14, length: 5 }, MatchBounds { start: 29, length: 5 }]
+        );
+    }
+
     #[test]
     fn highlight_unicode() {
         let temp_index = temp_index_with_documents();
@@ -855,4 +938,23 @@ mod tests {
             @"_the_ _do_ _or_ die can't be he do and or isn'_t_ _he_"
         );
     }
+
+    #[test]
+    fn phrase_slop_matches() {
+        let temp_index = temp_index_with_documents();
+        let rtxn = temp_index.read_txn().unwrap();
+        let mut builder = MatcherBuilder::new_test(&rtxn, &temp_index, "\"split world\"~1");
+        builder.highlight_prefix("_".to_string());
+        builder.highlight_suffix("_".to_string());
+
+        let format_options = FormatOptions { highlight: true, crop: None };
+
+        // the slop allows a single word between the words of the phrase.
+        let text = "split the world and split a big world";
+        let mut matcher = builder.build(text);
+        insta::assert_snapshot!(
+            matcher.format(format_options),
+            @"_split_ the _world_ and split a big world"
+        );
+    }
 }
@@ -1,4 +1,6 @@
+mod boost;
 mod bucket_sort;
+mod cost_estimator;
 mod db_cache;
 mod distinct;
 mod geo_sort;
@@ -15,29 +17,41 @@ mod resolve_query_graph;
 mod small_bitmap;
 
 mod exact_attribute;
+mod order;
 mod sort;
 
 #[cfg(test)]
 mod tests;
 
 use std::collections::{BTreeSet, HashSet};
+use std::time::Instant;
 
+use boost::apply_boost;
 use bucket_sort::{bucket_sort, BucketSortOutput};
-use charabia::TokenizerBuilder;
+use charabia::{Language, TokenizerBuilder};
+use cost_estimator::{evaluation_order, EvaluationOrder};
 use db_cache::DatabaseCache;
 use exact_attribute::ExactAttribute;
+use fxhash::FxHashMap;
 use graph_based_ranking_rule::{Exactness, Fid, Position, Proximity, Typo};
 use heed::RoTxn;
 use hnsw::Searcher;
 use interner::{DedupInterner, Interner};
 pub use logger::visual::VisualSearchLogger;
 pub use logger::{DefaultSearchLogger, SearchLogger};
+use order::Order;
 use query_graph::{QueryGraph, QueryNode};
-use query_term::{located_query_terms_from_tokens, LocatedQueryTerm, Phrase, QueryTerm};
+use query_term::{
+    located_query_terms_from_tokens, prefetch_typo_derivations, ExtractedTokens, LocatedQueryTerm,
+    Phrase, QueryTerm, TypoDerivations,
+};
 use ranking_rules::{
     BoxRankingRule, PlaceholderQuery, RankingRule, RankingRuleOutput, RankingRuleQueryTrait,
 };
-use resolve_query_graph::{compute_query_graph_docids, PhraseDocIdsCache};
+use rayon::ThreadPool;
+use resolve_query_graph::{
+    compute_query_graph_docids, resolve_negative_phrases, resolve_negative_words, PhraseDocIdsCache,
+};
 use roaring::RoaringBitmap;
 use sort::Sort;
 use space::Neighbor;
@@ -50,8 +64,8 @@ use crate::error::FieldIdMapMissingEntry;
 use crate::score_details::{ScoreDetails, ScoringStrategy};
 use crate::search::new::distinct::apply_distinct_rule;
 use crate::{
-    normalize_vector, AscDesc, DocumentId, Filter, Index, Member, Result, TermsMatchingStrategy,
-    UserError, BEU32,
+    allow_list, normalize_vector, AscDesc, DocumentId, Filter, Index, Member, Result,
+    TermsMatchingStrategy, TieBreaker, UserError, BEU32,
 };
 
 /// A structure used throughout the execution of a search query.
@@ -64,6 +78,27 @@ pub struct SearchContext<'ctx> {
     pub term_interner: Interner<QueryTerm>,
     pub phrase_docids: PhraseDocIdsCache,
     pub restricted_fids: Option<Vec<u16>>,
+    /// The maximum number of typo derivations kept for a single word, only the
+    /// [`limits::MAX_ONE_TYPO_COUNT`] and [`limits::MAX_TWO_TYPOS_COUNT`] ones apply by default.
+    pub typo_derivations_limit: Option<usize>,
+    /// The maximum number of candidates sorted in memory by the sort ranking rules.
+    pub sort_in_memory_threshold: usize,
+    /// The instant after which the documents are not ranked anymore.
+    pub deadline: Option<Instant>,
+    /// The languages the query is written in, replacing the languages detected in the index.
+    pub locales: Option<Vec<Language>>,
+    /// Whether every word of the query is a prefix, not only the last one.
+    pub all_words_as_prefix: bool,
+    /// Whether the last word of the query is a prefix.
+    pub autocomplete: bool,
+    /// The words ignored like stop words for this query only, in addition to the stop words of the index.
+    pub stop_words: BTreeSet<String>,
+    /// The order of the documents that tie on every ranking rule.
+    pub tie_breaker: TieBreaker,
+    /// The thread pool on which the typo derivations of the words of the query are found.
+    pub derivations_thread_pool: Option<&'ctx ThreadPool>,
+    /// The typo derivations found in parallel, until the terms are fully computed.
+    pub prefetched_typo_derivations: FxHashMap<Interned<QueryTerm>, TypoDerivations>,
 }
 
 impl<'ctx> SearchContext<'ctx> {
@@ -77,10 +112,26 @@ impl<'ctx> SearchContext<'ctx> {
             term_interner: <_>::default(),
             phrase_docids: <_>::default(),
             restricted_fids: None,
+            typo_derivations_limit: None,
+            sort_in_memory_threshold: limits::SORT_IN_MEMORY_THRESHOLD,
+            deadline: None,
+            locales: None,
+            all_words_as_prefix: false,
+            autocomplete: true,
+            stop_words: BTreeSet::new(),
+            tie_breaker: TieBreaker::default(),
+            derivations_thread_pool: None,
+            prefetched_typo_derivations: FxHashMap::default(),
         }
     }
 
-    pub fn searchable_attributes(&mut self, searchable_attributes: &'ctx [String]) -> Result<()> {
+    /// Whether the deadline of the search is reached, the ranking rules stop refining
+    /// their buckets and the filter stops being evaluated once it is.
+    pub fn deadline_reached(&self) -> bool {
+        self.deadline.map_or(false, |deadline| Instant::now() >= deadline)
+    }
+
+    pub fn searchable_attributes(&mut self, searchable_attributes: &[String]) -> Result<()> {
         let fids_map = self.index.fields_ids_map(self.txn)?;
         let searchable_names = self.index.searchable_fields(self.txn)?;
 
@@ -169,6 +220,11 @@ fn resolve_maximally_reduced_query_graph(
             .iter()
             .flat_map(|x| x.iter())
             .collect(),
+        TermsMatchingStrategy::Frequency => query_graph
+            .removal_order_for_terms_matching_strategy_frequency(ctx)?
+            .iter()
+            .flat_map(|x| x.iter())
+            .collect(),
         TermsMatchingStrategy::All => vec![],
     };
     graph.remove_nodes_keep_edges(&nodes_to_remove);
@@ -205,7 +261,7 @@ fn get_ranking_rules_for_placeholder_search<'ctx>(
     let mut sorted_fields = HashSet::new();
     let mut geo_sorted = false;
     let mut ranking_rules: Vec<BoxRankingRule<PlaceholderQuery>> = vec![];
-    let settings_ranking_rules = ctx.index.criteria(ctx.txn)?;
+    let settings_ranking_rules = ctx.index.cached_settings(ctx.txn)?.criteria.clone();
     for rr in settings_ranking_rules {
         match rr {
             // These rules need a query to have an effect; ignore them in placeholder search
@@ -213,7 +269,8 @@ fn get_ranking_rules_for_placeholder_search<'ctx>(
             | crate::Criterion::Typo
             | crate::Criterion::Attribute
             | crate::Criterion::Proximity
-            | crate::Criterion::Exactness => continue,
+            | crate::Criterion::Exactness
+            | crate::Criterion::Order => continue,
             crate::Criterion::Sort => {
                 if sort {
                     continue;
@@ -244,6 +301,7 @@ fn get_ranking_rules_for_placeholder_search<'ctx>(
             }
         }
     }
+    resolve_recency_field(ctx, &mut ranking_rules, &sorted_fields)?;
     Ok(ranking_rules)
 }
 
@@ -261,6 +319,7 @@ fn get_ranking_rules_for_query_graph_search<'ctx>(
     let mut sort = false;
     let mut attribute = false;
     let mut exactness = false;
+    let mut order = false;
     let mut sorted_fields = HashSet::new();
     let mut geo_sorted = false;
 
@@ -270,14 +329,15 @@ fn get_ranking_rules_for_query_graph_search<'ctx>(
     }
 
     let mut ranking_rules: Vec<BoxRankingRule<QueryGraph>> = vec![];
-    let settings_ranking_rules = ctx.index.criteria(ctx.txn)?;
+    let settings_ranking_rules = ctx.index.cached_settings(ctx.txn)?.criteria.clone();
     for rr in settings_ranking_rules {
         // Add Words before any of: typo, proximity, attribute
         match rr {
             crate::Criterion::Typo
             | crate::Criterion::Attribute
             | crate::Criterion::Proximity
-            | crate::Criterion::Exactness => {
+            | crate::Criterion::Exactness
+            | crate::Criterion::Order => {
                 if !words {
                     ranking_rules.push(Box::new(Words::new(terms_matching_strategy)));
                     words = true;
@@ -337,6 +397,13 @@ fn get_ranking_rules_for_query_graph_search<'ctx>(
                 ranking_rules.push(Box::new(Exactness::new()));
                 exactness = true;
             }
+            crate::Criterion::Order => {
+                if order {
+                    continue;
+                }
+                ranking_rules.push(Box::new(Order::new()));
+                order = true;
+            }
             crate::Criterion::Asc(field_name) => {
                 if sorted_fields.contains(&field_name) {
                     continue;
@@ -353,9 +420,26 @@ fn get_ranking_rules_for_query_graph_search<'ctx>(
             }
         }
     }
+    resolve_recency_field(ctx, &mut ranking_rules, &sorted_fields)?;
     Ok(ranking_rules)
 }
 
+/// Appends a descending sort on the recency field of the index, unless it is already sorted,
+/// to rank the most recent documents first among the ones that tie on every ranking rule.
+fn resolve_recency_field<'ctx, Query: RankingRuleQueryTrait>(
+    ctx: &SearchContext<'ctx>,
+    ranking_rules: &mut Vec<BoxRankingRule<'ctx, Query>>,
+    sorted_fields: &HashSet<String>,
+) -> Result<()> {
+    if let Some(field_name) = ctx.index.recency_field(ctx.txn)? {
+        if !sorted_fields.contains(field_name) {
+            let field_name = field_name.to_string();
+            ranking_rules.push(Box::new(Sort::new(ctx.index, ctx.txn, field_name, false)?));
+        }
+    }
+    Ok(())
+}
+
 fn resolve_sort_criteria<'ctx, Query: RankingRuleQueryTrait>(
     sort_criteria: &Option<Vec<AscDesc>>,
     ctx: &SearchContext<'ctx>,
@@ -428,33 +512,52 @@ pub fn execute_search(
     placeholder_search_logger: &mut dyn SearchLogger<PlaceholderQuery>,
     query_graph_logger: &mut dyn SearchLogger<QueryGraph>,
 ) -> Result<PartialSearchResult> {
-    let mut universe = if let Some(filters) = filters {
-        filters.evaluate(ctx.txn, ctx.index)?
-    } else {
-        ctx.index.documents_ids(ctx.txn)?
-    };
+    let mut universe = ctx.index.documents_ids(ctx.txn)?;
+    let mut filter_degraded = false;
 
     check_sort_criteria(ctx, sort_criteria.as_ref())?;
 
     if let Some(vector) = vector {
+        if let Some(filters) = filters {
+            universe &= evaluate_filter(ctx, filters, &mut filter_degraded)?;
+        }
         let mut searcher = Searcher::new();
         let hnsw = ctx.index.vector_hnsw(ctx.txn)?.unwrap_or_default();
-        let ef = hnsw.len().min(100);
-        let mut dest = vec![Neighbor { index: 0, distance: 0 }; ef];
         let vector = normalize_vector(vector.clone());
-        let neighbors = hnsw.nearest(&vector, ef, &mut searcher, &mut dest[..]);
 
+        let mut ef = hnsw.len().min(100);
         let mut docids = Vec::new();
-        let mut uniq_docids = RoaringBitmap::new();
-        for Neighbor { index, distance: _ } in neighbors.iter() {
-            let index = BEU32::new(*index as u32);
-            let docid = ctx.index.vector_id_docid.get(ctx.txn, &index)?.unwrap().get();
-            if universe.contains(docid) && uniq_docids.insert(docid) {
-                docids.push(docid);
-                if docids.len() == (from + length) {
-                    break;
+        loop {
+            let mut dest = vec![Neighbor { index: 0, distance: 0 }; ef];
+            let neighbors = hnsw.nearest(&vector, ef, &mut searcher, &mut dest[..]);
+
+            docids.clear();
+            let mut uniq_docids = RoaringBitmap::new();
+            let mut dead_points = 0;
+            for Neighbor { index, distance: _ } in neighbors.iter() {
+                let index = BEU32::new(*index as u32);
+                // the points of the deleted documents stay in the HNSW until it is compacted
+                let docid = match ctx.index.vector_id_docid.get(ctx.txn, &index)? {
+                    Some(docid) => docid.get(),
+                    None => {
+                        dead_points += 1;
+                        continue;
+                    }
+                };
+                if universe.contains(docid) && uniq_docids.insert(docid) {
+                    docids.push(docid);
+                    if docids.len() == (from + length) {
+                        break;
+                    }
                 }
             }
+
+            // The dead points took the place of live neighbours, the search is retried
+            // with a larger ef until enough of them are found or the whole HNSW is visited.
+            if docids.len() >= from + length || dead_points == 0 || ef == hnsw.len() {
+                break;
+            }
+            ef = (ef * 2).min(hnsw.len());
         }
 
         // return the nearest documents that are also part of the candidates
@@ -466,6 +569,7 @@ pub fn execute_search(
             document_scores: vec![Vec::new(); docids.len()],
             documents_ids: docids,
             located_query_terms: None,
+            degraded: filter_degraded,
         });
     }
 
@@ -478,16 +582,33 @@ pub fn execute_search(
         if let Some(ref stop_words) = stop_words {
             tokbuilder.stop_words(stop_words);
         }
+        let settings = ctx.index.cached_settings(ctx.txn)?;
+        let dictionary = settings.words_dict();
+        if let Some(ref dictionary) = dictionary {
+            tokbuilder.words_dict(dictionary);
+        }
 
+        let locales_allow_list = ctx.locales.as_deref().map(allow_list);
         let script_lang_map = ctx.index.script_language(ctx.txn)?;
-        if !script_lang_map.is_empty() {
+        if let Some(ref locales_allow_list) = locales_allow_list {
+            tokbuilder.allow_list(locales_allow_list);
+        } else if !script_lang_map.is_empty() {
             tokbuilder.allow_list(&script_lang_map);
         }
 
         let tokenizer = tokbuilder.build();
         let tokens = tokenizer.tokenize(query);
 
-        let query_terms = located_query_terms_from_tokens(ctx, tokens, words_limit)?;
+        let ExtractedTokens { query_terms, negative_words, negative_phrases } =
+            located_query_terms_from_tokens(ctx, tokens, words_limit)?;
+        if let Some(thread_pool) = ctx.derivations_thread_pool {
+            prefetch_typo_derivations(ctx, &query_terms, thread_pool)?;
+        }
+
+        // The documents containing a negative word or phrase are removed before ranking.
+        universe -= resolve_negative_words(ctx, &negative_words)?;
+        universe -= resolve_negative_phrases(ctx, &negative_phrases)?;
+
         if query_terms.is_empty() {
             // Do a placeholder search instead
             None
@@ -497,6 +618,30 @@ pub fn execute_search(
     } else {
         None
     };
+
+    let order = match (filters, &query_terms) {
+        (Some(filters), Some(query_terms)) => {
+            evaluation_order(ctx, filters, query_terms, terms_matching_strategy)?
+        }
+        _ => EvaluationOrder::FilterFirst,
+    };
+    if let (EvaluationOrder::FilterFirst, Some(filters)) = (order, filters) {
+        universe &= evaluate_filter(ctx, filters, &mut filter_degraded)?;
+    }
+
+    // The boost is not applied when the documents are explicitly sorted, the best ranked
+    // documents are sorted with their detailed scores and the page is taken after the boost.
+    let boost_fid = match ctx.index.boost_field(ctx.txn)? {
+        Some(field) if sort_criteria.as_ref().map_or(true, Vec::is_empty) => {
+            ctx.index.fields_ids_map(ctx.txn)?.id(field)
+        }
+        _ => None,
+    };
+    let (bucket_from, bucket_length, bucket_scoring_strategy) = match boost_fid {
+        Some(_) => (0, (from + length).max(limits::BOOST_WINDOW_SIZE), ScoringStrategy::Detailed),
+        None => (from, length, scoring_strategy),
+    };
+
     let bucket_sort_output = if let Some(query_terms) = query_terms {
         let (graph, new_located_query_terms) = QueryGraph::from_query(ctx, &query_terms)?;
         located_query_terms = Some(new_located_query_terms);
@@ -510,15 +655,24 @@ pub fn execute_search(
 
         universe =
             resolve_universe(ctx, &universe, &graph, terms_matching_strategy, query_graph_logger)?;
+        if let (EvaluationOrder::WordsFirst, Some(filters)) = (order, filters) {
+            // The filter is not evaluated when no document matches the query words,
+            // it is still validated the same way.
+            if universe.is_empty() {
+                filters.check(ctx.txn, ctx.index)?;
+            } else {
+                universe &= evaluate_filter(ctx, filters, &mut filter_degraded)?;
+            }
+        }
 
         bucket_sort(
             ctx,
             ranking_rules,
             &graph,
             &universe,
-            from,
-            length,
-            scoring_strategy,
+            bucket_from,
+            bucket_length,
+            bucket_scoring_strategy,
             query_graph_logger,
         )?
     } else {
@@ -529,14 +683,19 @@ pub fn execute_search(
             ranking_rules,
             &PlaceholderQuery,
             &universe,
-            from,
-            length,
-            scoring_strategy,
+            bucket_from,
+            bucket_length,
+            bucket_scoring_strategy,
             placeholder_search_logger,
         )?
     };
 
-    let BucketSortOutput { docids, scores, mut all_candidates } = bucket_sort_output;
+    let bucket_sort_output = match boost_fid {
+        Some(boost_fid) => apply_boost(ctx, boost_fid, bucket_sort_output, from, length)?,
+        None => bucket_sort_output,
+    };
+
+    let BucketSortOutput { docids, scores, mut all_candidates, degraded } = bucket_sort_output;
     let fields_ids_map = ctx.index.fields_ids_map(ctx.txn)?;
 
     // The candidates is the universe unless the exhaustive number of hits
@@ -554,9 +713,29 @@ pub fn execute_search(
         document_scores: scores,
         documents_ids: docids,
         located_query_terms,
+        degraded: degraded || filter_degraded,
     })
 }
 
+/// Evaluates the filter unless the deadline of the search is reached first,
+/// in which case no document is selected and the search is degraded.
+///
+/// The evaluation validates the filter, it is only checked when it is interrupted.
+fn evaluate_filter(
+    ctx: &SearchContext,
+    filters: &Filter,
+    degraded: &mut bool,
+) -> Result<RoaringBitmap> {
+    match filters.evaluate_before(ctx.txn, ctx.index, ctx.deadline)? {
+        Some(docids) => Ok(docids),
+        None => {
+            filters.check(ctx.txn, ctx.index)?;
+            *degraded = true;
+            Ok(RoaringBitmap::new())
+        }
+    }
+}
+
 fn check_sort_criteria(ctx: &SearchContext, sort_criteria: Option<&Vec<AscDesc>>) -> Result<()> {
     let sort_criteria = if let Some(sort_criteria) = sort_criteria {
         sort_criteria
@@ -570,7 +749,8 @@ fn check_sort_criteria(ctx: &SearchContext, sort_criteria: Option<&Vec<AscDesc>>
 
     // We check that the sort ranking rule exists and throw an
     // error if we try to use it and that it doesn't.
-    let sort_ranking_rule_missing = !ctx.index.criteria(ctx.txn)?.contains(&crate::Criterion::Sort);
+    let sort_ranking_rule_missing =
+        !ctx.index.cached_settings(ctx.txn)?.criteria.contains(&crate::Criterion::Sort);
     if sort_ranking_rule_missing {
         return Err(UserError::SortRankingRuleMissing.into());
     }
@@ -604,4 +784,5 @@ pub struct PartialSearchResult {
     pub candidates: RoaringBitmap,
     pub documents_ids: Vec<DocumentId>,
     pub document_scores: Vec<Vec<ScoreDetails>>,
+    pub degraded: bool,
 }
@@ -0,0 +1,142 @@
+use roaring::{MultiOps, RoaringBitmap};
+
+use super::interner::Interned;
+use super::query_graph::QueryGraph;
+use super::ranking_rules::{RankingRule, RankingRuleOutput};
+use crate::proximity::MAX_DISTANCE;
+use crate::score_details::{self, ScoreDetails};
+use crate::search::new::query_graph::QueryNodeData;
+use crate::search::new::query_term::ExactTerm;
+use crate::{Result, SearchContext, SearchLogger};
+
+/// A ranking rule that sorts the documents by increasing number of reversed pairs
+/// of consecutive query words.
+///
+/// The pair `a b` of the query is reversed in a document when `b` appears closer
+/// before `a` than `a` appears before `b`. The directional cost is the one used
+/// by the proximity ranking rule: a pair at distance `d` costs `d` in the order
+/// of the query, and `d + 1` in the reversed order.
+pub struct Order {
+    state: Option<State>,
+}
+
+struct State {
+    query_graph: QueryGraph,
+    /// The documents with at least `i` reversed pairs, `at_least_reversed[0]` is the universe.
+    at_least_reversed: Vec<RoaringBitmap>,
+    /// The number of reversed pairs of the next bucket.
+    reversed: usize,
+}
+
+impl Order {
+    pub fn new() -> Self {
+        Self { state: None }
+    }
+}
+
+impl<'ctx> RankingRule<'ctx, QueryGraph> for Order {
+    fn id(&self) -> String {
+        "order".to_owned()
+    }
+
+    fn start_iteration(
+        &mut self,
+        ctx: &mut SearchContext<'ctx>,
+        _logger: &mut dyn SearchLogger<QueryGraph>,
+        universe: &RoaringBitmap,
+        query: &QueryGraph,
+    ) -> Result<()> {
+        let words = query_words(ctx, query);
+
+        let mut at_least_reversed = vec![universe.clone()];
+        for pair in words.windows(2) {
+            let reversed = reversed_pair_docids(ctx, pair[0], pair[1], universe)?;
+            // one more reversed pair for the documents already counted, from the highest count down
+            at_least_reversed.push(RoaringBitmap::new());
+            for count in (1..at_least_reversed.len()).rev() {
+                let docids = &at_least_reversed[count - 1] & &reversed;
+                at_least_reversed[count] |= docids;
+            }
+        }
+
+        self.state = Some(State { query_graph: query.clone(), at_least_reversed, reversed: 0 });
+        Ok(())
+    }
+
+    fn next_bucket(
+        &mut self,
+        _ctx: &mut SearchContext<'ctx>,
+        _logger: &mut dyn SearchLogger<QueryGraph>,
+        universe: &RoaringBitmap,
+    ) -> Result<Option<RankingRuleOutput<QueryGraph>>> {
+        let Some(state) = &mut self.state else { return Ok(None) };
+        let max_reversed = state.at_least_reversed.len() - 1;
+        if state.reversed > max_reversed {
+            return Ok(None);
+        }
+
+        let mut candidates = universe & &state.at_least_reversed[state.reversed];
+        if let Some(more_reversed) = state.at_least_reversed.get(state.reversed + 1) {
+            candidates -= more_reversed;
+        }
+
+        let score = ScoreDetails::Order(score_details::Rank {
+            rank: (max_reversed - state.reversed) as u32 + 1,
+            max_rank: max_reversed as u32 + 1,
+        });
+        state.reversed += 1;
+
+        Ok(Some(RankingRuleOutput { query: state.query_graph.clone(), candidates, score }))
+    }
+
+    fn end_iteration(
+        &mut self,
+        _ctx: &mut SearchContext<'ctx>,
+        _logger: &mut dyn SearchLogger<QueryGraph>,
+    ) {
+        self.state = None;
+    }
+}
+
+/// Returns the single words of the query graph, sorted by their position in the query.
+fn query_words(ctx: &SearchContext, query_graph: &QueryGraph) -> Vec<Interned<String>> {
+    let mut words = Vec::new();
+    for (_, node) in query_graph.nodes.iter() {
+        if let QueryNodeData::Term(term) = &node.data {
+            if let Some(ExactTerm::Word(word)) = term.term_subset.exact_term(ctx) {
+                words.push((*term.term_ids.start(), word));
+            }
+        }
+    }
+    words.sort_unstable_by_key(|(term_id, _)| *term_id);
+    words.dedup_by_key(|(term_id, _)| *term_id);
+    words.into_iter().map(|(_, word)| word).collect()
+}
+
+/// Returns the documents of the universe in which the pair `left right` is reversed.
+fn reversed_pair_docids(
+    ctx: &mut SearchContext,
+    left: Interned<String>,
+    right: Interned<String>,
+    universe: &RoaringBitmap,
+) -> Result<RoaringBitmap> {
+    let mut in_order = Vec::with_capacity(MAX_DISTANCE as usize);
+    for proximity in 1..MAX_DISTANCE as u8 {
+        in_order.push(
+            ctx.get_db_word_pair_proximity_docids(left, right, proximity)?.unwrap_or_default()
+                & universe,
+        );
+    }
+
+    let mut reversed = RoaringBitmap::new();
+    for proximity in 1..MAX_DISTANCE as u8 {
+        // `right left` at distance `proximity` costs `proximity + 1`, so it only wins
+        // over the documents where `left right` is even further away
+        let docids =
+            ctx.get_db_word_pair_proximity_docids(right, left, proximity)?.unwrap_or_default()
+                & universe;
+        let closer_in_order = MultiOps::union(in_order.iter().take(proximity as usize + 1));
+        reversed |= docids - closer_in_order;
+    }
+    Ok(reversed)
+}
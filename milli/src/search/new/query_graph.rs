@@ -1,4 +1,4 @@
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 
@@ -319,6 +319,47 @@ impl QueryGraph {
             let rank = 1 + last_term_idx - term_idx;
             rank as u16
         };
+        self.removal_order_by_term_cost(ctx, cost_of_term_idx)
+    }
+
+    /// Returns the nodes to remove, in order, for the `Frequency` terms matching strategy.
+    ///
+    /// The terms whose original word is contained in the fewest documents are removed first,
+    /// the last terms of the query first when they are contained in as many documents.
+    pub fn removal_order_for_terms_matching_strategy_frequency(
+        &self,
+        ctx: &SearchContext,
+    ) -> Result<Vec<SmallBitmap<QueryNode>>> {
+        let mut term_frequencies = vec![];
+        for (_, node) in self.nodes.iter() {
+            let QueryNodeData::Term(t) = &node.data else { continue };
+            // The n-grams are removed with the terms they are made of.
+            if t.term_ids.start() != t.term_ids.end()
+                || t.term_subset.original_phrase(ctx).is_some()
+            {
+                continue;
+            }
+            let word = t.term_subset.original_word(ctx);
+            let frequency = ctx.index.word_documents_count(ctx.txn, &word)?.unwrap_or_default();
+            term_frequencies.push((*t.term_ids.start(), frequency));
+        }
+        term_frequencies.sort_by_key(|&(term_idx, frequency)| (frequency, Reverse(term_idx)));
+
+        let cost_of_term_idx = |term_idx: u8| {
+            // The terms that are only part of phrases are never removed.
+            let rank = term_frequencies.iter().position(|&(idx, _)| idx == term_idx);
+            rank.map_or(u16::MAX, |rank| rank as u16 + 1)
+        };
+        Ok(self.removal_order_by_term_cost(ctx, cost_of_term_idx))
+    }
+
+    /// Groups the removable term nodes by the cost of their terms, the nodes with the lowest
+    /// cost must be removed first, a node is removed with the most costly of its terms.
+    fn removal_order_by_term_cost(
+        &self,
+        ctx: &SearchContext,
+        cost_of_term_idx: impl Fn(u8) -> u16,
+    ) -> Vec<SmallBitmap<QueryNode>> {
         let mut nodes_to_remove = BTreeMap::<u16, SmallBitmap<QueryNode>>::new();
         let mut at_least_one_mandatory_term = false;
         for (node_id, node) in self.nodes.iter() {
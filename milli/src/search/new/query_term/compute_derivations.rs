@@ -1,10 +1,13 @@
 use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::collections::BTreeSet;
 use std::ops::ControlFlow;
 
 use fst::automaton::Str;
 use fst::{Automaton, IntoStreamer, Streamer};
 use heed::types::DecodeIgnore;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::ThreadPool;
 
 use super::*;
 use crate::search::fst_utils::{Complement, Intersection, StartsWith, Union};
@@ -70,26 +73,21 @@ fn find_zero_typo_prefix_derivations(
 }
 
 fn find_zero_one_typo_derivations(
-    ctx: &mut SearchContext,
-    word_interned: Interned<String>,
+    fst: &fst::Set<Cow<[u8]>>,
+    word: &str,
     is_prefix: bool,
-    mut visit: impl FnMut(Interned<String>, ZeroOrOneTypo) -> Result<ControlFlow<()>>,
+    mut visit: impl FnMut(&str, ZeroOrOneTypo) -> Result<ControlFlow<()>>,
 ) -> Result<()> {
-    let fst = ctx.get_words_fst()?;
-    let word = ctx.word_interner.get(word_interned).to_owned();
-    let word = word.as_str();
-
     let dfa = build_dfa(word, 1, is_prefix);
     let starts = StartsWith(Str::new(get_first(word)));
     let mut stream = fst.search_with_state(Intersection(starts, &dfa)).into_stream();
 
     while let Some((derived_word, state)) = stream.next() {
         let derived_word = std::str::from_utf8(derived_word)?;
-        let derived_word = ctx.word_interner.insert(derived_word.to_owned());
         let d = dfa.distance(state.1);
         match d.to_u8() {
             0 => {
-                if derived_word != word_interned {
+                if derived_word != word {
                     let cf = visit(derived_word, ZeroOrOneTypo::Zero)?;
                     if cf.is_break() {
                         break;
@@ -111,15 +109,11 @@ fn find_zero_one_typo_derivations(
 }
 
 fn find_zero_one_two_typo_derivations(
-    word_interned: Interned<String>,
+    fst: &fst::Set<Cow<[u8]>>,
+    word: &str,
     is_prefix: bool,
-    fst: fst::Set<Cow<[u8]>>,
-    word_interner: &mut DedupInterner<String>,
-    mut visit: impl FnMut(Interned<String>, NumberOfTypos) -> Result<ControlFlow<()>>,
+    mut visit: impl FnMut(&str, NumberOfTypos) -> Result<ControlFlow<()>>,
 ) -> Result<()> {
-    let word = word_interner.get(word_interned).to_owned();
-    let word = word.as_str();
-
     let starts = StartsWith(Str::new(get_first(word)));
     let first = Intersection(build_dfa(word, 1, is_prefix), Complement(&starts));
     let second_dfa = build_dfa(word, 2, is_prefix);
@@ -130,11 +124,10 @@ fn find_zero_one_two_typo_derivations(
 
     while let Some((derived_word, state)) = stream.next() {
         let derived_word = std::str::from_utf8(derived_word)?;
-        let derived_word_interned = word_interner.insert(derived_word.to_owned());
         // in the case the typo is on the first letter, we know the number of typo
         // is two
         if get_first(derived_word) != get_first(word) {
-            let cf = visit(derived_word_interned, NumberOfTypos::Two)?;
+            let cf = visit(derived_word, NumberOfTypos::Two)?;
             if cf.is_break() {
                 break;
             }
@@ -144,21 +137,21 @@ fn find_zero_one_two_typo_derivations(
             let d = second_dfa.distance((state.1).0);
             match d.to_u8() {
                 0 => {
-                    if derived_word_interned != word_interned {
-                        let cf = visit(derived_word_interned, NumberOfTypos::Zero)?;
+                    if derived_word != word {
+                        let cf = visit(derived_word, NumberOfTypos::Zero)?;
                         if cf.is_break() {
                             break;
                         }
                     }
                 }
                 1 => {
-                    let cf = visit(derived_word_interned, NumberOfTypos::One)?;
+                    let cf = visit(derived_word, NumberOfTypos::One)?;
                     if cf.is_break() {
                         break;
                     }
                 }
                 2 => {
-                    let cf = visit(derived_word_interned, NumberOfTypos::Two)?;
+                    let cf = visit(derived_word, NumberOfTypos::Two)?;
                     if cf.is_break() {
                         break;
                     }
@@ -170,6 +163,104 @@ fn find_zero_one_two_typo_derivations(
     Ok(())
 }
 
+/// The one and two typos derivations of a word, in the order of the words FST.
+#[derive(Debug, Default)]
+pub struct TypoDerivations {
+    one_typo: Vec<String>,
+    two_typos: Vec<String>,
+}
+
+/// Finds the derivations of a word with at most `max_typos` typos, keeping at most
+/// [`limits::MAX_ONE_TYPO_COUNT`] one typo and [`limits::MAX_TWO_TYPOS_COUNT`] two typos ones.
+///
+/// It only reads the words FST and can run on any thread.
+fn find_typo_derivations(
+    fst: &fst::Set<Cow<[u8]>>,
+    word: &str,
+    is_prefix: bool,
+    max_typos: u8,
+) -> Result<TypoDerivations> {
+    let mut derivations = TypoDerivations::default();
+    let TypoDerivations { one_typo, two_typos } = &mut derivations;
+
+    if max_typos == 1 {
+        find_zero_one_typo_derivations(fst, word, is_prefix, |derived_word, nbr_typos| {
+            match nbr_typos {
+                ZeroOrOneTypo::Zero => {}
+                ZeroOrOneTypo::One => {
+                    if one_typo.len() < limits::MAX_ONE_TYPO_COUNT {
+                        one_typo.push(derived_word.to_owned());
+                    } else {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+    } else if max_typos > 1 {
+        find_zero_one_two_typo_derivations(fst, word, is_prefix, |derived_word, nbr_typos| {
+            if one_typo.len() >= limits::MAX_ONE_TYPO_COUNT
+                && two_typos.len() >= limits::MAX_TWO_TYPOS_COUNT
+            {
+                // No chance we will add either one- or two-typo derivations anymore, stop iterating.
+                return Ok(ControlFlow::Break(()));
+            }
+            match nbr_typos {
+                NumberOfTypos::Zero => {}
+                NumberOfTypos::One => {
+                    if one_typo.len() < limits::MAX_ONE_TYPO_COUNT {
+                        one_typo.push(derived_word.to_owned());
+                    }
+                }
+                NumberOfTypos::Two => {
+                    if two_typos.len() < limits::MAX_TWO_TYPOS_COUNT {
+                        two_typos.push(derived_word.to_owned());
+                    }
+                }
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+    }
+
+    Ok(derivations)
+}
+
+/// Finds the typo derivations of the given terms in parallel on the thread pool, they are
+/// kept in the context until the terms are fully computed.
+///
+/// The words FST is walked for each term while the interners can only be used by the search
+/// thread, the derived words are interned once all of them have been found.
+pub fn prefetch_typo_derivations(
+    ctx: &mut SearchContext,
+    terms: &[LocatedQueryTerm],
+    thread_pool: &ThreadPool,
+) -> Result<()> {
+    let fst = ctx.get_words_fst()?;
+    let mut words = Vec::new();
+    for &LocatedQueryTerm { value: term, .. } in terms {
+        let QueryTerm { original, is_prefix, max_levenshtein_distance, one_typo, two_typo, .. } =
+            ctx.term_interner.get(term);
+        let uninit = one_typo.is_uninit() || two_typo.is_uninit();
+        if *max_levenshtein_distance > 0 && uninit {
+            let word = ctx.word_interner.get(*original).to_owned();
+            words.push((term, word, *is_prefix, *max_levenshtein_distance));
+        }
+    }
+
+    let derivations = thread_pool.install(|| {
+        words
+            .into_par_iter()
+            .map(|(term, word, is_prefix, max_typos)| {
+                find_typo_derivations(&fst, &word, is_prefix, max_typos)
+                    .map(|derivations| (term, derivations))
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    ctx.prefetched_typo_derivations.extend(derivations);
+
+    Ok(())
+}
+
 pub fn partially_initialized_term_from_word(
     ctx: &mut SearchContext,
     word: &str,
@@ -193,7 +284,7 @@ pub fn partially_initialized_term_from_word(
         });
     }
 
-    let fst = ctx.index.words_fst(ctx.txn)?;
+    let fst = ctx.get_words_fst()?;
 
     let use_prefix_db = is_prefix
         && (ctx
@@ -233,9 +324,10 @@ pub fn partially_initialized_term_from_word(
             },
         )?;
     }
-    let synonyms = ctx.index.synonyms(ctx.txn)?;
+    let settings = ctx.index.cached_settings(ctx.txn)?;
     let mut synonym_word_count = 0;
-    let synonyms = synonyms
+    let synonyms = settings
+        .synonyms
         .get(&vec![word.to_owned()])
         .cloned()
         .unwrap_or_default()
@@ -247,7 +339,7 @@ pub fn partially_initialized_term_from_word(
             }
             synonym_word_count += words.len();
             let words = words.into_iter().map(|w| Some(ctx.word_interner.insert(w))).collect();
-            Some(ctx.phrase_interner.insert(Phrase { words }))
+            Some(ctx.phrase_interner.insert(Phrase { words, slop: 0 }))
         })
         .collect();
     let zero_typo =
@@ -264,12 +356,17 @@ pub fn partially_initialized_term_from_word(
     })
 }
 
+/// Split the original word into two consecutive words or, when it can't be split in two,
+/// into three consecutive words.
 fn find_split_words(ctx: &mut SearchContext, word: &str) -> Result<Option<Interned<Phrase>>> {
-    if let Some((l, r)) = split_best_frequency(ctx, word)? {
-        Ok(Some(ctx.phrase_interner.insert(Phrase { words: vec![Some(l), Some(r)] })))
+    let words = if let Some((l, r)) = split_best_frequency(ctx, word)? {
+        vec![Some(l), Some(r)]
+    } else if let Some((l, m, r)) = split_in_three_best_frequency(ctx, word)? {
+        vec![Some(l), Some(m), Some(r)]
     } else {
-        Ok(None)
-    }
+        return Ok(None);
+    };
+    Ok(Some(ctx.phrase_interner.insert(Phrase { words, slop: 0 })))
 }
 
 impl Interned<QueryTerm> {
@@ -287,28 +384,26 @@ impl Interned<QueryTerm> {
 
         let original = *original;
         let is_prefix = *is_prefix;
-        // let original_str = ctx.word_interner.get(*original).to_owned();
+        let max_nbr_typos = *max_nbr_typos;
         if one_typo.is_init() {
             return Ok(());
         }
-        let mut one_typo_words = BTreeSet::new();
 
-        if *max_nbr_typos > 0 {
-            find_zero_one_typo_derivations(ctx, original, is_prefix, |derived_word, nbr_typos| {
-                match nbr_typos {
-                    ZeroOrOneTypo::Zero => {}
-                    ZeroOrOneTypo::One => {
-                        if one_typo_words.len() < limits::MAX_ONE_TYPO_COUNT {
-                            one_typo_words.insert(derived_word);
-                        } else {
-                            return Ok(ControlFlow::Break(()));
-                        }
-                    }
-                }
-                Ok(ControlFlow::Continue(()))
-            })?;
+        let derivations = match ctx.prefetched_typo_derivations.remove(&self) {
+            Some(derivations) => derivations,
+            None => {
+                let fst = ctx.get_words_fst()?;
+                let original_str = ctx.word_interner.get(original);
+                find_typo_derivations(&fst, original_str, is_prefix, max_nbr_typos)?
+            }
+        };
+        let mut one_typo_words = BTreeSet::new();
+        for word in derivations.one_typo {
+            one_typo_words.insert(ctx.word_interner.insert(word));
         }
 
+        keep_most_frequent_typo_derivations(ctx, &mut one_typo_words, &mut BTreeSet::new())?;
+
         let split_words = if allows_split_words {
             let original_str = ctx.word_interner.get(original).to_owned();
             find_split_words(ctx, original_str.as_str())?
@@ -324,7 +419,7 @@ impl Interned<QueryTerm> {
         let split_words = if let Some((ngram_words, split_words)) =
             self_mut.ngram_words.as_ref().zip(split_words.as_ref())
         {
-            let Phrase { words } = ctx.phrase_interner.get(*split_words);
+            let Phrase { words, .. } = ctx.phrase_interner.get(*split_words);
             if ngram_words.iter().ne(words.iter().flatten()) {
                 Some(*split_words)
             } else {
@@ -349,43 +444,30 @@ impl Interned<QueryTerm> {
             ..
         } = self_mut;
         let original_str = ctx.word_interner.get(*original).to_owned();
+        let is_prefix = *is_prefix;
+        let max_nbr_typos = *max_nbr_typos;
         if two_typo.is_init() {
             return Ok(());
         }
+
+        let derivations = match ctx.prefetched_typo_derivations.remove(&self) {
+            Some(derivations) => derivations,
+            None => {
+                let fst = ctx.get_words_fst()?;
+                find_typo_derivations(&fst, &original_str, is_prefix, max_nbr_typos)?
+            }
+        };
         let mut one_typo_words = BTreeSet::new();
+        for word in derivations.one_typo {
+            one_typo_words.insert(ctx.word_interner.insert(word));
+        }
         let mut two_typo_words = BTreeSet::new();
-
-        if *max_nbr_typos > 0 {
-            find_zero_one_two_typo_derivations(
-                *original,
-                *is_prefix,
-                ctx.index.words_fst(ctx.txn)?,
-                &mut ctx.word_interner,
-                |derived_word, nbr_typos| {
-                    if one_typo_words.len() >= limits::MAX_ONE_TYPO_COUNT
-                        && two_typo_words.len() >= limits::MAX_TWO_TYPOS_COUNT
-                    {
-                        // No chance we will add either one- or two-typo derivations anymore, stop iterating.
-                        return Ok(ControlFlow::Break(()));
-                    }
-                    match nbr_typos {
-                        NumberOfTypos::Zero => {}
-                        NumberOfTypos::One => {
-                            if one_typo_words.len() < limits::MAX_ONE_TYPO_COUNT {
-                                one_typo_words.insert(derived_word);
-                            }
-                        }
-                        NumberOfTypos::Two => {
-                            if two_typo_words.len() < limits::MAX_TWO_TYPOS_COUNT {
-                                two_typo_words.insert(derived_word);
-                            }
-                        }
-                    }
-                    Ok(ControlFlow::Continue(()))
-                },
-            )?;
+        for word in derivations.two_typos {
+            two_typo_words.insert(ctx.word_interner.insert(word));
         }
 
+        keep_most_frequent_typo_derivations(ctx, &mut one_typo_words, &mut two_typo_words)?;
+
         let split_words = find_split_words(ctx, original_str.as_str())?;
         let self_mut = ctx.term_interner.get_mut(self);
 
@@ -400,6 +482,46 @@ impl Interned<QueryTerm> {
     }
 }
 
+/// Only keeps the typo derivations that appear in the most documents when there are
+/// more than [`SearchContext::typo_derivations_limit`] of them, if it is set.
+///
+/// A short word searched in a huge dictionary can have a lot of derivations
+/// which would all have to be fetched and unioned by the ranking rules.
+fn keep_most_frequent_typo_derivations(
+    ctx: &SearchContext,
+    one_typo_words: &mut BTreeSet<Interned<String>>,
+    two_typo_words: &mut BTreeSet<Interned<String>>,
+) -> Result<()> {
+    let Some(limit) = ctx.typo_derivations_limit else { return Ok(()) };
+    if one_typo_words.len() + two_typo_words.len() <= limit {
+        return Ok(());
+    }
+
+    let mut derivations = Vec::with_capacity(one_typo_words.len() + two_typo_words.len());
+    for (words, nbr_typos) in [(&*one_typo_words, 1), (&*two_typo_words, 2)] {
+        for &word in words {
+            let word_str = ctx.word_interner.get(word);
+            let frequency = ctx.index.word_documents_count(ctx.txn, word_str)?.unwrap_or(0);
+            // on equal frequencies, the derivations with the fewest typos are kept
+            derivations.push((Reverse(frequency), nbr_typos, word));
+        }
+    }
+    derivations.sort_unstable();
+    derivations.truncate(limit);
+
+    one_typo_words.clear();
+    two_typo_words.clear();
+    for (_, nbr_typos, word) in derivations {
+        if nbr_typos == 1 {
+            one_typo_words.insert(word);
+        } else {
+            two_typo_words.insert(word);
+        }
+    }
+
+    Ok(())
+}
+
 /// Split the original word into the two words that appear the
 /// most next to each other in the index.
 ///
@@ -425,3 +547,58 @@ fn split_best_frequency(
 
     Ok(best.map(|(_, left, right)| (left, right)))
 }
+
+/// Split the original word into the three words that appear the most next to each other
+/// in the index, the frequency of a split is the lowest frequency of its two pairs of words.
+/// Only the first [`limits::MAX_THREE_WAY_SPLIT_CANDIDATES`] splits made of words of the index
+/// are considered.
+///
+/// Return `None` if the original word cannot be split or is longer than
+/// [`limits::MAX_THREE_WAY_SPLIT_WORD_LENGTH`].
+fn split_in_three_best_frequency(
+    ctx: &mut SearchContext,
+    original: &str,
+) -> Result<Option<(Interned<String>, Interned<String>, Interned<String>)>> {
+    if original.len() > limits::MAX_THREE_WAY_SPLIT_WORD_LENGTH {
+        return Ok(None);
+    }
+
+    // only the splits made of three words of the index are looked up in the database
+    let fst = ctx.get_words_fst()?;
+    let boundaries: Vec<_> = original.char_indices().skip(1).map(|(i, _)| i).collect();
+    let mut candidates = Vec::new();
+    'outer: for (n, &i) in boundaries.iter().enumerate() {
+        if !fst.contains(&original[..i]) {
+            continue;
+        }
+        for &j in &boundaries[n + 1..] {
+            if fst.contains(&original[i..j]) && fst.contains(&original[j..]) {
+                candidates.push((i, j));
+                if candidates.len() == limits::MAX_THREE_WAY_SPLIT_CANDIDATES {
+                    break 'outer;
+                }
+            }
+        }
+    }
+
+    let mut best = None;
+    for (i, j) in candidates {
+        let left = ctx.word_interner.insert(original[..i].to_owned());
+        let middle = ctx.word_interner.insert(original[i..j].to_owned());
+        let Some(left_frequency) = ctx.get_db_word_pair_proximity_docids_len(left, middle, 1)?
+        else {
+            continue;
+        };
+        let right = ctx.word_interner.insert(original[j..].to_owned());
+        if let Some(right_frequency) =
+            ctx.get_db_word_pair_proximity_docids_len(middle, right, 1)?
+        {
+            let frequency = left_frequency.min(right_frequency);
+            if best.map_or(true, |(old, _, _, _)| frequency > old) {
+                best = Some((frequency, left, middle, right));
+            }
+        }
+    }
+
+    Ok(best.map(|(_, left, middle, right)| (left, middle, right)))
+}
@@ -3,17 +3,21 @@ mod ntypo_subset;
 mod parse_query;
 mod phrase;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::iter::FromIterator;
 use std::ops::RangeInclusive;
 
 use compute_derivations::partially_initialized_term_from_word;
+pub use compute_derivations::{prefetch_typo_derivations, TypoDerivations};
 use either::Either;
 pub use ntypo_subset::NTypoTermSubset;
-pub use parse_query::{located_query_terms_from_tokens, make_ngram, number_of_typos_allowed};
+pub use parse_query::{
+    located_query_terms_from_tokens, make_ngram, number_of_typos_allowed, ExtractedTokens,
+};
 pub use phrase::Phrase;
 
 use super::interner::{DedupInterner, Interned};
+use super::matches::matching_words::MatchingWordKind;
 use super::{limits, SearchContext, Word};
 use crate::Result;
 
@@ -327,6 +331,10 @@ impl QueryTermSubset {
         Ok(result)
     }
 
+    pub fn original_word(&self, ctx: &SearchContext) -> String {
+        ctx.term_interner.get(self.original).original_word(ctx)
+    }
+
     pub fn original_phrase(&self, ctx: &SearchContext) -> Option<Interned<Phrase>> {
         let t = ctx.term_interner.get(self.original);
         if let Some(p) = t.zero_typo.phrase {
@@ -480,25 +488,54 @@ impl QueryTerm {
         ctx.word_interner.get(self.original).clone()
     }
     pub fn all_computed_derivations(&self) -> (Vec<Interned<String>>, Vec<Interned<Phrase>>) {
-        let mut words = BTreeSet::new();
-        let mut phrases = BTreeSet::new();
+        let (words, phrases) = self.all_computed_derivations_with_kind();
+        (
+            words.into_iter().map(|(word, _)| word).collect(),
+            phrases.into_iter().map(|(phrase, _)| phrase).collect(),
+        )
+    }
+    /// Returns all the computed derivations along with the reason why they were derived.
+    ///
+    /// A word or phrase derived in several ways is only returned once, with the most precise kind.
+    pub fn all_computed_derivations_with_kind(
+        &self,
+    ) -> (Vec<(Interned<String>, MatchingWordKind)>, Vec<(Interned<Phrase>, MatchingWordKind)>)
+    {
+        let mut words = BTreeMap::new();
+        let mut phrases = BTreeMap::new();
 
+        // The derivations are inserted from the most to the least precise kind.
         let ZeroTypoTerm { phrase, exact: zero_typo, prefix_of, synonyms, use_prefix_db: _ } =
             &self.zero_typo;
-        words.extend(zero_typo.iter().copied());
-        words.extend(prefix_of.iter().copied());
-        phrases.extend(phrase.iter().copied());
-        phrases.extend(synonyms.iter().copied());
+        for &word in zero_typo {
+            words.entry(word).or_insert(MatchingWordKind::Original);
+        }
+        for &phrase in phrase {
+            phrases.entry(phrase).or_insert(MatchingWordKind::Original);
+        }
+        for &word in prefix_of {
+            words.entry(word).or_insert(MatchingWordKind::PrefixCompletion);
+        }
 
         if let Lazy::Init(OneTypoTerm { split_words, one_typo }) = &self.one_typo {
-            words.extend(one_typo.iter().copied());
-            phrases.extend(split_words.iter().copied());
+            for &word in one_typo {
+                words.entry(word).or_insert(MatchingWordKind::Typo);
+            }
+            for &phrase in split_words {
+                phrases.entry(phrase).or_insert(MatchingWordKind::Typo);
+            }
         };
 
         if let Lazy::Init(TwoTypoTerm { two_typos }) = &self.two_typo {
-            words.extend(two_typos.iter().copied());
+            for &word in two_typos {
+                words.entry(word).or_insert(MatchingWordKind::Typo);
+            }
         };
 
+        for &phrase in synonyms {
+            phrases.entry(phrase).or_insert(MatchingWordKind::Synonym);
+        }
+
         (words.into_iter().collect(), phrases.into_iter().collect())
     }
 }
@@ -4,41 +4,93 @@ use charabia::{SeparatorKind, TokenKind};
 use super::*;
 use crate::{Result, SearchContext, MAX_WORD_LENGTH};
 
+/// The terms extracted from the tokenized search query.
+pub struct ExtractedTokens {
+    /// The terms of the query that the documents must match.
+    pub query_terms: Vec<LocatedQueryTerm>,
+    /// The words prefixed by a `-`, the documents containing them are excluded.
+    pub negative_words: Vec<Word>,
+    /// The phrases prefixed by a `-`, the documents containing them are excluded.
+    pub negative_phrases: Vec<Interned<Phrase>>,
+}
+
+impl ExtractedTokens {
+    fn push_phrase(&mut self, ctx: &mut SearchContext, phrase: PhraseBuilder, negative: bool) {
+        if negative {
+            if !phrase.is_empty() {
+                let phrase =
+                    ctx.phrase_interner.insert(Phrase { words: phrase.words, slop: phrase.slop });
+                self.negative_phrases.push(phrase);
+            }
+        } else if let Some(located_query_term) = phrase.build(ctx) {
+            self.query_terms.push(located_query_term);
+        }
+    }
+}
+
 /// Convert the tokenised search query into a list of located query terms.
+///
+/// The words and phrases prefixed by a `-` are not part of the query terms,
+/// they are returned separately as negative words and phrases.
 pub fn located_query_terms_from_tokens(
     ctx: &mut SearchContext,
     query: NormalizedTokenIter,
     words_limit: Option<usize>,
-) -> Result<Vec<LocatedQueryTerm>> {
+) -> Result<ExtractedTokens> {
     let nbr_typos = number_of_typos_allowed(ctx)?;
 
-    let mut located_terms = Vec::new();
+    let mut extracted = ExtractedTokens {
+        query_terms: Vec::new(),
+        negative_words: Vec::new(),
+        negative_phrases: Vec::new(),
+    };
 
     let mut phrase: Option<PhraseBuilder> = None;
+    let mut negative_phrase = false;
+    let mut negative_next_word = false;
+    let mut start_of_query = true;
 
     let parts_limit = words_limit.unwrap_or(usize::MAX);
+    let last_word_is_prefix = ctx.autocomplete || ctx.all_words_as_prefix;
 
     // start with the last position as we will wrap around to position 0 at the beginning of the loop below.
     let mut position = u16::MAX;
 
     let mut peekable = query.take(super::limits::MAX_TOKEN_COUNT).peekable();
-    while let Some(token) = peekable.next() {
+    while let Some(mut token) = peekable.next() {
         if token.lemma().is_empty() {
             continue;
         }
+        // The stop words of the query are ignored even as the last word, unlike the ones of
+        // the index which can still be the prefix of a word.
+        let query_stop_word =
+            matches!(token.kind, TokenKind::Word) && ctx.stop_words.contains(token.lemma());
+        if query_stop_word {
+            token.kind = TokenKind::StopWord;
+        }
         // early return if word limit is exceeded
-        if located_terms.len() >= parts_limit {
-            return Ok(located_terms);
+        if extracted.query_terms.len() >= parts_limit {
+            return Ok(extracted);
         }
 
         match token.kind {
+            // A negative word is not part of the query terms and doesn't have any position.
+            TokenKind::Word | TokenKind::StopWord if negative_next_word => {
+                if let TokenKind::Word = token.kind {
+                    let word = ctx.word_interner.insert(token.lemma().to_string());
+                    extracted.negative_words.push(Word::Original(word));
+                }
+                negative_next_word = false;
+            }
             TokenKind::Word | TokenKind::StopWord => {
                 // On first loop, goes from u16::MAX to 0, then normal increment.
                 position = position.wrapping_add(1);
 
                 // 1. if the word is quoted we push it in a phrase-buffer waiting for the ending quote,
                 // 2. if the word is not the last token of the query and is not a stop_word we push it as a non-prefix word,
-                // 3. if the word is the last token of the query we push it as a prefix word.
+                //    unless all the words are prefixes,
+                // 3. if the word is the last token of the query we push it as a prefix word,
+                //    unless the autocompletion is disabled.
                 if let Some(phrase) = &mut phrase {
                     phrase.push_word(ctx, &token, position)
                 } else if peekable.peek().is_some() {
@@ -49,31 +101,37 @@ pub fn located_query_terms_from_tokens(
                                 ctx,
                                 word,
                                 nbr_typos(word),
-                                false,
+                                ctx.all_words_as_prefix,
                                 false,
                             )?;
                             let located_term = LocatedQueryTerm {
                                 value: ctx.term_interner.push(term),
                                 positions: position..=position,
                             };
-                            located_terms.push(located_term);
+                            extracted.query_terms.push(located_term);
                         }
                         TokenKind::StopWord | TokenKind::Separator(_) | TokenKind::Unknown => {}
                     }
+                } else if query_stop_word
+                    || (!last_word_is_prefix && matches!(token.kind, TokenKind::StopWord))
+                {
+                    // The stop words of the index are not indexed,
+                    // they can only match as the prefix of a word.
+                    // The stop words of the query are always ignored.
                 } else {
                     let word = token.lemma();
                     let term = partially_initialized_term_from_word(
                         ctx,
                         word,
                         nbr_typos(word),
-                        true,
+                        last_word_is_prefix,
                         false,
                     )?;
                     let located_term = LocatedQueryTerm {
                         value: ctx.term_interner.push(term),
                         positions: position..=position,
                     };
-                    located_terms.push(located_term);
+                    extracted.query_terms.push(located_term);
                 }
             }
             TokenKind::Separator(separator_kind) => {
@@ -88,9 +146,7 @@ pub fn located_query_terms_from_tokens(
                     // If we have a hard separator inside a phrase, we immediately start a new phrase
                     let phrase = if separator_kind == SeparatorKind::Hard {
                         if let Some(phrase) = phrase {
-                            if let Some(located_query_term) = phrase.build(ctx) {
-                                located_terms.push(located_query_term)
-                            }
+                            extracted.push_phrase(ctx, phrase, negative_phrase);
                             Some(PhraseBuilder::empty())
                         } else {
                             None
@@ -106,30 +162,61 @@ pub fn located_query_terms_from_tokens(
                     }
 
                     // Consume the closing quote and the phrase
-                    if let Some(phrase) = phrase {
+                    if let Some(mut phrase) = phrase {
                         // Per the check above, quote_count > 0
                         quote_count -= 1;
-                        if let Some(located_query_term) = phrase.build(ctx) {
-                            located_terms.push(located_query_term)
+                        // A closing quote directly followed by `~` and a number gives the slop
+                        // of the phrase, e.g. `"quick fox"~2`
+                        if quote_count == 0 && token.lemma().ends_with("\"~") {
+                            if let Some(slop) = peekable
+                                .next_if(|next| {
+                                    matches!(next.kind, TokenKind::Word)
+                                        && next.lemma().parse::<u8>().is_ok()
+                                })
+                                .and_then(|next| next.lemma().parse().ok())
+                            {
+                                phrase.slop = slop;
+                            }
                         }
+                        extracted.push_phrase(ctx, phrase, negative_phrase);
                     }
 
                     // Start new phrase if the token ends with an opening quote
-                    (quote_count % 2 == 1).then_some(PhraseBuilder::empty())
+                    if quote_count % 2 == 1 {
+                        let separator = token.lemma().trim_end_matches('"');
+                        negative_phrase = ends_with_negation(separator, start_of_query);
+                        Some(PhraseBuilder::empty())
+                    } else {
+                        negative_phrase = false;
+                        None
+                    }
                 };
+
+                negative_next_word =
+                    phrase.is_none() && ends_with_negation(token.lemma(), start_of_query);
             }
             _ => (),
         }
+
+        start_of_query = false;
     }
 
     // If a quote is never closed, we consider all of the end of the query as a phrase.
     if let Some(phrase) = phrase.take() {
-        if let Some(located_query_term) = phrase.build(ctx) {
-            located_terms.push(located_query_term);
-        }
+        extracted.push_phrase(ctx, phrase, negative_phrase);
     }
 
-    Ok(located_terms)
+    Ok(extracted)
+}
+
+/// Returns `true` if the separator ends with a dash that negates the next word, i.e. a dash that
+/// is at the start of the query or preceded by a whitespace, `black -cat` but not `black-cat`.
+fn ends_with_negation(separator: &str, start_of_query: bool) -> bool {
+    match separator.strip_suffix('-') {
+        Some(before) if before.is_empty() => start_of_query,
+        Some(before) => before.ends_with(char::is_whitespace),
+        None => false,
+    }
 }
 
 pub fn number_of_typos_allowed<'ctx>(
@@ -199,12 +286,12 @@ pub fn make_ngram(
         partially_initialized_term_from_word(ctx, &ngram_str, max_nbr_typos, is_prefix, true)?;
 
     // Now add the synonyms
-    let index_synonyms = ctx.index.synonyms(ctx.txn)?;
+    let settings = ctx.index.cached_settings(ctx.txn)?;
 
     term.zero_typo.synonyms.extend(
-        index_synonyms.get(&words).cloned().unwrap_or_default().into_iter().map(|words| {
+        settings.synonyms.get(&words).cloned().unwrap_or_default().into_iter().map(|words| {
             let words = words.into_iter().map(|w| Some(ctx.word_interner.insert(w))).collect();
-            ctx.phrase_interner.insert(Phrase { words })
+            ctx.phrase_interner.insert(Phrase { words, slop: 0 })
         }),
     );
 
@@ -225,13 +312,14 @@ pub fn make_ngram(
 
 struct PhraseBuilder {
     words: Vec<Option<Interned<String>>>,
+    slop: u8,
     start: u16,
     end: u16,
 }
 
 impl PhraseBuilder {
     fn empty() -> Self {
-        Self { words: Default::default(), start: u16::MAX, end: u16::MAX }
+        Self { words: Default::default(), slop: 0, start: u16::MAX, end: u16::MAX }
     }
 
     fn is_empty(&self) -> bool {
@@ -259,7 +347,8 @@ impl PhraseBuilder {
         }
         Some(LocatedQueryTerm {
             value: ctx.term_interner.push({
-                let phrase = ctx.phrase_interner.insert(Phrase { words: self.words });
+                let phrase =
+                    ctx.phrase_interner.insert(Phrase { words: self.words, slop: self.slop });
                 let phrase_desc = phrase.description(ctx);
                 QueryTerm {
                     original: ctx.word_interner.insert(phrase_desc),
@@ -310,8 +399,9 @@ mod tests {
         let rtxn = index.read_txn()?;
         let mut ctx = SearchContext::new(&index, &rtxn);
         // panics with `attempt to add with overflow` before <https://github.com/meilisearch/meilisearch/issues/3785>
-        let located_query_terms = located_query_terms_from_tokens(&mut ctx, tokens, None)?;
-        assert!(located_query_terms.is_empty());
+        let ExtractedTokens { query_terms, .. } =
+            located_query_terms_from_tokens(&mut ctx, tokens, None)?;
+        assert!(query_terms.is_empty());
         Ok(())
     }
 }
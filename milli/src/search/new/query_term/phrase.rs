@@ -8,6 +8,9 @@ use crate::SearchContext;
 #[derive(Default, Clone, PartialEq, Eq, Hash)]
 pub struct Phrase {
     pub words: Vec<Option<Interned<String>>>,
+    /// The number of extra words allowed between the words of the phrase,
+    /// which must still appear in order. Written `"quick fox"~2` in the query.
+    pub slop: u8,
 }
 impl Interned<Phrase> {
     pub fn description(self, ctx: &SearchContext) -> String {
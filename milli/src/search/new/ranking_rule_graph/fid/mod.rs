@@ -80,8 +80,10 @@ impl RankingRuleGraphTrait for FidGraph {
         let max_fid: Option<u16> = {
             if let Some(max_fid) = ctx
                 .index
-                .searchable_fields_ids(ctx.txn)?
-                .map(|field_ids| field_ids.into_iter().max())
+                .cached_settings(ctx.txn)?
+                .searchable_fields_ids
+                .as_ref()
+                .map(|field_ids| field_ids.iter().copied().max())
             {
                 max_fid
             } else {
@@ -10,6 +10,7 @@ use super::query_graph::QueryNodeData;
 use super::query_term::{Phrase, QueryTermSubset};
 use super::small_bitmap::SmallBitmap;
 use super::{QueryGraph, SearchContext, Word};
+use crate::proximity::MAX_DISTANCE;
 use crate::search::new::query_term::LocatedQueryTermSubset;
 use crate::Result;
 
@@ -177,11 +178,40 @@ pub fn compute_query_graph_docids(
     panic!()
 }
 
+/// Returns the documents containing at least one of the given negative words.
+///
+/// Unlike the other query words, the negative words are not derived: only the documents
+/// containing the exact words are excluded, not the ones containing a typo or a prefix of them.
+pub fn resolve_negative_words(
+    ctx: &mut SearchContext,
+    negative_words: &[Word],
+) -> Result<RoaringBitmap> {
+    let mut negative_bitmap = RoaringBitmap::new();
+    for &word in negative_words {
+        if let Some(bitmap) = ctx.word_docids(word)? {
+            negative_bitmap |= bitmap;
+        }
+    }
+    Ok(negative_bitmap)
+}
+
+/// Returns the documents containing at least one of the given negative phrases.
+pub fn resolve_negative_phrases(
+    ctx: &mut SearchContext,
+    negative_phrases: &[Interned<Phrase>],
+) -> Result<RoaringBitmap> {
+    let mut negative_bitmap = RoaringBitmap::new();
+    for &phrase in negative_phrases {
+        negative_bitmap |= ctx.get_phrase_docids(phrase)?;
+    }
+    Ok(negative_bitmap)
+}
+
 pub fn compute_phrase_docids(
     ctx: &mut SearchContext,
     phrase: Interned<Phrase>,
 ) -> Result<RoaringBitmap> {
-    let Phrase { words } = ctx.phrase_interner.get(phrase).clone();
+    let Phrase { words, slop } = ctx.phrase_interner.get(phrase).clone();
 
     if words.is_empty() {
         return Ok(RoaringBitmap::new());
@@ -197,8 +227,15 @@ pub fn compute_phrase_docids(
 
     let winsize = words.len().min(3);
 
+    /// The docids of a pair of adjacent words are only decoded when intersected,
+    /// their number is read from the header of the bitmap.
+    enum PairDocids {
+        Adjacent(Interned<String>, Interned<String>),
+        Decoded(RoaringBitmap),
+    }
+
     for win in words.windows(winsize) {
-        // Get all the documents with the matching distance for each word pairs.
+        // Get the number of documents with the matching distance for each word pairs.
         let mut bitmaps = Vec::with_capacity(winsize.pow(2));
         for (offset, &s1) in win
             .iter()
@@ -211,40 +248,50 @@ pub fn compute_phrase_docids(
                 .enumerate()
                 .filter_map(|(index, word)| word.as_ref().map(|word| (index, word)))
             {
-                if dist == 0 {
-                    match ctx.get_db_word_pair_proximity_docids(s1, s2, 1)? {
-                        Some(m) => bitmaps.push(m),
+                if dist == 0 && slop == 0 {
+                    match ctx.get_db_word_pair_proximity_docids_len(s1, s2, 1)? {
+                        Some(len) => bitmaps.push((len, PairDocids::Adjacent(s1, s2))),
                         // If there are no documents for this pair, there will be no
                         // results for the phrase query.
                         None => return Ok(RoaringBitmap::new()),
                     }
                 } else {
+                    // the slop allows the words to be further apart, as long as they
+                    // have a proximity
+                    let max_proximity =
+                        (dist as u8 + 1).saturating_add(slop).min(MAX_DISTANCE as u8 - 1);
                     let mut bitmap = RoaringBitmap::new();
-                    for dist in 0..=dist {
-                        if let Some(m) =
-                            ctx.get_db_word_pair_proximity_docids(s1, s2, dist as u8 + 1)?
-                        {
+                    for proximity in 1..=max_proximity {
+                        if let Some(m) = ctx.get_db_word_pair_proximity_docids(s1, s2, proximity)? {
                             bitmap |= m;
                         }
                     }
                     if bitmap.is_empty() {
                         return Ok(bitmap);
                     } else {
-                        bitmaps.push(bitmap);
+                        bitmaps.push((bitmap.len(), PairDocids::Decoded(bitmap)));
                     }
                 }
             }
         }
 
         // We sort the bitmaps so that we perform the small intersections first, which is faster.
-        bitmaps.sort_unstable_by_key(|a| a.len());
+        bitmaps.sort_unstable_by_key(|(len, _)| *len);
 
-        for bitmap in bitmaps {
-            candidates &= bitmap;
+        for (_, bitmap) in bitmaps {
+            match bitmap {
+                PairDocids::Adjacent(s1, s2) => {
+                    match ctx.get_db_word_pair_proximity_docids(s1, s2, 1)? {
+                        Some(bitmap) => candidates &= bitmap,
+                        None => return Ok(RoaringBitmap::new()),
+                    }
+                }
+                PairDocids::Decoded(bitmap) => candidates &= bitmap,
+            }
 
-            // There will be no match, return early
+            // There will be no match, return early without decoding the other bitmaps
             if candidates.is_empty() {
-                break;
+                return Ok(candidates);
             }
         }
     }
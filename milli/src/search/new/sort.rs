@@ -1,14 +1,21 @@
-use heed::BytesDecode;
+use heed::types::{ByteSlice, Str, Unit};
+use heed::{BytesDecode, RoPrefix, RoTxn};
 use roaring::RoaringBitmap;
+use slice_group_by::GroupBy;
 
 use super::logger::SearchLogger;
 use super::{RankingRule, RankingRuleOutput, RankingRuleQueryTrait, SearchContext};
-use crate::heed_codec::facet::{FacetGroupKeyCodec, OrderedF64Codec};
+use crate::error::SerializationError;
+use crate::heed_codec::facet::{FacetGroupKeyCodec, FieldDocIdFacetCodec, OrderedF64Codec};
 use crate::heed_codec::{ByteSliceRefCodec, StrRefCodec};
+use crate::index::db_name;
 use crate::score_details::{self, ScoreDetails};
 use crate::search::facet::{ascending_facet_sort, descending_facet_sort};
 use crate::{FieldId, Index, Result};
 
+const FID_SIZE: usize = 2;
+const DOCID_SIZE: usize = 4;
+
 pub trait RankingRuleOutputIter<'ctx, Query> {
     fn next_bucket(&mut self) -> Result<Option<RankingRuleOutput<Query>>>;
 }
@@ -97,87 +104,38 @@ impl<'ctx, Query: RankingRuleQueryTrait> RankingRule<'ctx, Query> for Sort<'ctx,
     ) -> Result<()> {
         let iter: RankingRuleOutputIterWrapper<Query> = match self.field_id {
             Some(field_id) => {
-                let number_db = ctx
-                    .index
-                    .facet_id_f64_docids
-                    .remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>();
-                let string_db = ctx
-                    .index
-                    .facet_id_string_docids
-                    .remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>();
-
-                let (number_iter, string_iter) = if self.is_ascending {
-                    let number_iter = ascending_facet_sort(
-                        ctx.txn,
-                        number_db,
-                        field_id,
-                        parent_candidates.clone(),
-                    )?;
-                    let string_iter = ascending_facet_sort(
+                let buckets: Box<
+                    dyn Iterator<Item = Result<(RoaringBitmap, serde_json::Value)>> + 'ctx,
+                > = if parent_candidates.len() <= ctx.sort_in_memory_threshold as u64 {
+                    let buckets = in_memory_facet_sort(
+                        ctx.index,
                         ctx.txn,
-                        string_db,
                         field_id,
-                        parent_candidates.clone(),
+                        parent_candidates,
+                        self.is_ascending,
                     )?;
-
-                    (itertools::Either::Left(number_iter), itertools::Either::Left(string_iter))
+                    Box::new(buckets.into_iter().map(Ok))
                 } else {
-                    let number_iter = descending_facet_sort(
-                        ctx.txn,
-                        number_db,
-                        field_id,
-                        parent_candidates.clone(),
-                    )?;
-                    let string_iter = descending_facet_sort(
-                        ctx.txn,
-                        string_db,
-                        field_id,
-                        parent_candidates.clone(),
-                    )?;
-
-                    (itertools::Either::Right(number_iter), itertools::Either::Right(string_iter))
+                    facet_levels_sort(ctx, field_id, parent_candidates, self.is_ascending)?
                 };
-                let number_iter = number_iter.map(|r| -> Result<_> {
-                    let (docids, bytes) = r?;
-                    Ok((
-                        docids,
-                        serde_json::Value::Number(
-                            serde_json::Number::from_f64(
-                                OrderedF64Codec::bytes_decode(bytes).expect("some number"),
-                            )
-                            .expect("too big float"),
-                        ),
-                    ))
-                });
-                let string_iter = string_iter.map(|r| -> Result<_> {
-                    let (docids, bytes) = r?;
-                    Ok((
-                        docids,
-                        serde_json::Value::String(
-                            StrRefCodec::bytes_decode(bytes).expect("some string").to_owned(),
-                        ),
-                    ))
-                });
 
                 let query_graph = parent_query.clone();
                 let ascending = self.is_ascending;
                 let field_name = self.field_name.clone();
                 let must_redact = self.must_redact;
-                RankingRuleOutputIterWrapper::new(Box::new(number_iter.chain(string_iter).map(
-                    move |r| {
-                        let (docids, value) = r?;
-                        Ok(RankingRuleOutput {
-                            query: query_graph.clone(),
-                            candidates: docids,
-                            score: ScoreDetails::Sort(score_details::Sort {
-                                field_name: field_name.clone(),
-                                ascending,
-                                redacted: must_redact,
-                                value,
-                            }),
-                        })
-                    },
-                )))
+                RankingRuleOutputIterWrapper::new(Box::new(buckets.map(move |r| {
+                    let (docids, value) = r?;
+                    Ok(RankingRuleOutput {
+                        query: query_graph.clone(),
+                        candidates: docids,
+                        score: ScoreDetails::Sort(score_details::Sort {
+                            field_name: field_name.clone(),
+                            ascending,
+                            redacted: must_redact,
+                            value,
+                        }),
+                    })
+                })))
             }
             None => RankingRuleOutputIterWrapper::new(Box::new(std::iter::empty())),
         };
@@ -188,12 +146,15 @@ impl<'ctx, Query: RankingRuleQueryTrait> RankingRule<'ctx, Query> for Sort<'ctx,
 
     fn next_bucket(
         &mut self,
-        _ctx: &mut SearchContext<'ctx>,
+        ctx: &mut SearchContext<'ctx>,
         _logger: &mut dyn SearchLogger<Query>,
         universe: &RoaringBitmap,
     ) -> Result<Option<RankingRuleOutput<Query>>> {
         let iter = self.iter.as_mut().unwrap();
-        if let Some(mut bucket) = iter.next_bucket()? {
+        // The facet levels are not walked anymore once the deadline is reached,
+        // the remaining documents are returned in a single bucket.
+        let bucket = if ctx.deadline_reached() { None } else { iter.next_bucket()? };
+        if let Some(mut bucket) = bucket {
             bucket.candidates &= universe;
             Ok(Some(bucket))
         } else {
@@ -220,3 +181,160 @@ impl<'ctx, Query: RankingRuleQueryTrait> RankingRule<'ctx, Query> for Sort<'ctx,
         self.iter = None;
     }
 }
+
+/// Returns the documents grouped by facet value, by walking the facet levels of the field.
+///
+/// The numbers are returned before the strings.
+#[allow(clippy::type_complexity)]
+fn facet_levels_sort<'ctx>(
+    ctx: &SearchContext<'ctx>,
+    field_id: FieldId,
+    candidates: &RoaringBitmap,
+    is_ascending: bool,
+) -> Result<Box<dyn Iterator<Item = Result<(RoaringBitmap, serde_json::Value)>> + 'ctx>> {
+    let number_db =
+        ctx.index.facet_id_f64_docids.remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>();
+    let string_db =
+        ctx.index.facet_id_string_docids.remap_key_type::<FacetGroupKeyCodec<ByteSliceRefCodec>>();
+
+    let (number_iter, string_iter) = if is_ascending {
+        let number_iter = ascending_facet_sort(ctx.txn, number_db, field_id, candidates.clone())?;
+        let string_iter = ascending_facet_sort(ctx.txn, string_db, field_id, candidates.clone())?;
+
+        (itertools::Either::Left(number_iter), itertools::Either::Left(string_iter))
+    } else {
+        let number_iter = descending_facet_sort(ctx.txn, number_db, field_id, candidates.clone())?;
+        let string_iter = descending_facet_sort(ctx.txn, string_db, field_id, candidates.clone())?;
+
+        (itertools::Either::Right(number_iter), itertools::Either::Right(string_iter))
+    };
+    let number_iter = number_iter.map(|r| -> Result<_> {
+        let (docids, bytes) = r?;
+        Ok((
+            docids,
+            serde_json::Value::Number(
+                serde_json::Number::from_f64(OrderedF64Codec::bytes_decode(bytes).ok_or(
+                    SerializationError::Decoding { db_name: Some(db_name::FACET_ID_F64_DOCIDS) },
+                )?)
+                .expect("too big float"),
+            ),
+        ))
+    });
+    let string_iter = string_iter.map(|r| -> Result<_> {
+        let (docids, bytes) = r?;
+        Ok((
+            docids,
+            serde_json::Value::String(
+                StrRefCodec::bytes_decode(bytes)
+                    .ok_or(SerializationError::Decoding {
+                        db_name: Some(db_name::FACET_ID_STRING_DOCIDS),
+                    })?
+                    .to_owned(),
+            ),
+        ))
+    });
+
+    Ok(Box::new(number_iter.chain(string_iter)))
+}
+
+/// Returns the documents grouped by facet value, like [`facet_levels_sort`], by reading
+/// the facet values of every candidate and sorting them in memory.
+///
+/// A document is only returned with its best value, the first one in the sort order.
+fn in_memory_facet_sort(
+    index: &Index,
+    txn: &RoTxn,
+    field_id: FieldId,
+    candidates: &RoaringBitmap,
+    is_ascending: bool,
+) -> Result<Vec<(RoaringBitmap, serde_json::Value)>> {
+    let mut numbers = Vec::new();
+    let mut strings = Vec::new();
+    for docid in candidates {
+        let mut best_number = None;
+        for result in facet_number_values(docid, field_id, index, txn)? {
+            let ((_, _, number), ()) = result?;
+            best_number = match best_number {
+                Some(best) if (number < best) != is_ascending => Some(best),
+                _ => Some(number),
+            };
+        }
+        if let Some(number) = best_number {
+            numbers.push((number, docid));
+            continue;
+        }
+
+        let mut best_string = None;
+        for result in facet_string_values(docid, field_id, index, txn)? {
+            let ((_, _, string), _) = result?;
+            best_string = match best_string {
+                Some(best) if (string < best) != is_ascending => Some(best),
+                _ => Some(string),
+            };
+        }
+        if let Some(string) = best_string {
+            strings.push((string, docid));
+        }
+    }
+
+    if is_ascending {
+        numbers.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        strings.sort_by(|(a, _), (b, _)| a.cmp(b));
+    } else {
+        numbers.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        strings.sort_by(|(a, _), (b, _)| b.cmp(a));
+    }
+
+    let mut buckets = Vec::new();
+    for group in numbers.linear_group_by(|(a, _), (b, _)| a == b) {
+        let docids = group.iter().map(|(_, docid)| *docid).collect();
+        let number = serde_json::Number::from_f64(group[0].0).expect("too big float");
+        buckets.push((docids, serde_json::Value::Number(number)));
+    }
+    for group in strings.linear_group_by(|(a, _), (b, _)| a == b) {
+        let docids = group.iter().map(|(_, docid)| *docid).collect();
+        buckets.push((docids, serde_json::Value::String(group[0].0.to_owned())));
+    }
+    Ok(buckets)
+}
+
+/// Return an iterator over each number value in the given field of the given document.
+pub(super) fn facet_number_values<'a>(
+    docid: u32,
+    field_id: u16,
+    index: &Index,
+    txn: &'a RoTxn,
+) -> Result<RoPrefix<'a, FieldDocIdFacetCodec<OrderedF64Codec>, Unit>> {
+    let key = facet_values_prefix_key(field_id, docid);
+
+    let iter = index
+        .field_id_docid_facet_f64s
+        .remap_key_type::<ByteSlice>()
+        .prefix_iter(txn, &key)?
+        .remap_key_type();
+
+    Ok(iter)
+}
+
+/// Return an iterator over each normalized string value in the given field of the given document.
+fn facet_string_values<'a>(
+    docid: u32,
+    field_id: u16,
+    index: &Index,
+    txn: &'a RoTxn,
+) -> Result<RoPrefix<'a, FieldDocIdFacetCodec<StrRefCodec>, Str>> {
+    let key = facet_values_prefix_key(field_id, docid);
+
+    let iter = index
+        .field_id_docid_facet_strings
+        .remap_key_type::<ByteSlice>()
+        .prefix_iter(txn, &key)?
+        .remap_types();
+
+    Ok(iter)
+}
+
+#[allow(clippy::drop_non_drop)]
+fn facet_values_prefix_key(field_id: u16, docid: u32) -> [u8; FID_SIZE + DOCID_SIZE] {
+    concat_arrays::concat_arrays!(field_id.to_be_bytes(), docid.to_be_bytes())
+}
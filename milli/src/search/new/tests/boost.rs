@@ -0,0 +1,92 @@
+/*!
+This module tests the boost field of the settings:
+
+1. the best ranked documents are reordered by their global score multiplied by their boost
+2. the documents without a boost keep their score
+3. the page is taken after the documents are reordered
+4. the boost is not applied when the documents are explicitly sorted
+5. the ranking scores are the boosted scores, in the order of the documents
+*/
+
+use big_s::S;
+use maplit::hashset;
+
+use crate::index::tests::TempIndex;
+use crate::score_details::ScoringStrategy;
+use crate::{AscDesc, Criterion, Member, Search, SearchResult};
+
+fn create_index() -> TempIndex {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_sortable_fields(hashset! { S("id") });
+            s.set_boost_field(S("boost"));
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "hello world" },
+            { "id": 1, "text": "hello" },
+            { "id": 2, "text": "hello world", "boost": 0.9 },
+            { "id": 3, "text": "hello world", "boost": 3 },
+        ]))
+        .unwrap();
+    index
+}
+
+fn search(index: &TempIndex, query: &str, offset: usize, limit: usize) -> Vec<u32> {
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, index);
+    s.query(query);
+    s.offset(offset);
+    s.limit(limit);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    documents_ids
+}
+
+#[test]
+fn test_boost_field() {
+    let index = create_index();
+
+    assert_eq!(search(&index, "hello world", 0, 20), vec![3, 0, 2, 1]);
+    assert_eq!(search(&index, "hello world", 1, 2), vec![0, 2]);
+    // every document has the same score in a placeholder search
+    assert_eq!(search(&index, "", 0, 20), vec![3, 0, 1, 2]);
+
+    index.update_settings(|s| s.reset_boost_field()).unwrap();
+    assert_eq!(search(&index, "hello world", 0, 20), vec![0, 2, 3, 1]);
+}
+
+#[test]
+fn test_boosted_ranking_scores() {
+    let index = create_index();
+
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, &index);
+    s.query("hello world");
+    s.scoring_strategy(ScoringStrategy::Detailed);
+    let result = s.execute().unwrap();
+    assert_eq!(result.documents_ids, vec![3, 0, 2, 1]);
+
+    let scores = result.ranking_scores();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]), "{scores:?}");
+    // the documents 0 and 3 have the same score before the boost
+    assert_eq!(scores[0], scores[1] * 3.0);
+}
+
+#[test]
+fn test_boost_field_with_sort() {
+    let index = create_index();
+    index.update_settings(|s| s.set_criteria(vec![Criterion::Words, Criterion::Sort])).unwrap();
+
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, &index);
+    s.query("hello world");
+    s.sort_criteria(vec![AscDesc::Asc(Member::Field(S("id")))]);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    assert_eq!(documents_ids, vec![0, 2, 3, 1]);
+}
@@ -0,0 +1,77 @@
+/*!
+This module tests that the default ranking rules sort the documents bucket by bucket,
+in the following order:
+
+1. `words`: the documents containing the most query words
+2. `typo`: the documents with the fewest typos
+3. `proximity`: the documents where the query words are the closest
+4. `attribute`: the documents where the query words are in the most important attributes
+5. `exactness`: the documents with an attribute equal to the query
+6. the custom `asc`/`desc` ranking rules appended to the default ones
+
+Each ranking rule only sorts the documents inside the buckets of the previous ones.
+*/
+
+use crate::index::tests::TempIndex;
+use crate::{default_criteria, Criterion, Search, SearchResult};
+
+fn create_index() -> TempIndex {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["title".to_owned(), "description".to_owned()]);
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": 0, "title": "sunflower seeds", "rank": 1 },
+            { "id": 1, "title": "sunflower seeds", "rank": 2 },
+            // one typo
+            { "id": 2, "title": "sunflowar seeds", "rank": 8 },
+            // the words are not next to each other
+            { "id": 3, "title": "sunflower and seeds", "rank": 7 },
+            // the words are in different attributes
+            { "id": 4, "title": "seeds", "description": "sunflower", "rank": 6 },
+            // the words are in a less important attribute
+            { "id": 5, "description": "sunflower seeds", "rank": 5 },
+            // the title starts with the query but is not equal to it
+            { "id": 6, "title": "sunflower seeds and more", "rank": 4 },
+            // only one of the words
+            { "id": 7, "title": "sunflower", "rank": 9 },
+        ]))
+        .unwrap();
+    index
+}
+
+#[test]
+fn test_default_criteria() {
+    let index = create_index();
+
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, &index);
+    s.query("sunflower seeds");
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 6, 5, 3, 4, 2, 7]");
+}
+
+#[test]
+fn test_default_criteria_followed_by_custom_criterion() {
+    let index = create_index();
+    index
+        .update_settings(|s| {
+            let mut criteria = default_criteria();
+            criteria.push(Criterion::Desc("rank".to_owned()));
+            s.set_criteria(criteria);
+        })
+        .unwrap();
+
+    // the custom ranking rule only sorts the documents that tie on all the others
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, &index);
+    s.query("sunflower seeds");
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[1, 0, 6, 5, 3, 4, 2, 7]");
+}
@@ -1,5 +1,8 @@
+use big_s::S;
+use charabia::{Language, Script};
+
 use crate::index::tests::TempIndex;
-use crate::{Search, SearchResult};
+use crate::{LocalizedAttributesRule, Search, SearchResult};
 
 #[test]
 fn test_kanji_language_detection() {
@@ -21,3 +24,76 @@ fn test_kanji_language_detection() {
 
     insta::assert_snapshot!(format!("{documents_ids:?}"), @"[1]");
 }
+
+#[test]
+fn test_localized_attributes() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_localized_attributes_rules(vec![LocalizedAttributesRule::new(
+                vec![S("title_ja")],
+                vec![S("jpn")],
+            )]);
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "title_ja": "東京のお寿司。" },
+            { "id": 1, "title": "The sushi of Tokyo." }
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+    let mut search = Search::new(&txn, &index);
+    search.query("東京");
+    search.locales(vec![Language::Jpn]);
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0]");
+}
+
+#[test]
+fn test_localized_attributes_indexing() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_localized_attributes_rules(vec![LocalizedAttributesRule::new(
+                vec![S("title_ja")],
+                vec![S("jpn")],
+            )]);
+        })
+        .unwrap();
+    // the same text is detected as chinese when the field is not localized
+    index
+        .add_documents(documents!([
+            { "id": 0, "title_ja": "人人生而自由﹐在尊嚴和權利上一律平等。" },
+            { "id": 1, "title": "人人生而自由﹐在尊嚴和權利上一律平等。" }
+        ]))
+        .unwrap();
+
+    let rtxn = index.read_txn().unwrap();
+    let jpn_docids =
+        index.script_language_documents_ids(&rtxn, &(Script::Cj, Language::Jpn)).unwrap();
+    let cmn_docids =
+        index.script_language_documents_ids(&rtxn, &(Script::Cj, Language::Cmn)).unwrap();
+    insta::assert_snapshot!(format!("{jpn_docids:?}"), @"Some(RoaringBitmap<[0]>)");
+    insta::assert_snapshot!(format!("{cmn_docids:?}"), @"Some(RoaringBitmap<[1]>)");
+}
+
+#[test]
+fn test_invalid_locale() {
+    let index = TempIndex::new();
+
+    let error = index
+        .update_settings(|s| {
+            s.set_localized_attributes_rules(vec![LocalizedAttributesRule::new(
+                vec![S("title")],
+                vec![S("japanese")],
+            )]);
+        })
+        .unwrap_err();
+
+    insta::assert_snapshot!(error, @"`japanese` is not a supported locale, expected an ISO 639-3 code such as `eng` or `jpn`.");
+}
@@ -1,16 +1,24 @@
 pub mod attribute_fid;
 pub mod attribute_position;
+pub mod boost;
+pub mod default_criteria;
 pub mod distinct;
 pub mod exactness;
 pub mod geo_sort;
 pub mod integration;
 #[cfg(feature = "all-tokenizations")]
 pub mod language;
+pub mod negative;
 pub mod ngram_split_words;
+pub mod order;
+pub mod pagination;
+pub mod phrase;
+pub mod prefix;
 pub mod proximity;
 pub mod proximity_typo;
 pub mod sort;
 pub mod stop_words;
+pub mod tie_breaker;
 pub mod typo;
 pub mod typo_proximity;
 pub mod words_tms;
@@ -15,6 +15,7 @@ This module tests the following properties:
 12. Prefix tolerance is disabled for the last word if a space follows it
 13. Ngrams cannot be formed by combining a phrase and a word or two phrases
 14. Split words are not disabled by the `disableOnAttribute` or `disableOnWords` typo settings
+15. A word that cannot be split into two words can be split into three consecutive words
 */
 
 use crate::index::tests::TempIndex;
@@ -426,3 +427,52 @@ fn test_split_words_never_disabled() {
     ]
     "###);
 }
+
+#[test]
+fn test_split_words_in_three() {
+    let index = create_index();
+    index
+        .add_documents(documents!([
+            {
+                "id": 7,
+                "text": "a sun flower pot"
+            },
+            {
+                "id": 8,
+                "text": "a flower pot in the sun"
+            }
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, &index);
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    s.query("sunflowerpot ");
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+
+    // `sun flower pot` must be three consecutive words
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[7]");
+    let texts = collect_field_values(&index, &txn, "text", &documents_ids);
+    insta::assert_debug_snapshot!(texts, @r###"
+    [
+        "\"a sun flower pot\"",
+    ]
+    "###);
+    drop(txn);
+
+    index
+        .update_settings(|s| {
+            s.set_autorize_typos(false);
+        })
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, &index);
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    s.query("sunflowerpot ");
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[]");
+}
@@ -0,0 +1,63 @@
+/*!
+This module tests the `order` ranking rule:
+
+1. documents are sorted by increasing number of consecutive query words appearing in
+the reverse order of the query
+2. a pair of words counts as reversed only if the reversed occurrence is strictly closer,
+with the directional cost of the proximity ranking rule
+3. words that are too far apart to have a proximity are not considered reversed
+4. the rule is ignored by a placeholder search
+*/
+
+use crate::index::tests::TempIndex;
+use crate::{Criterion, Search, SearchResult};
+
+fn create_index() -> TempIndex {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_criteria(vec![Criterion::Words, Criterion::Order]);
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "fox brown quick" },
+            { "id": 1, "text": "quick brown fox" },
+            { "id": 2, "text": "brown quick fox" },
+            { "id": 3, "text": "quick fox brown" },
+            { "id": 4, "text": "quick the brown fox the quick" },
+            { "id": 5, "text": "fox the brown quick" },
+            { "id": 6, "text": "quick a b c d e f g h brown fox" },
+        ]))
+        .unwrap();
+    index
+}
+
+#[test]
+fn test_order() {
+    let index = create_index();
+
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, &index);
+    s.query("quick brown fox");
+    let SearchResult { documents_ids, document_scores, .. } = s.execute().unwrap();
+    assert_eq!(documents_ids, vec![1, 4, 6, 2, 3, 0, 5]);
+
+    let order_ranks: Vec<_> =
+        document_scores.iter().map(|scores| scores.last().unwrap().rank().unwrap().rank).collect();
+    assert_eq!(order_ranks, vec![3, 3, 3, 2, 2, 1, 1]);
+}
+
+#[test]
+fn test_order_placeholder() {
+    let index = create_index();
+
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, &index);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    assert_eq!(documents_ids, vec![0, 1, 2, 3, 4, 5, 6]);
+}
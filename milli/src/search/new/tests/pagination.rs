@@ -0,0 +1,99 @@
+/*!
+This module tests the `offset` and `limit` parameters of the search:
+
+1. every page of a placeholder search, a filtered placeholder search and a search with a query
+   is a slice of the complete list of results
+2. an offset greater than the number of candidates returns no document
+3. the candidates are the same whatever the page, it is the total number of hits
+*/
+
+use big_s::S;
+use maplit::hashset;
+
+use crate::index::tests::TempIndex;
+use crate::search::new::tests::collect_field_values;
+use crate::{Criterion, Filter, Search, SearchResult, TermsMatchingStrategy};
+
+fn create_index() -> TempIndex {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_filterable_fields(hashset! { S("even") });
+            s.set_criteria(vec![Criterion::Words, Criterion::Proximity]);
+        })
+        .unwrap();
+
+    let documents: Vec<_> = (0..25)
+        .map(|i| {
+            let text = match i % 3 {
+                0 => "the quick brown fox",
+                1 => "the quick fox",
+                _ => "the lazy dog",
+            };
+            serde_json::json!({ "id": i, "text": text, "even": i % 2 == 0 })
+        })
+        .collect();
+    index.add_documents(documents!(documents)).unwrap();
+    index
+}
+
+#[track_caller]
+fn assert_pages_are_slices(index: &TempIndex, configure: impl Fn(&mut Search)) {
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, index);
+    configure(&mut s);
+    s.limit(100);
+    let SearchResult { documents_ids, candidates, .. } = s.execute().unwrap();
+    let all_ids = collect_field_values(index, &txn, "id", &documents_ids);
+
+    for offset in [0, 1, 7, 20, 24] {
+        for limit in [0, 1, 5, 30] {
+            let mut s = Search::new(&txn, index);
+            configure(&mut s);
+            s.offset(offset).limit(limit);
+            let result = s.execute().unwrap();
+            let ids = collect_field_values(index, &txn, "id", &result.documents_ids);
+            let end = (offset + limit).min(all_ids.len());
+            let expected = all_ids.get(offset..end).unwrap_or_default();
+            assert_eq!(ids, expected, "offset: {offset}, limit: {limit}");
+            assert_eq!(result.candidates, candidates, "offset: {offset}, limit: {limit}");
+        }
+    }
+
+    let mut s = Search::new(&txn, index);
+    configure(&mut s);
+    s.offset(all_ids.len() + 1);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    assert!(documents_ids.is_empty());
+}
+
+#[test]
+fn placeholder_pagination() {
+    let index = create_index();
+    assert_pages_are_slices(&index, |_| ());
+}
+
+#[test]
+fn filtered_placeholder_pagination() {
+    let index = create_index();
+    assert_pages_are_slices(&index, |s| {
+        s.filter(Filter::from_str("even = true").unwrap().unwrap());
+    });
+}
+
+#[test]
+fn query_pagination() {
+    let index = create_index();
+    assert_pages_are_slices(&index, |s| {
+        s.query("quick fox");
+    });
+    assert_pages_are_slices(&index, |s| {
+        s.query("the quick dog");
+        s.terms_matching_strategy(TermsMatchingStrategy::Last);
+        s.exhaustive_number_hits(true);
+    });
+}
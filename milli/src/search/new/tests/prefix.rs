@@ -0,0 +1,142 @@
+/*!
+This module tests the following properties about the word prefix databases:
+1. The prefixes shared by enough words, up to the maximum prefix length, are indexed
+2. The documents of an indexed prefix are the documents of all the words starting with it
+3. A prefix search returns the same documents whether the prefix is indexed or not
+4. The last word of the query is not a prefix when the autocompletion is disabled
+*/
+
+use roaring::RoaringBitmap;
+
+use crate::index::tests::TempIndex;
+use crate::{Search, SearchResult, TermsMatchingStrategy};
+
+fn create_index(words_prefix_threshold: u32) -> TempIndex {
+    let mut index = TempIndex::new();
+    index.index_documents_config.words_prefix_threshold = Some(words_prefix_threshold);
+    index.index_documents_config.max_prefix_length = Some(3);
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "the world of words" },
+            { "id": 1, "text": "a worldwide word" },
+            { "id": 2, "text": "the worst work" },
+            { "id": 3, "text": "wonderful wombats" },
+            { "id": 4, "text": "the wolf is hungry" },
+            { "id": 5, "text": "nothing to see here" },
+        ]))
+        .unwrap();
+    index
+}
+
+fn search(index: &TempIndex, query: &str) -> Vec<u32> {
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, index);
+    s.query(query);
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    documents_ids
+}
+
+#[test]
+fn test_prefixes_are_indexed() {
+    let index = create_index(2);
+    let txn = index.read_txn().unwrap();
+
+    let prefixes_fst = index.words_prefixes_fst(&txn).unwrap();
+    for prefix in ["w", "wo", "wor"] {
+        assert!(prefixes_fst.contains(prefix), "{prefix} must be indexed");
+    }
+    // `worl` is too long, `hun` and `the` are the prefixes of a single word.
+    for prefix in ["worl", "hun", "the"] {
+        assert!(!prefixes_fst.contains(prefix), "{prefix} must not be indexed");
+    }
+
+    let words_fst = index.words_fst(&txn).unwrap();
+    for prefix in ["wo", "wor"] {
+        let mut expected = RoaringBitmap::new();
+        for word in words_fst.stream().into_strs().unwrap() {
+            if word.starts_with(prefix) {
+                expected |= index.word_docids.get(&txn, word.as_str()).unwrap().unwrap();
+            }
+        }
+        let prefix_docids = index.word_prefix_docids.get(&txn, prefix).unwrap().unwrap();
+        assert_eq!(prefix_docids, expected);
+    }
+}
+
+#[test]
+fn test_prefix_search_with_and_without_prefix_databases() {
+    let with_prefixes = create_index(2);
+    let without_prefixes = create_index(u32::MAX);
+
+    let txn = without_prefixes.read_txn().unwrap();
+    assert!(without_prefixes.words_prefixes_fst(&txn).unwrap().is_empty());
+    drop(txn);
+
+    for query in ["w", "wo", "wor", "worl", "the wo", "the world wor", "hun"] {
+        let mut expected = search(&without_prefixes, query);
+        let mut found = search(&with_prefixes, query);
+        expected.sort_unstable();
+        found.sort_unstable();
+        assert_eq!(found, expected, "query: {query}");
+    }
+
+    let mut found = search(&with_prefixes, "wor");
+    found.sort_unstable();
+    assert_eq!(found, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_all_words_as_prefix() {
+    let search_all_prefixes = |index: &TempIndex, query: &str| {
+        let txn = index.read_txn().unwrap();
+        let mut s = Search::new(&txn, index);
+        s.query(query);
+        s.terms_matching_strategy(TermsMatchingStrategy::All);
+        s.all_words_as_prefix(true);
+        let SearchResult { mut documents_ids, .. } = s.execute().unwrap();
+        documents_ids.sort_unstable();
+        documents_ids
+    };
+
+    for index in [create_index(2), create_index(u32::MAX)] {
+        // only the last word is a prefix by default
+        assert!(search(&index, "wo hun").is_empty());
+        assert_eq!(search_all_prefixes(&index, "wo hun"), vec![4]);
+        assert_eq!(search_all_prefixes(&index, "the wor"), vec![0, 2]);
+        assert_eq!(search_all_prefixes(&index, "wor wor"), vec![0, 1, 2]);
+        assert_eq!(search_all_prefixes(&index, "wom wonderful"), vec![3]);
+    }
+}
+
+#[test]
+fn test_autocomplete() {
+    let search_without_autocomplete = |index: &TempIndex, query: &str| {
+        let txn = index.read_txn().unwrap();
+        let mut s = Search::new(&txn, index);
+        s.query(query);
+        s.terms_matching_strategy(TermsMatchingStrategy::All);
+        s.autocomplete(false);
+        let SearchResult { mut documents_ids, .. } = s.execute().unwrap();
+        documents_ids.sort_unstable();
+        documents_ids
+    };
+
+    for index in [create_index(2), create_index(u32::MAX)] {
+        let mut found = search(&index, "the wor");
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 2]);
+        assert!(search_without_autocomplete(&index, "the wor").is_empty());
+        assert!(search_without_autocomplete(&index, "hun").is_empty());
+        // the complete words are still found
+        assert_eq!(search_without_autocomplete(&index, "the world"), search(&index, "the world"));
+    }
+}
@@ -9,6 +9,8 @@ This module tests the `sort` ranking rule:
 6. documents with either: (1) no value, (2) null, or (3) an object for the field-to-sort appear at the end of the bucket
 7. boolean values are translated to strings
 8. if a field contains an array, it is sorted by the best value in the array according to the sort rule
+9. the recency field of the settings sorts, most recent first, the documents that tie on every ranking rule
+10. the candidates are sorted the same way in memory and by walking the facet levels
 */
 
 use big_s::S;
@@ -353,3 +355,122 @@ fn test_redacted() {
     insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 2, 4, 5, 22, 23, 13, 1, 3, 12, 21, 11, 20, 6, 7, 8, 9, 10, 14, 15]");
     insta::assert_json_snapshot!(document_scores_json);
 }
+
+#[test]
+fn test_asc_desc_criteria_on_string_field() {
+    let index = create_index();
+    index
+        .update_settings(|s| {
+            s.set_criteria(vec![Criterion::Asc(S("letter"))]);
+        })
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let s = Search::new(&txn, &index);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19]");
+    drop(txn);
+
+    index
+        .update_settings(|s| {
+            s.set_criteria(vec![Criterion::Desc(S("letter"))]);
+        })
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let s = Search::new(&txn, &index);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[21, 22, 23, 20, 18, 19, 15, 16, 17, 9, 10, 11, 12, 13, 14, 8, 5, 6, 7, 2]");
+    drop(txn);
+
+    // the string criterion can be followed by a number criterion to break the ties
+    index
+        .update_settings(|s| {
+            s.set_criteria(vec![Criterion::Asc(S("letter")), Criterion::Desc(S("rank"))]);
+        })
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let s = Search::new(&txn, &index);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[1, 0, 4, 3, 2, 7, 6, 5, 8, 14, 13, 12, 11, 10, 9, 17, 16, 15, 19, 18]");
+
+    let letter_values = collect_field_values(&index, &txn, "letter", &documents_ids);
+    insta::assert_debug_snapshot!(&letter_values[..5], @r###"
+    [
+        "\"A\"",
+        "\"A\"",
+        "\"B\"",
+        "\"B\"",
+        "\"B\"",
+    ]
+    "###);
+}
+
+#[test]
+fn test_recency_field_tiebreak() {
+    let index = TempIndex::new();
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_sortable_fields(hashset! { S("letter"), S("updated_at") });
+            s.set_criteria(vec![Criterion::Words, Criterion::Sort]);
+            s.set_recency_field(S("updated_at"));
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "the fox", "letter": "A", "updated_at": 10 },
+            { "id": 1, "text": "the fox", "letter": "B", "updated_at": 30 },
+            { "id": 2, "text": "the fox", "letter": "A", "updated_at": 20 },
+            { "id": 3, "text": "the quick fox", "letter": "B", "updated_at": 40 },
+            { "id": 4, "text": "the fox", "letter": "A" },
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    // the documents without a recency value come last
+    let s = Search::new(&txn, &index);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[3, 1, 2, 0, 4]");
+
+    // the recency is only used to break the ties of the sort
+    let mut s = Search::new(&txn, &index);
+    s.query("the fox");
+    s.sort_criteria(vec![AscDesc::Asc(Member::Field(S("letter")))]);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[2, 0, 4, 3, 1]");
+
+    // an explicit sort on the recency field takes precedence
+    let mut s = Search::new(&txn, &index);
+    s.sort_criteria(vec![AscDesc::Asc(Member::Field(S("updated_at")))]);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 2, 1, 3, 4]");
+}
+
+#[test]
+fn test_sort_in_memory_and_with_facet_levels() {
+    let index = create_index();
+    let txn = index.read_txn().unwrap();
+
+    for field in ["letter", "rank", "vague"] {
+        for sort in [AscDesc::Asc(Member::Field(S(field))), AscDesc::Desc(Member::Field(S(field)))]
+        {
+            let search = |sort_in_memory_threshold| {
+                let mut s = Search::new(&txn, &index);
+                s.limit(100);
+                s.scoring_strategy(crate::score_details::ScoringStrategy::Detailed);
+                s.sort_criteria(vec![sort.clone()]);
+                s.sort_in_memory_threshold(sort_in_memory_threshold);
+                let SearchResult { documents_ids, document_scores, .. } = s.execute().unwrap();
+                (documents_ids, format!("{document_scores:?}"))
+            };
+            assert_eq!(search(0), search(usize::MAX), "{sort:?}");
+        }
+    }
+}
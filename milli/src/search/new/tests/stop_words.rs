@@ -6,7 +6,9 @@ This module tests the following properties about stop words:
 - they are ignored in phrases
 - If a query consists only of stop words, a placeholder query is used instead
 - A prefix word is never ignored, even if the prefix is a stop word
+- A last word that is not a prefix is ignored if it is a stop word
 - Phrases consisting only of stop words are ignored
+- Additional stop words can be given for a single query, they are ignored even as the last word
 */
 
 use std::collections::BTreeSet;
@@ -458,3 +460,60 @@ fn test_stop_words_in_phrase() {
     // As a result the scores are empty lists
     insta::assert_snapshot!(format!("{document_scores:#?}"));
 }
+
+#[test]
+fn test_query_stop_words() {
+    let index = create_index();
+
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, &index);
+    s.query("captain marvel trailer");
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[]");
+
+    // `trailer` is not part of the mandatory words of this query anymore, even as the last word
+    for query in ["captain marvel trailer", "captain trailer marvel"] {
+        let mut s = Search::new(&txn, &index);
+        s.query(query);
+        s.terms_matching_strategy(TermsMatchingStrategy::All);
+        s.stop_words(BTreeSet::from_iter(["trailer".to_owned()]));
+        let SearchResult { documents_ids, .. } = s.execute().unwrap();
+        insta::assert_snapshot!(format!("{documents_ids:?}"), @"[1]");
+    }
+}
+
+#[test]
+fn test_last_stop_word_without_autocomplete() {
+    let index = TempIndex::new();
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_stop_words(BTreeSet::from_iter(["of".to_owned(), "the".to_owned()]));
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "title": "the lord of the rings" },
+            { "id": 1, "title": "theory of everything" },
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    // `the` is the prefix of `theory`
+    let mut s = Search::new(&txn, &index);
+    s.query("lord of the");
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[]");
+
+    // `the` is not indexed, it is ignored when it is not a prefix
+    let mut s = Search::new(&txn, &index);
+    s.query("lord of the");
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    s.autocomplete(false);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0]");
+}
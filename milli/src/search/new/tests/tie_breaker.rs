@@ -0,0 +1,134 @@
+/*!
+This module tests the tie-breaker of the documents that are equal for every ranking rule:
+
+1. by default, they are returned by increasing internal id
+2. with the external id tie-breaker, they are returned by external id, before and after
+the documents are reindexed, whether the bucket is sorted in memory or not
+3. the pages of the results are slices of the complete list of results
+*/
+
+use crate::index::tests::TempIndex;
+use crate::search::new::tests::collect_field_values;
+use crate::{Criterion, Search, SearchResult, TieBreaker};
+
+fn create_index() -> TempIndex {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_criteria(vec![Criterion::Words, Criterion::Proximity]);
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": "c", "text": "the quick fox" },
+            { "id": "e", "text": "the quick lazy fox" },
+            { "id": "a", "text": "the quick fox" },
+            { "id": "d", "text": "the quick fox" },
+            { "id": "b", "text": "the lazy dog" },
+        ]))
+        .unwrap();
+    index
+}
+
+fn search_ids(index: &TempIndex, configure: impl Fn(&mut Search)) -> Vec<String> {
+    let txn = index.read_txn().unwrap();
+    let mut s = Search::new(&txn, index);
+    configure(&mut s);
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    collect_field_values(index, &txn, "id", &documents_ids)
+}
+
+#[test]
+fn test_internal_id_tie_breaker() {
+    let index = create_index();
+
+    let ids = search_ids(&index, |s| {
+        s.query("quick fox");
+    });
+    insta::assert_debug_snapshot!(ids, @r###"
+    [
+        "\"c\"",
+        "\"a\"",
+        "\"d\"",
+        "\"e\"",
+    ]
+    "###);
+}
+
+#[test]
+fn test_external_id_tie_breaker() {
+    let index = create_index();
+
+    for sort_in_memory_threshold in [0, 1000] {
+        let ids = search_ids(&index, |s| {
+            s.query("quick fox")
+                .tie_breaker(TieBreaker::ExternalId)
+                .sort_in_memory_threshold(sort_in_memory_threshold);
+        });
+        insta::assert_debug_snapshot!(ids, @r###"
+        [
+            "\"a\"",
+            "\"c\"",
+            "\"d\"",
+            "\"e\"",
+        ]
+        "###);
+
+        let ids = search_ids(&index, |s| {
+            s.tie_breaker(TieBreaker::ExternalId)
+                .sort_in_memory_threshold(sort_in_memory_threshold);
+        });
+        insta::assert_debug_snapshot!(ids, @r###"
+        [
+            "\"a\"",
+            "\"b\"",
+            "\"c\"",
+            "\"d\"",
+            "\"e\"",
+        ]
+        "###);
+    }
+
+    // the reindexed documents get new internal ids
+    index.delete_document("a");
+    index.delete_document("c");
+    index
+        .add_documents(documents!([
+            { "id": "c", "text": "the quick fox" },
+            { "id": "a", "text": "the quick fox" },
+        ]))
+        .unwrap();
+    let ids = search_ids(&index, |s| {
+        s.query("quick fox").tie_breaker(TieBreaker::ExternalId);
+    });
+    insta::assert_debug_snapshot!(ids, @r###"
+    [
+        "\"a\"",
+        "\"c\"",
+        "\"d\"",
+        "\"e\"",
+    ]
+    "###);
+}
+
+#[test]
+fn test_tie_breaker_pagination() {
+    let index = create_index();
+
+    for tie_breaker in [TieBreaker::InternalId, TieBreaker::ExternalId] {
+        let all_ids = search_ids(&index, |s| {
+            s.query("quick fox").tie_breaker(tie_breaker);
+        });
+        for offset in 0..all_ids.len() {
+            let ids = search_ids(&index, |s| {
+                s.query("quick fox").tie_breaker(tie_breaker).offset(offset).limit(2);
+            });
+            let end = (offset + 2).min(all_ids.len());
+            assert_eq!(ids, all_ids[offset..end]);
+        }
+    }
+}
@@ -16,6 +16,9 @@ if `words` doesn't exist before it.
 12. Prefix tolerance costs nothing according to the typo ranking rule
 13. Split words cost 1 typo according to the typo ranking rule
 14. Synonyms cost nothing according to the typo ranking rule
+15. Only the typo derivations that appear in the most documents are kept when there are too many of them
+16. The typo derivations found in parallel on a thread pool are the same as the ones found by the search thread
+17. The `typo` ranking rule buckets the documents by the sum of the typos of all the query words
 */
 
 use std::collections::HashMap;
@@ -584,6 +587,37 @@ fn test_typo_bucketing() {
     "###);
 }
 
+#[test]
+fn test_typo_bucketing_sums_the_typos_of_the_words() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_criteria(vec![Criterion::Words, Criterion::Typo]);
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "sunflower fields" },
+            { "id": 1, "text": "sunflawer fields" },
+            { "id": 2, "text": "sunflawer fieldz" },
+            { "id": 3, "text": "sunflower fieldz" },
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, &index);
+    s.terms_matching_strategy(TermsMatchingStrategy::All);
+    s.query("sunflower fields");
+    let SearchResult { documents_ids, .. } = s.execute().unwrap();
+    // 0 typo, then one typo in either word, then one typo in both words
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 3, 2]");
+}
+
 #[test]
 fn test_typo_synonyms() {
     let index = create_index();
@@ -635,3 +669,89 @@ fn test_typo_synonyms() {
     ]
     "###);
 }
+
+#[test]
+fn test_typo_derivations_limit() {
+    let index = TempIndex::new();
+
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_criteria(vec![Criterion::Words]);
+        })
+        .unwrap();
+
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "worle" },
+            { "id": 1, "text": "worle" },
+            { "id": 2, "text": "worle" },
+            { "id": 3, "text": "worls" },
+            { "id": 4, "text": "world" },
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, &index);
+    s.query("world");
+    let SearchResult { mut documents_ids, .. } = s.execute().unwrap();
+    documents_ids.sort_unstable();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 2, 3, 4]");
+
+    // only `worle`, the most frequent derivation, is kept
+    let mut s = Search::new(&txn, &index);
+    s.query("world");
+    s.typo_derivations_limit(1);
+    let SearchResult { mut documents_ids, .. } = s.execute().unwrap();
+    documents_ids.sort_unstable();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 2, 4]");
+    drop(txn);
+
+    // the limit can be set in the settings of the index and overridden by the search
+    index.update_settings(|s| s.set_typo_derivations_limit(1)).unwrap();
+    let txn = index.read_txn().unwrap();
+
+    let mut s = Search::new(&txn, &index);
+    s.query("world");
+    let SearchResult { mut documents_ids, .. } = s.execute().unwrap();
+    documents_ids.sort_unstable();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 2, 4]");
+
+    let mut s = Search::new(&txn, &index);
+    s.query("world");
+    s.typo_derivations_limit(2);
+    let SearchResult { mut documents_ids, .. } = s.execute().unwrap();
+    documents_ids.sort_unstable();
+    insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0, 1, 2, 3, 4]");
+}
+
+#[test]
+fn test_typo_derivations_thread_pool() {
+    let index = create_index();
+    index.update_settings(|s| s.set_criteria(vec![Criterion::Typo])).unwrap();
+    let txn = index.read_txn().unwrap();
+    let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+
+    for query in [
+        "the quack brown fox jumps over the lazy dog",
+        "the quicest brownest fox jummps over the laziest dog",
+        "the quickbrown fox jumps over the lazy dog",
+        "the sunflowar is yellow",
+        "zunflower",
+    ] {
+        let mut s = Search::new(&txn, &index);
+        s.terms_matching_strategy(TermsMatchingStrategy::Last);
+        s.query(query);
+        let SearchResult { documents_ids, document_scores, .. } = s.execute().unwrap();
+
+        let mut s = Search::new(&txn, &index);
+        s.terms_matching_strategy(TermsMatchingStrategy::Last);
+        s.query(query);
+        s.derivations_thread_pool(&thread_pool);
+        let result = s.execute().unwrap();
+        assert_eq!(result.documents_ids, documents_ids, "{query}");
+        assert_eq!(result.document_scores, document_scores, "{query}");
+    }
+}
@@ -10,6 +10,8 @@ account by the proximity ranking rule.
 5. Unclosed double quotes still make a phrase
 6. The `all` term matching strategy does not remove any term from the query
 7. The search is capable of returning no results if no documents match the query
+8. The `frequency` term matching strategy starts removing the terms contained in the fewest
+documents, the most frequent term is never removed
 */
 
 use crate::index::tests::TempIndex;
@@ -458,3 +460,37 @@ fn test_words_tms_all() {
     let texts = collect_field_values(&index, &txn, "text", &documents_ids);
     insta::assert_debug_snapshot!(texts, @"[]");
 }
+
+#[test]
+fn test_words_tms_frequency() {
+    let index = TempIndex::new();
+    index
+        .update_settings(|s| {
+            s.set_primary_key("id".to_owned());
+            s.set_searchable_fields(vec!["text".to_owned()]);
+            s.set_criteria(vec![Criterion::Words]);
+        })
+        .unwrap();
+    index
+        .add_documents(documents!([
+            { "id": 0, "text": "the quick fox" },
+            { "id": 1, "text": "the lazy dog" },
+            { "id": 2, "text": "quick rare bird" },
+            { "id": 3, "text": "the dog" },
+        ]))
+        .unwrap();
+
+    let txn = index.read_txn().unwrap();
+    let search = |strategy| {
+        let mut s = Search::new(&txn, &index);
+        s.query("rare quick the");
+        s.terms_matching_strategy(strategy);
+        let SearchResult { documents_ids, .. } = s.execute().unwrap();
+        documents_ids
+    };
+
+    // `rare` is removed first, then `quick`, and `the` is never removed.
+    insta::assert_snapshot!(format!("{:?}", search(TermsMatchingStrategy::Frequency)), @"[0, 1, 3]");
+    insta::assert_snapshot!(format!("{:?}", search(TermsMatchingStrategy::Last)), @"[2]");
+    insta::assert_snapshot!(format!("{:?}", search(TermsMatchingStrategy::All)), @"[]");
+}
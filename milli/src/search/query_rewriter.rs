@@ -0,0 +1,122 @@
+//! The query rewriters customize the queries before they are tokenized, e.g. to strip
+//! the emojis, to expand the abbreviations of a domain or to apply the rules of a campaign.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Rewrites the query of a search before it is tokenized.
+///
+/// The rewriters of a [`Search`](crate::Search) are applied in the order they were added,
+/// each one receiving the query rewritten by the previous one. A closure taking and returning
+/// a string is also a rewriter.
+pub trait QueryRewriter: Send + Sync {
+    fn rewrite<'q>(&self, query: &'q str) -> Cow<'q, str>;
+}
+
+impl<F> QueryRewriter for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn rewrite<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        Cow::Owned(self(query))
+    }
+}
+
+/// Replaces the words of the query that are abbreviations by their expansion,
+/// e.g. `nyc` by `new york city`. The words are compared without their case.
+#[derive(Debug, Default, Clone)]
+pub struct ExpandAbbreviations {
+    expansions: HashMap<String, String>,
+}
+
+impl ExpandAbbreviations {
+    pub fn new<I, A, E>(expansions: I) -> ExpandAbbreviations
+    where
+        I: IntoIterator<Item = (A, E)>,
+        A: AsRef<str>,
+        E: Into<String>,
+    {
+        let expansions = expansions
+            .into_iter()
+            .map(|(abbreviation, expansion)| {
+                (abbreviation.as_ref().to_lowercase(), expansion.into())
+            })
+            .collect();
+        ExpandAbbreviations { expansions }
+    }
+}
+
+impl QueryRewriter for ExpandAbbreviations {
+    fn rewrite<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        let expansion = |word: &str| self.expansions.get(&word.to_lowercase());
+        if !query.split_whitespace().any(|word| expansion(word).is_some()) {
+            return Cow::Borrowed(query);
+        }
+
+        let words: Vec<_> = query
+            .split_whitespace()
+            .map(|word| expansion(word).map_or(word, String::as_str))
+            .collect();
+        let mut rewritten = words.join(" ");
+        // a trailing whitespace means that the last word is not a prefix
+        if query.ends_with(char::is_whitespace) {
+            rewritten.push(' ');
+        }
+        Cow::Owned(rewritten)
+    }
+}
+
+/// Applies the rewriters in order, returns the query unchanged if there are none.
+pub(crate) fn rewrite_query<'q>(rewriters: &[&dyn QueryRewriter], query: &'q str) -> Cow<'q, str> {
+    let mut query = Cow::Borrowed(query);
+    for rewriter in rewriters {
+        let rewritten = match rewriter.rewrite(&query) {
+            Cow::Owned(rewritten) => Some(rewritten),
+            Cow::Borrowed(_) => None,
+        };
+        if let Some(rewritten) = rewritten {
+            query = Cow::Owned(rewritten);
+        }
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn expand_the_abbreviations() {
+        let rewriter = ExpandAbbreviations::new([("NYC", "new york city"), ("la", "los angeles")]);
+        assert_eq!(rewriter.rewrite("hotels nyc"), "hotels new york city");
+        assert_eq!(rewriter.rewrite("La  pizza "), "los angeles pizza ");
+        assert!(matches!(rewriter.rewrite("hotels paris"), Cow::Borrowed("hotels paris")));
+    }
+
+    #[test]
+    fn apply_the_rewriters_in_order() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hotels in new york city" },
+                { "id": 1, "title": "hotels in los angeles" },
+                { "id": 2, "title": "the nyc marathon" },
+            ]))
+            .unwrap();
+
+        let strip_emojis = |query: &str| {
+            query.chars().filter(|c| !matches!(*c, '\u{1F300}'..='\u{1FAFF}')).collect::<String>()
+        };
+        let abbreviations = ExpandAbbreviations::new([("nyc", "new york city")]);
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("🏨 nyc");
+        search.terms_matching_strategy(crate::TermsMatchingStrategy::All);
+        search.query_rewriter(&strip_emojis).query_rewriter(&abbreviations);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        insta::assert_snapshot!(format!("{documents_ids:?}"), @"[0]");
+    }
+}
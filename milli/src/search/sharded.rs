@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::thread;
+
+use roaring::RoaringBitmap;
+
+use super::{SearchQuery, SearchResult};
+use crate::score_details::{ScoreDetails, ScoringStrategy};
+use crate::{DocumentId, Index, Result};
+
+/// Executes a search on every shard of a sharded index and merges the results.
+///
+/// Every shard is searched in its own thread with its own read transaction. The ranked
+/// documents of the shards are merged by global score and the documents that are returned
+/// by several shards are deduplicated by external id, only the best ranked one is kept.
+///
+/// The ranking rules that don't give a score, like the sort, are ignored while merging.
+pub struct ShardedSearch<'a> {
+    shards: &'a [Index],
+    query: SearchQuery,
+}
+
+/// A document returned by a [`ShardedSearch`].
+#[derive(Debug, Clone)]
+pub struct ShardedHit {
+    /// The position of the shard in the list of shards.
+    pub shard: usize,
+    /// The internal id of the document in its shard.
+    pub document_id: DocumentId,
+    pub external_id: String,
+    pub score: Vec<ScoreDetails>,
+}
+
+#[derive(Debug, Default)]
+pub struct ShardedSearchResult {
+    pub hits: Vec<ShardedHit>,
+    /// The candidates of every shard, in the order of the shards.
+    pub candidates: Vec<RoaringBitmap>,
+}
+
+impl ShardedSearchResult {
+    /// An estimation of the number of distinct matching documents,
+    /// the documents present in several shards are counted several times.
+    pub fn estimated_total_hits(&self) -> u64 {
+        self.candidates.iter().map(RoaringBitmap::len).sum()
+    }
+}
+
+impl<'a> ShardedSearch<'a> {
+    pub fn new(shards: &'a [Index], query: SearchQuery) -> ShardedSearch<'a> {
+        ShardedSearch { shards, query }
+    }
+
+    pub fn execute(&self) -> Result<ShardedSearchResult> {
+        let shards_hits: Vec<Result<(Vec<ShardedHit>, RoaringBitmap)>> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .shards
+                .iter()
+                .enumerate()
+                .map(|(shard, index)| scope.spawn(move || self.execute_on_shard(shard, index)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        });
+
+        let mut candidates = Vec::with_capacity(shards_hits.len());
+        let mut heads = Vec::with_capacity(shards_hits.len());
+        for result in shards_hits {
+            let (hits, shard_candidates) = result?;
+            candidates.push(shard_candidates);
+            heads.push(hits.into_iter().peekable());
+        }
+
+        // A k-way merge of the ranked documents of the shards, the global score
+        // follows the order of the buckets, the first shard wins the ties.
+        let wanted = self.query.offset + self.query.limit;
+        let mut seen_external_ids = HashSet::new();
+        let mut hits = Vec::new();
+        while hits.len() < wanted {
+            let mut best: Option<(usize, f64)> = None;
+            for (shard, head) in heads.iter_mut().enumerate() {
+                if let Some(hit) = head.peek() {
+                    let score = ScoreDetails::global_score(hit.score.iter());
+                    if best.map_or(true, |(_, best_score)| score > best_score) {
+                        best = Some((shard, score));
+                    }
+                }
+            }
+            let hit = match best.and_then(|(shard, _)| heads[shard].next()) {
+                Some(hit) => hit,
+                None => break,
+            };
+            if seen_external_ids.insert(hit.external_id.clone()) {
+                hits.push(hit);
+            }
+        }
+
+        let hits = hits.into_iter().skip(self.query.offset).collect();
+        Ok(ShardedSearchResult { hits, candidates })
+    }
+
+    fn execute_on_shard(
+        &self,
+        shard: usize,
+        index: &Index,
+    ) -> Result<(Vec<ShardedHit>, RoaringBitmap)> {
+        let rtxn = index.read_txn()?;
+        let mut search = self.query.search(&rtxn, index)?;
+        // A shard can't know which of its documents are in the requested
+        // page of the merged results, it must return all the previous ones.
+        search
+            .offset(0)
+            .limit(self.query.offset + self.query.limit)
+            .scoring_strategy(ScoringStrategy::Detailed);
+
+        let SearchResult { candidates, documents_ids, document_scores, .. } = search.execute()?;
+
+        let mut hits = Vec::with_capacity(documents_ids.len());
+        for (document_id, score) in documents_ids.into_iter().zip(document_scores) {
+            // A document without a valid external id can't be a duplicate.
+            let external_id = index
+                .external_id_of(&rtxn, document_id)?
+                .unwrap_or_else(|| format!("{shard}\u{0}{document_id}"));
+            hits.push(ShardedHit { shard, document_id, external_id, score });
+        }
+
+        Ok((hits, candidates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    fn create_shard(
+        documents: crate::documents::DocumentsBatchReader<std::io::Cursor<Vec<u8>>>,
+    ) -> TempIndex {
+        let index = TempIndex::new();
+        index
+            .update_settings(|s| {
+                s.set_primary_key(S("id"));
+                s.set_filterable_fields(hashset! { S("genre") });
+            })
+            .unwrap();
+        index.add_documents(documents).unwrap();
+        index
+    }
+
+    fn shards() -> (Vec<TempIndex>, Vec<Index>) {
+        let shards = vec![
+            create_shard(documents!([
+                { "id": 0, "title": "the quick brown fox", "genre": "tale" },
+                { "id": 1, "title": "the lazy dog", "genre": "tale" },
+                { "id": 2, "title": "a dog", "genre": "puzzle" },
+            ])),
+            create_shard(documents!([
+                { "id": "1", "title": "the lazy dog", "genre": "tale" },
+                { "id": 3, "title": "the dog and the quick fox", "genre": "tale" },
+                { "id": 4, "title": "brown bread", "genre": "cooking" },
+            ])),
+        ];
+        let inner = shards.iter().map(|shard| shard.inner.clone()).collect();
+        (shards, inner)
+    }
+
+    fn external_ids(result: &ShardedSearchResult) -> Vec<&str> {
+        result.hits.iter().map(|hit| hit.external_id.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_and_deduplicate() {
+        let (_shards, shards) = shards();
+
+        let query = SearchQuery { query: Some(S("dog")), ..Default::default() };
+        let result = ShardedSearch::new(&shards, query).execute().unwrap();
+        let mut ids = external_ids(&result);
+        ids.sort_unstable();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert_eq!(result.estimated_total_hits(), 4);
+
+        let scores: Vec<_> =
+            result.hits.iter().map(|hit| ScoreDetails::global_score(hit.score.iter())).collect();
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+
+        let query = SearchQuery {
+            query: Some(S("quick fox")),
+            filter: Some(S("genre = tale")),
+            ..Default::default()
+        };
+        let result = ShardedSearch::new(&shards, query).execute().unwrap();
+        let mut ids = external_ids(&result);
+        ids.sort_unstable();
+        assert_eq!(ids, ["0", "3"]);
+    }
+
+    #[test]
+    fn paginate_merged_results() {
+        let (_shards, shards) = shards();
+
+        let query = SearchQuery { limit: 10, ..Default::default() };
+        let all = ShardedSearch::new(&shards, query).execute().unwrap();
+        assert_eq!(all.hits.len(), 5);
+
+        let query = SearchQuery { offset: 2, limit: 2, ..Default::default() };
+        let page = ShardedSearch::new(&shards, query).execute().unwrap();
+        assert_eq!(external_ids(&page), external_ids(&all)[2..4]);
+    }
+}
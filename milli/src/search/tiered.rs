@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use roaring::RoaringBitmap;
+
+use super::{SearchQuery, SearchResult};
+use crate::score_details::{ScoreDetails, ScoringStrategy};
+use crate::{DocumentId, Index, Result, SealedSearchSettings, SealedSegment, UserError};
+
+/// Executes a search on an index and on the sealed segments exported from it, and merges
+/// the results.
+///
+/// The sealed segments only contain the words of the documents, they are searched with the
+/// tokenizer and typo tolerance settings of the index but they can't be filtered nor sorted,
+/// such queries return an error. The hits are merged by the score of the words ranking rule,
+/// the only one the segments know, the documents of the index come first on equal scores.
+/// The index holds the latest version of the documents: a document of a segment whose
+/// external id is still in the index, or in a previous segment, is never returned.
+pub struct TieredSearch<'a, D> {
+    index: &'a Index,
+    segments: &'a [SealedSegment<D>],
+    query: SearchQuery,
+}
+
+/// Where a document returned by a [`TieredSearch`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// The index, i.e. the live tier.
+    Live,
+    /// The sealed segment at this position in the list of segments.
+    Sealed(usize),
+}
+
+/// A document returned by a [`TieredSearch`].
+#[derive(Debug, Clone)]
+pub struct TieredHit {
+    pub tier: Tier,
+    /// The internal id of the document in its tier.
+    pub document_id: DocumentId,
+    pub external_id: String,
+    pub score: Vec<ScoreDetails>,
+}
+
+#[derive(Debug, Default)]
+pub struct TieredSearchResult {
+    pub hits: Vec<TieredHit>,
+    pub live_candidates: RoaringBitmap,
+    /// The candidates of every sealed segment, in the order of the segments, without the
+    /// documents replaced by the index or by a previous segment.
+    pub sealed_candidates: Vec<RoaringBitmap>,
+}
+
+impl TieredSearchResult {
+    /// The number of distinct matching documents.
+    pub fn estimated_total_hits(&self) -> u64 {
+        self.live_candidates.len()
+            + self.sealed_candidates.iter().map(RoaringBitmap::len).sum::<u64>()
+    }
+}
+
+impl<'a, D: AsRef<[u8]>> TieredSearch<'a, D> {
+    pub fn new(
+        index: &'a Index,
+        segments: &'a [SealedSegment<D>],
+        query: SearchQuery,
+    ) -> TieredSearch<'a, D> {
+        TieredSearch { index, segments, query }
+    }
+
+    pub fn execute(&self) -> Result<TieredSearchResult> {
+        if !self.segments.is_empty() {
+            if self.query.filter.is_some() {
+                return Err(UserError::SealedSegmentsCannotBeFiltered.into());
+            }
+            if self.query.sort_criteria.is_some() {
+                return Err(UserError::SealedSegmentsCannotBeSorted.into());
+            }
+        }
+
+        let rtxn = self.index.read_txn()?;
+        let mut search = self.query.search(&rtxn, self.index)?;
+        // The hits of the segments can be ranked before any document of the index,
+        // every tier must return enough documents to fill the page alone.
+        let wanted = self.query.offset + self.query.limit;
+        search.offset(0).limit(wanted).scoring_strategy(ScoringStrategy::Detailed);
+
+        let SearchResult { candidates, documents_ids, document_scores, .. } = search.execute()?;
+
+        let mut hits = Vec::with_capacity(documents_ids.len());
+        for (document_id, score) in documents_ids.into_iter().zip(document_scores) {
+            // A document without a valid external id gets one that no segment can contain.
+            let external_id = self
+                .index
+                .external_id_of(&rtxn, document_id)?
+                .unwrap_or_else(|| format!("\u{0}{document_id}"));
+            hits.push(TieredHit { tier: Tier::Live, document_id, external_id, score });
+        }
+
+        let mut sealed_candidates = Vec::new();
+        let external_documents_ids = self.index.external_documents_ids(&rtxn)?;
+        let settings = SealedSearchSettings::from_index(self.index, &rtxn)?;
+        let query = self.query.query.as_deref().unwrap_or_default();
+        let mut seen_external_ids = HashSet::new();
+        for (position, segment) in self.segments.iter().enumerate() {
+            let result = segment.search_with_settings(query, &settings)?;
+            // All the candidates are checked to count the distinct documents.
+            let mut candidates = RoaringBitmap::new();
+            for document_id in result.candidates.iter() {
+                let Some((external_id, _)) = segment.document(document_id)? else { continue };
+                if external_documents_ids.get(external_id).is_some()
+                    || !seen_external_ids.insert(external_id.to_string())
+                {
+                    continue;
+                }
+                if candidates.len() < wanted as u64 {
+                    hits.push(TieredHit {
+                        tier: Tier::Sealed(position),
+                        document_id,
+                        external_id: external_id.to_string(),
+                        score: result.score.clone(),
+                    });
+                }
+                candidates.insert(document_id);
+            }
+            sealed_candidates.push(candidates);
+        }
+
+        // The sort is stable, the documents of the index stay first on equal scores.
+        hits.sort_by(|a, b| words_score(&b.score).total_cmp(&words_score(&a.score)));
+        let hits = hits.into_iter().skip(self.query.offset).take(self.query.limit).collect();
+        Ok(TieredSearchResult { hits, live_candidates: candidates, sealed_candidates })
+    }
+}
+
+/// The score of the words ranking rule, the only one computed by the sealed segments.
+fn words_score(score: &[ScoreDetails]) -> f64 {
+    ScoreDetails::global_score(
+        score.iter().filter(|details| matches!(details, ScoreDetails::Words(_))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{AscDesc, Error, Member};
+
+    /// Returns an index with the documents 3 and 4, and a segment
+    /// with the documents 0 to 3, the document 3 was updated after it was sealed.
+    fn create_tiers() -> (TempIndex, Vec<SealedSegment<Vec<u8>>>) {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the old quick fox" },
+                { "id": 1, "title": "the old lazy dog" },
+                { "id": 2, "title": "the old quick dog" },
+                { "id": 3, "title": "the old brown dog" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let all_documents = index.documents_ids(&rtxn).unwrap();
+        let segment = index.sealed_segment(&rtxn, &all_documents).unwrap();
+        drop(rtxn);
+
+        for external_id in ["0", "1", "2"] {
+            index.delete_document(external_id);
+        }
+        index
+            .add_documents(documents!([
+                { "id": 3, "title": "the new brown cat" },
+                { "id": 4, "title": "the new quick dog" },
+            ]))
+            .unwrap();
+
+        (index, vec![SealedSegment::new(segment).unwrap()])
+    }
+
+    fn external_ids(result: &TieredSearchResult) -> Vec<&str> {
+        result.hits.iter().map(|hit| hit.external_id.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_live_and_sealed_documents() {
+        let (index, segments) = create_tiers();
+
+        let query = SearchQuery::new().query("quick");
+        let result = TieredSearch::new(&index, &segments, query).execute().unwrap();
+        assert_eq!(external_ids(&result), ["4", "0", "2"]);
+        assert_eq!(result.hits[0].tier, Tier::Live);
+        assert_eq!(result.hits[1].tier, Tier::Sealed(0));
+        assert_eq!(result.estimated_total_hits(), 3);
+
+        // the sealed version of the document 3 is replaced by the live one
+        let query = SearchQuery::new().query("dog");
+        let result = TieredSearch::new(&index, &segments, query).execute().unwrap();
+        assert_eq!(external_ids(&result), ["4", "1", "2"]);
+        assert_eq!(result.sealed_candidates, vec![RoaringBitmap::from_iter([1, 2])]);
+        assert_eq!(result.estimated_total_hits(), 3);
+
+        // the sealed document matching all the words is ranked first
+        let query = SearchQuery::new().query("quick fox");
+        let result = TieredSearch::new(&index, &segments, query).execute().unwrap();
+        assert_eq!(external_ids(&result), ["0", "4"]);
+
+        // the segments are searched with the typo tolerance of the index
+        let query = SearchQuery::new().query("qvick");
+        let result = TieredSearch::new(&index, &segments, query).execute().unwrap();
+        assert_eq!(external_ids(&result), ["4", "0", "2"]);
+    }
+
+    #[test]
+    fn filter_and_sort_are_rejected() {
+        let (index, segments) = create_tiers();
+        index
+            .update_settings(|s| {
+                s.set_filterable_fields(hashset! { S("id") });
+                s.set_sortable_fields(hashset! { S("id") });
+            })
+            .unwrap();
+
+        let filter =
+            SearchQuery { query: Some(S("dog")), filter: Some(S("id > 3")), ..Default::default() };
+        let error = TieredSearch::new(&index, &segments, filter.clone()).execute().unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::SealedSegmentsCannotBeFiltered)));
+        let sort = SearchQuery {
+            sort_criteria: Some(vec![AscDesc::Asc(Member::Field(S("id")))]),
+            ..Default::default()
+        };
+        let error = TieredSearch::new(&index, &segments, sort).execute().unwrap_err();
+        assert!(matches!(error, Error::UserError(UserError::SealedSegmentsCannotBeSorted)));
+
+        // the index alone can still be filtered
+        let result = TieredSearch::new(&index, &segments[..0], filter).execute().unwrap();
+        assert_eq!(external_ids(&result), ["4"]);
+    }
+
+    #[test]
+    fn paginate_merged_results() {
+        let (index, segments) = create_tiers();
+
+        let query = SearchQuery::new().limit(10);
+        let all = TieredSearch::new(&index, &segments, query).execute().unwrap();
+        assert_eq!(all.hits.len(), 5);
+        assert_eq!(external_ids(&all)[2..], ["0", "1", "2"]);
+
+        let query = SearchQuery::new().offset(1).limit(2);
+        let page = TieredSearch::new(&index, &segments, query).execute().unwrap();
+        assert_eq!(external_ids(&page), external_ids(&all)[1..3]);
+    }
+}
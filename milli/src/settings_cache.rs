@@ -0,0 +1,58 @@
+//! The settings read by every search are parsed once and cached by the [`Index`](crate::Index),
+//! instead of being deserialized for every query.
+//!
+//! Every update of these settings writes a new random token in the main database, a read
+//! transaction only uses the cached settings if they were read with the token it sees. The
+//! searches thus always use the settings of their snapshot, and the token of an aborted
+//! update can't be reused by another one.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arc_swap::ArcSwapOption;
+
+use crate::{Criterion, FieldId};
+
+/// The settings read by every search, see [`Index::cached_settings`](crate::Index::cached_settings).
+#[derive(Debug, Default)]
+pub struct CachedSettings {
+    pub criteria: Vec<Criterion>,
+    pub searchable_fields_ids: Option<Vec<FieldId>>,
+    pub filterable_fields: HashSet<String>,
+    pub synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    /// The words of the dictionary, see [`Index::dictionary`](crate::Index::dictionary).
+    pub dictionary: Option<Vec<String>>,
+}
+
+impl CachedSettings {
+    /// Returns the words of the dictionary as expected by the tokenizer,
+    /// see [`charabia::TokenizerBuilder::words_dict`].
+    pub fn words_dict(&self) -> Option<Vec<&str>> {
+        self.dictionary.as_ref().map(|words| words.iter().map(String::as_str).collect())
+    }
+}
+
+/// The last settings read, along with the token they were read with.
+///
+/// The clones of an index share the same cache.
+#[derive(Default, Clone)]
+pub(crate) struct SettingsCache {
+    inner: Arc<ArcSwapOption<(Option<Vec<u8>>, Arc<CachedSettings>)>>,
+}
+
+impl SettingsCache {
+    /// Returns the cached settings if they were read with this token.
+    pub fn get(&self, token: Option<&[u8]>) -> Option<Arc<CachedSettings>> {
+        let cached = self.inner.load();
+        match cached.as_deref() {
+            Some((cached_token, settings)) if cached_token.as_deref() == token => {
+                Some(settings.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn store(&self, token: Option<&[u8]>, settings: Arc<CachedSettings>) {
+        self.inner.store(Some(Arc::new((token.map(ToOwned::to_owned), settings))));
+    }
+}
@@ -0,0 +1,173 @@
+//! This module scans the words of an index to suggest the ones that could become stop words,
+//! i.e. the words appearing in such a large share of the documents that they don't help to
+//! rank them, like `the` or `of` in English.
+//!
+//! The suggestions are only suggestions, a word appearing in most of the documents of an
+//! index can still be meaningful, e.g. the name of a brand in the index of its products.
+
+#[cfg(feature = "update")]
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use heed::RoTxn;
+#[cfg(feature = "update")]
+use heed::RwTxn;
+use serde::Serialize;
+
+use crate::heed_codec::RoaringBitmapLenCodec;
+#[cfg(feature = "update")]
+use crate::update::{IndexerConfig, Settings};
+use crate::{Index, Result};
+
+const DEFAULT_MIN_RATIO: f64 = 0.5;
+const DEFAULT_SUGGESTIONS_LIMIT: usize = 50;
+
+/// A word appearing in a large share of the documents.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StopWordSuggestion {
+    pub word: String,
+    /// The number of documents containing the word.
+    pub documents: u64,
+    /// The share of the documents of the index containing the word, between 0 and 1.
+    pub ratio: f64,
+}
+
+/// Suggests the words contained in a share of the documents above a ratio.
+pub struct StopWordsSuggestions<'a> {
+    min_ratio: f64,
+    limit: usize,
+    rtxn: &'a RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> StopWordsSuggestions<'a> {
+    pub fn new(rtxn: &'a RoTxn, index: &'a Index) -> StopWordsSuggestions<'a> {
+        StopWordsSuggestions {
+            min_ratio: DEFAULT_MIN_RATIO,
+            limit: DEFAULT_SUGGESTIONS_LIMIT,
+            rtxn,
+            index,
+        }
+    }
+
+    /// Sets the minimum share of the documents, between 0 and 1, that must contain a word
+    /// for it to be suggested, defaults to 0.5.
+    pub fn min_ratio(&mut self, min_ratio: f64) -> &mut Self {
+        self.min_ratio = min_ratio;
+        self
+    }
+
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Returns the suggestions sorted by decreasing number of documents, the words that are
+    /// already stop words are not suggested.
+    pub fn execute(&self) -> Result<Vec<StopWordSuggestion>> {
+        let number_of_documents = self.index.number_of_documents(self.rtxn)?;
+        if number_of_documents == 0 {
+            return Ok(Vec::new());
+        }
+        let stop_words = self.index.stop_words(self.rtxn)?;
+
+        let mut documents_by_word = HashMap::new();
+        let word_docids = self.index.word_docids.remap_data_type::<RoaringBitmapLenCodec>();
+        for result in word_docids.iter(self.rtxn)? {
+            let (word, documents) = result?;
+            documents_by_word.insert(word, documents);
+        }
+        // the words of the exact attributes are stored in another database, a word appearing
+        // in both databases must only count its documents once.
+        for result in self.index.exact_word_docids.iter(self.rtxn)? {
+            let (word, mut docids) = result?;
+            if let Some(word_docids) = self.index.word_docids.get(self.rtxn, word)? {
+                docids |= word_docids;
+            }
+            documents_by_word.insert(word, docids.len());
+        }
+
+        let mut suggestions: Vec<_> = documents_by_word
+            .into_iter()
+            .filter(|(word, _)| stop_words.as_ref().map_or(true, |sw| !sw.contains(word)))
+            .map(|(word, documents)| StopWordSuggestion {
+                word: word.to_string(),
+                documents,
+                ratio: documents as f64 / number_of_documents as f64,
+            })
+            .filter(|suggestion| suggestion.ratio >= self.min_ratio)
+            .collect();
+
+        suggestions.sort_unstable_by(|a, b| {
+            b.documents.cmp(&a.documents).then_with(|| a.word.cmp(&b.word))
+        });
+        suggestions.truncate(self.limit);
+
+        Ok(suggestions)
+    }
+}
+
+/// Adds the words to the stop words of the index and reindexes the documents,
+/// e.g. to apply the [`StopWordSuggestion`]s that were accepted.
+#[cfg(feature = "update")]
+pub fn add_stop_words(
+    wtxn: &mut RwTxn,
+    index: &Index,
+    indexer_config: &IndexerConfig,
+    words: impl IntoIterator<Item = String>,
+) -> Result<()> {
+    let mut stop_words = BTreeSet::new();
+    if let Some(current) = index.stop_words(wtxn)? {
+        for word in current.stream().into_strs()? {
+            stop_words.insert(word);
+        }
+    }
+    stop_words.extend(words);
+
+    let mut settings = Settings::new(wtxn, index, indexer_config);
+    settings.set_stop_words(stop_words);
+    settings.execute(|_| (), || false)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn suggest_the_most_frequent_words() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox" },
+                { "id": 1, "title": "the lazy dog" },
+                { "id": 2, "title": "the dog and the fox" },
+                { "id": 3, "title": "a cat" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let suggestions = index.stop_words_suggestions(&rtxn).execute().unwrap();
+        let suggestions: Vec<_> =
+            suggestions.iter().map(|s| (s.word.as_str(), s.documents)).collect();
+        assert_eq!(suggestions, vec![("the", 3), ("dog", 2), ("fox", 2)]);
+
+        let suggestions = index.stop_words_suggestions(&rtxn).min_ratio(0.7).execute().unwrap();
+        assert_eq!(
+            suggestions,
+            vec![StopWordSuggestion { word: S("the"), documents: 3, ratio: 0.75 }]
+        );
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        add_stop_words(&mut wtxn, &index, &index.indexer_config, [S("the")]).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let suggestions = index.stop_words_suggestions(&rtxn).limit(1).execute().unwrap();
+        let suggestions: Vec<_> = suggestions.iter().map(|s| s.word.as_str()).collect();
+        assert_eq!(suggestions, vec!["dog"]);
+    }
+}
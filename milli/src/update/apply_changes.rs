@@ -0,0 +1,254 @@
+use std::io::Cursor;
+use std::result::Result as StdResult;
+
+use crate::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use crate::error::UserError;
+use crate::update::{
+    DeleteDocuments, DocumentAdditionResult, IndexDocuments, IndexDocumentsConfig,
+    IndexDocumentsMethod, IndexerConfig, UpdateIndexingStep,
+};
+use crate::{Index, Object, Result};
+
+/// A change of a document, keyed by its primary key, as streamed by the change data capture
+/// of another database, e.g. Debezium.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentChange {
+    /// Inserts the document, replacing the document with the same primary key, if any.
+    Insert(Object),
+    /// Updates the given fields of the document with the same primary key,
+    /// the document is inserted if there is none.
+    Update(Object),
+    /// Deletes the document with this primary key, if any.
+    Delete(String),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyChangesResult {
+    /// The number of inserted or updated documents.
+    pub indexed_documents: u64,
+    /// The number of deleted documents.
+    pub deleted_documents: u64,
+    /// The total number of documents in the index after the changes.
+    pub number_of_documents: u64,
+}
+
+/// Applies a list of document changes, in order, in a single transaction.
+///
+/// The consecutive changes of the same kind are applied at once, a list alternating between
+/// kinds of changes is slower to apply than a list of changes grouped by kind.
+pub struct ApplyChanges<'t, 'u, 'i, 'a, FP, FA> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    indexer_config: &'a IndexerConfig,
+    config: IndexDocumentsConfig,
+    progress: FP,
+    should_abort: FA,
+}
+
+impl<'t, 'u, 'i, 'a, FP, FA> ApplyChanges<'t, 'u, 'i, 'a, FP, FA>
+where
+    FP: Fn(UpdateIndexingStep) + Sync,
+    FA: Fn() -> bool + Sync,
+{
+    /// The `update_method` of the config is ignored, it is given by the kind of each change.
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        indexer_config: &'a IndexerConfig,
+        config: IndexDocumentsConfig,
+        progress: FP,
+        should_abort: FA,
+    ) -> ApplyChanges<'t, 'u, 'i, 'a, FP, FA> {
+        ApplyChanges { wtxn, index, indexer_config, config, progress, should_abort }
+    }
+
+    /// Applies the changes, stops at the first change that is invalid, e.g. a document without
+    /// a primary key, in which case the transaction must be aborted.
+    pub fn execute(
+        mut self,
+        changes: Vec<DocumentChange>,
+    ) -> Result<StdResult<ApplyChangesResult, UserError>> {
+        let mut result = ApplyChangesResult {
+            number_of_documents: self.index.number_of_documents(self.wtxn)?,
+            ..Default::default()
+        };
+
+        let mut changes = changes.into_iter().peekable();
+        while let Some(change) = changes.next() {
+            let addition = match change {
+                DocumentChange::Insert(document) => {
+                    let mut documents = vec![document];
+                    while let Some(DocumentChange::Insert(document)) =
+                        changes.next_if(|change| matches!(change, DocumentChange::Insert(_)))
+                    {
+                        documents.push(document);
+                    }
+                    self.index_documents(IndexDocumentsMethod::ReplaceDocuments, documents)?
+                }
+                DocumentChange::Update(document) => {
+                    let mut documents = vec![document];
+                    while let Some(DocumentChange::Update(document)) =
+                        changes.next_if(|change| matches!(change, DocumentChange::Update(_)))
+                    {
+                        documents.push(document);
+                    }
+                    self.index_documents(IndexDocumentsMethod::UpdateDocuments, documents)?
+                }
+                DocumentChange::Delete(external_id) => {
+                    let mut builder = DeleteDocuments::new(self.wtxn, self.index)?;
+                    builder.strategy(self.config.deletion_strategy);
+                    builder.delete_external_id(&external_id);
+                    while let Some(DocumentChange::Delete(external_id)) =
+                        changes.next_if(|change| matches!(change, DocumentChange::Delete(_)))
+                    {
+                        builder.delete_external_id(&external_id);
+                    }
+                    let deletion = builder.execute()?;
+                    result.deleted_documents += deletion.deleted_documents;
+                    result.number_of_documents = deletion.remaining_documents;
+                    continue;
+                }
+            };
+
+            match addition {
+                Ok(addition) => {
+                    result.indexed_documents += addition.indexed_documents;
+                    result.number_of_documents = addition.number_of_documents;
+                }
+                Err(error) => return Ok(Err(error)),
+            }
+        }
+
+        Ok(Ok(result))
+    }
+
+    fn index_documents(
+        &mut self,
+        update_method: IndexDocumentsMethod,
+        documents: Vec<Object>,
+    ) -> Result<StdResult<DocumentAdditionResult, UserError>> {
+        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        for document in &documents {
+            builder.append_json_object(document)?;
+        }
+        let documents = DocumentsBatchReader::from_reader(Cursor::new(builder.into_inner()?))?;
+
+        let config = IndexDocumentsConfig { update_method, ..self.config.clone() };
+        let builder = IndexDocuments::new(
+            self.wtxn,
+            self.index,
+            self.indexer_config,
+            config,
+            &self.progress,
+            &self.should_abort,
+        )?;
+        let (builder, user_result) = builder.add_documents(documents)?;
+        if let Err(error) = user_result {
+            return Ok(Err(error));
+        }
+        Ok(Ok(builder.execute()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::obkv_to_json;
+
+    fn object(value: serde_json::Value) -> Object {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn apply_changes_in_order() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "kevin", "age": 20 },
+                { "id": 1, "name": "kevina" },
+                { "id": 2, "name": "benoit" },
+            ]))
+            .unwrap();
+
+        let changes = vec![
+            DocumentChange::Update(object(json!({ "id": 0, "age": 21 }))),
+            DocumentChange::Insert(object(json!({ "id": 1, "name": "kevina", "city": "Paris" }))),
+            DocumentChange::Delete(String::from("2")),
+            DocumentChange::Insert(object(json!({ "id": 2, "name": "benoît" }))),
+            DocumentChange::Insert(object(json!({ "id": 3, "name": "tamo" }))),
+            DocumentChange::Delete(String::from("3")),
+            DocumentChange::Delete(String::from("4")),
+        ];
+
+        let mut wtxn = index.write_txn().unwrap();
+        let result = ApplyChanges::new(
+            &mut wtxn,
+            &index,
+            &index.indexer_config,
+            index.index_documents_config.clone(),
+            |_| (),
+            || false,
+        )
+        .execute(changes)
+        .unwrap()
+        .unwrap();
+        wtxn.commit().unwrap();
+
+        assert_eq!(
+            result,
+            ApplyChangesResult {
+                indexed_documents: 4,
+                deleted_documents: 2,
+                number_of_documents: 3
+            }
+        );
+
+        let rtxn = index.read_txn().unwrap();
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let fields: Vec<_> = fields_ids_map.ids().collect();
+        let mut documents: Vec<_> = index
+            .all_documents(&rtxn)
+            .unwrap()
+            .map(|result| {
+                let (_, obkv) = result.unwrap();
+                serde_json::Value::Object(obkv_to_json(&fields, &fields_ids_map, obkv).unwrap())
+            })
+            .collect();
+        documents.sort_by_key(|document| document["id"].as_u64());
+        assert_eq!(
+            documents,
+            vec![
+                json!({ "id": 0, "name": "kevin", "age": 21 }),
+                json!({ "id": 1, "name": "kevina", "city": "Paris" }),
+                json!({ "id": 2, "name": "benoît" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_at_an_invalid_change() {
+        let index = TempIndex::new();
+        index.add_documents(documents!([{ "id": 0, "name": "kevin" }])).unwrap();
+
+        let changes = vec![
+            DocumentChange::Delete(String::from("0")),
+            DocumentChange::Insert(object(json!({ "name": "no primary key" }))),
+        ];
+
+        let mut wtxn = index.write_txn().unwrap();
+        let result = ApplyChanges::new(
+            &mut wtxn,
+            &index,
+            &index.indexer_config,
+            index.index_documents_config.clone(),
+            |_| (),
+            || false,
+        )
+        .execute(changes)
+        .unwrap();
+        assert!(matches!(result, Err(UserError::MissingDocumentId { .. })));
+    }
+}
@@ -12,7 +12,17 @@ impl AvailableDocumentsIds {
         docids: &RoaringBitmap,
         soft_deleted_docids: &RoaringBitmap,
     ) -> AvailableDocumentsIds {
-        let used_docids = docids | soft_deleted_docids;
+        AvailableDocumentsIds::from_documents_ids_starting_at(docids, soft_deleted_docids, 0)
+    }
+
+    /// Only gives out the ids that are greater than or equal to `first_docid`.
+    pub fn from_documents_ids_starting_at(
+        docids: &RoaringBitmap,
+        soft_deleted_docids: &RoaringBitmap,
+        first_docid: u32,
+    ) -> AvailableDocumentsIds {
+        let mut used_docids = docids | soft_deleted_docids;
+        used_docids.insert_range(0..first_docid);
 
         match used_docids.max() {
             Some(last_id) => {
@@ -87,4 +97,16 @@ mod tests {
             (0..=u32::max_value()).filter(|&n| ![0, 1, 10, 11, 100, 101, 405, 406].contains(&n));
         left.zip(right).take(500).for_each(|(l, r)| assert_eq!(l, r));
     }
+
+    #[test]
+    fn starting_at() {
+        let mut base = RoaringBitmap::new();
+        base.insert(12);
+        base.insert(15);
+
+        let left =
+            AvailableDocumentsIds::from_documents_ids_starting_at(&base, &RoaringBitmap::new(), 10);
+        let right = (10..=u32::max_value()).filter(|&n| n != 12 && n != 15);
+        left.zip(right).take(500).for_each(|(l, r)| assert_eq!(l, r));
+    }
 }
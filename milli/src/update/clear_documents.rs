@@ -2,7 +2,7 @@ use roaring::RoaringBitmap;
 use time::OffsetDateTime;
 
 use crate::facet::FacetType;
-use crate::{ExternalDocumentsIds, FieldDistribution, Index, Result};
+use crate::{ExternalDocumentsIds, FacetNormalization, FieldDistribution, Index, Result};
 
 pub struct ClearDocuments<'t, 'u, 'i> {
     wtxn: &'t mut heed::RwTxn<'i, 'u>,
@@ -21,6 +21,7 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
             main: _main,
             word_docids,
             exact_word_docids,
+            word_docids_count,
             word_prefix_docids,
             exact_word_prefix_docids,
             word_pair_proximity_docids,
@@ -42,6 +43,10 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
             field_id_docid_facet_strings,
             vector_id_docid,
             documents,
+            // the recorded queries are not related to the documents
+            query_suggestions: _,
+            corrupted_entries: _,
+            settings_cache: _,
         } = self.index;
 
         let empty_roaring = RoaringBitmap::default();
@@ -60,6 +65,8 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         self.index.delete_geo_rtree(self.wtxn)?;
         self.index.delete_geo_faceted_documents_ids(self.wtxn)?;
         self.index.delete_vector_hnsw(self.wtxn)?;
+        // No facet is left, the new ones can be normalized like the words.
+        self.index.put_facet_normalization(self.wtxn, FacetNormalization::Words)?;
 
         // We clean all the faceted documents ids.
         for field_id in faceted_fields {
@@ -80,6 +87,7 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         // Clear the other databases.
         word_docids.clear(self.wtxn)?;
         exact_word_docids.clear(self.wtxn)?;
+        word_docids_count.clear(self.wtxn)?;
         word_prefix_docids.clear(self.wtxn)?;
         exact_word_prefix_docids.clear(self.wtxn)?;
         word_pair_proximity_docids.clear(self.wtxn)?;
@@ -108,6 +116,9 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
 
 #[cfg(test)]
 mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
     use super::*;
     use crate::index::tests::TempIndex;
 
@@ -153,4 +164,49 @@ mod tests {
         assert!(index.field_id_docid_facet_strings.is_empty(&rtxn).unwrap());
         assert!(index.documents.is_empty(&rtxn).unwrap());
     }
+
+    #[test]
+    fn clear_documents_keeps_the_settings() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_searchable_fields(vec![S("name")]);
+                settings.set_filterable_fields(hashset! { S("age") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "kevin", "age": 20 },
+                { "id": 1, "name": "kevina", "age": 21 }
+            ]))
+            .unwrap();
+
+        // Nothing is cleared if the transaction is aborted.
+        let mut wtxn = index.write_txn().unwrap();
+        ClearDocuments::new(&mut wtxn, &index).execute().unwrap();
+        wtxn.abort().unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 2);
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        assert_eq!(ClearDocuments::new(&mut wtxn, &index).execute().unwrap(), 2);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 0);
+        assert_eq!(index.primary_key(&rtxn).unwrap(), Some("id"));
+        assert_eq!(index.searchable_fields(&rtxn).unwrap(), Some(vec!["name"]));
+        assert_eq!(index.filterable_fields(&rtxn).unwrap(), hashset! { S("age") });
+        drop(rtxn);
+
+        // The index can be filled again with the same settings.
+        index.add_documents(documents!([{ "id": 2, "name": "benoit", "age": 22 }])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.query("benoit");
+        search.filter(crate::Filter::from_str("age = 22").unwrap().unwrap());
+        assert_eq!(search.execute().unwrap().documents_ids.len(), 1);
+    }
 }
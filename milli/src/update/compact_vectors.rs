@@ -0,0 +1,173 @@
+use hnsw::Searcher;
+use space::KnnPoints;
+
+use crate::index::Hnsw;
+use crate::{Index, Result, BEU32};
+
+/// The share of dead points above which the deletions rebuild the HNSW.
+pub const DEFAULT_DEAD_POINTS_RATIO_THRESHOLD: f64 = 0.5;
+
+/// Rebuilds the HNSW without the points of the deleted documents.
+///
+/// The points can't be removed from an HNSW, the deletions only forget the vector ids of the
+/// deleted documents, leaving dead points in the graph that are skipped by the search. This
+/// operation rebuilds the graph with the live points once they are too many.
+pub struct CompactVectors<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    dead_points_ratio_threshold: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VectorsCompactionResult {
+    /// The number of points of the HNSW before the compaction.
+    pub points_before: u64,
+    /// The number of points of the HNSW after the compaction.
+    pub points_after: u64,
+}
+
+impl<'t, 'u, 'i> CompactVectors<'t, 'u, 'i> {
+    pub fn new(wtxn: &'t mut heed::RwTxn<'i, 'u>, index: &'i Index) -> CompactVectors<'t, 'u, 'i> {
+        CompactVectors {
+            wtxn,
+            index,
+            dead_points_ratio_threshold: DEFAULT_DEAD_POINTS_RATIO_THRESHOLD,
+        }
+    }
+
+    /// Sets the share of dead points, between 0 and 1, above which the HNSW is rebuilt,
+    /// 0 always rebuilds it.
+    pub fn dead_points_ratio_threshold(&mut self, threshold: f64) -> &mut Self {
+        self.dead_points_ratio_threshold = threshold;
+        self
+    }
+
+    /// Returns `None` if the HNSW didn't have enough dead points to be rebuilt.
+    pub fn execute(self) -> Result<Option<VectorsCompactionResult>> {
+        let vector_id_docid = self.index.vector_id_docid;
+        let current_hnsw = match self.index.vector_hnsw(self.wtxn)? {
+            Some(hnsw) if !hnsw.is_empty() => hnsw,
+            _ => return Ok(None),
+        };
+
+        let points_before = current_hnsw.len() as u64;
+        let live_points = vector_id_docid.len(self.wtxn)?;
+        let dead_points = points_before.saturating_sub(live_points);
+        let dead_ratio = dead_points as f64 / points_before as f64;
+        if dead_points == 0 || dead_ratio < self.dead_points_ratio_threshold {
+            return Ok(None);
+        }
+
+        let mut new_hnsw = Hnsw::default();
+        let mut searcher = Searcher::new();
+        let mut new_vector_id_docids = Vec::with_capacity(live_points as usize);
+        for result in vector_id_docid.iter(self.wtxn)? {
+            let (vector_id, docid) = result?;
+            let vector = current_hnsw.get_point(vector_id.get() as usize).clone();
+            let vector_id = new_hnsw.insert(vector, &mut searcher);
+            new_vector_id_docids.push((vector_id as u32, docid));
+        }
+
+        vector_id_docid.clear(self.wtxn)?;
+        for (vector_id, docid) in new_vector_id_docids {
+            vector_id_docid.put(self.wtxn, &BEU32::new(vector_id), &docid)?;
+        }
+        self.index.put_vector_hnsw(self.wtxn, &new_hnsw)?;
+
+        Ok(Some(VectorsCompactionResult { points_before, points_after: new_hnsw.len() as u64 }))
+    }
+}
+
+/// Forgets the vector ids of the deleted documents, their points stay in the HNSW
+/// until it is compacted.
+pub(crate) fn remove_vector_ids(
+    wtxn: &mut heed::RwTxn,
+    index: &Index,
+    to_delete_docids: &roaring::RoaringBitmap,
+) -> Result<()> {
+    let mut iter = index.vector_id_docid.iter_mut(wtxn)?;
+    while let Some(result) = iter.next() {
+        let (_, docid) = result?;
+        if to_delete_docids.contains(docid.get()) {
+            // safety: we don't keep references from inside the LMDB database.
+            unsafe { iter.del_current()? };
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::update::DeletionStrategy;
+
+    #[test]
+    fn compact_the_points_of_the_deleted_documents() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.deletion_strategy = DeletionStrategy::AlwaysHard;
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": [1.0, 0.0] },
+                { "id": 1, "_vectors": [0.0, 1.0] },
+                { "id": 2, "_vectors": [1.0, 1.0] },
+                { "id": 3, "_vectors": [-1.0, 0.0] },
+            ]))
+            .unwrap();
+
+        // a single deletion doesn't reach the threshold
+        index.delete_document("0");
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.vector_hnsw(&rtxn).unwrap().unwrap().len(), 4);
+        assert_eq!(index.vector_id_docid.len(&rtxn).unwrap(), 3);
+
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0]);
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids.len(), 3);
+        assert!(!documents_ids.contains(&0));
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut compaction = CompactVectors::new(&mut wtxn, &index);
+        compaction.dead_points_ratio_threshold(0.0);
+        let result = compaction.execute().unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(result, Some(VectorsCompactionResult { points_before: 4, points_after: 3 }));
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.vector_hnsw(&rtxn).unwrap().unwrap().len(), 3);
+        assert_eq!(index.vector_id_docid.len(&rtxn).unwrap(), 3);
+    }
+
+    #[test]
+    fn search_skips_the_dead_points() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.deletion_strategy = DeletionStrategy::AlwaysHard;
+
+        // the points are spread on a quarter circle, the first ones are the nearest of [1, 0]
+        let documents = (0..200).map(|i| {
+            let angle = i as f32 * 0.005;
+            let document = serde_json::json!({ "id": i, "_vectors": [angle.cos(), angle.sin()] });
+            document.as_object().unwrap().clone()
+        });
+        index
+            .add_documents(crate::documents::documents_batch_reader_from_objects(documents))
+            .unwrap();
+
+        // the nearest points are dead but not enough to rebuild the HNSW
+        for i in 0..90 {
+            index.delete_document(&i.to_string());
+        }
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.vector_hnsw(&rtxn).unwrap().unwrap().len(), 200);
+
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0]);
+        search.limit(20);
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids.len(), 20);
+        assert!(documents_ids.iter().all(|&docid| docid >= 90));
+    }
+}
@@ -4,21 +4,20 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 use fst::IntoStreamer;
 use heed::types::{ByteSlice, DecodeIgnore, Str, UnalignedSlice};
 use heed::{BytesDecode, BytesEncode, Database, RwIter};
-use hnsw::Searcher;
 use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize};
-use space::KnnPoints;
 use time::OffsetDateTime;
 
+use super::compact_vectors::remove_vector_ids;
 use super::facet::delete::FacetsDelete;
-use super::ClearDocuments;
+use super::{ClearDocuments, CompactVectors};
 use crate::error::InternalError;
 use crate::facet::FacetType;
 use crate::heed_codec::facet::FieldDocIdFacetCodec;
 use crate::heed_codec::CboRoaringBitmapCodec;
-use crate::index::Hnsw;
 use crate::{
-    ExternalDocumentsIds, FieldId, FieldIdMapMissingEntry, Index, Result, RoaringBitmapCodec, BEU32,
+    ExternalDocumentsIds, FieldId, FieldIdMapMissingEntry, Filter, Index, Result,
+    RoaringBitmapCodec, BEU32, BEU64,
 };
 
 pub struct DeleteDocuments<'t, 'u, 'i> {
@@ -109,12 +108,31 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         self.delete_document(docid);
         Some(docid)
     }
+
+    /// Deletes the documents matching the filter, returns the number of matching documents.
+    pub fn delete_documents_by_filter(&mut self, filter: &Filter) -> Result<u64> {
+        let docids = filter.evaluate(self.wtxn, self.index)?;
+        self.delete_documents(&docids);
+        Ok(docids.len())
+    }
+
     pub fn execute(self) -> Result<DocumentDeletionResult> {
         let DetailedDocumentDeletionResult { deleted_documents, remaining_documents } =
             self.execute_inner()?;
 
         Ok(DocumentDeletionResult { deleted_documents, remaining_documents })
     }
+
+    /// Deletes the documents to delete, if any, and definitely removes all the soft-deleted
+    /// documents from the databases, returns the number of removed documents.
+    pub fn purge_soft_deleted(mut self) -> Result<u64> {
+        let soft_deleted_docids = self.index.soft_deleted_documents_ids(self.wtxn)?;
+        let purged = (soft_deleted_docids | &self.to_delete_docids).len();
+        self.strategy = DeletionStrategy::AlwaysHard;
+        self.execute_inner()?;
+        Ok(purged)
+    }
+
     pub(crate) fn execute_inner(mut self) -> Result<DetailedDocumentDeletionResult> {
         self.index.set_updated_at(self.wtxn, &OffsetDateTime::now_utc())?;
 
@@ -225,6 +243,7 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             main: _main,
             word_docids,
             exact_word_docids,
+            word_docids_count,
             word_prefix_docids,
             exact_word_prefix_docids,
             word_pair_proximity_docids,
@@ -244,8 +263,11 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             facet_id_exists_docids,
             facet_id_is_null_docids,
             facet_id_is_empty_docids,
-            vector_id_docid,
+            vector_id_docid: _,
             documents,
+            query_suggestions: _,
+            corrupted_entries: _,
+            settings_cache: _,
         } = self.index;
         // Remove from the documents database
         for docid in &self.to_delete_docids {
@@ -264,13 +286,20 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         let mut words_to_delete = BTreeSet::default();
         // We iterate over the words and delete the documents ids
         // from the word docids database.
-        remove_from_word_docids(
+        let word_counts = remove_from_word_docids(
             self.wtxn,
             word_docids,
             &self.to_delete_docids,
             &mut words_to_keep,
             &mut words_to_delete,
         )?;
+        for (word, count) in word_counts {
+            if count == 0 {
+                word_docids_count.delete(self.wtxn, &word)?;
+            } else {
+                word_docids_count.put(self.wtxn, &word, &BEU64::new(count))?;
+            }
+        }
         remove_from_word_docids(
             self.wtxn,
             exact_word_docids,
@@ -434,29 +463,10 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             &self.to_delete_docids,
         )?;
 
-        // An ugly and slow way to remove the vectors from the HNSW
-        // It basically reconstructs the HNSW from scratch without editing the current one.
-        let current_hnsw = self.index.vector_hnsw(self.wtxn)?.unwrap_or_default();
-        if !current_hnsw.is_empty() {
-            let mut new_hnsw = Hnsw::default();
-            let mut searcher = Searcher::new();
-            let mut new_vector_id_docids = Vec::new();
-
-            for result in vector_id_docid.iter(self.wtxn)? {
-                let (vector_id, docid) = result?;
-                if !self.to_delete_docids.contains(docid.get()) {
-                    let vector = current_hnsw.get_point(vector_id.get() as usize).clone();
-                    let vector_id = new_hnsw.insert(vector, &mut searcher);
-                    new_vector_id_docids.push((vector_id as u32, docid));
-                }
-            }
-
-            vector_id_docid.clear(self.wtxn)?;
-            for (vector_id, docid) in new_vector_id_docids {
-                vector_id_docid.put(self.wtxn, &BEU32::new(vector_id), &docid)?;
-            }
-            self.index.put_vector_hnsw(self.wtxn, &new_hnsw)?;
-        }
+        // The points can't be removed from the HNSW, we only forget the vector ids of the
+        // deleted documents and rebuild the HNSW once there are too many dead points.
+        remove_vector_ids(self.wtxn, self.index, &self.to_delete_docids)?;
+        CompactVectors::new(self.wtxn, self.index).execute()?;
 
         self.index.put_soft_deleted_documents_ids(self.wtxn, &RoaringBitmap::new())?;
 
@@ -525,7 +535,8 @@ fn remove_from_word_docids(
     to_remove: &RoaringBitmap,
     words_to_keep: &mut BTreeSet<String>,
     words_to_remove: &mut BTreeSet<String>,
-) -> Result<()> {
+) -> Result<Vec<(String, u64)>> {
+    let mut new_counts = Vec::new();
     // We create an iterator to be able to get the content and delete the word docids.
     // It's faster to acquire a cursor to get and delete or put, as we avoid traversing
     // the LMDB B-Tree two times but only once.
@@ -537,17 +548,19 @@ fn remove_from_word_docids(
             // safety: we don't keep references from inside the LMDB database.
             unsafe { iter.del_current()? };
             words_to_remove.insert(key.to_owned());
+            new_counts.push((key.to_owned(), 0));
         } else {
             words_to_keep.insert(key.to_owned());
             if docids.len() != previous_len {
                 let key = key.to_owned();
                 // safety: we don't keep references from inside the LMDB database.
                 unsafe { iter.put_current(&key, &docids)? };
+                new_counts.push((key, docids.len()));
             }
         }
     }
 
-    Ok(())
+    Ok(new_counts)
 }
 
 fn remove_docids_from_field_id_docid_facet_value(
@@ -790,6 +803,125 @@ mod tests {
         db_snap!(index, facet_id_string_docids, deletion_strategy);
     }
 
+    fn delete_documents_by_filter_(deletion_strategy: DeletionStrategy) {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the ghostly manor", "genre": "horror" },
+                { "id": 1, "title": "the haunted manor", "genre": "horror" },
+                { "id": 2, "title": "the sunny manor", "genre": "comedy" },
+            ]))
+            .unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        let filter = Filter::from_str("genre = horror").unwrap().unwrap();
+        assert_eq!(builder.delete_documents_by_filter(&filter).unwrap(), 2);
+        builder.delete_external_id("2");
+        builder.strategy(deletion_strategy);
+        let result = builder.execute().unwrap();
+        assert_eq!(result, DocumentDeletionResult { deleted_documents: 3, remaining_documents: 0 });
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        for query in ["ghostly", "manor", "haunt", "sunny"] {
+            let results = index.search(&rtxn).query(query).execute().unwrap();
+            assert!(results.documents_ids.is_empty());
+        }
+    }
+
+    #[test]
+    fn delete_documents_by_filter() {
+        delete_documents_by_filter_(DeletionStrategy::AlwaysHard);
+        delete_documents_by_filter_(DeletionStrategy::AlwaysSoft);
+    }
+
+    #[test]
+    fn delete_by_filter_removes_orphan_words() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("genre") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the ghostly manor", "genre": "horror" },
+                { "id": 1, "title": "the sunny manor", "genre": "comedy" },
+            ]))
+            .unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        let filter = Filter::from_str("genre = horror").unwrap().unwrap();
+        builder.delete_documents_by_filter(&filter).unwrap();
+        builder.strategy(DeletionStrategy::AlwaysHard);
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let words_fst = index.words_fst(&rtxn).unwrap();
+        assert!(!words_fst.contains("ghostly"));
+        assert!(words_fst.contains("manor"));
+        assert!(index.word_docids.get(&rtxn, "ghostly").unwrap().is_none());
+
+        let results = index.search(&rtxn).query("ghostly").execute().unwrap();
+        assert!(results.documents_ids.is_empty());
+        let results = index.search(&rtxn).query("manor").execute().unwrap();
+        assert_eq!(results.documents_ids, vec![1]);
+    }
+
+    #[test]
+    fn purge_soft_deleted_documents() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the ghostly manor" },
+                { "id": 1, "title": "the sunny manor" },
+                { "id": 2, "title": "the rainy manor" },
+            ]))
+            .unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        delete_documents(&mut wtxn, &index, &["0"], DeletionStrategy::AlwaysSoft);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.soft_deleted_documents_ids(&rtxn).unwrap().len(), 1);
+        // The soft-deleted document is still indexed but never returned.
+        assert!(index.word_docids.get(&rtxn, "ghostly").unwrap().is_some());
+        let results = index.search(&rtxn).query("ghostly").execute().unwrap();
+        assert!(results.documents_ids.is_empty());
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        builder.delete_external_id("1");
+        assert_eq!(builder.purge_soft_deleted().unwrap(), 2);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.soft_deleted_documents_ids(&rtxn).unwrap().is_empty());
+        assert!(index.word_docids.get(&rtxn, "ghostly").unwrap().is_none());
+        assert!(index.word_docids.get(&rtxn, "sunny").unwrap().is_none());
+        assert!(!index.words_fst(&rtxn).unwrap().contains("ghostly"));
+        assert_eq!(index.documents_ids(&rtxn).unwrap(), RoaringBitmap::from_iter([2]));
+        let results = index.search(&rtxn).query("manor").execute().unwrap();
+        assert_eq!(results.documents_ids, vec![2]);
+        drop(rtxn);
+
+        // Purging without any soft-deleted document is a no-op.
+        let mut wtxn = index.write_txn().unwrap();
+        let builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        assert_eq!(builder.purge_soft_deleted().unwrap(), 0);
+        wtxn.commit().unwrap();
+    }
+
     #[test]
     fn filtered_placeholder_search_should_not_return_deleted_documents() {
         filtered_placeholder_search_should_not_return_deleted_documents_(
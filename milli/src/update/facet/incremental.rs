@@ -133,7 +133,7 @@ impl FacetsUpdateIncrementalInner {
                             txn,
                             prefix.as_slice(),
                         )?;
-                    let (key_bytes, value) = iter.next().unwrap()?;
+                    let (key_bytes, value) = iter.next().ok_or(Error::Decoding)??;
                     Ok((
                         FacetGroupKeyCodec::<ByteSliceRefCodec>::bytes_decode(key_bytes)
                             .ok_or(Error::Encoding)?
@@ -326,7 +326,7 @@ impl FacetsUpdateIncrementalInner {
             let (
                 FacetGroupKey { left_bound: right_left_bound, .. },
                 FacetGroupValue { bitmap: mut values_right, .. },
-            ) = iter.next().unwrap()?;
+            ) = iter.next().ok_or(Error::Decoding)??;
 
             for next in iter.by_ref() {
                 let (_, value) = next?;
@@ -399,7 +399,7 @@ impl FacetsUpdateIncrementalInner {
             let mut first_key = None;
             let mut values = RoaringBitmap::new();
             for _ in 0..group_size {
-                let (key_bytes, value_i) = groups_iter.next().unwrap()?;
+                let (key_bytes, value_i) = groups_iter.next().ok_or(Error::Decoding)??;
                 let key_i = FacetGroupKeyCodec::<ByteSliceRefCodec>::bytes_decode(key_bytes)
                     .ok_or(Error::Encoding)?;
 
@@ -411,7 +411,7 @@ impl FacetsUpdateIncrementalInner {
             let key = FacetGroupKey {
                 field_id,
                 level: highest_level + 1,
-                left_bound: first_key.unwrap().left_bound,
+                left_bound: first_key.ok_or(Error::Decoding)?.left_bound,
             };
             let value = FacetGroupValue { size: group_size, bitmap: values };
             to_add.push((key.into_owned(), value));
@@ -422,7 +422,7 @@ impl FacetsUpdateIncrementalInner {
             let mut first_key = None;
             let mut values = RoaringBitmap::new();
             for _ in 0..nbr_leftover_elements {
-                let (key_bytes, value_i) = groups_iter.next().unwrap()?;
+                let (key_bytes, value_i) = groups_iter.next().ok_or(Error::Decoding)??;
                 let key_i = FacetGroupKeyCodec::<ByteSliceRefCodec>::bytes_decode(key_bytes)
                     .ok_or(Error::Encoding)?;
 
@@ -434,7 +434,7 @@ impl FacetsUpdateIncrementalInner {
             let key = FacetGroupKey {
                 field_id,
                 level: highest_level + 1,
-                left_bound: first_key.unwrap().left_bound,
+                left_bound: first_key.ok_or(Error::Decoding)?.left_bound,
             };
             // Note: nbr_leftover_elements can be casted to a u8 since it is bounded by `max_group_size`
             // when it is created above.
@@ -516,7 +516,7 @@ impl FacetsUpdateIncrementalInner {
             let mut updated_deletion_key = deletion_key.clone();
             let reduced_range = facet_value == deletion_key.left_bound;
             if reduced_range {
-                updated_deletion_key.left_bound = next_key.clone().unwrap();
+                updated_deletion_key.left_bound = next_key.clone().ok_or(Error::Decoding)?;
             }
             updated_value.bitmap -= docids;
             let _ = self.db.delete(txn, &deletion_key.as_ref())?;
@@ -537,7 +537,7 @@ impl FacetsUpdateIncrementalInner {
         docids: &RoaringBitmap,
     ) -> Result<DeletionResult> {
         let key = FacetGroupKey { field_id, level: 0, left_bound: facet_value };
-        let mut bitmap = self.db.get(txn, &key)?.unwrap().bitmap;
+        let mut bitmap = self.db.get(txn, &key)?.ok_or(Error::Decoding)?.bitmap;
         bitmap -= docids;
 
         if bitmap.is_empty() {
@@ -73,7 +73,7 @@ Related PR: https://github.com/meilisearch/milli/pull/619
 */
 
 pub const FACET_MAX_GROUP_SIZE: u8 = 8;
-pub const FACET_GROUP_SIZE: u8 = 4;
+pub use crate::heed_codec::facet::FACET_GROUP_SIZE;
 pub const FACET_MIN_LEVEL_SIZE: u8 = 5;
 
 use std::fs::File;
@@ -0,0 +1,754 @@
+//! Builds a fresh index from segments that were produced elsewhere, e.g. by distributed workers.
+//!
+//! A segment is a set of grenad files, one per database, in which the entries are sorted and
+//! encoded like in the LMDB databases of the index. Every database is built with a single k-way
+//! merge of the segments whose output is appended into LMDB, the derived structures (the words
+//! FST, the facet levels and the prefix databases) are computed once at the end.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryInto;
+use std::fs::File;
+
+use fst::Streamer;
+use heed::types::ByteSlice;
+use heed::{BytesDecode, Database, RoTxn};
+use roaring::RoaringBitmap;
+
+use super::helpers::{
+    as_cloneable_grenad, create_writer, keep_first, merge_cbo_roaring_bitmaps,
+    merge_roaring_bitmaps, writer_into_reader, GrenadParameters, MergeFn, MergeableReader,
+};
+use super::typed_chunk::{write_typed_chunk_into_index, TypedChunk};
+use super::{IndexDocuments, IndexDocumentsConfig};
+use crate::error::{SerializationError, UserError};
+use crate::heed_codec::ScriptLanguageCodec;
+use crate::index::db_name;
+use crate::update::IndexerConfig;
+use crate::{
+    CboRoaringBitmapCodec, DocumentId, ExternalDocumentsIds, FacetNormalization, FieldDistribution,
+    FieldsIdsMap, Index, Result, RoaringBitmapCodec,
+};
+
+/// The databases that can be part of a [`Segment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SegmentDatabase {
+    /// The big-endian internal document ids associated with the obkv documents.
+    Documents,
+    /// The external document ids associated with the big-endian internal document ids.
+    ExternalDocumentsIds,
+    WordDocids,
+    ExactWordDocids,
+    WordPairProximityDocids,
+    WordPositionDocids,
+    WordFidDocids,
+    FieldIdWordCountDocids,
+    /// The level 0 entries of the number facets, the value is a bare `CboRoaringBitmap`.
+    FacetIdF64Docids,
+    /// The level 0 entries of the string facets, the value is a bare `CboRoaringBitmap`.
+    FacetIdStringDocids,
+    FacetIdExistsDocids,
+    FacetIdIsNullDocids,
+    FacetIdIsEmptyDocids,
+    FieldIdDocidFacetF64s,
+    FieldIdDocidFacetStrings,
+    /// The big-endian internal document ids associated with the native-endian
+    /// latitude and longitude of the documents.
+    GeoPoints,
+    /// The big-endian internal document ids followed by the big-endian `u16` index of
+    /// the vector in the document, associated with the `f32`s of the vectors.
+    VectorPoints,
+    /// The script and language names separated by a `\0`, the value is a `RoaringBitmap`.
+    ScriptLanguageDocids,
+}
+
+impl SegmentDatabase {
+    pub const ALL: [SegmentDatabase; 18] = [
+        SegmentDatabase::Documents,
+        SegmentDatabase::ExternalDocumentsIds,
+        SegmentDatabase::WordDocids,
+        SegmentDatabase::ExactWordDocids,
+        SegmentDatabase::WordPairProximityDocids,
+        SegmentDatabase::WordPositionDocids,
+        SegmentDatabase::WordFidDocids,
+        SegmentDatabase::FieldIdWordCountDocids,
+        SegmentDatabase::FacetIdF64Docids,
+        SegmentDatabase::FacetIdStringDocids,
+        SegmentDatabase::FacetIdExistsDocids,
+        SegmentDatabase::FacetIdIsNullDocids,
+        SegmentDatabase::FacetIdIsEmptyDocids,
+        SegmentDatabase::FieldIdDocidFacetF64s,
+        SegmentDatabase::FieldIdDocidFacetStrings,
+        SegmentDatabase::GeoPoints,
+        SegmentDatabase::VectorPoints,
+        SegmentDatabase::ScriptLanguageDocids,
+    ];
+
+    fn merge_function(&self) -> MergeFn {
+        match self {
+            SegmentDatabase::Documents => refuse_shared_internal_id,
+            SegmentDatabase::ExternalDocumentsIds => refuse_shared_external_id,
+            SegmentDatabase::FieldIdDocidFacetF64s
+            | SegmentDatabase::FieldIdDocidFacetStrings
+            | SegmentDatabase::GeoPoints
+            | SegmentDatabase::VectorPoints => keep_first,
+            SegmentDatabase::WordDocids
+            | SegmentDatabase::ExactWordDocids
+            | SegmentDatabase::ScriptLanguageDocids => merge_roaring_bitmaps,
+            SegmentDatabase::WordPairProximityDocids
+            | SegmentDatabase::WordPositionDocids
+            | SegmentDatabase::WordFidDocids
+            | SegmentDatabase::FieldIdWordCountDocids
+            | SegmentDatabase::FacetIdF64Docids
+            | SegmentDatabase::FacetIdStringDocids
+            | SegmentDatabase::FacetIdExistsDocids
+            | SegmentDatabase::FacetIdIsNullDocids
+            | SegmentDatabase::FacetIdIsEmptyDocids => merge_cbo_roaring_bitmaps,
+        }
+    }
+}
+
+/// The merge function of the documents, an internal id must only be part of one segment.
+fn refuse_shared_internal_id<'a>(key: &[u8], _values: &[Cow<'a, [u8]>]) -> Result<Cow<'a, [u8]>> {
+    let docid = key
+        .try_into()
+        .map(DocumentId::from_be_bytes)
+        .map_err(|_| SerializationError::Decoding { db_name: Some(db_name::DOCUMENTS) })?;
+    Err(UserError::BulkBuildOverlappingSegments { document_id: docid.to_string() }.into())
+}
+
+/// The merge function of the external ids, an external id must only be part of one segment.
+fn refuse_shared_external_id<'a>(key: &[u8], _values: &[Cow<'a, [u8]>]) -> Result<Cow<'a, [u8]>> {
+    let document_id = String::from_utf8_lossy(key).into_owned();
+    Err(UserError::BulkBuildOverlappingSegments { document_id }.into())
+}
+
+/// A part of an index, the entries of every database must be sorted by key.
+///
+/// The segments of a bulk build must have been produced with the same fields ids map
+/// and must contain disjoint sets of documents, the build fails otherwise.
+/// Their facet strings must also have been normalized the same way.
+#[derive(Default)]
+pub struct Segment {
+    databases: BTreeMap<SegmentDatabase, grenad::Reader<File>>,
+    facet_normalization: Option<FacetNormalization>,
+}
+
+impl Segment {
+    pub fn new() -> Segment {
+        Segment::default()
+    }
+
+    /// Sets the entries of one database of this segment, returns the previous ones.
+    pub fn insert(
+        &mut self,
+        database: SegmentDatabase,
+        reader: grenad::Reader<File>,
+    ) -> Option<grenad::Reader<File>> {
+        self.databases.insert(database, reader)
+    }
+
+    /// Sets how the facet strings of this segment have been normalized,
+    /// the strings are considered normalized like the words otherwise.
+    pub fn set_facet_normalization(&mut self, normalization: FacetNormalization) {
+        self.facet_normalization = Some(normalization);
+    }
+
+    /// Extracts the entries of an index that are related to the given documents.
+    pub fn from_index(
+        rtxn: &RoTxn,
+        index: &Index,
+        documents_ids: &RoaringBitmap,
+    ) -> Result<Segment> {
+        let documents_ids = &(index.documents_ids(rtxn)? & documents_ids);
+        let mut segment = Segment::new();
+        segment.set_facet_normalization(index.facet_normalization(rtxn)?);
+
+        let reader = dump_database(rtxn, index.documents.remap_types(), |key, value, buffer| {
+            keep_document_entry(key, 0, value, buffer, documents_ids, db_name::DOCUMENTS)
+        })?;
+        segment.insert(SegmentDatabase::Documents, reader);
+
+        let external_documents_ids = index.external_documents_ids(rtxn)?;
+        let external_documents_ids = external_documents_ids.to_fst()?;
+        let mut writer = create_writer(grenad::CompressionType::None, None, tempfile::tempfile()?);
+        let mut stream = external_documents_ids.stream();
+        while let Some((external_id, docid)) = stream.next() {
+            let docid = docid as DocumentId;
+            if documents_ids.contains(docid) {
+                writer.insert(external_id, docid.to_be_bytes())?;
+            }
+        }
+        segment.insert(SegmentDatabase::ExternalDocumentsIds, writer_into_reader(writer)?);
+
+        for (database, lmdb_database) in [
+            (SegmentDatabase::WordDocids, index.word_docids.remap_types()),
+            (SegmentDatabase::ExactWordDocids, index.exact_word_docids.remap_types()),
+            (SegmentDatabase::ScriptLanguageDocids, index.script_language_docids.remap_types()),
+        ] {
+            let reader = dump_database(rtxn, lmdb_database, |_, value, buffer| {
+                let mut docids = RoaringBitmapCodec::deserialize_from(value)?;
+                docids &= documents_ids;
+                docids.serialize_into(&mut *buffer)?;
+                Ok(!docids.is_empty())
+            })?;
+            segment.insert(database, reader);
+        }
+
+        for (database, lmdb_database) in [
+            (
+                SegmentDatabase::WordPairProximityDocids,
+                index.word_pair_proximity_docids.remap_types(),
+            ),
+            (SegmentDatabase::WordPositionDocids, index.word_position_docids.remap_types()),
+            (SegmentDatabase::WordFidDocids, index.word_fid_docids.remap_types()),
+            (
+                SegmentDatabase::FieldIdWordCountDocids,
+                index.field_id_word_count_docids.remap_types(),
+            ),
+            (SegmentDatabase::FacetIdExistsDocids, index.facet_id_exists_docids.remap_types()),
+            (SegmentDatabase::FacetIdIsNullDocids, index.facet_id_is_null_docids.remap_types()),
+            (SegmentDatabase::FacetIdIsEmptyDocids, index.facet_id_is_empty_docids.remap_types()),
+        ] {
+            let reader = dump_database(rtxn, lmdb_database, |_, value, buffer| {
+                keep_cbo_roaring_bitmap(value, buffer, documents_ids)
+            })?;
+            segment.insert(database, reader);
+        }
+
+        for (database, lmdb_database) in [
+            (SegmentDatabase::FacetIdF64Docids, index.facet_id_f64_docids.remap_types()),
+            (SegmentDatabase::FacetIdStringDocids, index.facet_id_string_docids.remap_types()),
+        ] {
+            let reader = dump_database(rtxn, lmdb_database, |key, value, buffer| {
+                // The keys are composed of the field id, the level and the left bound, only the
+                // level 0 is extracted and the values are stripped of their group size.
+                match (key.get(2), value.split_first()) {
+                    (Some(&0), Some((_size, bitmap))) => {
+                        keep_cbo_roaring_bitmap(bitmap, buffer, documents_ids)
+                    }
+                    _ => Ok(false),
+                }
+            })?;
+            segment.insert(database, reader);
+        }
+
+        for (database, lmdb_database, name) in [
+            (
+                SegmentDatabase::FieldIdDocidFacetF64s,
+                index.field_id_docid_facet_f64s.remap_types(),
+                db_name::FIELD_ID_DOCID_FACET_F64S,
+            ),
+            (
+                SegmentDatabase::FieldIdDocidFacetStrings,
+                index.field_id_docid_facet_strings.remap_types(),
+                db_name::FIELD_ID_DOCID_FACET_STRINGS,
+            ),
+        ] {
+            // The keys are composed of the field id followed by the document id.
+            let reader = dump_database(rtxn, lmdb_database, |key, value, buffer| {
+                keep_document_entry(key, 2, value, buffer, documents_ids, name)
+            })?;
+            segment.insert(database, reader);
+        }
+
+        // The rtree is not sorted by document id, the points are sorted before being written.
+        let mut geo_points = BTreeMap::new();
+        if let Some(rtree) = index.geo_rtree(rtxn)? {
+            for point in rtree.iter() {
+                let (docid, [lat, lng]) = point.data;
+                if documents_ids.contains(docid) {
+                    let mut bytes = Vec::with_capacity(16);
+                    bytes.extend_from_slice(&lat.to_ne_bytes());
+                    bytes.extend_from_slice(&lng.to_ne_bytes());
+                    geo_points.insert(docid, bytes);
+                }
+            }
+        }
+        let mut writer = create_writer(grenad::CompressionType::None, None, tempfile::tempfile()?);
+        for (docid, bytes) in geo_points {
+            writer.insert(docid.to_be_bytes(), bytes)?;
+        }
+        segment.insert(SegmentDatabase::GeoPoints, writer_into_reader(writer)?);
+
+        // The vectors are stored normalized in the HNSW, they are normalized again once written.
+        let hnsw = index.vector_hnsw(rtxn)?;
+        let mut vector_points: BTreeMap<DocumentId, Vec<&Vec<f32>>> = BTreeMap::new();
+        if let Some(hnsw) = &hnsw {
+            for result in index.vector_id_docid.iter(rtxn)? {
+                let (vector_id, docid) = result?;
+                if documents_ids.contains(docid.get()) {
+                    let vector = hnsw.get_point(vector_id.get() as usize);
+                    vector_points.entry(docid.get()).or_default().push(vector);
+                }
+            }
+        }
+        let mut writer = create_writer(grenad::CompressionType::None, None, tempfile::tempfile()?);
+        for (docid, vectors) in vector_points {
+            for (i, vector) in vectors.into_iter().enumerate().take(u16::MAX as usize) {
+                let mut key = docid.to_be_bytes().to_vec();
+                key.extend_from_slice(&(i as u16).to_be_bytes());
+                writer.insert(key, bytemuck::cast_slice(vector))?;
+            }
+        }
+        segment.insert(SegmentDatabase::VectorPoints, writer_into_reader(writer)?);
+
+        Ok(segment)
+    }
+}
+
+/// Writes the entries of a database into a grenad file, the `keep` function
+/// writes the value to store into the buffer and returns `false` to skip an entry.
+fn dump_database(
+    rtxn: &RoTxn,
+    database: Database<ByteSlice, ByteSlice>,
+    mut keep: impl FnMut(&[u8], &[u8], &mut Vec<u8>) -> Result<bool>,
+) -> Result<grenad::Reader<File>> {
+    let mut writer = create_writer(grenad::CompressionType::None, None, tempfile::tempfile()?);
+    let mut buffer = Vec::new();
+    for result in database.iter(rtxn)? {
+        let (key, value) = result?;
+        buffer.clear();
+        if keep(key, value, &mut buffer)? {
+            writer.insert(key, &buffer)?;
+        }
+    }
+    writer_into_reader(writer)
+}
+
+fn keep_cbo_roaring_bitmap(
+    value: &[u8],
+    buffer: &mut Vec<u8>,
+    documents_ids: &RoaringBitmap,
+) -> Result<bool> {
+    let mut docids = CboRoaringBitmapCodec::deserialize_from(value)?;
+    docids &= documents_ids;
+    CboRoaringBitmapCodec::serialize_into(&docids, buffer);
+    Ok(!docids.is_empty())
+}
+
+fn keep_document_entry(
+    key: &[u8],
+    offset: usize,
+    value: &[u8],
+    buffer: &mut Vec<u8>,
+    documents_ids: &RoaringBitmap,
+    db_name: &'static str,
+) -> Result<bool> {
+    let docid = key
+        .get(offset..offset + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(DocumentId::from_be_bytes)
+        .ok_or(SerializationError::Decoding { db_name: Some(db_name) })?;
+    buffer.extend_from_slice(value);
+    Ok(documents_ids.contains(docid))
+}
+
+/// Builds an empty index from [`Segment`]s.
+///
+/// The settings must have been applied to the index beforehand
+/// and the fields ids map must be the one used to produce the segments.
+pub struct BulkBuild<'t, 'u, 'i, 'a> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    indexer_config: &'a IndexerConfig,
+    fields_ids_map: FieldsIdsMap,
+    primary_key: String,
+    segments: Vec<Segment>,
+}
+
+impl<'t, 'u, 'i, 'a> BulkBuild<'t, 'u, 'i, 'a> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        indexer_config: &'a IndexerConfig,
+        fields_ids_map: FieldsIdsMap,
+        primary_key: String,
+    ) -> BulkBuild<'t, 'u, 'i, 'a> {
+        BulkBuild { wtxn, index, indexer_config, fields_ids_map, primary_key, segments: Vec::new() }
+    }
+
+    pub fn add_segment(&mut self, segment: Segment) {
+        self.segments.push(segment);
+    }
+
+    /// Returns the number of documents in the index after the build.
+    #[logging_timer::time("BulkBuild::{}")]
+    pub fn execute(self) -> Result<u64> {
+        let BulkBuild { wtxn, index, indexer_config, fields_ids_map, primary_key, mut segments } =
+            self;
+
+        if !index.documents.is_empty(wtxn)? {
+            return Err(UserError::BulkBuildIntoNonEmptyIndex.into());
+        }
+        if let Some(current) = index.primary_key(wtxn)? {
+            if current != primary_key {
+                return Err(UserError::PrimaryKeyCannotBeChanged(current.to_string()).into());
+            }
+        }
+        index.put_primary_key(wtxn, &primary_key)?;
+
+        index.put_fields_ids_map(wtxn, &fields_ids_map)?;
+        let user_defined_facets = index.user_defined_faceted_fields(wtxn)?;
+        let faceted_fields: HashSet<String> = fields_ids_map
+            .names()
+            .filter(|&field| crate::is_faceted(field, &user_defined_facets))
+            .map(|field| field.to_string())
+            .collect();
+        index.put_faceted_fields(wtxn, &faceted_fields)?;
+        // The facet strings of the segments are written as they have been normalized.
+        let facet_normalization = segments.iter().find_map(|segment| segment.facet_normalization);
+        index.put_facet_normalization(
+            wtxn,
+            facet_normalization.unwrap_or(FacetNormalization::Words),
+        )?;
+        if let Some(searchable_fields) = index.user_defined_searchable_fields(wtxn)? {
+            let searchable_fields: Vec<String> =
+                searchable_fields.into_iter().map(str::to_string).collect();
+            index.put_all_searchable_fields_from_fields_ids_map(
+                wtxn,
+                &searchable_fields.iter().map(String::as_ref).collect::<Vec<_>>(),
+                &fields_ids_map,
+            )?;
+        }
+
+        let params = GrenadParameters {
+            chunk_compression_type: indexer_config.chunk_compression_type,
+            chunk_compression_level: indexer_config.chunk_compression_level,
+            max_memory: indexer_config.max_memory,
+            max_nb_chunks: indexer_config.max_nb_chunks,
+        };
+
+        // The k-way merge of the segments, database by database.
+        let mut merged = BTreeMap::new();
+        for database in SegmentDatabase::ALL {
+            let readers: Vec<_> =
+                segments.iter_mut().filter_map(|s| s.databases.remove(&database)).collect();
+            merged.insert(database, readers.merge(database.merge_function(), &params)?);
+        }
+        let mut take = |database: SegmentDatabase| merged.remove(&database).unwrap();
+
+        let documents = unsafe { as_cloneable_grenad(&take(SegmentDatabase::Documents))? };
+        let mut documents_ids = RoaringBitmap::new();
+        let mut field_distribution = FieldDistribution::new();
+        let mut cursor = documents.clone().into_cursor()?;
+        while let Some((key, value)) = cursor.move_on_next()? {
+            let docid = key
+                .try_into()
+                .map(DocumentId::from_be_bytes)
+                .map_err(|_| SerializationError::Decoding { db_name: Some(db_name::DOCUMENTS) })?;
+            documents_ids.insert(docid);
+            for (field_id, _) in obkv::KvReaderU16::new(value).iter() {
+                if let Some(name) = fields_ids_map.name(field_id) {
+                    *field_distribution.entry(name.to_string()).or_default() += 1;
+                }
+            }
+        }
+
+        let mut builder = fst::MapBuilder::memory();
+        let mut cursor = take(SegmentDatabase::ExternalDocumentsIds).into_cursor()?;
+        while let Some((external_id, docid)) = cursor.move_on_next()? {
+            let docid = docid
+                .try_into()
+                .map(DocumentId::from_be_bytes)
+                .map_err(|_| SerializationError::Decoding { db_name: None })?;
+            builder.insert(external_id, docid as u64)?;
+        }
+        let hard = builder.into_map().map_data(Cow::Owned)?;
+        let soft = fst::Map::default().map_data(Cow::Owned)?;
+        let external_documents_ids = ExternalDocumentsIds::new(hard, soft, RoaringBitmap::new());
+
+        let word_docids_reader = take(SegmentDatabase::WordDocids);
+        let exact_word_docids_reader = take(SegmentDatabase::ExactWordDocids);
+        let word_pair_proximity_docids = take(SegmentDatabase::WordPairProximityDocids);
+        let word_position_docids = take(SegmentDatabase::WordPositionDocids);
+        let word_fid_docids = take(SegmentDatabase::WordFidDocids);
+        let prefix_sources = unsafe {
+            (
+                as_cloneable_grenad(&word_docids_reader)?,
+                as_cloneable_grenad(&exact_word_docids_reader)?,
+                as_cloneable_grenad(&word_pair_proximity_docids)?,
+                as_cloneable_grenad(&word_position_docids)?,
+                as_cloneable_grenad(&word_fid_docids)?,
+            )
+        };
+
+        let mut script_language_docids = HashMap::new();
+        let mut cursor = take(SegmentDatabase::ScriptLanguageDocids).into_cursor()?;
+        while let Some((key, value)) = cursor.move_on_next()? {
+            let script_language =
+                ScriptLanguageCodec::bytes_decode(key).ok_or(SerializationError::Decoding {
+                    db_name: Some(db_name::SCRIPT_LANGUAGE_DOCIDS),
+                })?;
+            script_language_docids
+                .insert(script_language, RoaringBitmapCodec::deserialize_from(value)?);
+        }
+
+        let typed_chunks = vec![
+            TypedChunk::Documents(documents),
+            TypedChunk::WordDocids { word_docids_reader, exact_word_docids_reader },
+            TypedChunk::WordPairProximityDocids(word_pair_proximity_docids),
+            TypedChunk::WordPositionDocids(word_position_docids),
+            TypedChunk::WordFidDocids(word_fid_docids),
+            TypedChunk::FieldIdWordcountDocids(take(SegmentDatabase::FieldIdWordCountDocids)),
+            TypedChunk::FieldIdFacetNumberDocids(take(SegmentDatabase::FacetIdF64Docids)),
+            TypedChunk::FieldIdFacetStringDocids(take(SegmentDatabase::FacetIdStringDocids)),
+            TypedChunk::FieldIdFacetExistsDocids(take(SegmentDatabase::FacetIdExistsDocids)),
+            TypedChunk::FieldIdFacetIsNullDocids(take(SegmentDatabase::FacetIdIsNullDocids)),
+            TypedChunk::FieldIdFacetIsEmptyDocids(take(SegmentDatabase::FacetIdIsEmptyDocids)),
+            TypedChunk::FieldIdDocidFacetNumbers(unsafe {
+                as_cloneable_grenad(&take(SegmentDatabase::FieldIdDocidFacetF64s))?
+            }),
+            TypedChunk::FieldIdDocidFacetStrings(unsafe {
+                as_cloneable_grenad(&take(SegmentDatabase::FieldIdDocidFacetStrings))?
+            }),
+            TypedChunk::GeoPoints(take(SegmentDatabase::GeoPoints)),
+            TypedChunk::VectorPoints(take(SegmentDatabase::VectorPoints)),
+            TypedChunk::ScriptLanguageDocids(script_language_docids),
+        ];
+        for typed_chunk in typed_chunks {
+            write_typed_chunk_into_index(typed_chunk, index, wtxn, true)?;
+        }
+
+        index.put_field_distribution(wtxn, &field_distribution)?;
+        index.put_external_documents_ids(wtxn, &external_documents_ids)?;
+        index.put_documents_ids(wtxn, &documents_ids)?;
+
+        let (word_docids, exact_word_docids, word_pair_proximity, word_position, word_fid) =
+            prefix_sources;
+        let indexer = IndexDocuments::new(
+            wtxn,
+            index,
+            indexer_config,
+            IndexDocumentsConfig::default(),
+            |_| (),
+            || false,
+        )?;
+        indexer.execute_prefix_databases(
+            Some(word_docids),
+            Some(exact_word_docids),
+            Some(word_pair_proximity),
+            Some(word_position),
+            Some(word_fid),
+        )?;
+
+        Ok(documents_ids.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Filter, Search, SearchResult};
+
+    fn create_source_index() -> TempIndex {
+        let index = TempIndex::new();
+        index
+            .update_settings(|s| {
+                s.set_primary_key(S("id"));
+                s.set_filterable_fields(hashset! { S("genre"), S("year") });
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox", "genre": "tale", "year": 1990 },
+                { "id": 1, "title": "the lazy dog", "genre": "tale", "year": 2001 },
+                { "id": 2, "title": "a quick riddle", "genre": "puzzle", "year": 2010 },
+                { "id": 3, "title": "brown bread recipes", "genre": "cooking" },
+                { "id": 4, "title": "the fox and the dog", "genre": "tale", "year": 1990 },
+            ]))
+            .unwrap();
+        index
+    }
+
+    fn search(index: &Index, query: &str, filter: Option<&str>) -> Vec<u32> {
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, index);
+        search.query(query);
+        if let Some(filter) = filter.and_then(|f| Filter::from_str(f).unwrap()) {
+            search.filter(filter);
+        }
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        documents_ids
+    }
+
+    #[test]
+    fn bulk_build_from_segments() {
+        let source = create_source_index();
+        let rtxn = source.read_txn().unwrap();
+        let fields_ids_map = source.fields_ids_map(&rtxn).unwrap();
+
+        let index = TempIndex::new();
+        index
+            .update_settings(|s| s.set_filterable_fields(hashset! { S("genre"), S("year") }))
+            .unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder =
+            BulkBuild::new(&mut wtxn, &index, &index.indexer_config, fields_ids_map, S("id"));
+        for docids in [RoaringBitmap::from_iter([0, 3]), RoaringBitmap::from_iter([1, 2, 4])] {
+            builder.add_segment(Segment::from_index(&rtxn, &source, &docids).unwrap());
+        }
+        assert_eq!(builder.execute().unwrap(), 5);
+        wtxn.commit().unwrap();
+
+        let built_rtxn = index.read_txn().unwrap();
+        let fields = ["id", "title", "genre", "year"];
+        assert_eq!(
+            index.documents_with_fields(&built_rtxn, 0..5, &fields).unwrap(),
+            source.documents_with_fields(&rtxn, 0..5, &fields).unwrap()
+        );
+        assert_eq!(
+            index.field_distribution(&built_rtxn).unwrap(),
+            source.field_distribution(&rtxn).unwrap()
+        );
+        assert_eq!(index.external_documents_ids(&built_rtxn).unwrap().get("3"), Some(3));
+        assert_eq!(
+            index.words_fst(&built_rtxn).unwrap().stream().into_strs().unwrap(),
+            source.words_fst(&rtxn).unwrap().stream().into_strs().unwrap()
+        );
+
+        for (query, filter) in [
+            ("the fox", None),
+            ("brown", None),
+            ("qui", None),
+            ("dog", Some("genre = tale")),
+            ("", Some("year = 1990")),
+            ("", Some("year EXISTS")),
+        ] {
+            assert_eq!(search(&index, query, filter), search(&source, query, filter));
+        }
+    }
+
+    #[test]
+    fn bulk_build_requires_an_empty_index() {
+        let source = create_source_index();
+        let rtxn = source.read_txn().unwrap();
+        let fields_ids_map = source.fields_ids_map(&rtxn).unwrap();
+
+        let index = create_source_index();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder =
+            BulkBuild::new(&mut wtxn, &index, &index.indexer_config, fields_ids_map, S("id"));
+        builder.add_segment(
+            Segment::from_index(&rtxn, &source, &RoaringBitmap::from_iter([0])).unwrap(),
+        );
+        let error = builder.execute().unwrap_err();
+        assert!(matches!(error, crate::Error::UserError(UserError::BulkBuildIntoNonEmptyIndex)));
+    }
+
+    #[test]
+    fn bulk_build_requires_disjoint_segments() {
+        let source = create_source_index();
+        let rtxn = source.read_txn().unwrap();
+        let fields_ids_map = source.fields_ids_map(&rtxn).unwrap();
+
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder =
+            BulkBuild::new(&mut wtxn, &index, &index.indexer_config, fields_ids_map, S("id"));
+        for docids in [RoaringBitmap::from_iter([0, 1]), RoaringBitmap::from_iter([1, 2])] {
+            builder.add_segment(Segment::from_index(&rtxn, &source, &docids).unwrap());
+        }
+        let error = builder.execute().unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::UserError(UserError::BulkBuildOverlappingSegments { document_id })
+                if document_id == "1"
+        ));
+    }
+
+    fn vectors(index: &Index) -> Vec<(DocumentId, Vec<f32>)> {
+        let rtxn = index.read_txn().unwrap();
+        let hnsw = index.vector_hnsw(&rtxn).unwrap().unwrap();
+        index
+            .vector_id_docid
+            .iter(&rtxn)
+            .unwrap()
+            .map(|result| {
+                let (vector_id, docid) = result.unwrap();
+                (docid.get(), hnsw.get_point(vector_id.get() as usize).clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bulk_build_geo_points_vectors_and_scripts() {
+        let source = TempIndex::new();
+        source
+            .update_settings(|s| {
+                s.set_primary_key(S("id"));
+                s.set_filterable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+        source
+            .add_documents(documents!([
+                { "id": 0, "title": "the quick brown fox", "_geo": { "lat": 45.4777599, "lng": 9.1967508 }, "_vectors": [3.0, 4.0] },
+                { "id": 1, "title": "東京の犬", "_geo": { "lat": 48.8566, "lng": 2.3522 }, "_vectors": [[0.0, 1.0], [2.0, 1.0]] },
+                { "id": 2, "title": "the lazy dog", "_vectors": [1.0, 0.0] },
+            ]))
+            .unwrap();
+        let rtxn = source.read_txn().unwrap();
+        let fields_ids_map = source.fields_ids_map(&rtxn).unwrap();
+
+        let index = TempIndex::new();
+        index.update_settings(|s| s.set_filterable_fields(hashset! { S("_geo") })).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder =
+            BulkBuild::new(&mut wtxn, &index, &index.indexer_config, fields_ids_map, S("id"));
+        for docids in [RoaringBitmap::from_iter([1]), RoaringBitmap::from_iter([0, 2])] {
+            builder.add_segment(Segment::from_index(&rtxn, &source, &docids).unwrap());
+        }
+        assert_eq!(builder.execute().unwrap(), 3);
+        wtxn.commit().unwrap();
+
+        let built_rtxn = index.read_txn().unwrap();
+        let geo_points = |index: &Index, rtxn: &RoTxn| {
+            let mut points: Vec<_> =
+                index.geo_rtree(rtxn).unwrap().unwrap().iter().map(|point| point.data).collect();
+            points.sort_by_key(|(docid, _)| *docid);
+            points
+        };
+        assert_eq!(geo_points(&index, &built_rtxn), geo_points(&source, &rtxn));
+        assert_eq!(
+            index.geo_faceted_documents_ids(&built_rtxn).unwrap(),
+            source.geo_faceted_documents_ids(&rtxn).unwrap()
+        );
+        let filter = Some("_geoRadius(45.4777599, 9.1967508, 2000)");
+        assert_eq!(search(&index, "", filter), search(&source, "", filter));
+
+        let (built_vectors, source_vectors) = (vectors(&index), vectors(&source));
+        assert_eq!(built_vectors.len(), source_vectors.len());
+        for ((built_docid, built), (source_docid, source)) in
+            built_vectors.iter().zip(&source_vectors)
+        {
+            assert_eq!(built_docid, source_docid);
+            assert!(built.iter().zip(source).all(|(b, s)| (b - s).abs() < 1e-6));
+        }
+
+        let script_language_docids = |index: &Index, rtxn: &RoTxn| {
+            index.script_language_docids.iter(rtxn).unwrap().map(Result::unwrap).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            script_language_docids(&index, &built_rtxn),
+            script_language_docids(&source, &rtxn)
+        );
+
+        let word_docids_count = |index: &Index, rtxn: &RoTxn| {
+            index
+                .word_docids_count
+                .iter(rtxn)
+                .unwrap()
+                .map(|result| result.map(|(word, count)| (word.to_string(), count.get())).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(word_docids_count(&index, &built_rtxn), word_docids_count(&source, &rtxn));
+    }
+}
@@ -12,7 +12,8 @@ use super::helpers::{concat_u32s_array, create_sorter, sorter_into_reader, Grena
 use crate::error::{InternalError, SerializationError};
 use crate::update::index_documents::MergeFn;
 use crate::{
-    absolute_from_relative_position, FieldId, Result, MAX_POSITION_PER_ATTRIBUTE, MAX_WORD_LENGTH,
+    absolute_from_relative_position, FieldId, LocalizedFieldsIds, Result,
+    MAX_POSITION_PER_ATTRIBUTE, MAX_WORD_LENGTH,
 };
 
 pub type ScriptLanguageDocidsMap = HashMap<(Script, Language), RoaringBitmap>;
@@ -28,7 +29,10 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
     indexer: GrenadParameters,
     searchable_fields: &Option<HashSet<FieldId>>,
     stop_words: Option<&fst::Set<&[u8]>>,
+    dictionary: Option<&[&str]>,
+    searchable_numbers_and_booleans: bool,
     max_positions_per_attributes: Option<u32>,
+    localized_fields: &LocalizedFieldsIds,
 ) -> Result<(RoaringBitmap, grenad::Reader<File>, ScriptLanguageDocidsMap)> {
     let max_positions_per_attributes = max_positions_per_attributes
         .map_or(MAX_POSITION_PER_ATTRIBUTE, |max| max.min(MAX_POSITION_PER_ATTRIBUTE));
@@ -46,11 +50,17 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
     );
 
     let mut buffers = Buffers::default();
-    let mut tokenizer_builder = TokenizerBuilder::new();
-    if let Some(stop_words) = stop_words {
-        tokenizer_builder.stop_words(stop_words);
-    }
-    let tokenizer = tokenizer_builder.build();
+    let tokenizer = tokenizer_builder(stop_words, dictionary).build();
+
+    // the localized fields are tokenized for the languages of their rule, in every document.
+    let localized_tokenizers: HashMap<_, _> = localized_fields
+        .iter()
+        .map(|(&field_id, allow_list)| {
+            let mut tokenizer_builder = tokenizer_builder(stop_words, dictionary);
+            tokenizer_builder.allow_list(allow_list);
+            (field_id, tokenizer_builder.build())
+        })
+        .collect();
 
     let mut cursor = obkv_documents.into_cursor()?;
     while let Some((key, value)) = cursor.move_on_next()? {
@@ -69,7 +79,9 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
         extract_tokens_from_document(
             &obkv,
             searchable_fields,
+            searchable_numbers_and_booleans,
             &tokenizer,
+            &localized_tokenizers,
             max_positions_per_attributes,
             &mut buffers,
             &mut script_language_word_count,
@@ -92,10 +104,7 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
             // then we don't rerun the extraction.
             if !script_language.is_empty() {
                 // build a new temporary tokenizer including the allow list.
-                let mut tokenizer_builder = TokenizerBuilder::new();
-                if let Some(stop_words) = stop_words {
-                    tokenizer_builder.stop_words(stop_words);
-                }
+                let mut tokenizer_builder = tokenizer_builder(stop_words, dictionary);
                 tokenizer_builder.allow_list(&script_language);
                 let tokenizer = tokenizer_builder.build();
 
@@ -105,7 +114,9 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
                 extract_tokens_from_document(
                     &obkv,
                     searchable_fields,
+                    searchable_numbers_and_booleans,
                     &tokenizer,
+                    &localized_tokenizers,
                     max_positions_per_attributes,
                     &mut buffers,
                     &mut script_language_word_count,
@@ -128,10 +139,28 @@ pub fn extract_docid_word_positions<R: io::Read + io::Seek>(
         .map(|reader| (documents_ids, reader, script_language_docids))
 }
 
+/// Returns a tokenizer builder using the stop words and the dictionary of the index.
+fn tokenizer_builder<'a, 's>(
+    stop_words: Option<&'a fst::Set<&'s [u8]>>,
+    dictionary: Option<&'a [&'a str]>,
+) -> TokenizerBuilder<'a, &'s [u8]> {
+    let mut tokenizer_builder = TokenizerBuilder::new();
+    if let Some(stop_words) = stop_words {
+        tokenizer_builder.stop_words(stop_words);
+    }
+    if let Some(dictionary) = dictionary {
+        tokenizer_builder.words_dict(dictionary);
+    }
+    tokenizer_builder
+}
+
+#[allow(clippy::too_many_arguments)]
 fn extract_tokens_from_document(
     obkv: &KvReader<FieldId>,
     searchable_fields: &Option<HashSet<FieldId>>,
+    searchable_numbers_and_booleans: bool,
     tokenizer: &Tokenizer,
+    localized_tokenizers: &HashMap<FieldId, Tokenizer>,
     max_positions_per_attributes: u32,
     buffers: &mut Buffers,
     script_language_word_count: &mut HashMap<Script, Vec<(Language, usize)>>,
@@ -141,7 +170,10 @@ fn extract_tokens_from_document(
         if searchable_fields.as_ref().map_or(true, |sf| sf.contains(&field_id)) {
             let value = serde_json::from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
             buffers.field_buffer.clear();
-            if let Some(field) = json_to_string(&value, &mut buffers.field_buffer) {
+            let field =
+                json_to_string(&value, searchable_numbers_and_booleans, &mut buffers.field_buffer);
+            if let Some(field) = field {
+                let tokenizer = localized_tokenizers.get(&field_id).unwrap_or(tokenizer);
                 let tokens = process_tokens(tokenizer.tokenize(field))
                     .take_while(|(p, _)| (*p as u32) < max_positions_per_attributes);
 
@@ -177,18 +209,25 @@ fn extract_tokens_from_document(
 }
 
 /// Transform a JSON value into a string that can be indexed.
-fn json_to_string<'a>(value: &'a Value, buffer: &'a mut String) -> Option<&'a str> {
-    fn inner(value: &Value, output: &mut String) -> bool {
+///
+/// The numbers and booleans are ignored when `with_numbers_and_booleans` is `false`.
+fn json_to_string<'a>(
+    value: &'a Value,
+    with_numbers_and_booleans: bool,
+    buffer: &'a mut String,
+) -> Option<&'a str> {
+    fn inner(value: &Value, with_numbers_and_booleans: bool, output: &mut String) -> bool {
         use std::fmt::Write;
         match value {
             Value::Null | Value::Object(_) => false,
+            Value::Bool(_) | Value::Number(_) if !with_numbers_and_booleans => false,
             Value::Bool(boolean) => write!(output, "{}", boolean).is_ok(),
             Value::Number(number) => write!(output, "{}", number).is_ok(),
             Value::String(string) => write!(output, "{}", string).is_ok(),
             Value::Array(array) => {
                 let mut count = 0;
                 for value in array {
-                    if inner(value, output) {
+                    if inner(value, with_numbers_and_booleans, output) {
                         output.push_str(". ");
                         count += 1;
                     }
@@ -201,7 +240,7 @@ fn json_to_string<'a>(value: &'a Value, buffer: &'a mut String) -> Option<&'a st
 
     if let Value::String(string) = value {
         Some(string)
-    } else if inner(value, buffer) {
+    } else if inner(value, with_numbers_and_booleans, buffer) {
         Some(buffer)
     } else {
         None
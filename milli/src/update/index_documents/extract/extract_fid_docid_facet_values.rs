@@ -13,7 +13,10 @@ use super::helpers::{create_sorter, keep_first, sorter_into_reader, GrenadParame
 use crate::error::InternalError;
 use crate::facet::value_encoding::f64_into_bytes;
 use crate::update::index_documents::{create_writer, writer_into_reader};
-use crate::{CboRoaringBitmapCodec, DocumentId, FieldId, Result, BEU32, MAX_FACET_VALUE_LENGTH};
+use crate::{
+    CboRoaringBitmapCodec, DocumentId, FacetNormalization, FieldId, Result, BEU32,
+    MAX_FACET_VALUE_LENGTH,
+};
 
 /// The extracted facet values stored in grenad files by type.
 pub struct ExtractedFacetValues {
@@ -33,6 +36,7 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
     obkv_documents: grenad::Reader<R>,
     indexer: GrenadParameters,
     faceted_fields: &HashSet<FieldId>,
+    facet_normalization: FacetNormalization,
 ) -> Result<ExtractedFacetValues> {
     let max_memory = indexer.max_memory_by_thread();
 
@@ -82,7 +86,7 @@ pub fn extract_fid_docid_facet_values<R: io::Read + io::Seek>(
 
                 let value = from_slice(field_bytes).map_err(InternalError::SerdeJson)?;
 
-                match extract_facet_values(&value) {
+                match extract_facet_values(&value, facet_normalization) {
                     FilterableValues::Null => {
                         facet_is_null_docids.entry(field_id).or_default().insert(document);
                     }
@@ -175,10 +179,11 @@ enum FilterableValues {
     Values { numbers: Vec<f64>, strings: Vec<(String, String)> },
 }
 
-fn extract_facet_values(value: &Value) -> FilterableValues {
+fn extract_facet_values(value: &Value, normalization: FacetNormalization) -> FilterableValues {
     fn inner_extract_facet_values(
         value: &Value,
         can_recurse: bool,
+        normalization: FacetNormalization,
         output_numbers: &mut Vec<f64>,
         output_strings: &mut Vec<(String, String)>,
     ) {
@@ -190,14 +195,24 @@ fn extract_facet_values(value: &Value) -> FilterableValues {
                     output_numbers.push(float);
                 }
             }
-            Value::String(original) => {
-                let normalized = crate::normalize_facet(original);
-                output_strings.push((normalized, original.clone()));
-            }
+            Value::String(original) => match crate::facet_string_as_number(original) {
+                // the numbers sent as strings are indexed like the other numbers
+                Some(number) => output_numbers.push(number),
+                None => {
+                    let normalized = crate::normalize_facet(original, normalization);
+                    output_strings.push((normalized, original.clone()));
+                }
+            },
             Value::Array(values) => {
                 if can_recurse {
                     for value in values {
-                        inner_extract_facet_values(value, false, output_numbers, output_strings);
+                        inner_extract_facet_values(
+                            value,
+                            false,
+                            normalization,
+                            output_numbers,
+                            output_strings,
+                        );
                     }
                 }
             }
@@ -213,7 +228,7 @@ fn extract_facet_values(value: &Value) -> FilterableValues {
         otherwise => {
             let mut numbers = Vec::new();
             let mut strings = Vec::new();
-            inner_extract_facet_values(otherwise, true, &mut numbers, &mut strings);
+            inner_extract_facet_values(otherwise, true, normalization, &mut numbers, &mut strings);
             FilterableValues::Values { numbers, strings }
         }
     }
@@ -33,7 +33,7 @@ use super::helpers::{
     GrenadParameters, MergeFn, MergeableReader,
 };
 use super::{helpers, TypedChunk};
-use crate::{FieldId, Result};
+use crate::{FacetNormalization, FieldId, LocalizedFieldsIds, Result};
 
 /// Extract data for each databases from obkv documents in parallel.
 /// Send data in grenad file over provided Sender.
@@ -45,12 +45,16 @@ pub(crate) fn data_from_obkv_documents(
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     searchable_fields: Option<HashSet<FieldId>>,
     faceted_fields: HashSet<FieldId>,
+    facet_normalization: FacetNormalization,
     primary_key_id: FieldId,
     geo_fields_ids: Option<(FieldId, FieldId)>,
     vectors_field_id: Option<FieldId>,
     stop_words: Option<fst::Set<&[u8]>>,
+    dictionary: Option<&[&str]>,
+    searchable_numbers_and_booleans: bool,
     max_positions_per_attributes: Option<u32>,
     exact_attributes: HashSet<FieldId>,
+    localized_fields: LocalizedFieldsIds,
 ) -> Result<()> {
     original_obkv_chunks
         .par_bridge()
@@ -70,11 +74,15 @@ pub(crate) fn data_from_obkv_documents(
                     lmdb_writer_sx.clone(),
                     &searchable_fields,
                     &faceted_fields,
+                    facet_normalization,
                     primary_key_id,
                     geo_fields_ids,
                     vectors_field_id,
                     &stop_words,
+                    dictionary,
+                    searchable_numbers_and_booleans,
                     max_positions_per_attributes,
+                    &localized_fields,
                 )
             })
             .collect();
@@ -281,11 +289,15 @@ fn send_and_extract_flattened_documents_data(
     lmdb_writer_sx: Sender<Result<TypedChunk>>,
     searchable_fields: &Option<HashSet<FieldId>>,
     faceted_fields: &HashSet<FieldId>,
+    facet_normalization: FacetNormalization,
     primary_key_id: FieldId,
     geo_fields_ids: Option<(FieldId, FieldId)>,
     vectors_field_id: Option<FieldId>,
     stop_words: &Option<fst::Set<&[u8]>>,
+    dictionary: Option<&[&str]>,
+    searchable_numbers_and_booleans: bool,
     max_positions_per_attributes: Option<u32>,
+    localized_fields: &LocalizedFieldsIds,
 ) -> Result<(
     grenad::Reader<CursorClonableMmap>,
     (
@@ -340,7 +352,10 @@ fn send_and_extract_flattened_documents_data(
                         indexer,
                         searchable_fields,
                         stop_words.as_ref(),
+                        dictionary,
+                        searchable_numbers_and_booleans,
                         max_positions_per_attributes,
+                        localized_fields,
                     )?;
 
                 // send documents_ids to DB writer
@@ -366,6 +381,7 @@ fn send_and_extract_flattened_documents_data(
                     flattened_documents_chunk.clone(),
                     indexer,
                     faceted_fields,
+                    facet_normalization,
                 )?;
 
                 // send docid_fid_facet_numbers_chunk to DB writer
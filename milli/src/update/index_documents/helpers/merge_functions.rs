@@ -4,7 +4,7 @@ use std::result::Result as StdResult;
 
 use roaring::RoaringBitmap;
 
-use crate::heed_codec::CboRoaringBitmapCodec;
+use crate::heed_codec::{CboRoaringBitmapCodec, RoaringBitmapCodec};
 use crate::update::index_documents::transform::Operation;
 use crate::Result;
 
@@ -34,7 +34,7 @@ pub fn merge_roaring_bitmaps<'a>(_key: &[u8], values: &[Cow<'a, [u8]>]) -> Resul
         let merged = values
             .iter()
             .map(AsRef::as_ref)
-            .map(RoaringBitmap::deserialize_from)
+            .map(RoaringBitmapCodec::deserialize_from)
             .map(StdResult::unwrap)
             .reduce(|a, b| a | b)
             .unwrap();
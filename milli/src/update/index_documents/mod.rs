@@ -1,6 +1,8 @@
+mod bulk_build;
 mod enrich;
 mod extract;
 mod helpers;
+mod sharded;
 mod transform;
 mod typed_chunk;
 
@@ -19,6 +21,7 @@ use serde::{Deserialize, Serialize};
 use slice_group_by::GroupBy;
 use typed_chunk::{write_typed_chunk_into_index, TypedChunk};
 
+pub use self::bulk_build::{BulkBuild, Segment, SegmentDatabase};
 use self::enrich::enrich_documents_batch;
 pub use self::enrich::{
     extract_finite_float_from_value, validate_document_id, validate_document_id_value,
@@ -30,6 +33,7 @@ pub use self::helpers::{
     sorter_into_lmdb_database, valid_lmdb_key, writer_into_reader, ClonableMmap, MergeFn,
 };
 use self::helpers::{grenad_obkv_into_chunks, GrenadParameters};
+pub use self::sharded::ShardedIndexDocuments;
 pub use self::transform::{Transform, TransformOutput};
 use crate::documents::{obkv_to_object, DocumentsBatchReader};
 use crate::error::{Error, InternalError, UserError};
@@ -91,6 +95,12 @@ pub struct IndexDocumentsConfig {
     pub update_method: IndexDocumentsMethod,
     pub deletion_strategy: DeletionStrategy,
     pub autogenerate_docids: bool,
+    /// The new documents are given internal ids greater than or equal to this one.
+    pub first_document_id: crate::DocumentId,
+    /// Derives the internal ids of the new documents from a hash of their external ids,
+    /// `first_document_id` is ignored. Indexing the same documents in the same order always
+    /// gives the same internal ids, at the cost of sparser and therefore bigger bitmaps.
+    pub hash_external_ids: bool,
 }
 
 impl<'t, 'u, 'i, 'a, FP, FA> IndexDocuments<'t, 'u, 'i, 'a, FP, FA>
@@ -112,6 +122,8 @@ where
             indexer_config,
             config.update_method,
             config.autogenerate_docids,
+            config.first_document_id,
+            config.hash_external_ids,
         )?);
 
         Ok(IndexDocuments {
@@ -284,6 +296,7 @@ where
             self.index.searchable_fields_ids(self.wtxn)?.map(HashSet::from_iter);
         // get filterable fields for facet databases
         let faceted_fields = self.index.faceted_fields_ids(self.wtxn)?;
+        let facet_normalization = self.index.facet_normalization(self.wtxn)?;
         // get the fid of the `_geo.lat` and `_geo.lng` fields.
         let geo_fields_ids = match self.index.fields_ids_map(self.wtxn)?.id("_geo") {
             Some(gfid) => {
@@ -308,7 +321,12 @@ where
         let vectors_field_id = self.index.fields_ids_map(self.wtxn)?.id("_vectors");
 
         let stop_words = self.index.stop_words(self.wtxn)?;
+        let settings = self.index.cached_settings(self.wtxn)?;
+        let dictionary = settings.words_dict();
+        let searchable_numbers_and_booleans =
+            self.index.searchable_numbers_and_booleans(self.wtxn)?;
         let exact_attributes = self.index.exact_attributes_ids(self.wtxn)?;
+        let localized_fields = self.index.localized_fields_ids(self.wtxn)?;
 
         let pool_params = GrenadParameters {
             chunk_compression_type: self.indexer_config.chunk_compression_type,
@@ -340,12 +358,16 @@ where
                     lmdb_writer_sx.clone(),
                     searchable_fields,
                     faceted_fields,
+                    facet_normalization,
                     primary_key_id,
                     geo_fields_ids,
                     vectors_field_id,
                     stop_words,
+                    dictionary.as_deref(),
+                    searchable_numbers_and_booleans,
                     max_positions_per_attributes,
                     exact_attributes,
+                    localized_fields,
                 )
             });
 
@@ -684,7 +706,7 @@ mod tests {
     use maplit::hashset;
 
     use super::*;
-    use crate::documents::documents_batch_reader_from_objects;
+    use crate::documents::{documents_batch_reader_from_objects, DocumentsBatchBuilder};
     use crate::index::tests::TempIndex;
     use crate::search::TermsMatchingStrategy;
     use crate::update::DeleteDocuments;
@@ -794,6 +816,49 @@ mod tests {
         drop(rtxn);
     }
 
+    #[test]
+    fn partial_update_reindexes_the_merged_document() {
+        let mut index = TempIndex::new();
+        index
+            .update_settings(|settings| settings.set_filterable_fields(hashset! { S("genre") }))
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "the quick fox", "genre": "tale" },
+                { "id": 2, "title": "the lazy dog", "genre": "tale" }
+            ]))
+            .unwrap();
+
+        // Only the genre is sent, the title of the stored document must be kept.
+        index.index_documents_config.update_method = IndexDocumentsMethod::UpdateDocuments;
+        index.add_documents(documents!([ { "id": 1, "genre": "fable" } ])).unwrap();
+
+        db_snap!(index, documents, @r###"
+        {"id":2,"title":"the lazy dog","genre":"tale"}
+        {"id":1,"title":"the quick fox","genre":"fable"}
+        "###);
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.query("fox");
+        let fox_ids = search.execute().unwrap().documents_ids;
+        assert_eq!(fox_ids.len(), 1);
+
+        let fable_ids = crate::Filter::from_str("genre = fable")
+            .unwrap()
+            .unwrap()
+            .evaluate(&rtxn, &index)
+            .unwrap();
+        assert_eq!(fable_ids, fox_ids.into_iter().collect());
+        let tale_ids = crate::Filter::from_str("genre = tale")
+            .unwrap()
+            .unwrap()
+            .evaluate(&rtxn, &index)
+            .unwrap();
+        assert_eq!(tale_ids.len(), 1);
+        assert!(tale_ids.is_disjoint(&fable_ids));
+    }
+
     #[test]
     fn not_auto_generated_documents_ids() {
         let index = TempIndex::new();
@@ -1311,11 +1376,17 @@ mod tests {
 
         search.filter(crate::Filter::from_str(r#"nested = array"#).unwrap().unwrap());
         let error = search.execute().map(|_| unreachable!()).unwrap_err(); // nested is not filterable
-        assert!(matches!(error, crate::Error::UserError(crate::UserError::InvalidFilter(_))));
+        assert!(matches!(
+            error,
+            crate::Error::UserError(crate::UserError::InvalidFilterableAttribute { .. })
+        ));
 
         search.filter(crate::Filter::from_str(r#"nested = "I lied""#).unwrap().unwrap());
         let error = search.execute().map(|_| unreachable!()).unwrap_err(); // nested is not filterable
-        assert!(matches!(error, crate::Error::UserError(crate::UserError::InvalidFilter(_))));
+        assert!(matches!(
+            error,
+            crate::Error::UserError(crate::UserError::InvalidFilterableAttribute { .. })
+        ));
     }
 
     #[test]
@@ -2305,6 +2376,45 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn add_json_ndjson_and_csv_documents_in_the_same_transaction() {
+        let index = TempIndex::new();
+
+        let mut json = DocumentsBatchBuilder::new(Vec::new());
+        json.append_json_array(Cursor::new(r#"[{ "id": 1, "title": "json" }]"#)).unwrap();
+        let mut ndjson = DocumentsBatchBuilder::new(Vec::new());
+        ndjson.append_ndjson(Cursor::new("{ \"id\": 2, \"title\": \"ndjson\" }\n")).unwrap();
+        let mut csv = DocumentsBatchBuilder::new(Vec::new());
+        csv.append_csv(csv::Reader::from_reader(Cursor::new("id:number,title\n3,csv\n"))).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        let mut builder = IndexDocuments::new(
+            &mut wtxn,
+            &index,
+            &index.indexer_config,
+            index.index_documents_config.clone(),
+            |_| (),
+            || false,
+        )
+        .unwrap();
+        for batch in [json, ndjson, csv] {
+            let documents =
+                DocumentsBatchReader::from_reader(Cursor::new(batch.into_inner().unwrap()))
+                    .unwrap();
+            let (new_builder, added) = builder.add_documents(documents).unwrap();
+            assert_eq!(added.unwrap(), 1);
+            builder = new_builder;
+        }
+        builder.execute().unwrap();
+        wtxn.commit().unwrap();
+
+        db_snap!(index, documents, @r###"
+        {"id":1,"title":"json"}
+        {"id":2,"title":"ndjson"}
+        {"id":3,"title":"csv"}
+        "###);
+    }
+
     #[test]
     fn delete_document_and_then_add_documents_in_the_same_transform() {
         let mut index = TempIndex::new();
@@ -2684,4 +2794,79 @@ mod tests {
         let res = index.search(&rtxn).execute().unwrap();
         index.documents(&rtxn, res.documents_ids).unwrap();
     }
+
+    #[test]
+    fn hashed_documents_ids() {
+        let create_index = || {
+            let mut index = TempIndex::new();
+            index.index_documents_config.hash_external_ids = true;
+            index
+                .add_documents(documents!([
+                    { "id": "kevin", "name": "kevin" },
+                    { "id": "kevina", "name": "kevina" },
+                    { "id": "benoit", "name": "benoit" },
+                ]))
+                .unwrap();
+            index
+        };
+        let documents_ids = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+            ["kevin", "kevina", "benoit"]
+                .iter()
+                .map(|id| external_documents_ids.get(id).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let index = create_index();
+        assert_eq!(documents_ids(&index), documents_ids(&create_index()));
+
+        index.add_documents(documents!([ { "id": "kevin", "name": "updated kevin" } ])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 3);
+        let res = index.search(&rtxn).query("updated").execute().unwrap();
+        assert_eq!(res.documents_ids, [documents_ids(&index)[0]]);
+    }
+
+    #[test]
+    fn skip_identical_documents() {
+        for hash_external_ids in [false, true] {
+            let mut index = TempIndex::new();
+            index.index_documents_config.hash_external_ids = hash_external_ids;
+            let documents = || {
+                documents!([
+                    { "id": "kevin", "name": "kevin", "age": 20 },
+                    { "id": "kevina", "name": "kevina" },
+                    { "id": "benoit", "name": "benoit" },
+                ])
+            };
+            index.add_documents(documents()).unwrap();
+
+            let rtxn = index.read_txn().unwrap();
+            let documents_ids = index.documents_ids(&rtxn).unwrap();
+            drop(rtxn);
+
+            // Sending the same documents again doesn't touch the stored ones.
+            index.add_documents(documents()).unwrap();
+            let rtxn = index.read_txn().unwrap();
+            assert_eq!(index.documents_ids(&rtxn).unwrap(), documents_ids);
+            assert!(index.soft_deleted_documents_ids(&rtxn).unwrap().is_empty());
+            let kevin = index.external_documents_ids(&rtxn).unwrap().get("kevin").unwrap();
+            drop(rtxn);
+
+            // Only the documents that changed are replaced.
+            index
+                .add_documents(documents!([
+                    { "id": "kevin", "name": "kevin", "age": 21 },
+                    { "id": "benoit", "name": "benoit" },
+                ]))
+                .unwrap();
+            let rtxn = index.read_txn().unwrap();
+            let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+            assert_ne!(external_documents_ids.get("kevin").unwrap(), kevin);
+            assert_eq!(index.number_of_documents(&rtxn).unwrap(), 3);
+            let new_documents_ids = index.documents_ids(&rtxn).unwrap();
+            assert_eq!((&documents_ids - &new_documents_ids).len(), 1);
+        }
+    }
 }
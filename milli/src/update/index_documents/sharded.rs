@@ -0,0 +1,322 @@
+//! Indexes a batch of documents into several shard indexes in parallel and merges them.
+//!
+//! The documents are split into contiguous ranges, one per shard, and every shard is given a
+//! disjoint range of internal document ids along with the same fields ids map. This way the
+//! shards can be written by independent writers and then merged with a [`BulkBuild`].
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::result::Result as StdResult;
+use std::thread;
+
+use super::bulk_build::{BulkBuild, Segment};
+use super::enrich::validate_document_id_value;
+use super::{IndexDocuments, IndexDocumentsConfig};
+use crate::documents::{obkv_to_object, DocumentsBatchBuilder, DocumentsBatchReader};
+use crate::error::UserError;
+use crate::update::IndexerConfig;
+use crate::{DocumentId, FieldsIdsMap, Index, Result};
+
+/// Indexes documents into empty shard indexes, one writer thread per shard.
+///
+/// The shards must share the same settings, including the primary key.
+pub struct ShardedIndexDocuments<'a> {
+    shards: &'a [Index],
+    indexer_config: &'a IndexerConfig,
+    config: IndexDocumentsConfig,
+}
+
+impl<'a> ShardedIndexDocuments<'a> {
+    /// # Panics
+    ///
+    /// Panics if there is no shard.
+    pub fn new(
+        shards: &'a [Index],
+        indexer_config: &'a IndexerConfig,
+        config: IndexDocumentsConfig,
+    ) -> ShardedIndexDocuments<'a> {
+        assert!(!shards.is_empty(), "at least one shard is required");
+        ShardedIndexDocuments { shards, indexer_config, config }
+    }
+
+    /// Splits the documents between the shards and indexes them in parallel.
+    ///
+    /// All the versions of a document are sent to the shard that received its first version,
+    /// so that they are replaced or merged like in a single index.
+    /// Returns the number of indexed documents.
+    #[logging_timer::time("ShardedIndexDocuments::{}")]
+    pub fn execute<R: Read + Seek>(
+        &self,
+        reader: DocumentsBatchReader<R>,
+    ) -> Result<StdResult<u64, UserError>> {
+        let rtxn = self.shards[0].read_txn()?;
+        for shard in self.shards {
+            if !shard.documents.is_empty(&shard.read_txn()?)? {
+                return Ok(Err(UserError::BulkBuildIntoNonEmptyIndex));
+            }
+        }
+        let primary_key = match self.shards[0].primary_key(&rtxn)? {
+            Some(primary_key) => primary_key.to_string(),
+            None => return Ok(Err(UserError::NoPrimaryKeyCandidateFound)),
+        };
+        let mut fields_ids_map = self.shards[0].fields_ids_map(&rtxn)?;
+        drop(rtxn);
+
+        let documents_count = reader.documents_count() as usize;
+        let shards_count = self.shards.len();
+        let (mut cursor, batch_index) = reader.into_cursor_and_fields_index();
+
+        let mut builders = Vec::with_capacity(shards_count);
+        for _ in 0..shards_count {
+            builders.push(DocumentsBatchBuilder::new(tempfile::tempfile()?));
+        }
+        let mut shard_of_external_ids = HashMap::new();
+        let mut position = 0;
+        while let Some(document) = cursor.next_document()? {
+            let object = obkv_to_object(&document, &batch_index)?;
+
+            // Every field must be known beforehand, including the flattened ones,
+            // for the shards to give the same ids to the same fields.
+            let flattened = flatten_serde_json::flatten(&object);
+            for name in object.keys().chain(flattened.keys()) {
+                fields_ids_map.insert(name).ok_or(UserError::AttributeLimitReached)?;
+            }
+
+            let default_shard = position * shards_count / documents_count;
+            let shard = match flattened.get(&primary_key) {
+                Some(value) => match validate_document_id_value(value.clone())? {
+                    Ok(external_id) => {
+                        *shard_of_external_ids.entry(external_id).or_insert(default_shard)
+                    }
+                    Err(user_error) => return Ok(Err(user_error)),
+                },
+                None if self.config.autogenerate_docids => default_shard,
+                None => {
+                    return Ok(Err(UserError::MissingDocumentId { primary_key, document: object }))
+                }
+            };
+            builders[shard].append_json_object(&object)?;
+            position += 1;
+        }
+
+        let mut readers = Vec::with_capacity(shards_count);
+        let mut first_document_id: DocumentId = 0;
+        for builder in builders {
+            let count = builder.documents_count();
+            let mut file = builder.into_inner()?;
+            file.rewind()?;
+            readers.push((DocumentsBatchReader::from_reader(file)?, first_document_id));
+            first_document_id += count;
+        }
+
+        let fields_ids_map = &fields_ids_map;
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .shards
+                .iter()
+                .zip(readers)
+                .map(|(shard, (reader, first_document_id))| {
+                    scope.spawn(move || {
+                        self.index_shard(shard, fields_ids_map, first_document_id, reader)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        });
+
+        let mut indexed_documents = 0;
+        for result in results {
+            match result? {
+                Ok(count) => indexed_documents += count,
+                Err(user_error) => return Ok(Err(user_error)),
+            }
+        }
+        Ok(Ok(indexed_documents))
+    }
+
+    fn index_shard<R: Read + Seek>(
+        &self,
+        shard: &Index,
+        fields_ids_map: &FieldsIdsMap,
+        first_document_id: DocumentId,
+        reader: DocumentsBatchReader<R>,
+    ) -> Result<StdResult<u64, UserError>> {
+        let mut wtxn = shard.write_txn()?;
+        shard.put_fields_ids_map(&mut wtxn, fields_ids_map)?;
+        let config = IndexDocumentsConfig { first_document_id, ..self.config.clone() };
+        let builder =
+            IndexDocuments::new(&mut wtxn, shard, self.indexer_config, config, |_| (), || false)?;
+        let (builder, user_result) = builder.add_documents(reader)?;
+        let indexed_documents = match user_result {
+            Ok(count) => count,
+            Err(user_error) => return Ok(Err(user_error)),
+        };
+        builder.execute()?;
+        wtxn.commit()?;
+        Ok(Ok(indexed_documents))
+    }
+
+    /// Merges the shards into an empty index, see [`BulkBuild`].
+    ///
+    /// Returns the number of documents in the index after the merge.
+    #[logging_timer::time("ShardedIndexDocuments::{}")]
+    pub fn merge_into<'t, 'u, 'i>(
+        &self,
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+    ) -> Result<u64> {
+        let rtxn = self.shards[0].read_txn()?;
+        let fields_ids_map = self.shards[0].fields_ids_map(&rtxn)?;
+        let primary_key = match self.shards[0].primary_key(&rtxn)? {
+            Some(primary_key) => primary_key.to_string(),
+            None => return Err(UserError::NoPrimaryKeyCandidateFound.into()),
+        };
+        drop(rtxn);
+
+        // The segments are extracted in parallel, every shard with its own read transaction.
+        let segments: Vec<Result<Segment>> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .shards
+                .iter()
+                .map(|shard| {
+                    scope.spawn(move || {
+                        let rtxn = shard.read_txn()?;
+                        let documents_ids = shard.documents_ids(&rtxn)?;
+                        Segment::from_index(&rtxn, shard, &documents_ids)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        });
+
+        let mut builder =
+            BulkBuild::new(wtxn, index, self.indexer_config, fields_ids_map, primary_key);
+        for segment in segments {
+            builder.add_segment(segment?);
+        }
+        builder.execute()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Filter, Search, SearchResult};
+
+    fn documents() -> DocumentsBatchReader<std::io::Cursor<Vec<u8>>> {
+        documents!([
+            { "id": 0, "title": "the quick brown fox", "genre": "tale" },
+            { "id": 1, "title": "the lazy dog", "genre": "tale" },
+            { "id": 2, "title": "a quick riddle", "genre": "puzzle" },
+            { "id": 3, "title": "brown bread recipes", "meta": { "author": "kevin" } },
+            { "id": 1, "title": "the lazy cat", "genre": "tale" },
+            { "id": 4, "title": "the fox and the dog", "genre": "tale" },
+            { "id": 5, "title": "a dog story", "meta": { "year": 2020 } },
+        ])
+    }
+
+    fn create_index() -> TempIndex {
+        let index = TempIndex::new();
+        index
+            .update_settings(|s| {
+                s.set_primary_key(S("id"));
+                s.set_filterable_fields(hashset! { S("genre"), S("meta.author") });
+            })
+            .unwrap();
+        index
+    }
+
+    fn search(index: &Index, query: &str, filter: Option<&str>) -> Vec<String> {
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, index);
+        search.query(query);
+        if let Some(filter) = filter.and_then(|f| Filter::from_str(f).unwrap()) {
+            search.filter(filter);
+        }
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        index
+            .documents_with_fields(&rtxn, documents_ids, &["id"])
+            .unwrap()
+            .into_iter()
+            .map(|(_, document)| document["id"].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn index_in_shards_and_merge() {
+        let reference = create_index();
+        reference.add_documents(documents()).unwrap();
+
+        let shards: Vec<_> = (0..3).map(|_| create_index()).collect();
+        let inner_shards: Vec<_> = shards.iter().map(|shard| shard.inner.clone()).collect();
+        let config = IndexDocumentsConfig::default();
+        let sharded = ShardedIndexDocuments::new(&inner_shards, &reference.indexer_config, config);
+        assert_eq!(sharded.execute(documents()).unwrap().unwrap(), 7);
+
+        // The internal ids of the shards are disjoint.
+        let mut seen = roaring::RoaringBitmap::new();
+        for shard in &shards {
+            let documents_ids = shard.documents_ids(&shard.read_txn().unwrap()).unwrap();
+            assert!(seen.is_disjoint(&documents_ids));
+            seen |= documents_ids;
+        }
+
+        let index = create_index();
+        let mut wtxn = index.write_txn().unwrap();
+        assert_eq!(sharded.merge_into(&mut wtxn, &index).unwrap(), 6);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let reference_rtxn = reference.read_txn().unwrap();
+        assert_eq!(
+            index.field_distribution(&rtxn).unwrap(),
+            reference.field_distribution(&reference_rtxn).unwrap()
+        );
+        for (query, filter) in [
+            ("the fox", None),
+            ("lazy", None),
+            ("qui", None),
+            ("dog", Some("genre = tale")),
+            ("", Some("meta.author = kevin")),
+        ] {
+            assert_eq!(search(&index, query, filter), search(&reference, query, filter));
+        }
+    }
+
+    #[test]
+    fn sharded_indexing_requires_a_primary_key() {
+        let shards = vec![TempIndex::new(), TempIndex::new()];
+        let inner_shards: Vec<_> = shards.iter().map(|shard| shard.inner.clone()).collect();
+        let sharded = ShardedIndexDocuments::new(
+            &inner_shards,
+            &shards[0].indexer_config,
+            IndexDocumentsConfig::default(),
+        );
+        let user_error = sharded.execute(documents()).unwrap().unwrap_err();
+        assert!(matches!(user_error, UserError::NoPrimaryKeyCandidateFound));
+    }
+
+    #[test]
+    fn sharded_indexing_requires_empty_shards() {
+        let shards = vec![create_index(), create_index()];
+        shards[1].add_documents(documents()).unwrap();
+        let inner_shards: Vec<_> = shards.iter().map(|shard| shard.inner.clone()).collect();
+        let sharded = ShardedIndexDocuments::new(
+            &inner_shards,
+            &shards[0].indexer_config,
+            IndexDocumentsConfig::default(),
+        );
+        let user_error = sharded.execute(documents()).unwrap().unwrap_err();
+        assert!(matches!(user_error, UserError::BulkBuildIntoNonEmptyIndex));
+    }
+}
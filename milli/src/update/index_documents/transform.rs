@@ -21,7 +21,8 @@ use crate::error::{Error, InternalError, UserError};
 use crate::index::{db_name, main_key};
 use crate::update::{AvailableDocumentsIds, ClearDocuments, UpdateIndexingStep};
 use crate::{
-    FieldDistribution, FieldId, FieldIdMapMissingEntry, FieldsIdsMap, Index, Result, BEU32,
+    DocumentId, FieldDistribution, FieldId, FieldIdMapMissingEntry, FieldsIdsMap, Index, Result,
+    BEU32,
 };
 
 pub struct TransformOutput {
@@ -50,6 +51,8 @@ pub struct Transform<'a, 'i> {
     pub autogenerate_docids: bool,
     pub index_documents_method: IndexDocumentsMethod,
     available_documents_ids: AvailableDocumentsIds,
+    /// The internal ids already in use when they are derived from the external ids.
+    hashed_documents_ids: Option<RoaringBitmap>,
 
     // Both grenad follows the same format:
     // key | value
@@ -72,6 +75,28 @@ pub enum Operation {
     Deletion,
 }
 
+/// Derives the internal id of a document from its external id and marks it as used.
+///
+/// The hash is stable across machines and versions, the collisions are resolved
+/// by taking the next unused id, in the order the documents are indexed.
+fn hashed_document_id(used_documents_ids: &mut RoaringBitmap, external_id: &str) -> Result<u32> {
+    if used_documents_ids.len() > u32::MAX as u64 {
+        return Err(UserError::DocumentLimitReached.into());
+    }
+
+    // The 32 bits FNV-1a hash function.
+    let mut docid: u32 = 0x811c9dc5;
+    for byte in external_id.bytes() {
+        docid ^= byte as u32;
+        docid = docid.wrapping_mul(0x01000193);
+    }
+
+    while !used_documents_ids.insert(docid) {
+        docid = docid.wrapping_add(1);
+    }
+    Ok(docid)
+}
+
 /// Create a mapping between the field ids found in the document batch and the one that were
 /// already present in the index.
 ///
@@ -102,6 +127,8 @@ impl<'a, 'i> Transform<'a, 'i> {
         indexer_settings: &'a IndexerConfig,
         index_documents_method: IndexDocumentsMethod,
         autogenerate_docids: bool,
+        first_document_id: DocumentId,
+        hash_external_ids: bool,
     ) -> Result<Self> {
         // We must choose the appropriate merge function for when two or more documents
         // with the same user id must be merged or fully replaced in the same batch.
@@ -137,10 +164,13 @@ impl<'a, 'i> Transform<'a, 'i> {
             fields_ids_map: index.fields_ids_map(wtxn)?,
             indexer_settings,
             autogenerate_docids,
-            available_documents_ids: AvailableDocumentsIds::from_documents_ids(
+            available_documents_ids: AvailableDocumentsIds::from_documents_ids_starting_at(
                 &documents_ids,
                 &soft_deleted_documents_ids,
+                first_document_id,
             ),
+            hashed_documents_ids: hash_external_ids
+                .then(|| &documents_ids | &soft_deleted_documents_ids),
             original_sorter,
             flattened_sorter,
             index_documents_method,
@@ -232,10 +262,15 @@ impl<'a, 'i> Transform<'a, 'i> {
                             original_docid = Some(docid);
                         }
                     }
-                    let docid = self
-                        .available_documents_ids
-                        .next()
-                        .ok_or(UserError::DocumentLimitReached)?;
+                    let docid = match self.hashed_documents_ids.as_mut() {
+                        Some(used_documents_ids) => {
+                            hashed_document_id(used_documents_ids, entry.key())?
+                        }
+                        None => self
+                            .available_documents_ids
+                            .next()
+                            .ok_or(UserError::DocumentLimitReached)?,
+                    };
                     entry.insert(docid as u64);
                     docid
                 }
@@ -260,6 +295,10 @@ impl<'a, 'i> Transform<'a, 'i> {
                     self.replaced_documents_ids.remove(original_docid);
                     // and we need to put back the original id as it was before
                     self.new_external_documents_ids_builder.remove(external_id);
+                    // and the id we were about to use is free again
+                    if let Some(used_documents_ids) = self.hashed_documents_ids.as_mut() {
+                        used_documents_ids.remove(docid);
+                    }
                     skip_insertion = true;
                 } else {
                     // we associate the base document with the new key, everything will get merged later.
@@ -856,4 +895,16 @@ mod test {
         .unwrap();
         assert_eq!(*ret, doc_0);
     }
+
+    #[test]
+    fn hashed_document_id_collisions() {
+        let mut used = RoaringBitmap::new();
+        let docid = hashed_document_id(&mut used, "kevin").unwrap();
+        assert_eq!(hashed_document_id(&mut RoaringBitmap::new(), "kevin").unwrap(), docid);
+
+        // The same external id can't be given the same internal id twice.
+        assert_eq!(hashed_document_id(&mut used, "kevin").unwrap(), docid.wrapping_add(1));
+        used.insert(docid.wrapping_add(2));
+        assert_eq!(hashed_document_id(&mut used, "kevin").unwrap(), docid.wrapping_add(3));
+    }
 }
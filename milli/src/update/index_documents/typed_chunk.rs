@@ -22,8 +22,9 @@ use crate::facet::FacetType;
 use crate::update::facet::FacetsUpdate;
 use crate::update::index_documents::helpers::{as_cloneable_grenad, try_split_array_at};
 use crate::{
-    lat_lng_to_xyz, normalize_vector, CboRoaringBitmapCodec, DocumentId, GeoPoint, Index, Result,
-    BEU32,
+    lat_lng_to_xyz, normalize_vector, BitmapCompression, CboRoaringBitmapCodec,
+    DeltaRoaringBitmapCodec, DocumentId, GeoPoint, Index, Result, RoaringBitmapCodec,
+    RoaringBitmapLenCodec, BEU32, BEU64,
 };
 
 pub(crate) enum TypedChunk {
@@ -58,6 +59,7 @@ pub(crate) fn write_typed_chunk_into_index(
     index_is_empty: bool,
 ) -> Result<(RoaringBitmap, bool)> {
     let mut is_merged_database = false;
+    let compression = index.bitmap_compression(wtxn)?;
     match typed_chunk {
         TypedChunk::Documents(obkv_documents_iter) => {
             let mut cursor = obkv_documents_iter.into_cursor()?;
@@ -86,9 +88,13 @@ pub(crate) fn write_typed_chunk_into_index(
                 &index.word_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
-                merge_roaring_bitmaps,
+                |value, buffer| compress_bitmap(value, buffer, compression),
+                |new_value, db_value, buffer| {
+                    merge_roaring_bitmaps(new_value, db_value, buffer)?;
+                    compress_merged_bitmap(buffer, compression)
+                },
             )?;
+            write_word_docids_count(word_docids_iter.clone(), index, wtxn)?;
 
             let exact_word_docids_iter = unsafe { as_cloneable_grenad(&exact_word_docids_reader) }?;
             append_entries_into_database(
@@ -96,8 +102,11 @@ pub(crate) fn write_typed_chunk_into_index(
                 &index.exact_word_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
-                merge_roaring_bitmaps,
+                |value, buffer| compress_bitmap(value, buffer, compression),
+                |new_value, db_value, buffer| {
+                    merge_roaring_bitmaps(new_value, db_value, buffer)?;
+                    compress_merged_bitmap(buffer, compression)
+                },
             )?;
 
             // create fst from word docids
@@ -118,8 +127,11 @@ pub(crate) fn write_typed_chunk_into_index(
                 &index.word_position_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
-                merge_cbo_roaring_bitmaps,
+                |value, buffer| compress_bitmap(value, buffer, compression),
+                |new_value, db_value, buffer| {
+                    merge_cbo_roaring_bitmaps(new_value, db_value, buffer)?;
+                    compress_merged_bitmap(buffer, compression)
+                },
             )?;
             is_merged_database = true;
         }
@@ -129,8 +141,11 @@ pub(crate) fn write_typed_chunk_into_index(
                 &index.word_fid_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
-                merge_cbo_roaring_bitmaps,
+                |value, buffer| compress_bitmap(value, buffer, compression),
+                |new_value, db_value, buffer| {
+                    merge_cbo_roaring_bitmaps(new_value, db_value, buffer)?;
+                    compress_merged_bitmap(buffer, compression)
+                },
             )?;
             is_merged_database = true;
         }
@@ -183,8 +198,11 @@ pub(crate) fn write_typed_chunk_into_index(
                 &index.word_pair_proximity_docids,
                 wtxn,
                 index_is_empty,
-                |value, _buffer| Ok(value),
-                merge_cbo_roaring_bitmaps,
+                |value, buffer| compress_bitmap(value, buffer, compression),
+                |new_value, db_value, buffer| {
+                    merge_cbo_roaring_bitmaps(new_value, db_value, buffer)?;
+                    compress_merged_bitmap(buffer, compression)
+                },
             )?;
             is_merged_database = true;
         }
@@ -305,7 +323,7 @@ fn merge_word_docids_reader_into_fst(
 
 fn merge_roaring_bitmaps(new_value: &[u8], db_value: &[u8], buffer: &mut Vec<u8>) -> Result<()> {
     let new_value = RoaringBitmap::deserialize_from(new_value)?;
-    let db_value = RoaringBitmap::deserialize_from(db_value)?;
+    let db_value = RoaringBitmapCodec::deserialize_from(db_value)?;
     let value = new_value | db_value;
     Ok(serialize_roaring_bitmap(&value, buffer)?)
 }
@@ -321,6 +339,50 @@ fn merge_cbo_roaring_bitmaps(
     )?)
 }
 
+/// Writes the number of documents of the words of the chunk, read from the headers of the
+/// bitmaps just written in the word docids database.
+fn write_word_docids_count<R: io::Read + io::Seek>(
+    word_docids: grenad::Reader<R>,
+    index: &Index,
+    wtxn: &mut RwTxn,
+) -> Result<()> {
+    let word_docids_len = index.word_docids.remap_types::<ByteSlice, RoaringBitmapLenCodec>();
+    let word_docids_count = index.word_docids_count.remap_key_type::<ByteSlice>();
+    let mut cursor = word_docids.into_cursor()?;
+    while let Some((key, _value)) = cursor.move_on_next()? {
+        if let Some(count) = word_docids_len.get(wtxn, key)? {
+            word_docids_count.put(wtxn, key, &BEU64::new(count))?;
+        }
+    }
+    Ok(())
+}
+
+/// Re-encodes a bitmap with the delta encoding when the index uses it and it is smaller.
+fn compress_bitmap<'a>(
+    value: &'a [u8],
+    buffer: &'a mut Vec<u8>,
+    compression: BitmapCompression,
+) -> Result<&'a [u8]> {
+    if compression == BitmapCompression::Delta
+        && DeltaRoaringBitmapCodec::compress_into(value, buffer)?
+    {
+        Ok(&buffer[..])
+    } else {
+        Ok(value)
+    }
+}
+
+/// Re-encodes the merged bitmap of the buffer like [`compress_bitmap`].
+fn compress_merged_bitmap(buffer: &mut Vec<u8>, compression: BitmapCompression) -> Result<()> {
+    let mut compressed = Vec::new();
+    if compression == BitmapCompression::Delta
+        && DeltaRoaringBitmapCodec::compress_into(buffer, &mut compressed)?
+    {
+        *buffer = compressed;
+    }
+    Ok(())
+}
+
 /// Write provided entries in database using serialize_value function.
 /// merge_values function is used if an entry already exist in the database.
 fn write_entries_into_database<R, K, V, FS, FM>(
@@ -1,30 +1,41 @@
+pub use self::apply_changes::{ApplyChanges, ApplyChangesResult, DocumentChange};
 pub use self::available_documents_ids::AvailableDocumentsIds;
 pub use self::clear_documents::ClearDocuments;
+pub use self::compact_vectors::{
+    CompactVectors, VectorsCompactionResult, DEFAULT_DEAD_POINTS_RATIO_THRESHOLD,
+};
 pub use self::delete_documents::{DeleteDocuments, DeletionStrategy, DocumentDeletionResult};
 pub use self::facet::bulk::FacetsUpdateBulk;
 pub use self::facet::incremental::FacetsUpdateIncrementalInner;
 pub use self::index_documents::{
-    merge_cbo_roaring_bitmaps, merge_roaring_bitmaps, DocumentAdditionResult, DocumentId,
-    IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod, MergeFn,
+    merge_cbo_roaring_bitmaps, merge_roaring_bitmaps, validate_document_id_value, BulkBuild,
+    DocumentAdditionResult, DocumentId, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod,
+    MergeFn, Segment, SegmentDatabase, ShardedIndexDocuments,
 };
 pub use self::indexer_config::IndexerConfig;
+pub use self::optimize::{OptimizationResult, OptimizationStep, OptimizeIndex};
 pub use self::prefix_word_pairs::{
     PrefixWordPairsProximityDocids, MAX_LENGTH_FOR_PREFIX_PROXIMITY_DB,
     MAX_PROXIMITY_FOR_PREFIX_PROXIMITY_DB,
 };
+pub use self::quarantine_corrupted_entries::QuarantineCorruptedEntries;
 pub use self::settings::{Setting, Settings};
 pub use self::update_step::UpdateIndexingStep;
 pub use self::word_prefix_docids::WordPrefixDocids;
 pub use self::words_prefix_integer_docids::WordPrefixIntegerDocids;
 pub use self::words_prefixes_fst::WordsPrefixesFst;
 
+mod apply_changes;
 mod available_documents_ids;
 mod clear_documents;
+mod compact_vectors;
 mod delete_documents;
 pub(crate) mod facet;
 mod index_documents;
 mod indexer_config;
+mod optimize;
 mod prefix_word_pairs;
+mod quarantine_corrupted_entries;
 mod settings;
 mod update_step;
 mod word_prefix_docids;
@@ -0,0 +1,286 @@
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::time::{Duration, Instant};
+
+use fst::IntoStreamer;
+use log::debug;
+
+use crate::facet::FacetType;
+pub use crate::index::OptimizationStep;
+use crate::update::{CompactVectors, DeleteDocuments, FacetsUpdateBulk, WordsPrefixesFst};
+use crate::{Index, Result};
+
+/// Performs the deferred maintenance work of an index within a time budget.
+///
+/// The steps are never interrupted, the budget is checked before starting each of them and
+/// the steps left are recorded in the index. The next optimization resumes from them, so the
+/// heavy maintenance can be spread over the idle periods of the engine. At least one step is
+/// performed by every optimization, a full pass is started when nothing is left.
+pub struct OptimizeIndex<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    time_budget: Option<Duration>,
+    words_prefix_threshold: Option<u32>,
+    max_prefix_length: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptimizationResult {
+    /// The steps performed by this optimization, in order.
+    pub performed: Vec<OptimizationStep>,
+    /// The steps left for the next optimizations.
+    pub remaining: Vec<OptimizationStep>,
+}
+
+impl<'t, 'u, 'i> OptimizeIndex<'t, 'u, 'i> {
+    pub fn new(wtxn: &'t mut heed::RwTxn<'i, 'u>, index: &'i Index) -> OptimizeIndex<'t, 'u, 'i> {
+        OptimizeIndex {
+            wtxn,
+            index,
+            time_budget: None,
+            words_prefix_threshold: None,
+            max_prefix_length: None,
+        }
+    }
+
+    /// Sets the duration after which no more step is started, all the steps are
+    /// performed by default.
+    pub fn time_budget(&mut self, budget: Duration) -> &mut Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Sets the number of words a prefix must match to be kept in the prefix databases,
+    /// must be the same as the one used by the indexing.
+    pub fn words_prefix_threshold(&mut self, value: u32) -> &mut Self {
+        self.words_prefix_threshold = Some(value);
+        self
+    }
+
+    /// Sets the maximum length of the prefixes in bytes,
+    /// must be the same as the one used by the indexing.
+    pub fn max_prefix_length(&mut self, value: usize) -> &mut Self {
+        self.max_prefix_length = Some(value);
+        self
+    }
+
+    #[logging_timer::time("OptimizeIndex::{}")]
+    pub fn execute(mut self) -> Result<OptimizationResult> {
+        let started_at = Instant::now();
+
+        let mut remaining = self.index.pending_optimizations(self.wtxn)?;
+        if remaining.is_empty() {
+            remaining = OptimizationStep::ALL.to_vec();
+        }
+
+        let mut performed = Vec::new();
+        while !remaining.is_empty() {
+            let out_of_budget =
+                self.time_budget.map_or(false, |budget| started_at.elapsed() >= budget);
+            if !performed.is_empty() && out_of_budget {
+                break;
+            }
+
+            let step = remaining.remove(0);
+            debug!("Performing the {step:?} optimization step");
+            self.perform(step)?;
+            performed.push(step);
+        }
+
+        if remaining.is_empty() {
+            self.index.delete_pending_optimizations(self.wtxn)?;
+        } else {
+            self.index.put_pending_optimizations(self.wtxn, &remaining)?;
+        }
+
+        Ok(OptimizationResult { performed, remaining })
+    }
+
+    fn perform(&mut self, step: OptimizationStep) -> Result<()> {
+        match step {
+            OptimizationStep::PurgeSoftDeleted => {
+                if !self.index.soft_deleted_documents_ids(self.wtxn)?.is_empty() {
+                    DeleteDocuments::new(self.wtxn, self.index)?.purge_soft_deleted()?;
+                }
+            }
+            OptimizationStep::RemergeWordsFst => self.remerge_words_fst()?,
+            OptimizationStep::RefreshPrefixDatabases => self.refresh_prefix_databases()?,
+            OptimizationStep::RebuildFacetLevels => {
+                let field_ids = Vec::from_iter(self.index.faceted_fields_ids(self.wtxn)?);
+                for facet_type in [FacetType::Number, FacetType::String] {
+                    FacetsUpdateBulk::new_not_updating_level_0(
+                        self.index,
+                        field_ids.clone(),
+                        facet_type,
+                    )
+                    .execute(self.wtxn)?;
+                }
+            }
+            OptimizationStep::CompactVectors => {
+                CompactVectors::new(self.wtxn, self.index).execute()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the words FST from scratch, the deletions only remove the words they
+    /// know of from it.
+    fn remerge_words_fst(&mut self) -> Result<()> {
+        let mut sets = Vec::with_capacity(2);
+        for db in [self.index.word_docids, self.index.exact_word_docids] {
+            let mut builder = fst::SetBuilder::memory();
+            for result in db.remap_data_type::<heed::types::DecodeIgnore>().iter(self.wtxn)? {
+                let (word, ()) = result?;
+                builder.insert(word)?;
+            }
+            sets.push(builder.into_set());
+        }
+
+        let mut builder = fst::SetBuilder::memory();
+        builder.extend_stream(sets[0].op().add(&sets[1]).union())?;
+        self.index.put_words_fst(self.wtxn, &builder.into_set())?;
+        Ok(())
+    }
+
+    /// Recomputes the prefix FST and removes the prefixes that are no longer part of it.
+    ///
+    /// The deletions only remove the prefixes that don't match any document, the new prefixes
+    /// are never added here: they can only appear when words are indexed.
+    fn refresh_prefix_databases(&mut self) -> Result<()> {
+        let previous_prefixes =
+            self.index.words_prefixes_fst(self.wtxn)?.map_data(|cow| cow.into_owned())?;
+
+        let mut builder = WordsPrefixesFst::new(self.wtxn, self.index);
+        if let Some(value) = self.words_prefix_threshold {
+            builder.threshold(value);
+        }
+        if let Some(value) = self.max_prefix_length {
+            builder.max_prefix_length(value);
+        }
+        builder.execute()?;
+
+        let (kept_prefixes, removed_prefixes) = {
+            let current_prefixes = self.index.words_prefixes_fst(self.wtxn)?;
+            let mut builder = fst::SetBuilder::memory();
+            builder.extend_stream(previous_prefixes.op().add(&current_prefixes).intersection())?;
+            let removed = previous_prefixes.op().add(&current_prefixes).difference();
+            let removed: HashSet<String> = removed.into_stream().into_strs()?.into_iter().collect();
+            (builder.into_set(), removed)
+        };
+        self.index.put_words_prefixes_fst(self.wtxn, &kept_prefixes)?;
+
+        if removed_prefixes.is_empty() {
+            return Ok(());
+        }
+
+        for db in [self.index.word_prefix_docids, self.index.exact_word_prefix_docids] {
+            for prefix in &removed_prefixes {
+                db.delete(self.wtxn, prefix)?;
+            }
+        }
+
+        for db in [self.index.word_prefix_position_docids, self.index.word_prefix_fid_docids] {
+            let mut iter = db.iter_mut(self.wtxn)?.lazily_decode_data();
+            while let Some(((prefix, _), _)) = iter.next().transpose()? {
+                if removed_prefixes.contains(prefix) {
+                    // safety: we don't keep references from inside the LMDB database.
+                    unsafe { iter.del_current()? };
+                }
+            }
+        }
+
+        let mut iter =
+            self.index.word_prefix_pair_proximity_docids.iter_mut(self.wtxn)?.lazily_decode_data();
+        while let Some(((_, _, prefix), _)) = iter.next().transpose()? {
+            if removed_prefixes.contains(prefix) {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+            }
+        }
+        drop(iter);
+
+        let mut iter =
+            self.index.prefix_word_pair_proximity_docids.iter_mut(self.wtxn)?.lazily_decode_data();
+        while let Some(((_, prefix, _), _)) = iter.next().transpose()? {
+            if removed_prefixes.contains(prefix) {
+                // safety: we don't keep references from inside the LMDB database.
+                unsafe { iter.del_current()? };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::documents::documents_batch_reader_from_objects;
+    use crate::index::tests::TempIndex;
+    use crate::update::DeletionStrategy;
+
+    #[test]
+    fn optimize_within_a_time_budget() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.deletion_strategy = DeletionStrategy::AlwaysSoft;
+        index.index_documents_config.words_prefix_threshold = Some(50);
+        index.update_settings(|s| s.set_filterable_fields(hashset! { S("rank") })).unwrap();
+
+        // every word starts with the prefix "word"
+        let documents = (0..60).map(|i| {
+            let object = serde_json::json!({ "id": i, "title": format!("word{i:02}"), "rank": i });
+            object.as_object().unwrap().clone()
+        });
+        index.add_documents(documents_batch_reader_from_objects(documents)).unwrap();
+        for i in 0..20 {
+            index.delete_document(&i.to_string());
+        }
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.soft_deleted_documents_ids(&rtxn).unwrap().len(), 20);
+        assert!(index.words_prefixes_fst(&rtxn).unwrap().contains("word"));
+        drop(rtxn);
+
+        // a step is always performed, even without any time left
+        let mut wtxn = index.write_txn().unwrap();
+        let mut optimize = OptimizeIndex::new(&mut wtxn, &index);
+        optimize.time_budget(Duration::ZERO);
+        let result = optimize.execute().unwrap();
+        assert_eq!(result.performed, [OptimizationStep::PurgeSoftDeleted]);
+        assert_eq!(result.remaining, OptimizationStep::ALL[1..]);
+        assert_eq!(index.pending_optimizations(&wtxn).unwrap(), result.remaining);
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.soft_deleted_documents_ids(&rtxn).unwrap().is_empty());
+        // the purge keeps the prefixes that still match some words
+        assert!(index.words_prefixes_fst(&rtxn).unwrap().contains("word"));
+        drop(rtxn);
+
+        // the next optimization resumes from the recorded steps
+        let mut wtxn = index.write_txn().unwrap();
+        let mut optimize = OptimizeIndex::new(&mut wtxn, &index);
+        optimize.words_prefix_threshold(50);
+        let result = optimize.execute().unwrap();
+        assert_eq!(result.performed, OptimizationStep::ALL[1..]);
+        assert!(result.remaining.is_empty());
+        assert!(index.pending_optimizations(&wtxn).unwrap().is_empty());
+        wtxn.commit().unwrap();
+
+        // the 40 remaining words are not enough to keep the prefixes
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.words_prefixes_fst(&rtxn).unwrap().is_empty());
+        assert!(index.word_prefix_docids.get(&rtxn, "word").unwrap().is_none());
+        let words_fst = index.words_fst(&rtxn).unwrap();
+        assert!(!words_fst.contains("word19"));
+        assert!(words_fst.contains("word20"));
+
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.query("word").filter(crate::Filter::from_str("rank >= 50").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids.len(), 10);
+    }
+}
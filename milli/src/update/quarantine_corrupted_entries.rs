@@ -0,0 +1,144 @@
+use heed::types::ByteSlice;
+use heed::{BytesDecode, Database};
+use log::warn;
+
+use crate::index::db_name;
+use crate::{Index, Result};
+
+/// Moves the entries of the docids databases that can't be decoded into the
+/// [`corrupted_entries`](Index::corrupted_entries) database.
+///
+/// The searches skip the corrupted entries they read and record them, see
+/// [`Index::skipped_corrupted_entries`]. Once they are quarantined, they are no longer read,
+/// and the searches only miss the documents they referenced until they are reindexed.
+pub struct QuarantineCorruptedEntries<'t, 'u, 'i> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+}
+
+impl<'t, 'u, 'i> QuarantineCorruptedEntries<'t, 'u, 'i> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+    ) -> QuarantineCorruptedEntries<'t, 'u, 'i> {
+        QuarantineCorruptedEntries { wtxn, index }
+    }
+
+    /// Returns the number of entries that were quarantined.
+    pub fn execute(self) -> Result<u64> {
+        let Self { wtxn, index } = self;
+
+        macro_rules! quarantine {
+            ($($name:ident => $field:ident),* $(,)?) => {{
+                let mut count = 0;
+                $(count += quarantine_database(wtxn, index, db_name::$name, index.$field)?;)*
+                count
+            }};
+        }
+
+        // The facet databases are trees whose levels are built on the level 0, their entries
+        // can't be removed without rebuilding the levels.
+        let count = quarantine! {
+            WORD_DOCIDS => word_docids,
+            EXACT_WORD_DOCIDS => exact_word_docids,
+            WORD_DOCIDS_COUNT => word_docids_count,
+            WORD_PREFIX_DOCIDS => word_prefix_docids,
+            EXACT_WORD_PREFIX_DOCIDS => exact_word_prefix_docids,
+            WORD_PAIR_PROXIMITY_DOCIDS => word_pair_proximity_docids,
+            WORD_PREFIX_PAIR_PROXIMITY_DOCIDS => word_prefix_pair_proximity_docids,
+            PREFIX_WORD_PAIR_PROXIMITY_DOCIDS => prefix_word_pair_proximity_docids,
+            WORD_POSITION_DOCIDS => word_position_docids,
+            WORD_FIELD_ID_DOCIDS => word_fid_docids,
+            WORD_PREFIX_POSITION_DOCIDS => word_prefix_position_docids,
+            WORD_PREFIX_FIELD_ID_DOCIDS => word_prefix_fid_docids,
+            FIELD_ID_WORD_COUNT_DOCIDS => field_id_word_count_docids,
+            SCRIPT_LANGUAGE_DOCIDS => script_language_docids,
+            FACET_ID_EXISTS_DOCIDS => facet_id_exists_docids,
+            FACET_ID_IS_NULL_DOCIDS => facet_id_is_null_docids,
+            FACET_ID_IS_EMPTY_DOCIDS => facet_id_is_empty_docids,
+        };
+
+        Ok(count)
+    }
+}
+
+/// Moves the entries whose key or value can't be decoded by the codecs of the database.
+fn quarantine_database<KC, DC>(
+    wtxn: &mut heed::RwTxn,
+    index: &Index,
+    name: &str,
+    database: Database<KC, DC>,
+) -> Result<u64>
+where
+    KC: for<'a> BytesDecode<'a>,
+    DC: for<'a> BytesDecode<'a>,
+{
+    let database = database.remap_types::<ByteSlice, ByteSlice>();
+
+    let mut corrupted = Vec::new();
+    for result in database.iter(wtxn)? {
+        let (key, value) = result?;
+        if KC::bytes_decode(key).is_none() || DC::bytes_decode(value).is_none() {
+            corrupted.push((key.to_vec(), value.to_vec()));
+        }
+    }
+
+    for (key, value) in &corrupted {
+        warn!("Quarantining a corrupted entry of the {name} database: {key:?}");
+        database.delete(wtxn, key)?;
+        index.corrupted_entries.put(wtxn, &(name, key.as_slice()), value)?;
+        index.skipped_corrupted_entries.lock().unwrap().remove(&(name.to_string(), key.clone()));
+    }
+
+    Ok(corrupted.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{CorruptedEntry, Search, SearchResult};
+
+    #[test]
+    fn quarantine_an_undecodable_bitmap() {
+        let index = TempIndex::new();
+        index.add_documents(documents!([{ "id": 0, "title": "hello world" }])).unwrap();
+
+        let mut wtxn = index.write_txn().unwrap();
+        index
+            .word_docids
+            .remap_data_type::<ByteSlice>()
+            .put(&mut wtxn, "hello", &[1, 2, 3])
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, .. } =
+            Search::new(&rtxn, &index).query("hello").execute().unwrap();
+        assert!(documents_ids.is_empty());
+        assert_eq!(
+            index.skipped_corrupted_entries(),
+            vec![(db_name::WORD_DOCIDS.to_string(), b"hello".to_vec())]
+        );
+        drop(rtxn);
+
+        let mut wtxn = index.write_txn().unwrap();
+        let count = QuarantineCorruptedEntries::new(&mut wtxn, &index).execute().unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(count, 1);
+        assert!(index.skipped_corrupted_entries().is_empty());
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            index.corrupted_entries(&rtxn).unwrap(),
+            vec![CorruptedEntry {
+                database: db_name::WORD_DOCIDS.to_string(),
+                key: b"hello".to_vec(),
+                value: vec![1, 2, 3],
+            }]
+        );
+        let SearchResult { documents_ids, .. } =
+            Search::new(&rtxn, &index).query("world").execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+    }
+}
@@ -1,7 +1,7 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::result::Result as StdResult;
 
-use charabia::{Normalize, Tokenizer, TokenizerBuilder};
+use charabia::{Tokenizer, TokenizerBuilder};
 use deserr::{DeserializeError, Deserr};
 use itertools::Itertools;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -14,7 +14,7 @@ use crate::error::UserError;
 use crate::index::{DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS};
 use crate::update::index_documents::IndexDocumentsMethod;
 use crate::update::{IndexDocuments, UpdateIndexingStep};
-use crate::{FieldsIdsMap, Index, OrderBy, Result};
+use crate::{FieldsIdsMap, Index, LocalizedAttributesRule, OrderBy, Result, View};
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Setting<T> {
@@ -112,10 +112,18 @@ pub struct Settings<'a, 't, 'u, 'i> {
     sortable_fields: Setting<HashSet<String>>,
     criteria: Setting<Vec<Criterion>>,
     stop_words: Setting<BTreeSet<String>>,
+    /// Words that the tokenizer must never split.
+    dictionary: Setting<BTreeSet<String>>,
     distinct_field: Setting<String>,
+    /// The field sorting, in descending order, the documents that tie on every ranking rule.
+    recency_field: Setting<String>,
+    /// The numeric field multiplying the global score of the documents.
+    boost_field: Setting<String>,
     synonyms: Setting<HashMap<String, Vec<String>>>,
     primary_key: Setting<String>,
     authorize_typos: Setting<bool>,
+    /// Whether the numbers and booleans of the searchable fields are indexed as words.
+    searchable_numbers_and_booleans: Setting<bool>,
     min_word_len_two_typos: Setting<u8>,
     min_word_len_one_typo: Setting<u8>,
     exact_words: Setting<BTreeSet<String>>,
@@ -124,6 +132,9 @@ pub struct Settings<'a, 't, 'u, 'i> {
     max_values_per_facet: Setting<usize>,
     sort_facet_values_by: Setting<HashMap<String, OrderBy>>,
     pagination_max_total_hits: Setting<usize>,
+    typo_derivations_limit: Setting<usize>,
+    views: Setting<BTreeMap<String, View>>,
+    localized_attributes_rules: Setting<Vec<LocalizedAttributesRule>>,
 }
 
 impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
@@ -141,10 +152,14 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             sortable_fields: Setting::NotSet,
             criteria: Setting::NotSet,
             stop_words: Setting::NotSet,
+            dictionary: Setting::NotSet,
             distinct_field: Setting::NotSet,
+            recency_field: Setting::NotSet,
+            boost_field: Setting::NotSet,
             synonyms: Setting::NotSet,
             primary_key: Setting::NotSet,
             authorize_typos: Setting::NotSet,
+            searchable_numbers_and_booleans: Setting::NotSet,
             exact_words: Setting::NotSet,
             min_word_len_two_typos: Setting::NotSet,
             min_word_len_one_typo: Setting::NotSet,
@@ -152,6 +167,9 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             max_values_per_facet: Setting::NotSet,
             sort_facet_values_by: Setting::NotSet,
             pagination_max_total_hits: Setting::NotSet,
+            typo_derivations_limit: Setting::NotSet,
+            views: Setting::NotSet,
+            localized_attributes_rules: Setting::NotSet,
             indexer_config,
         }
     }
@@ -205,6 +223,15 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             if stop_words.is_empty() { Setting::Reset } else { Setting::Set(stop_words) }
     }
 
+    pub fn reset_dictionary(&mut self) {
+        self.dictionary = Setting::Reset;
+    }
+
+    pub fn set_dictionary(&mut self, dictionary: BTreeSet<String>) {
+        self.dictionary =
+            if dictionary.is_empty() { Setting::Reset } else { Setting::Set(dictionary) }
+    }
+
     pub fn reset_distinct_field(&mut self) {
         self.distinct_field = Setting::Reset;
     }
@@ -213,6 +240,22 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.distinct_field = Setting::Set(distinct_field);
     }
 
+    pub fn reset_recency_field(&mut self) {
+        self.recency_field = Setting::Reset;
+    }
+
+    pub fn set_recency_field(&mut self, recency_field: String) {
+        self.recency_field = Setting::Set(recency_field);
+    }
+
+    pub fn reset_boost_field(&mut self) {
+        self.boost_field = Setting::Reset;
+    }
+
+    pub fn set_boost_field(&mut self, boost_field: String) {
+        self.boost_field = Setting::Set(boost_field);
+    }
+
     pub fn reset_synonyms(&mut self) {
         self.synonyms = Setting::Reset;
     }
@@ -237,6 +280,14 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.authorize_typos = Setting::Reset;
     }
 
+    pub fn set_searchable_numbers_and_booleans(&mut self, val: bool) {
+        self.searchable_numbers_and_booleans = Setting::Set(val);
+    }
+
+    pub fn reset_searchable_numbers_and_booleans(&mut self) {
+        self.searchable_numbers_and_booleans = Setting::Reset;
+    }
+
     pub fn set_min_word_len_two_typos(&mut self, val: u8) {
         self.min_word_len_two_typos = Setting::Set(val);
     }
@@ -293,6 +344,30 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.pagination_max_total_hits = Setting::Reset;
     }
 
+    pub fn set_typo_derivations_limit(&mut self, value: usize) {
+        self.typo_derivations_limit = Setting::Set(value);
+    }
+
+    pub fn reset_typo_derivations_limit(&mut self) {
+        self.typo_derivations_limit = Setting::Reset;
+    }
+
+    pub fn set_views(&mut self, value: BTreeMap<String, View>) {
+        self.views = Setting::Set(value);
+    }
+
+    pub fn reset_views(&mut self) {
+        self.views = Setting::Reset;
+    }
+
+    pub fn set_localized_attributes_rules(&mut self, value: Vec<LocalizedAttributesRule>) {
+        self.localized_attributes_rules = Setting::Set(value);
+    }
+
+    pub fn reset_localized_attributes_rules(&mut self) {
+        self.localized_attributes_rules = Setting::Reset;
+    }
+
     fn reindex<FP, FA>(
         &mut self,
         progress_callback: &FP,
@@ -316,6 +391,8 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             self.indexer_config,
             IndexDocumentsMethod::ReplaceDocuments,
             false,
+            0,
+            false,
         )?;
 
         // We clear the databases and remap the documents fields based on the new `FieldsIdsMap`.
@@ -368,6 +445,32 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(true)
     }
 
+    fn update_recency_field(&mut self) -> Result<bool> {
+        match self.recency_field {
+            Setting::Set(ref attr) => {
+                self.index.put_recency_field(self.wtxn, attr)?;
+            }
+            Setting::Reset => {
+                self.index.delete_recency_field(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn update_boost_field(&mut self) -> Result<bool> {
+        match self.boost_field {
+            Setting::Set(ref attr) => {
+                self.index.put_boost_field(self.wtxn, attr)?;
+            }
+            Setting::Reset => {
+                self.index.delete_boost_field(self.wtxn)?;
+            }
+            Setting::NotSet => return Ok(false),
+        }
+        Ok(true)
+    }
+
     /// Updates the index's searchable attributes. This causes the field map to be recomputed to
     /// reflect the order of the searchable attributes.
     fn update_searchable(&mut self) -> Result<bool> {
@@ -425,9 +528,7 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
                 let current = self.index.stop_words(self.wtxn)?;
 
                 // Apply an unlossy normalization on stop_words
-                let stop_words = stop_words
-                    .iter()
-                    .map(|w| w.as_str().normalize(&Default::default()).into_owned());
+                let stop_words = stop_words.iter().map(|w| crate::normalize_str(w));
 
                 // since we can't compare a BTreeSet with an FST we are going to convert the
                 // BTreeSet to an FST and then compare bytes per bytes the two FSTs.
@@ -449,6 +550,29 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         }
     }
 
+    fn update_dictionary(&mut self) -> Result<bool> {
+        match self.dictionary {
+            Setting::Set(ref dictionary) => {
+                let current = self.index.dictionary(self.wtxn)?;
+
+                // The words are not normalized, the tokenizer looks for them in the original text.
+                let fst = fst::Set::from_iter(dictionary)?;
+
+                // Does the new FST differ from the previous one?
+                if current
+                    .map_or(true, |current| current.as_fst().as_bytes() != fst.as_fst().as_bytes())
+                {
+                    self.index.put_dictionary(self.wtxn, &fst)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_dictionary(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
     fn update_synonyms(&mut self) -> Result<bool> {
         match self.synonyms {
             Setting::Set(ref synonyms) => {
@@ -470,6 +594,11 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
                 if let Some(ref stop_words) = stop_words {
                     builder.stop_words(stop_words);
                 }
+                let settings = self.index.cached_settings(self.wtxn)?;
+                let dictionary = settings.words_dict();
+                if let Some(ref dictionary) = dictionary {
+                    builder.words_dict(dictionary);
+                }
                 let tokenizer = builder.build();
 
                 let mut new_synonyms = HashMap::new();
@@ -524,6 +653,27 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         }
     }
 
+    fn update_localized_attributes_rules(&mut self) -> Result<bool> {
+        match self.localized_attributes_rules {
+            Setting::Set(ref rules) => {
+                // We make sure that the locales of the rules are supported before storing them.
+                for rule in rules {
+                    rule.languages()?;
+                }
+
+                let old_rules = self.index.localized_attributes_rules(self.wtxn)?;
+                if rules != &old_rules {
+                    self.index.put_localized_attributes_rules(self.wtxn, rules)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            Setting::Reset => Ok(self.index.delete_localized_attributes_rules(self.wtxn)?),
+            Setting::NotSet => Ok(false),
+        }
+    }
+
     fn update_filterable(&mut self) -> Result<()> {
         match self.filterable_fields {
             Setting::Set(ref fields) => {
@@ -616,6 +766,21 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         }
     }
 
+    fn update_searchable_numbers_and_booleans(&mut self) -> Result<bool> {
+        let old_flag = self.index.searchable_numbers_and_booleans(self.wtxn)?;
+        match self.searchable_numbers_and_booleans {
+            Setting::Set(flag) => {
+                self.index.put_searchable_numbers_and_booleans(self.wtxn, flag)?;
+                Ok(flag != old_flag)
+            }
+            Setting::Reset => {
+                self.index.delete_searchable_numbers_and_booleans(self.wtxn)?;
+                Ok(!old_flag)
+            }
+            Setting::NotSet => Ok(false),
+        }
+    }
+
     fn update_min_typo_word_len(&mut self) -> Result<()> {
         let one = self.min_word_len_one_typo.or_reset(DEFAULT_MIN_WORD_LEN_ONE_TYPO);
         let two = self.min_word_len_two_typos.or_reset(DEFAULT_MIN_WORD_LEN_TWO_TYPOS);
@@ -662,6 +827,11 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
                 if let Some(ref stop_words) = stop_words {
                     builder.stop_words(stop_words);
                 }
+                let settings = self.index.cached_settings(self.wtxn)?;
+                let dictionary = settings.words_dict();
+                if let Some(ref dictionary) = dictionary {
+                    builder.words_dict(dictionary);
+                }
                 let tokenizer = builder.build();
 
                 let mut words: Vec<_> =
@@ -724,6 +894,38 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(())
     }
 
+    fn update_typo_derivations_limit(&mut self) -> Result<()> {
+        match self.typo_derivations_limit {
+            Setting::Set(limit) => {
+                self.index.put_typo_derivations_limit(self.wtxn, limit)?;
+            }
+            Setting::Reset => {
+                self.index.delete_typo_derivations_limit(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+
+        Ok(())
+    }
+
+    fn update_views(&mut self) -> Result<()> {
+        match self.views.as_ref() {
+            Setting::Set(views) => {
+                // We make sure that the filters of the views are valid before storing them.
+                for view in views.values() {
+                    view.filter()?;
+                }
+                self.index.put_views(self.wtxn, views)?;
+            }
+            Setting::Reset => {
+                self.index.delete_views(self.wtxn)?;
+            }
+            Setting::NotSet => (),
+        }
+
+        Ok(())
+    }
+
     pub fn execute<FP, FA>(mut self, progress_callback: FP, should_abort: FA) -> Result<()>
     where
         FP: Fn(UpdateIndexingStep) + Sync,
@@ -734,10 +936,14 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         let old_faceted_fields = self.index.user_defined_faceted_fields(self.wtxn)?;
         let old_fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
 
+        // The dictionary is updated first as the synonyms and the exact words are tokenized with it.
+        let dictionary_updated = self.update_dictionary()?;
         self.update_displayed()?;
         self.update_filterable()?;
         self.update_sortable()?;
         self.update_distinct_field()?;
+        self.update_recency_field()?;
+        self.update_boost_field()?;
         self.update_criteria()?;
         self.update_primary_key()?;
         self.update_authorize_typos()?;
@@ -746,6 +952,8 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.update_max_values_per_facet()?;
         self.update_sort_facet_values_by()?;
         self.update_pagination_max_total_hits()?;
+        self.update_typo_derivations_limit()?;
+        self.update_views()?;
 
         // If there is new faceted fields we indicate that we must reindex as we must
         // index new fields as facets. It means that the distinct attribute,
@@ -757,12 +965,18 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         let synonyms_updated = self.update_synonyms()?;
         let searchable_updated = self.update_searchable()?;
         let exact_attributes_updated = self.update_exact_attributes()?;
+        let searchable_numbers_and_booleans_updated =
+            self.update_searchable_numbers_and_booleans()?;
+        let localized_attributes_rules_updated = self.update_localized_attributes_rules()?;
 
         if stop_words_updated
+            || dictionary_updated
             || faceted_updated
             || synonyms_updated
             || searchable_updated
             || exact_attributes_updated
+            || searchable_numbers_and_booleans_updated
+            || localized_attributes_rules_updated
         {
             self.reindex(&progress_callback, &should_abort, old_fields_ids_map)?;
         }
@@ -842,6 +1056,43 @@ mod tests {
         assert_eq!(documents[0].1.get(0), Some(&br#""kevin""#[..]));
     }
 
+    #[test]
+    fn non_searchable_fields_are_not_indexed_as_words() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title")]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "hello", "internal_id": "xk42bz" },
+                { "id": 2, "title": "world", "internal_id": "qp17rt" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.word_docids.get(&rtxn, "hello").unwrap().is_some());
+        assert!(index.word_docids.get(&rtxn, "xk42bz").unwrap().is_none());
+        assert!(!index.words_fst(&rtxn).unwrap().contains("qp17rt"));
+        let result = index.search(&rtxn).query("xk42bz").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        drop(rtxn);
+
+        // once the field is searchable its words are indexed
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("internal_id")]);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.word_docids.get(&rtxn, "xk42bz").unwrap().is_some());
+        let result = index.search(&rtxn).query("xk42bz").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+    }
+
     #[test]
     fn mixup_searchable_with_displayed_fields() {
         let mut index = TempIndex::new();
@@ -1220,6 +1471,42 @@ mod tests {
         assert_eq!(result.documents_ids.len(), 1); // there is one benoit in our data
     }
 
+    #[test]
+    fn set_and_reset_dictionary() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "J.R.R. Tolkien" },
+                { "id": 1, "name": "J. R. R. Martin" },
+            ]))
+            .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_dictionary(btreeset! { S("J.R.R.") });
+            })
+            .unwrap();
+
+        // the words of the dictionary are not split, neither in the documents nor in the queries
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.word_docids.get(&rtxn, "j.r.r.").unwrap().is_some());
+        let result = index.search(&rtxn).query("J.R.R. ").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.reset_dictionary();
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.dictionary(&rtxn).unwrap().is_none());
+        assert!(index.word_docids.get(&rtxn, "j.r.r.").unwrap().is_none());
+        let result = index.search(&rtxn).query("J.R.R. ").execute().unwrap();
+        assert_eq!(result.documents_ids.len(), 2);
+    }
+
     #[test]
     fn set_and_reset_synonyms() {
         let mut index = TempIndex::new();
@@ -1460,6 +1747,48 @@ mod tests {
         assert!(!index.authorize_typos(&txn).unwrap());
     }
 
+    #[test]
+    fn searchable_numbers_and_booleans() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hello", "score": 42, "available": true },
+                { "id": 1, "title": "world", "score": 12, "available": false },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.searchable_numbers_and_booleans(&rtxn).unwrap());
+        let SearchResult { documents_ids, .. } = index.search(&rtxn).query("42").execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| settings.set_searchable_numbers_and_booleans(false))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(!index.searchable_numbers_and_booleans(&rtxn).unwrap());
+        let SearchResult { documents_ids, .. } = index.search(&rtxn).query("42").execute().unwrap();
+        assert!(documents_ids.is_empty());
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("false").execute().unwrap();
+        assert!(documents_ids.is_empty());
+        // the strings are still searchable
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("world").execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+        drop(rtxn);
+
+        index.update_settings(|settings| settings.reset_searchable_numbers_and_booleans()).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let SearchResult { documents_ids, .. } =
+            index.search(&rtxn).query("false").execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+    }
+
     #[test]
     fn update_min_word_len_for_typo() {
         let index = TempIndex::new();
@@ -1537,10 +1866,14 @@ mod tests {
                     sortable_fields,
                     criteria,
                     stop_words,
+                    dictionary,
                     distinct_field,
+                    recency_field,
+                    boost_field,
                     synonyms,
                     primary_key,
                     authorize_typos,
+                    searchable_numbers_and_booleans,
                     min_word_len_two_typos,
                     min_word_len_one_typo,
                     exact_words,
@@ -1548,6 +1881,9 @@ mod tests {
                     max_values_per_facet,
                     sort_facet_values_by,
                     pagination_max_total_hits,
+                    typo_derivations_limit,
+                    views,
+                    localized_attributes_rules,
                 } = settings;
                 assert!(matches!(searchable_fields, Setting::NotSet));
                 assert!(matches!(displayed_fields, Setting::NotSet));
@@ -1555,10 +1891,14 @@ mod tests {
                 assert!(matches!(sortable_fields, Setting::NotSet));
                 assert!(matches!(criteria, Setting::NotSet));
                 assert!(matches!(stop_words, Setting::NotSet));
+                assert!(matches!(dictionary, Setting::NotSet));
                 assert!(matches!(distinct_field, Setting::NotSet));
+                assert!(matches!(recency_field, Setting::NotSet));
+                assert!(matches!(boost_field, Setting::NotSet));
                 assert!(matches!(synonyms, Setting::NotSet));
                 assert!(matches!(primary_key, Setting::NotSet));
                 assert!(matches!(authorize_typos, Setting::NotSet));
+                assert!(matches!(searchable_numbers_and_booleans, Setting::NotSet));
                 assert!(matches!(min_word_len_two_typos, Setting::NotSet));
                 assert!(matches!(min_word_len_one_typo, Setting::NotSet));
                 assert!(matches!(exact_words, Setting::NotSet));
@@ -1566,6 +1906,9 @@ mod tests {
                 assert!(matches!(max_values_per_facet, Setting::NotSet));
                 assert!(matches!(sort_facet_values_by, Setting::NotSet));
                 assert!(matches!(pagination_max_total_hits, Setting::NotSet));
+                assert!(matches!(typo_derivations_limit, Setting::NotSet));
+                assert!(matches!(views, Setting::NotSet));
+                assert!(matches!(localized_attributes_rules, Setting::NotSet));
             })
             .unwrap();
     }
@@ -1601,4 +1944,37 @@ mod tests {
         let docs = docs.unwrap();
         assert_eq!(docs.len(), 5);
     }
+
+    #[test]
+    fn only_the_settings_changing_the_indexed_data_reindex() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "kevin", "age": 20 },
+                { "id": 1, "name": "kevina", "age": 21 }
+            ]))
+            .unwrap();
+
+        // Returns whether the documents have been reindexed by the settings update.
+        let reindexed = |update: &dyn Fn(&mut Settings)| {
+            let mut wtxn = index.write_txn().unwrap();
+            let mut builder = Settings::new(&mut wtxn, &index, &index.indexer_config);
+            update(&mut builder);
+            let steps = AtomicUsize::new(0);
+            builder.execute(|_| drop(steps.fetch_add(1, Ordering::Relaxed)), || false).unwrap();
+            wtxn.commit().unwrap();
+            steps.into_inner() > 0
+        };
+
+        assert!(!reindexed(&|s| s.set_displayed_fields(vec![S("name")])));
+        assert!(!reindexed(&|s| s.set_criteria(vec![Criterion::Words, Criterion::Typo])));
+        assert!(!reindexed(&|s| s.set_max_values_per_facet(10)));
+        assert!(reindexed(&|s| s.set_searchable_fields(vec![S("name")])));
+        assert!(reindexed(&|s| s.set_stop_words(btreeset! { S("the") })));
+        assert!(reindexed(&|s| s.set_filterable_fields(hashset! { S("age") })));
+        // The same value again doesn't need any reindexing.
+        assert!(!reindexed(&|s| s.set_filterable_fields(hashset! { S("age") })));
+    }
 }
@@ -0,0 +1,134 @@
+//! This module provides the `View` type, a named read-only projection of an index.
+
+use heed::RoTxn;
+use serde::{Deserialize, Serialize};
+
+use crate::{FieldId, Filter, Index, Result};
+
+/// A view is a stored filter associated with a projection of the displayed fields.
+///
+/// Searching through a view is like searching an index that only contains the documents
+/// matching the view filter and that only displays the fields of the view.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct View {
+    /// The filter applied to every search made through this view.
+    pub filter: Option<String>,
+    /// The fields displayed by this view, `None` means that the
+    /// displayed fields of the index are used.
+    pub displayed_fields: Option<Vec<String>>,
+}
+
+impl View {
+    /// Parses the filter of this view, returns `None` if the view doesn't filter anything.
+    pub fn filter(&self) -> Result<Option<Filter>> {
+        match &self.filter {
+            Some(filter) => Filter::from_str(filter),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the ids of the fields displayed by this view, restricted to the displayed fields
+    /// of the index. If it returns `None` it means that all the attributes are displayed.
+    pub fn displayed_fields_ids(
+        &self,
+        rtxn: &RoTxn,
+        index: &Index,
+    ) -> Result<Option<Vec<FieldId>>> {
+        let index_displayed = index.displayed_fields_ids(rtxn)?;
+        let view_fields = match &self.displayed_fields {
+            Some(fields) => fields,
+            None => return Ok(index_displayed),
+        };
+
+        let fields_ids_map = index.fields_ids_map(rtxn)?;
+        let fields_ids = view_fields
+            .iter()
+            .filter_map(|name| fields_ids_map.id(name))
+            .filter(|id| index_displayed.as_ref().map_or(true, |displayed| displayed.contains(id)))
+            .collect();
+
+        Ok(Some(fields_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::{btreemap, hashset};
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Search, SearchResult};
+
+    #[test]
+    fn search_through_a_view() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("id"), S("tenant") });
+                settings.set_views(btreemap! {
+                    S("blue") => View {
+                        filter: Some(S("tenant = blue")),
+                        displayed_fields: Some(vec![S("id"), S("title")]),
+                    },
+                });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "hello world", "tenant": "blue" },
+                { "id": 1, "title": "hello kitty", "tenant": "red" },
+                { "id": 2, "title": "hello bob", "tenant": "blue" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let view = index.view(&rtxn, "blue").unwrap().unwrap();
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("hello");
+        search.view(&view).unwrap();
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2]);
+
+        // the view filter is combined with the filter of the query
+        let mut search = Search::new(&rtxn, &index);
+        search.query("hello");
+        search.filter(Filter::from_str("id = 2").unwrap().unwrap());
+        search.view(&view).unwrap();
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+
+        // the retrieved documents only display the fields of the view
+        let documents = index.view_documents(&rtxn, &view, vec![0, 1, 2]).unwrap();
+        let ids: Vec<_> = documents.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![0, 2]);
+        for (_, document) in documents {
+            assert_eq!(document.keys().collect::<Vec<_>>(), vec!["id", "title"]);
+        }
+
+        // the displayed fields of the index also apply to the view
+        drop(rtxn);
+        index.update_settings(|settings| settings.set_displayed_fields(vec![S("id")])).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        let documents = index.view_documents(&rtxn, &view, Some(0)).unwrap();
+        assert_eq!(documents[0].1.keys().collect::<Vec<_>>(), vec!["id"]);
+    }
+
+    #[test]
+    fn invalid_view_filter() {
+        let index = TempIndex::new();
+
+        let error = index
+            .update_settings(|settings| {
+                settings.set_views(btreemap! {
+                    S("broken") => View { filter: Some(S("tenant =")), displayed_fields: None },
+                });
+            })
+            .unwrap_err();
+        assert!(matches!(error, crate::Error::UserError(crate::UserError::InvalidFilter(_))));
+    }
+}
@@ -147,7 +147,7 @@ pub fn expected_order(
                     new_groups
                         .extend(group.linear_group_by_key(|d| d.asc_desc_rank).map(Vec::from));
                 }
-                Criterion::Asc(_) | Criterion::Desc(_) | Criterion::Sort => {
+                Criterion::Asc(_) | Criterion::Desc(_) | Criterion::Sort | Criterion::Order => {
                     new_groups.push(group.clone())
                 }
             }
@@ -160,6 +160,9 @@ pub fn expected_order(
         TermsMatchingStrategy::All => {
             groups.into_iter().flatten().filter(|d| d.word_rank == 0).collect()
         }
+        TermsMatchingStrategy::Frequency => {
+            unimplemented!("the word ranks of the test documents don't depend on frequencies")
+        }
     }
 }
 
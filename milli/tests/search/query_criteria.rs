@@ -356,3 +356,28 @@ fn criteria_ascdesc() {
         assert_eq!(documents_ids, expected_document_ids);
     }
 }
+
+#[test]
+fn frequency_matching_strategy() {
+    let criteria = vec![Words];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.terms_matching_strategy(TermsMatchingStrategy::Frequency);
+
+    let SearchResult { documents_ids, .. } = search.execute().unwrap();
+    let documents_ids = search::internal_to_external_ids(&index, &documents_ids);
+
+    // the documents matching all the query words come first, in the same order
+    // as when all the words must match, the other ones depend on the frequencies.
+    let expected_external_ids: Vec<_> =
+        search::expected_order(&criteria, DISALLOW_OPTIONAL_WORDS, &[])
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+    assert!(documents_ids.len() >= expected_external_ids.len());
+    assert_eq!(documents_ids[..expected_external_ids.len()], expected_external_ids[..]);
+}